@@ -7,6 +7,7 @@ fn bench_parse(c: &mut Criterion) {
     for i in 0..2000 {
         data.push_str(&format!("@r{i}\nACGTACGTACGTACGT\n+\n################\n"));
     }
+    let data: &'static str = Box::leak(data.into_boxed_str());
     c.bench_function("parse_2000_singleline", |b| {
         b.iter(|| {
             let rdr = BufReader::new(data.as_bytes());
@@ -16,6 +17,7 @@ fn bench_parse(c: &mut Criterion) {
                     error_policy: ErrorPolicy::Return,
                     fastq_only: true,
                     line_mode: LineMode::Single,
+                    ..Default::default()
                 },
             );
             let mut n = 0usize;
@@ -0,0 +1,59 @@
+/* C interface to kira_cdh_compat_fastq_reader.
+ * Build: cargo rustc --release --lib --features ffi --crate-type cdylib */
+#ifndef KIRA_FASTQ_H
+#define KIRA_FASTQ_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define FQ_OK                   0
+#define FQ_ERR_IO               1
+#define FQ_ERR_MISSING_HEADER   2
+#define FQ_ERR_FASTA_HEADER     3
+#define FQ_ERR_MISSING_PLUS     4
+#define FQ_ERR_UNEXPECTED_EOF   5
+#define FQ_ERR_LENGTH_MISMATCH  6
+#define FQ_ERR_EMPTY_SEQUENCE   7
+#define FQ_ERR_INVALID_ARG      8
+#define FQ_ERR_GZIP_INTEGRITY   9
+#define FQ_ERR_LIMIT_EXCEEDED   10
+#define FQ_ERR_BLANK_LINE       11
+#define FQ_ERR_INVALID_SEQUENCE 12
+#define FQ_ERR_PAIR_DESYNC      13
+#define FQ_ERR_ORPHAN           14
+#define FQ_ERR_INVALID_QUALITY  15
+#define FQ_ERR_PLUS_MISMATCH    16
+
+typedef struct FqReader FqReader;
+
+/* Pointers stay valid until the next fq_next/fq_close on the same reader.
+ * Strings are not NUL-terminated; desc is NULL when absent. */
+typedef struct FqRecord {
+    const uint8_t *id;
+    size_t id_len;
+    const uint8_t *desc;
+    size_t desc_len;
+    const uint8_t *seq;
+    size_t seq_len;
+    const uint8_t *qual;
+    size_t qual_len;
+} FqRecord;
+
+/* Returns NULL on failure; see fq_error_code(NULL). */
+FqReader *fq_open(const char *path, int strict, int multi_line);
+/* 1 = record written, 0 = EOF, -1 = error (see fq_error_code). */
+int fq_next(FqReader *reader, FqRecord *out);
+int fq_error_code(const FqReader *reader);
+/* Static NUL-terminated description of an error code; never NULL. */
+const char *fq_strerror(int code);
+void fq_close(FqReader *reader);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KIRA_FASTQ_H */
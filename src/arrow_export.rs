@@ -0,0 +1,142 @@
+//! Arrow record batches and Parquet output (feature `arrow`).
+//!
+//! [`record_batch`] turns records into one Arrow [`RecordBatch`] with the
+//! [`schema`] below; [`ParquetWriter`] buffers records into such batches
+//! and writes them as Parquet row groups, ready for DuckDB, Polars or
+//! Spark without a separate conversion step.
+//!
+//! | column | type | |
+//! |---|---|---|
+//! | `id` | `Utf8` | |
+//! | `desc` | `Utf8` | null when absent |
+//! | `seq` | `LargeBinary` | |
+//! | `qual` | `LargeBinary` | |
+
+use crate::record::FastqRecord;
+
+use arrow_array::builder::{LargeBinaryBuilder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Records per batch (and Parquet row group) of [`ParquetWriter`].
+pub const DEFAULT_BATCH_ROWS: usize = 64 * 1024;
+
+/// Schema of [`record_batch`] and the Parquet files of [`ParquetWriter`].
+pub fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("desc", DataType::Utf8, true),
+        Field::new("seq", DataType::LargeBinary, false),
+        Field::new("qual", DataType::LargeBinary, false),
+    ]))
+}
+
+/// `records` as one batch with the [`schema`].
+pub fn record_batch(records: &[FastqRecord]) -> Result<RecordBatch, ArrowError> {
+    let bases: usize = records.iter().map(FastqRecord::len).sum();
+    let mut id = StringBuilder::new();
+    let mut desc = StringBuilder::new();
+    let mut seq = LargeBinaryBuilder::with_capacity(records.len(), bases);
+    let mut qual = LargeBinaryBuilder::with_capacity(records.len(), bases);
+    for rec in records {
+        id.append_value(rec.id());
+        desc.append_option(rec.desc());
+        seq.append_value(rec.seq());
+        qual.append_value(rec.qual());
+    }
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(id.finish()),
+        Arc::new(desc.finish()),
+        Arc::new(seq.finish()),
+        Arc::new(qual.finish()),
+    ];
+    RecordBatch::try_new(schema(), columns)
+}
+
+/// Streaming Parquet writer, Snappy-compressed. Call
+/// [`finish`](Self::finish) when done: the file footer is written last.
+pub struct ParquetWriter<W: Write + Send = File> {
+    inner: ArrowWriter<W>,
+    pending: Vec<FastqRecord>,
+    batch_rows: usize,
+    records: u64,
+}
+
+impl ParquetWriter<File> {
+    /// Create (or truncate) `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_writer(File::create(path)?)
+    }
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    /// Write to any destination.
+    pub fn from_writer(out: W) -> io::Result<Self> {
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        Ok(Self {
+            inner: ArrowWriter::try_new(out, schema(), Some(props)).map_err(io::Error::other)?,
+            pending: Vec::new(),
+            batch_rows: DEFAULT_BATCH_ROWS,
+            records: 0,
+        })
+    }
+
+    /// Records per batch and row group (at least 1; default
+    /// [`DEFAULT_BATCH_ROWS`]).
+    pub fn with_batch_rows(mut self, rows: usize) -> Self {
+        self.batch_rows = rows.max(1);
+        self
+    }
+
+    /// Buffer one record; a full batch is written out.
+    pub fn write_record(&mut self, rec: &FastqRecord) -> io::Result<()> {
+        self.pending.push(rec.clone());
+        self.records += 1;
+        if self.pending.len() >= self.batch_rows {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    /// Write a batch built elsewhere; it must have the [`schema`].
+    pub fn write_batch(&mut self, batch: &RecordBatch) -> io::Result<()> {
+        self.flush_batch()?;
+        self.inner.write(batch).map_err(io::Error::other)?;
+        self.inner.flush().map_err(io::Error::other)?;
+        self.records += batch.num_rows() as u64;
+        Ok(())
+    }
+
+    /// Records written so far, buffered ones included.
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Write the buffered records and the footer, and return the
+    /// destination.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_batch()?;
+        self.inner.into_inner().map_err(io::Error::other)
+    }
+
+    fn flush_batch(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let batch = record_batch(&self.pending).map_err(io::Error::other)?;
+        self.pending.clear();
+        self.inner.write(&batch).map_err(io::Error::other)?;
+        // one row group per batch
+        self.inner.flush().map_err(io::Error::other)
+    }
+}
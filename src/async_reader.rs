@@ -1,19 +1,37 @@
 #![cfg(feature = "async")]
 
-use crate::error::{FastqError, FormatError, IoContext};
-use crate::policy::{ErrorPolicy, LineMode, ReaderOptions};
-use crate::record::FastqRecord;
+use crate::core::RecordParser;
+use crate::error::{FastqError, FormatError, IoContext, SkipReport};
+use crate::instrument::{self, Meter};
+use crate::limits::LimitError;
+use crate::policy::{ErrorPolicy, InputFormat, LineMode, QualityEncoding, ReaderOptions, Throttle};
+use crate::reader::SkipHandler;
+use crate::record::{FastqRecord, SeqRecord};
+use crate::throttle::{RateLimiter, ThrottledAsyncRead};
 
 use async_compression::tokio::bufread::GzipDecoder;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::fs::File;
-use tokio::io::{self, AsyncBufRead, AsyncBufReadExt, BufReader};
+use tokio::io::{self, AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
 
 #[derive(Debug)]
 pub enum AsyncSource {
     Path(PathBuf),
     Reader,
+    /// A reader tagged via [`AsyncFastqReader::from_async_bufread_labeled`].
+    Labeled(String),
+}
+
+impl AsyncSource {
+    /// The user-supplied label, for [`AsyncSource::Labeled`].
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            AsyncSource::Labeled(l) => Some(l),
+            _ => None,
+        }
+    }
 }
 
 /// Async FASTQ reader (plain/.gz), streaming.
@@ -23,7 +41,17 @@ pub struct AsyncFastqReader {
     opts: ReaderOptions,
     line_num: u64,
     byte_pos: u64,
-    pending_header: Option<String>,
+    record_bytes: usize,
+    record_rate: Option<RateLimiter>,
+    label: Option<Arc<str>>,
+    parser: RecordParser,
+    line: Vec<u8>,
+    /// Error held back by `next_batch` behind the records before it.
+    held_err: Option<FastqError>,
+    on_skip: Option<SkipHandler>,
+    skip_report: SkipReport,
+    skipped: u64,
+    meter: Meter,
 }
 
 impl AsyncFastqReader {
@@ -33,12 +61,17 @@ impl AsyncFastqReader {
         opts: ReaderOptions,
     ) -> Result<Self, FastqError> {
         let path = path.as_ref().to_path_buf();
+        let label: Arc<str> = path.display().to_string().into();
         let mut f = File::open(&path).await.map_err(|e| {
             FastqError::io_err(
                 e,
                 IoContext {
                     byte_pos: 0,
                     line_num: 0,
+                    record_num: 0,
+                    record_line: 0,
+                    label: Some(label.clone()),
+                    virtual_offset: None,
                 },
             )
         })?;
@@ -46,53 +79,230 @@ impl AsyncFastqReader {
         let is_gz = path.extension().and_then(|s| s.to_str()) == Some("gz")
             || looks_like_gzip_async(&mut f).await.unwrap_or(false);
 
+        let f: Box<dyn AsyncRead + Unpin + Send> =
+            match opts.throttle.and_then(Throttle::bytes_per_sec) {
+                Some(rate) => Box::new(ThrottledAsyncRead::new(f, rate)),
+                None => Box::new(f),
+            };
         let inner: Box<dyn AsyncBufRead + Unpin + Send> = if is_gz {
-            let gz = GzipDecoder::new(BufReader::with_capacity(256 * 1024, f));
-            Box::new(BufReader::with_capacity(256 * 1024, gz))
+            let gz = GzipDecoder::new(BufReader::with_capacity(
+                opts.decompress_buffer(256 * 1024),
+                f,
+            ));
+            Box::new(BufReader::with_capacity(opts.read_buffer(256 * 1024), gz))
         } else {
-            Box::new(BufReader::with_capacity(256 * 1024, f))
+            Box::new(BufReader::with_capacity(opts.read_buffer(256 * 1024), f))
         };
 
-        let rdr = BufReader::with_capacity(256 * 1024, inner);
+        let rdr = BufReader::with_capacity(opts.read_buffer(256 * 1024), inner);
 
         Ok(Self {
+            meter: Meter::new(Some(&label)),
             src: AsyncSource::Path(path),
             rdr,
+            parser: RecordParser::new(&opts),
+            record_rate: opts
+                .throttle
+                .and_then(Throttle::records_per_sec)
+                .map(RateLimiter::new),
             opts,
             line_num: 0,
+            record_bytes: 0,
+            label: Some(label),
             byte_pos: 0,
-            pending_header: None,
+            line: Vec::with_capacity(256),
+            held_err: None,
+            on_skip: None,
+            skip_report: SkipReport::default(),
+            skipped: 0,
         })
     }
 
+    /// Stream a FASTQ over HTTP(S), labeled with `url`. The body is
+    /// decompressed when the URL path ends in `.gz` or it starts with the
+    /// gzip magic. A connection dropped mid-body is resumed with a `Range`
+    /// request (up to five attempts, with backoff); a failure past that is
+    /// an I/O error, subject to the error policy like any other.
+    #[cfg(feature = "remote")]
+    pub async fn from_url(url: &str, opts: ReaderOptions) -> Result<Self, FastqError> {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        let gz_ext = path.ends_with(".gz");
+        Self::from_remote(crate::remote::Target::Url(url.to_owned()), gz_ext, opts).await
+    }
+
+    /// Stream `s3://bucket/key`, with region, endpoint and credentials
+    /// from the environment (see [`S3Object::new`](crate::s3::S3Object::new)).
+    #[cfg(feature = "s3")]
+    pub async fn from_s3(bucket: &str, key: &str, opts: ReaderOptions) -> Result<Self, FastqError> {
+        Self::from_s3_object(crate::s3::S3Object::new(bucket, key), opts).await
+    }
+
+    /// Stream an S3 object as configured, in ranged parts of
+    /// `obj.part_size` bytes; gzip and retries are handled as by
+    /// [`from_url`](Self::from_url). Errors are labeled `s3://bucket/key`.
+    #[cfg(feature = "s3")]
+    pub async fn from_s3_object(
+        obj: crate::s3::S3Object,
+        opts: ReaderOptions,
+    ) -> Result<Self, FastqError> {
+        let gz_ext = obj.key.ends_with(".gz");
+        Self::from_remote(crate::remote::Target::S3(obj), gz_ext, opts).await
+    }
+
+    #[cfg(feature = "remote")]
+    async fn from_remote(
+        target: crate::remote::Target,
+        gz_ext: bool,
+        opts: ReaderOptions,
+    ) -> Result<Self, FastqError> {
+        let label = target.label();
+        let ctx = IoContext {
+            byte_pos: 0,
+            line_num: 0,
+            record_num: 0,
+            record_line: 0,
+            label: Some(label.as_str().into()),
+            virtual_offset: None,
+        };
+        let body = crate::remote::open(target)
+            .await
+            .map_err(|e| FastqError::io_err(e, ctx.clone()))?;
+        let mut body = BufReader::with_capacity(opts.decompress_buffer(256 * 1024), body);
+        let head = body
+            .fill_buf()
+            .await
+            .map_err(|e| FastqError::io_err(e, ctx))?;
+        let is_gz = gz_ext || head.starts_with(&[0x1F, 0x8B]);
+        let inner: Box<dyn AsyncBufRead + Unpin + Send> = if is_gz {
+            Box::new(BufReader::with_capacity(
+                opts.read_buffer(256 * 1024),
+                GzipDecoder::new(body),
+            ))
+        } else {
+            Box::new(body)
+        };
+        Ok(Self::from_async_bufread_inner(inner, opts, Some(label)))
+    }
+
     /// Wrap any async `AsyncBufRead`.
     pub fn from_async_bufread<R>(reader: R, opts: ReaderOptions) -> Self
+    where
+        R: AsyncBufRead + Unpin + Send + 'static,
+    {
+        Self::from_async_bufread_inner(reader, opts, None)
+    }
+
+    /// Like [`from_async_bufread`](Self::from_async_bufread), tagging the
+    /// source with `label`; it is carried in every error's [`IoContext`].
+    pub fn from_async_bufread_labeled<R>(
+        reader: R,
+        label: impl Into<String>,
+        opts: ReaderOptions,
+    ) -> Self
+    where
+        R: AsyncBufRead + Unpin + Send + 'static,
+    {
+        Self::from_async_bufread_inner(reader, opts, Some(label.into()))
+    }
+
+    fn from_async_bufread_inner<R>(reader: R, opts: ReaderOptions, label: Option<String>) -> Self
     where
         R: AsyncBufRead + Unpin + Send + 'static,
     {
         let inner: Box<dyn AsyncBufRead + Unpin + Send> =
-            Box::new(BufReader::with_capacity(256 * 1024, reader));
-        let rdr = BufReader::with_capacity(256 * 1024, inner);
+            match opts.throttle.and_then(Throttle::bytes_per_sec) {
+                Some(rate) => Box::new(BufReader::with_capacity(
+                    opts.read_buffer(256 * 1024),
+                    ThrottledAsyncRead::new(reader, rate),
+                )),
+                None => Box::new(BufReader::with_capacity(
+                    opts.read_buffer(256 * 1024),
+                    reader,
+                )),
+            };
+        let rdr = BufReader::with_capacity(opts.read_buffer(256 * 1024), inner);
         Self {
-            src: AsyncSource::Reader,
+            meter: Meter::new(label.as_deref()),
+            label: label.as_deref().map(Arc::from),
+            src: label.map_or(AsyncSource::Reader, AsyncSource::Labeled),
             rdr,
+            parser: RecordParser::new(&opts),
+            record_rate: opts
+                .throttle
+                .and_then(Throttle::records_per_sec)
+                .map(RateLimiter::new),
             opts,
             line_num: 0,
+            record_bytes: 0,
             byte_pos: 0,
-            pending_header: None,
+            line: Vec::with_capacity(256),
+            held_err: None,
+            on_skip: None,
+            skip_report: SkipReport::default(),
+            skipped: 0,
         }
     }
 
-    /// Fetch next record (async).
+    /// Where this reader's bytes come from.
+    pub fn source(&self) -> &AsyncSource {
+        &self.src
+    }
+
+    /// Fetch next record (async). FASTA entries (`fastq_only: false`)
+    /// count as malformed records here; see [`next_entry`](Self::next_entry).
     pub async fn next_record(&mut self) -> Option<Result<FastqRecord, FastqError>> {
+        loop {
+            match self.next_entry().await? {
+                Ok(SeqRecord::Fastq(rec)) => return Some(Ok(rec)),
+                Ok(SeqRecord::Fasta(entry)) => {
+                    let err = FastqError::fmt_err(FormatError::FastaHeaderDetected, self.ctx());
+                    instrument::parse_error(&err);
+                    if self.opts.error_policy == ErrorPolicy::Return {
+                        return Some(Err(err));
+                    }
+                    let header = match &entry.desc {
+                        Some(desc) => format!("{} {desc}", entry.id),
+                        None => entry.id,
+                    };
+                    if let Err(err) = self.report_skip(err, "FASTA entry", Some(header)) {
+                        return Some(Err(err));
+                    }
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    /// Next entry of a FASTA/FASTQ stream (`ReaderOptions::fastq_only:
+    /// false`); otherwise every entry is FASTQ.
+    pub async fn next_entry(&mut self) -> Option<Result<SeqRecord, FastqError>> {
+        if let Some(err) = self.held_err.take() {
+            return Some(Err(err));
+        }
         loop {
             match self.read_one().await {
-                Ok(Some(rec)) => return Some(Ok(rec)),
-                Ok(None) => return None,
+                Ok(Some(rec)) => {
+                    self.meter.record(self.byte_pos);
+                    if let Some(pause) = self.record_rate.as_mut().and_then(|r| r.record(1)) {
+                        tokio::time::sleep(pause).await;
+                    }
+                    return Some(Ok(rec));
+                }
+                Ok(None) => {
+                    self.meter.finish(self.byte_pos);
+                    return None;
+                }
                 Err(err) => {
-                    if self.opts.error_policy == ErrorPolicy::Skip {
-                        log::warn!("skipping malformed record: {err}");
+                    instrument::parse_error(&err);
+                    if self.opts.error_policy.skips() && err.is_skippable() {
+                        let header = matches!(err, FastqError::Format { .. })
+                            .then(|| self.parser.failed_header().map(str::to_owned))
+                            .flatten();
+                        if let Err(err) = self.report_skip(err, "malformed record", header) {
+                            return Some(Err(err));
+                        }
                         if !self.resync_to_next_header().await {
+                            self.meter.finish(self.byte_pos);
                             return None;
                         }
                         continue;
@@ -104,186 +314,198 @@ impl AsyncFastqReader {
         }
     }
 
-    async fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+    /// Hand every record dropped under `ErrorPolicy::Skip` to `f` instead
+    /// of logging it; see
+    /// [`FastqReader::with_skip_handler`](crate::FastqReader::with_skip_handler).
+    pub fn with_skip_handler<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&FastqError) + Send + 'static,
+    {
+        self.on_skip = Some(Box::new(f));
+        self
+    }
+
+    /// Records dropped so far under `ErrorPolicy::Skip`/`Collect`.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Records dropped so far under `ErrorPolicy::Collect`.
+    pub fn skip_report(&self) -> &SkipReport {
+        &self.skip_report
+    }
+
+    /// As [`FastqReader`](crate::FastqReader): past
+    /// `ReaderOptions::max_skipped` the limit error is returned instead.
+    fn report_skip(
+        &mut self,
+        err: FastqError,
+        what: &str,
+        header: Option<String>,
+    ) -> Result<(), FastqError> {
+        if let Some(limit) = self.opts.max_skipped.filter(|&max| self.skipped >= max) {
+            let source = LimitError::TooManySkipped { limit };
+            return Err(FastqError::LimitExceeded {
+                source,
+                ctx: self.ctx(),
+            });
+        }
+        self.skipped += 1;
+        instrument::skipped();
+        match &mut self.on_skip {
+            Some(f) => f(&err),
+            None => self.meter.skip(what, &err),
+        }
+        if let ErrorPolicy::Collect { limit } = self.opts.error_policy {
+            self.skip_report.push(err, header, limit);
+        }
+        Ok(())
+    }
+
+    /// Up to `n` records; empty at EOF. As
+    /// [`FastqReader::next_batch`](crate::FastqReader::next_batch), an error
+    /// after some records is returned by the next call.
+    pub async fn next_batch(&mut self, n: usize) -> Result<Vec<FastqRecord>, FastqError> {
+        let mut batch = Vec::with_capacity(n);
+        while batch.len() < n {
+            match self.next_record().await {
+                Some(Ok(rec)) => batch.push(rec),
+                Some(Err(err)) if batch.is_empty() => return Err(err),
+                Some(Err(err)) => {
+                    self.held_err = Some(err);
+                    break;
+                }
+                None => break,
+            }
+        }
+        Ok(batch)
+    }
+
+    async fn read_line(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
         buf.clear();
-        let n = self.rdr.read_line(buf).await?;
+        let n = match self.opts.max_record_bytes {
+            None => self.rdr.read_until(b'\n', buf).await?,
+            Some(limit) => {
+                let budget = limit.saturating_sub(self.record_bytes);
+                let n = (&mut self.rdr)
+                    .take(budget as u64 + 1)
+                    .read_until(b'\n', buf)
+                    .await?;
+                if n > budget {
+                    return Err(LimitError::RecordTooLarge { limit }.into_io());
+                }
+                n
+            }
+        };
+        self.record_bytes += n;
         if n > 0 {
             self.line_num += 1;
             self.byte_pos += n as u64;
-            if buf.ends_with('\n') {
+            if buf.ends_with(b"\n") {
                 buf.pop();
             }
-            if buf.ends_with('\r') {
+            if buf.ends_with(b"\r") {
                 buf.pop();
             }
         }
         Ok(n)
     }
 
-    async fn read_one(&mut self) -> Result<Option<FastqRecord>, FastqError> {
-        // header
-        let mut header = if let Some(h) = self.pending_header.take() {
-            h
-        } else {
-            let mut h = String::with_capacity(128);
-            loop {
-                let n = self
-                    .read_line(&mut h)
-                    .await
-                    .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                if n == 0 {
-                    return Ok(None);
-                }
-                if !h.is_empty() {
-                    break;
-                }
-            }
-            h
-        };
+    async fn read_one(&mut self) -> Result<Option<SeqRecord>, FastqError> {
+        let mut line = std::mem::take(&mut self.line);
+        let res = self.read_one_with(&mut line).await;
+        self.line = line;
+        res
+    }
 
-        if !header.starts_with('@') {
-            if self.opts.fastq_only && header.starts_with('>') {
-                return Err(FastqError::fmt_err(
-                    FormatError::FastaHeaderDetected,
-                    self.ctx(),
-                ));
+    async fn read_one_with(&mut self, line: &mut Vec<u8>) -> Result<Option<SeqRecord>, FastqError> {
+        loop {
+            if self.parser.is_between_records() {
+                self.record_bytes = 0;
             }
-            return Err(FastqError::fmt_err(FormatError::MissingHeader, self.ctx()));
-        }
-
-        let mut parts = header[1..].splitn(2, char::is_whitespace);
-        let id = parts.next().unwrap_or("").to_string();
-        let desc = parts.next().map(|s| s.trim().to_string());
-
-        let mut line = String::with_capacity(256);
-
-        match self.opts.line_mode {
-            LineMode::Single => {
-                // seq
-                let n = self
-                    .read_line(&mut line)
-                    .await
-                    .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                if n == 0 {
-                    return Err(FastqError::fmt_err(FormatError::UnexpectedEof, self.ctx()));
-                }
-                if line.is_empty() {
-                    return Err(FastqError::fmt_err(FormatError::EmptySequence, self.ctx()));
-                }
-                let seq = line.as_bytes().to_vec();
-
-                // plus
-                let n = self
-                    .read_line(&mut line)
-                    .await
-                    .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                if n == 0 {
-                    return Err(FastqError::fmt_err(FormatError::UnexpectedEof, self.ctx()));
-                }
-                if !line.starts_with('+') {
-                    return Err(FastqError::fmt_err(FormatError::MissingPlus, self.ctx()));
-                }
-
-                // qual
-                let n = self
-                    .read_line(&mut line)
-                    .await
-                    .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                if n == 0 {
-                    return Err(FastqError::fmt_err(FormatError::UnexpectedEof, self.ctx()));
-                }
-                let qual = line.as_bytes().to_vec();
-
-                if qual.len() != seq.len() {
-                    return Err(FastqError::fmt_err(
-                        FormatError::LengthMismatch {
-                            seq: seq.len(),
-                            qual: qual.len(),
-                        },
-                        self.ctx(),
-                    ));
-                }
-
-                Ok(Some(FastqRecord {
-                    id,
-                    desc,
-                    seq,
-                    qual,
-                }))
+            let n = self
+                .read_line(line)
+                .await
+                .map_err(|e| FastqError::io_err(e, self.ctx()))?;
+            if n == 0 {
+                return self
+                    .parser
+                    .finish_entry()
+                    .map_err(|e| FastqError::fmt_err(e, self.ctx()));
             }
-            LineMode::Multi => {
-                let mut seq = Vec::<u8>::with_capacity(256);
-                loop {
-                    let n = self
-                        .read_line(&mut line)
-                        .await
-                        .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                    if n == 0 {
-                        return Err(FastqError::fmt_err(FormatError::UnexpectedEof, self.ctx()));
-                    }
-                    if line.starts_with('+') {
-                        break;
-                    }
-                    seq.extend_from_slice(line.as_bytes());
-                }
-                if seq.is_empty() {
-                    return Err(FastqError::fmt_err(FormatError::EmptySequence, self.ctx()));
-                }
-
-                let mut qual = Vec::<u8>::with_capacity(seq.len());
-                while qual.len() < seq.len() {
-                    let n = self
-                        .read_line(&mut line)
-                        .await
-                        .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                    if n == 0 {
-                        return Err(FastqError::fmt_err(FormatError::UnexpectedEof, self.ctx()));
-                    }
-                    qual.extend_from_slice(line.as_bytes());
-                }
-
-                if qual.len() != seq.len() {
-                    return Err(FastqError::fmt_err(
-                        FormatError::LengthMismatch {
-                            seq: seq.len(),
-                            qual: qual.len(),
-                        },
-                        self.ctx(),
-                    ));
-                }
-
-                Ok(Some(FastqRecord {
-                    id,
-                    desc,
-                    seq,
-                    qual,
-                }))
+            if let Some(rec) = self
+                .parser
+                .push_entry(line)
+                .map_err(|e| FastqError::fmt_err(e, self.ctx()))?
+            {
+                // a FASTA entry ends on the next header, which is not its own
+                self.record_bytes = 0;
+                return Ok(Some(rec));
             }
         }
     }
 
     async fn resync_to_next_header(&mut self) -> bool {
-        let mut buf = String::with_capacity(256);
+        self.parser.resync();
+        let mut buf = Vec::with_capacity(256);
         loop {
+            self.record_bytes = 0;
             match self.read_line(&mut buf).await {
-                Ok(0) => return false,
+                Ok(0) | Err(_) => return false,
                 Ok(_) => {
-                    if buf.starts_with('@') {
-                        self.pending_header = Some(buf.clone());
+                    // only an '@' line ends the resync, and it always parses as a header
+                    let _ = self.parser.push_line(&buf);
+                    if !self.parser.is_between_records() {
                         return true;
                     }
                 }
-                Err(_) => return false,
             }
         }
     }
 
+    /// Records whose qualities were rebased to Phred+33 under
+    /// `ReaderOptions::rebase_quality`.
+    pub fn rebased_records(&self) -> u64 {
+        self.parser.rebased_records()
+    }
+
+    /// Records dropped by the `min_len`, `max_len` and `max_n_fraction`
+    /// filters.
+    pub fn filtered_records(&self) -> u64 {
+        self.parser.filtered_records()
+    }
+
+    /// Quality encoding detected so far under `rebase_quality`.
+    pub fn detected_quality_encoding(&self) -> Option<QualityEncoding> {
+        self.parser.detected_quality_encoding()
+    }
+
+    /// Line mode in effect; under `LineMode::Auto`, `None` until the first
+    /// records settled it.
+    pub fn line_mode(&self) -> Option<LineMode> {
+        self.parser.line_mode()
+    }
+
+    /// Input format; under `InputFormat::Auto`, `None` until the first
+    /// record was read.
+    pub fn input_format(&self) -> Option<InputFormat> {
+        self.parser.input_format()
+    }
+
+    /// Current position and source label, as attached to errors.
+    pub fn position(&self) -> IoContext {
+        self.ctx()
+    }
+
     #[inline]
     fn ctx(&self) -> IoContext {
-        IoContext {
-            byte_pos: self.byte_pos,
-            line_num: self.line_num,
-        }
+        IoContext::at(
+            self.byte_pos,
+            self.line_num,
+            self.label.clone(),
+            &self.parser,
+            0,
+        )
     }
 }
 
@@ -0,0 +1,133 @@
+#![cfg(all(feature = "async", feature = "gzip"))]
+
+//! Async counterpart of [`FastqWriter`](crate::FastqWriter) on tokio.
+
+use crate::convert::Compression;
+use crate::record::FastqRecord;
+use crate::writer::{WriterOptions, write_fastq};
+
+use async_compression::Level;
+use async_compression::tokio::write::GzipEncoder;
+#[cfg(feature = "zstd")]
+use async_compression::tokio::write::ZstdEncoder;
+use std::path::Path;
+use tokio::fs::File;
+use tokio::io::{self, AsyncWrite, AsyncWriteExt, BufWriter};
+
+enum AsyncSink<W: AsyncWrite + Unpin> {
+    Plain(BufWriter<W>),
+    Gzip(GzipEncoder<BufWriter<W>>),
+    #[cfg(feature = "zstd")]
+    Zstd(ZstdEncoder<BufWriter<W>>),
+}
+
+/// Async FASTQ writer (plain/.gz/.zst). Records are serialized into a
+/// reused buffer and written without blocking the runtime; call
+/// [`finish`](Self::finish) to write the compression trailer.
+///
+/// `Compression::Zstd` needs the `zstd` feature; BGZF (and zstd without
+/// it) is rejected with `ErrorKind::Unsupported`. `WriterOptions::threads`
+/// is ignored.
+pub struct AsyncFastqWriter<W: AsyncWrite + Unpin = File> {
+    sink: AsyncSink<W>,
+    eol: &'static [u8],
+    buf: Vec<u8>,
+    records: u64,
+}
+
+impl AsyncFastqWriter<File> {
+    /// Create (or truncate) `path`; compression follows the extension
+    /// unless set in `opts`.
+    pub async fn from_path<P: AsRef<Path>>(path: P, opts: WriterOptions) -> io::Result<Self> {
+        let path = path.as_ref();
+        let compression = opts
+            .compression
+            .unwrap_or_else(|| Compression::from_path(path));
+        let opts = WriterOptions {
+            compression: Some(compression),
+            ..opts
+        };
+        check_compression(compression)?;
+        Self::from_async_write(File::create(path).await?, opts)
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncFastqWriter<W> {
+    /// Write to any `AsyncWrite` (socket, pipe, `Vec<u8>`, ...); plain
+    /// unless `opts.compression` says otherwise.
+    pub fn from_async_write(out: W, opts: WriterOptions) -> io::Result<Self> {
+        let compression = opts.compression.unwrap_or(Compression::None);
+        check_compression(compression)?;
+        let out = BufWriter::with_capacity(1 << 20, out);
+        let sink = match compression {
+            Compression::Gzip => AsyncSink::Gzip(GzipEncoder::with_quality(
+                out,
+                Level::Precise(opts.level.min(9) as i32),
+            )),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => AsyncSink::Zstd(ZstdEncoder::with_quality(
+                out,
+                Level::Precise(opts.level.min(9) as i32),
+            )),
+            _ => AsyncSink::Plain(out),
+        };
+        Ok(Self {
+            sink,
+            eol: opts.line_ending.as_bytes(),
+            buf: Vec::with_capacity(1024),
+            records: 0,
+        })
+    }
+
+    /// Write one record, including its kept `+` text.
+    pub async fn write_record(&mut self, rec: &FastqRecord) -> io::Result<()> {
+        self.buf.clear();
+        write_fastq(&mut self.buf, rec, self.eol)?;
+        match &mut self.sink {
+            AsyncSink::Plain(w) => w.write_all(&self.buf).await?,
+            AsyncSink::Gzip(w) => w.write_all(&self.buf).await?,
+            #[cfg(feature = "zstd")]
+            AsyncSink::Zstd(w) => w.write_all(&self.buf).await?,
+        }
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Records written so far.
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Flush buffered data, finish compression and return the destination.
+    pub async fn finish(self) -> io::Result<W> {
+        let mut out = match self.sink {
+            AsyncSink::Plain(w) => w,
+            AsyncSink::Gzip(mut enc) => {
+                enc.shutdown().await?;
+                enc.into_inner()
+            }
+            #[cfg(feature = "zstd")]
+            AsyncSink::Zstd(mut enc) => {
+                enc.shutdown().await?;
+                enc.into_inner()
+            }
+        };
+        out.flush().await?;
+        Ok(out.into_inner())
+    }
+}
+
+fn check_compression(compression: Compression) -> io::Result<()> {
+    match compression {
+        Compression::None | Compression::Gzip => Ok(()),
+        Compression::Zstd if cfg!(feature = "zstd") => Ok(()),
+        Compression::Zstd => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "zstd output needs the `zstd` feature",
+        )),
+        Compression::Bgzf => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the async writer supports plain, gzip and zstd output only",
+        )),
+    }
+}
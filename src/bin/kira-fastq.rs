@@ -0,0 +1,253 @@
+//! `kira-fastq`: small inspection/conversion tool over the library API.
+//!
+//! Build with `cargo build --features cli`.
+
+use clap::{Arg, ArgAction, ArgMatches, Command, value_parser};
+use kira_cdh_compat_fastq_reader::{
+    Compression, ConvertOptions, ErrorPolicy, FastqReader, FastqWriter, LineMode, OutputFormat,
+    ReaderOptions, WriterOptions, convert_to_writer,
+};
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+fn cli() -> Command {
+    let input = Arg::new("input")
+        .required(true)
+        .help("FASTQ file (plain or .gz); '-' reads stdin");
+    let multi = Arg::new("multi-line")
+        .long("multi-line")
+        .action(ArgAction::SetTrue)
+        .help("Allow sequence/quality to span several lines");
+    let strict = Arg::new("strict")
+        .long("strict")
+        .action(ArgAction::SetTrue)
+        .help("Stop at the first malformed record instead of skipping it");
+
+    Command::new("kira-fastq")
+        .about("Inspect, validate and convert FASTQ with CD-HIT-compatible parsing")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("inspect")
+                .about("Show input format details and the first records")
+                .arg(input.clone())
+                .arg(multi.clone())
+                .arg(
+                    Arg::new("n")
+                        .short('n')
+                        .value_parser(value_parser!(usize))
+                        .default_value("3"),
+                ),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Count records and bases, summarize lengths and qualities")
+                .arg(input.clone())
+                .arg(multi.clone())
+                .arg(strict.clone()),
+        )
+        .subcommand(
+            Command::new("convert")
+                .about("Rewrite records as FASTQ or FASTA, compressed per the output extension (.gz, .bgz, .zst)")
+                .arg(input.clone())
+                .arg(
+                    Arg::new("output")
+                        .required(true)
+                        .help("Output path; '-' writes stdout"),
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .value_parser(["fastq", "fasta"])
+                        .default_value("fastq"),
+                )
+                .arg(multi.clone())
+                .arg(strict.clone()),
+        )
+        .subcommand(
+            Command::new("head")
+                .about("Print the first N records as FASTQ")
+                .arg(input.clone())
+                .arg(multi.clone())
+                .arg(
+                    Arg::new("n")
+                        .short('n')
+                        .value_parser(value_parser!(usize))
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            Command::new("validate")
+                .about("Parse strictly; exit non-zero on the first malformed record")
+                .arg(input)
+                .arg(multi),
+        )
+}
+
+fn reader_options(m: &ArgMatches, policy: ErrorPolicy) -> ReaderOptions {
+    ReaderOptions {
+        error_policy: policy,
+        strict_gzip: policy == ErrorPolicy::Return,
+        line_mode: if m.get_flag("multi-line") {
+            LineMode::Multi
+        } else {
+            LineMode::Single
+        },
+        // `head`/`convert` pass records through unchanged
+        keep_plus_line: true,
+        ..Default::default()
+    }
+}
+
+fn open(m: &ArgMatches, policy: ErrorPolicy) -> io::Result<FastqReader> {
+    let path = m.get_one::<String>("input").expect("required");
+    FastqReader::from_path(path, reader_options(m, policy)).map_err(io::Error::other)
+}
+
+fn policy(m: &ArgMatches) -> ErrorPolicy {
+    if m.try_get_one::<bool>("strict").ok().flatten() == Some(&true) {
+        ErrorPolicy::Return
+    } else {
+        ErrorPolicy::Skip
+    }
+}
+
+/// Compression of the file at `path` as the reader picks it: by extension,
+/// else by magic bytes.
+fn compression_of(path: &str) -> io::Result<&'static str> {
+    let by_name = match Compression::from_path(Path::new(path)) {
+        Compression::Gzip | Compression::Bgzf => Some("gzip"),
+        Compression::Zstd => Some("zstd"),
+        Compression::None => None,
+    };
+    if let Some(name) = by_name {
+        return Ok(name);
+    }
+    let mut magic = Vec::with_capacity(4);
+    File::open(path)?.take(4).read_to_end(&mut magic)?;
+    Ok(if magic.starts_with(&[0x1F, 0x8B]) {
+        "gzip"
+    } else if magic == [0x28, 0xB5, 0x2F, 0xFD] {
+        "zstd"
+    } else {
+        "none"
+    })
+}
+
+fn inspect(m: &ArgMatches) -> io::Result<()> {
+    let path = m.get_one::<String>("input").expect("required");
+    let n = *m.get_one::<usize>("n").expect("defaulted");
+    println!("input: {path}");
+    if path != "-" {
+        println!("compression: {}", compression_of(path)?);
+    }
+    let mut rdr = open(m, ErrorPolicy::Return)?;
+    for (i, rec) in rdr.by_ref().take(n).enumerate() {
+        match rec {
+            Ok(r) => println!(
+                "#{i}: id={} desc={:?} len={} mean_q={:.1}",
+                r.id(),
+                r.desc().unwrap_or(""),
+                r.len(),
+                r.mean_quality().unwrap_or(0.0)
+            ),
+            Err(e) => {
+                println!("#{i}: error: {e}");
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn stats(m: &ArgMatches) -> io::Result<()> {
+    let rdr = open(m, policy(m))?;
+    let (mut records, mut bases, mut qsum) = (0u64, 0u64, 0f64);
+    let (mut min_len, mut max_len) = (usize::MAX, 0usize);
+    for rec in rdr {
+        let rec = rec.map_err(io::Error::other)?;
+        records += 1;
+        bases += rec.len() as u64;
+        min_len = min_len.min(rec.len());
+        max_len = max_len.max(rec.len());
+        qsum += rec.mean_quality().unwrap_or(0.0) * rec.len() as f64;
+    }
+    println!("records\t{records}");
+    println!("bases\t{bases}");
+    if records > 0 {
+        println!("min_len\t{min_len}");
+        println!("max_len\t{max_len}");
+        println!("mean_len\t{:.2}", bases as f64 / records as f64);
+        println!("mean_q\t{:.2}", qsum / bases.max(1) as f64);
+    }
+    Ok(())
+}
+
+fn convert(m: &ArgMatches) -> io::Result<()> {
+    let input = m.get_one::<String>("input").expect("required");
+    let out = m.get_one::<String>("output").expect("required");
+    let fasta = m.get_one::<String>("to").map(String::as_str) == Some("fasta");
+    let opts = ConvertOptions {
+        reader: reader_options(m, policy(m)),
+        format: if fasta {
+            OutputFormat::Fasta
+        } else {
+            OutputFormat::Fastq
+        },
+        ..Default::default()
+    };
+    if out == "-" {
+        let (_, mut stdout) =
+            convert_to_writer(input, io::stdout().lock(), &opts).map_err(io::Error::other)?;
+        stdout.flush()
+    } else {
+        kira_cdh_compat_fastq_reader::convert(input, out, &opts)
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+}
+
+fn head(m: &ArgMatches) -> io::Result<()> {
+    let n = *m.get_one::<usize>("n").expect("defaulted");
+    let mut w = FastqWriter::from_writer(io::stdout().lock(), WriterOptions::default())?;
+    for rec in open(m, ErrorPolicy::Skip)?.take(n) {
+        w.write_record(&rec.map_err(io::Error::other)?)?;
+    }
+    w.finish()?.flush()
+}
+
+fn validate(m: &ArgMatches) -> io::Result<bool> {
+    let mut records = 0u64;
+    for rec in open(m, ErrorPolicy::Return)? {
+        if let Err(e) = rec {
+            eprintln!("invalid after {records} records: {e}");
+            return Ok(false);
+        }
+        records += 1;
+    }
+    println!("ok: {records} records");
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let matches = cli().get_matches();
+    let res = match matches.subcommand() {
+        Some(("inspect", m)) => inspect(m).map(|_| true),
+        Some(("stats", m)) => stats(m).map(|_| true),
+        Some(("convert", m)) => convert(m).map(|_| true),
+        Some(("head", m)) => head(m).map(|_| true),
+        Some(("validate", m)) => validate(m),
+        _ => unreachable!("subcommand_required"),
+    };
+    match res {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("kira-fastq: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Streaming MD5 / SHA-256 over the bytes a reader consumes (digests from
+//! the `md-5` and `sha2` crates).
+
+use crate::policy::ChecksumAlgorithm;
+
+use md5::Md5;
+use sha2::{Digest as _, Sha256};
+use std::fmt;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+
+/// Finished digest bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    pub algorithm: ChecksumAlgorithm,
+    pub bytes: Vec<u8>,
+}
+
+impl Digest {
+    /// Lowercase hex, as printed by `md5sum` / `sha256sum`.
+    pub fn to_hex(&self) -> String {
+        self.bytes.iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+/// Checksums of a fully consumed input; see
+/// [`FastqReader::checksums`](crate::FastqReader::checksums).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputChecksums {
+    /// Digest of the bytes as stored (compressed for `.gz`).
+    pub raw: Option<Digest>,
+    /// Digest of the decompressed FASTQ text.
+    pub decompressed: Option<Digest>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum Hasher {
+    Md5(Md5),
+    Sha256(Sha256),
+}
+
+impl Hasher {
+    pub(crate) fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Md5 => Self::Md5(Md5::new()),
+            ChecksumAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Md5(h) => h.update(data),
+            Self::Sha256(h) => h.update(data),
+        }
+    }
+
+    pub(crate) fn finish(self) -> Digest {
+        match self {
+            Self::Md5(h) => Digest {
+                algorithm: ChecksumAlgorithm::Md5,
+                bytes: h.finalize().to_vec(),
+            },
+            Self::Sha256(h) => Digest {
+                algorithm: ChecksumAlgorithm::Sha256,
+                bytes: h.finalize().to_vec(),
+            },
+        }
+    }
+}
+
+/// Shared handle to a hasher fed by a [`HashingReader`].
+pub(crate) type HasherHandle = Arc<Mutex<Hasher>>;
+
+/// `Read` adapter hashing everything that passes through it.
+pub(crate) struct HashingReader<R> {
+    inner: R,
+    hasher: HasherHandle,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub(crate) fn new(inner: R, algorithm: ChecksumAlgorithm) -> (Self, HasherHandle) {
+        let hasher = Arc::new(Mutex::new(Hasher::new(algorithm)));
+        (
+            Self {
+                inner,
+                hasher: Arc::clone(&hasher),
+            },
+            hasher,
+        )
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+pub(crate) fn finish_handle(handle: &HasherHandle) -> Digest {
+    handle
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .finish()
+}
+
+/// Hashers attached to a reader's raw and decompressed streams.
+#[derive(Default)]
+pub(crate) struct ChecksumTaps {
+    raw: Option<HasherHandle>,
+    decompressed: Option<HasherHandle>,
+}
+
+impl ChecksumTaps {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.raw.is_none() && self.decompressed.is_none()
+    }
+
+    pub(crate) fn tap_raw(
+        &mut self,
+        r: Box<dyn Read + Send>,
+        algorithm: Option<ChecksumAlgorithm>,
+    ) -> Box<dyn Read + Send> {
+        Self::tap(&mut self.raw, r, algorithm)
+    }
+
+    pub(crate) fn tap_decompressed(
+        &mut self,
+        r: Box<dyn Read + Send>,
+        algorithm: Option<ChecksumAlgorithm>,
+    ) -> Box<dyn Read + Send> {
+        Self::tap(&mut self.decompressed, r, algorithm)
+    }
+
+    fn tap(
+        slot: &mut Option<HasherHandle>,
+        r: Box<dyn Read + Send>,
+        algorithm: Option<ChecksumAlgorithm>,
+    ) -> Box<dyn Read + Send> {
+        match algorithm {
+            None => r,
+            Some(alg) => {
+                let (r, handle) = HashingReader::new(r, alg);
+                *slot = Some(handle);
+                Box::new(r)
+            }
+        }
+    }
+
+    pub(crate) fn result(&self) -> InputChecksums {
+        InputChecksums {
+            raw: self.raw.as_ref().map(finish_handle),
+            decompressed: self.decompressed.as_ref().map(finish_handle),
+        }
+    }
+}
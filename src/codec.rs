@@ -0,0 +1,174 @@
+//! FASTQ as a `tokio_util` codec, for framed pipelines (sockets, HTTP
+//! bodies) read with `FramedRead`.
+
+use crate::core::{FormatError, RecordParser};
+use crate::error::{FastqError, IoContext};
+use crate::instrument;
+use crate::limits::LimitError;
+use crate::policy::ReaderOptions;
+use crate::reader::SkipHandler;
+use crate::record::FastqRecord;
+
+use bytes::BytesMut;
+use tokio_util::codec::Decoder;
+
+/// Decodes [`FastqRecord`]s from a byte stream with the same parser and
+/// [`ReaderOptions`] policies as [`FastqReader`](crate::FastqReader):
+/// error policy and resync, line mode, blank lines, quality handling and
+/// `max_record_bytes`. The input must be plain text; decompression and
+/// throttling belong to the transport.
+pub struct FastqCodec {
+    parser: RecordParser,
+    opts: ReaderOptions,
+    line_num: u64,
+    byte_pos: u64,
+    record_bytes: usize,
+    /// Bytes of `src` already searched for a line end.
+    scanned: usize,
+    skipped: u64,
+    /// Discarding lines after a skipped record until the next header.
+    resyncing: bool,
+    on_skip: Option<SkipHandler>,
+}
+
+impl FastqCodec {
+    pub fn new(opts: ReaderOptions) -> Self {
+        Self {
+            parser: RecordParser::new(&opts),
+            opts,
+            line_num: 0,
+            byte_pos: 0,
+            record_bytes: 0,
+            scanned: 0,
+            skipped: 0,
+            resyncing: false,
+            on_skip: None,
+        }
+    }
+
+    /// Hand every record dropped under `ErrorPolicy::Skip` to `f` instead
+    /// of logging it; see
+    /// [`FastqReader::with_skip_handler`](crate::FastqReader::with_skip_handler).
+    pub fn with_skip_handler<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&FastqError) + Send + 'static,
+    {
+        self.on_skip = Some(Box::new(f));
+        self
+    }
+
+    /// Records dropped under `ErrorPolicy::Skip` so far.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Context at the current position, as carried by errors.
+    pub fn position(&self) -> IoContext {
+        IoContext::at(self.byte_pos, self.line_num, None, &self.parser, 0)
+    }
+
+    /// Feed one line (terminator included); a skippable error under
+    /// `ErrorPolicy::Skip` starts a resync instead.
+    fn line(&mut self, mut line: &[u8]) -> Result<Option<FastqRecord>, FastqError> {
+        self.line_num += 1;
+        self.byte_pos += line.len() as u64;
+        line = line.strip_suffix(b"\n").unwrap_or(line);
+        line = line.strip_suffix(b"\r").unwrap_or(line);
+        if self.resyncing {
+            // only an '@' line ends the resync, and it always parses as a header
+            let _ = self.parser.push_line(line);
+            self.resyncing = self.parser.is_between_records();
+            return Ok(None);
+        }
+        let res = self.parser.push_line(line);
+        self.policy(res)
+    }
+
+    fn check_limit(&self, pending: usize) -> Result<(), FastqError> {
+        match self.opts.max_record_bytes {
+            Some(limit) if self.record_bytes + pending > limit => Err(FastqError::io_err(
+                LimitError::RecordTooLarge { limit }.into_io(),
+                self.position(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn policy<T>(&mut self, res: Result<Option<T>, FormatError>) -> Result<Option<T>, FastqError> {
+        match res {
+            Ok(out) => Ok(out),
+            Err(e) => {
+                let err = FastqError::fmt_err(e, self.position());
+                if self.opts.error_policy.skips() && err.is_skippable() {
+                    if let Some(limit) = self.opts.max_skipped.filter(|&max| self.skipped >= max) {
+                        let source = LimitError::TooManySkipped { limit };
+                        let ctx = self.position();
+                        return Err(FastqError::LimitExceeded { source, ctx });
+                    }
+                    self.skipped += 1;
+                    match &mut self.on_skip {
+                        Some(f) => f(&err),
+                        None => instrument::skip_event("malformed record", &err),
+                    }
+                    self.parser.resync();
+                    self.resyncing = true;
+                    Ok(None)
+                } else {
+                    Err(err)
+                }
+            }
+        }
+    }
+}
+
+impl Default for FastqCodec {
+    fn default() -> Self {
+        Self::new(ReaderOptions::default())
+    }
+}
+
+impl Decoder for FastqCodec {
+    type Item = FastqRecord;
+    type Error = FastqError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<FastqRecord>, FastqError> {
+        loop {
+            if self.parser.is_between_records() {
+                self.record_bytes = 0;
+            }
+            let Some(nl) = src[self.scanned..].iter().position(|&b| b == b'\n') else {
+                self.scanned = src.len();
+                self.check_limit(src.len())?;
+                return Ok(None);
+            };
+            let nl = self.scanned + nl;
+            self.scanned = 0;
+            self.check_limit(nl + 1)?;
+            self.record_bytes += nl + 1;
+            let line = src.split_to(nl + 1);
+            if let Some(rec) = self.line(&line)? {
+                return Ok(Some(rec));
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<FastqRecord>, FastqError> {
+        if let Some(rec) = self.decode(src)? {
+            return Ok(Some(rec));
+        }
+        if !src.is_empty() {
+            // last line without a terminator
+            let line = src.split();
+            self.scanned = 0;
+            self.record_bytes += line.len();
+            if let Some(rec) = self.line(&line)? {
+                return Ok(Some(rec));
+            }
+        }
+        if self.resyncing {
+            return Ok(None);
+        }
+        let res = self.parser.finish().map(|()| None::<FastqRecord>);
+        self.policy(res)
+    }
+}
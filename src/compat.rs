@@ -0,0 +1,293 @@
+//! Compatibility traits for migrating from other FASTQ crates.
+
+use crate::record::{FastqRecord, RefRecord};
+use std::borrow::Cow;
+use std::str::Utf8Error;
+
+/// Record accessors mirroring `seq_io::fastq::Record`, implemented for
+/// [`FastqRecord`] and [`RefRecord`].
+///
+/// Code generic over `R: Record` (`rec.id()?`, `rec.seq()`, `rec.qual()`)
+/// ports by swapping the trait import. On a concrete record the inherent
+/// `id() -> &str` and `desc() -> Option<&str>` shadow the trait's
+/// `Result`-returning ones, so seq_io-style calls there must be fully
+/// qualified: `Record::id(&rec)?`. Unlike seq_io, [`head`](Self::head)
+/// returns a `Cow<[u8]>`, owned when a description has to be joined.
+pub trait Record {
+    /// Record ID (header up to the first whitespace, without `@`).
+    fn id_bytes(&self) -> &[u8];
+    /// Description following the ID, if any.
+    fn desc_bytes(&self) -> Option<&[u8]>;
+    /// Sequence line(s), without line terminators.
+    fn seq(&self) -> &[u8];
+    /// Quality line(s), without line terminators.
+    fn qual(&self) -> &[u8];
+
+    /// Full header without `@`; borrowed unless a description must be joined.
+    fn head(&self) -> Cow<'_, [u8]> {
+        match self.desc_bytes() {
+            None => Cow::Borrowed(self.id_bytes()),
+            Some(desc) => {
+                let id = self.id_bytes();
+                let mut head = Vec::with_capacity(id.len() + 1 + desc.len());
+                head.extend_from_slice(id);
+                head.push(b' ');
+                head.extend_from_slice(desc);
+                Cow::Owned(head)
+            }
+        }
+    }
+
+    fn id(&self) -> Result<&str, Utf8Error> {
+        std::str::from_utf8(self.id_bytes())
+    }
+
+    fn desc(&self) -> Option<Result<&str, Utf8Error>> {
+        self.desc_bytes().map(std::str::from_utf8)
+    }
+}
+
+impl Record for FastqRecord {
+    #[inline]
+    fn id_bytes(&self) -> &[u8] {
+        FastqRecord::id(self).as_bytes()
+    }
+    #[inline]
+    fn desc_bytes(&self) -> Option<&[u8]> {
+        FastqRecord::desc(self).map(str::as_bytes)
+    }
+    #[inline]
+    fn seq(&self) -> &[u8] {
+        FastqRecord::seq(self)
+    }
+    #[inline]
+    fn qual(&self) -> &[u8] {
+        FastqRecord::qual(self)
+    }
+}
+
+impl Record for RefRecord<'_> {
+    #[inline]
+    fn id_bytes(&self) -> &[u8] {
+        self.id.as_bytes()
+    }
+    #[inline]
+    fn desc_bytes(&self) -> Option<&[u8]> {
+        self.desc.map(str::as_bytes)
+    }
+    #[inline]
+    fn seq(&self) -> &[u8] {
+        self.seq
+    }
+    #[inline]
+    fn qual(&self) -> &[u8] {
+        self.qual
+    }
+}
+
+/// Record adapter mirroring `needletail::parser::SequenceRecord`.
+///
+/// Wraps a borrowed record and exposes needletail's accessors plus the
+/// `Sequence`-style helpers (normalization, reverse complement, k-mers), so
+/// tools written against needletail can switch readers without touching
+/// their per-record code.
+#[derive(Debug, Clone, Copy)]
+pub struct SequenceRecord<'a> {
+    rec: RefRecord<'a>,
+}
+
+impl<'a> SequenceRecord<'a> {
+    #[inline]
+    pub fn new(rec: RefRecord<'a>) -> Self {
+        Self { rec }
+    }
+
+    /// Full header without `@` (needletail returns ID and description together).
+    pub fn id(&self) -> Cow<'a, [u8]> {
+        match self.rec.desc {
+            None => Cow::Borrowed(self.rec.id.as_bytes()),
+            Some(desc) => Cow::Owned(format!("{} {}", self.rec.id, desc).into_bytes()),
+        }
+    }
+
+    /// Sequence with line breaks removed; always borrowed since records are
+    /// already joined by the reader.
+    #[inline]
+    pub fn seq(&self) -> Cow<'a, [u8]> {
+        Cow::Borrowed(self.rec.seq)
+    }
+
+    #[inline]
+    pub fn raw_seq(&self) -> &'a [u8] {
+        self.rec.seq
+    }
+
+    /// Quality string; FASTQ records always carry one.
+    #[inline]
+    pub fn qual(&self) -> Option<&'a [u8]> {
+        Some(self.rec.qual)
+    }
+
+    #[inline]
+    pub fn num_bases(&self) -> usize {
+        self.rec.seq.len()
+    }
+
+    /// Normalize the sequence the way needletail does: uppercase, `U` -> `T`,
+    /// `.`/`~` -> `-`, whitespace dropped, anything else -> `N` (IUPAC codes
+    /// are kept when `iupac` is set). Borrowed when nothing changes.
+    pub fn normalize(&self, iupac: bool) -> Cow<'a, [u8]> {
+        normalize(self.rec.seq, iupac)
+    }
+
+    /// Reverse complement of the sequence (IUPAC-aware, case-preserving).
+    pub fn reverse_complement(&self) -> Vec<u8> {
+        self.rec.seq.iter().rev().map(|&b| complement(b)).collect()
+    }
+
+    /// All k-mers of the raw sequence, in order.
+    pub fn kmers(&self, k: u8) -> Kmers<'a> {
+        Kmers {
+            seq: self.rec.seq,
+            k: k as usize,
+            pos: 0,
+        }
+    }
+
+    /// Canonical k-mers: for each position, the lexicographically smaller of
+    /// the k-mer and its reverse complement, taken from `rc` (as returned by
+    /// [`reverse_complement`](Self::reverse_complement)). Windows containing
+    /// non-ACGT bases are skipped. Yields `(position, kmer, from_rc)`.
+    pub fn canonical_kmers<'b>(&self, k: u8, rc: &'b [u8]) -> CanonicalKmers<'b>
+    where
+        'a: 'b,
+    {
+        CanonicalKmers {
+            seq: self.rec.seq,
+            rc,
+            k: k as usize,
+            pos: 0,
+        }
+    }
+}
+
+impl<'a> From<RefRecord<'a>> for SequenceRecord<'a> {
+    #[inline]
+    fn from(rec: RefRecord<'a>) -> Self {
+        Self::new(rec)
+    }
+}
+
+impl<'a> From<&'a FastqRecord> for SequenceRecord<'a> {
+    #[inline]
+    fn from(rec: &'a FastqRecord) -> Self {
+        Self::new(rec.as_ref_record())
+    }
+}
+
+/// Iterator over the k-mers of a sequence.
+#[derive(Debug, Clone)]
+pub struct Kmers<'a> {
+    seq: &'a [u8],
+    k: usize,
+    pos: usize,
+}
+
+impl<'a> Iterator for Kmers<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.k == 0 || self.pos + self.k > self.seq.len() {
+            return None;
+        }
+        let kmer = &self.seq[self.pos..self.pos + self.k];
+        self.pos += 1;
+        Some(kmer)
+    }
+}
+
+/// Iterator over canonical k-mers; see [`SequenceRecord::canonical_kmers`].
+#[derive(Debug, Clone)]
+pub struct CanonicalKmers<'a> {
+    seq: &'a [u8],
+    rc: &'a [u8],
+    k: usize,
+    pos: usize,
+}
+
+impl<'a> Iterator for CanonicalKmers<'a> {
+    type Item = (usize, &'a [u8], bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.seq.len();
+        if self.k == 0 || self.rc.len() != len {
+            return None;
+        }
+        while self.pos + self.k <= len {
+            let pos = self.pos;
+            self.pos += 1;
+            let fwd = &self.seq[pos..pos + self.k];
+            if !fwd.iter().all(|b| matches!(b, b'A' | b'C' | b'G' | b'T')) {
+                continue;
+            }
+            let rev = &self.rc[len - pos - self.k..len - pos];
+            return Some(if rev < fwd {
+                (pos, rev, true)
+            } else {
+                (pos, fwd, false)
+            });
+        }
+        None
+    }
+}
+
+fn normalize(seq: &[u8], iupac: bool) -> Cow<'_, [u8]> {
+    let map = |b: u8| -> Option<u8> {
+        match b {
+            b'A' | b'C' | b'G' | b'T' | b'N' | b'-' => Some(b),
+            b'a' | b'c' | b'g' | b't' | b'n' => Some(b.to_ascii_uppercase()),
+            b'U' | b'u' => Some(b'T'),
+            b'.' | b'~' => Some(b'-'),
+            b' ' | b'\t' | b'\r' | b'\n' => None,
+            b'B' | b'D' | b'H' | b'K' | b'M' | b'R' | b'S' | b'V' | b'W' | b'Y' if iupac => Some(b),
+            b'b' | b'd' | b'h' | b'k' | b'm' | b'r' | b's' | b'v' | b'w' | b'y' if iupac => {
+                Some(b.to_ascii_uppercase())
+            }
+            _ => Some(b'N'),
+        }
+    };
+    if seq.iter().all(|&b| map(b) == Some(b)) {
+        return Cow::Borrowed(seq);
+    }
+    Cow::Owned(seq.iter().filter_map(|&b| map(b)).collect())
+}
+
+fn complement(b: u8) -> u8 {
+    match b {
+        b'A' => b'T',
+        b'T' | b'U' => b'A',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'a' => b't',
+        b't' | b'u' => b'a',
+        b'c' => b'g',
+        b'g' => b'c',
+        b'R' => b'Y',
+        b'Y' => b'R',
+        b'K' => b'M',
+        b'M' => b'K',
+        b'B' => b'V',
+        b'V' => b'B',
+        b'D' => b'H',
+        b'H' => b'D',
+        b'r' => b'y',
+        b'y' => b'r',
+        b'k' => b'm',
+        b'm' => b'k',
+        b'b' => b'v',
+        b'v' => b'b',
+        b'd' => b'h',
+        b'h' => b'd',
+        other => other,
+    }
+}
@@ -0,0 +1,420 @@
+//! One-pass conversion between input and output formats.
+//!
+//! Streams any input [`FastqReader`] accepts into plain, gzip, BGZF or
+//! zstd (feature `zstd`) output as FASTQ or FASTA. Gzip and BGZF output is
+//! cut into independent blocks (gzip members / BGZF blocks) so several
+//! worker threads can compress at once while output order is preserved.
+
+use crate::error::{FastqError, IoContext};
+use crate::policy::ReaderOptions;
+use crate::reader::FastqReader;
+use crate::writer::{write_fasta, write_fastq};
+
+use flate2::Compression as Level;
+use flate2::write::{DeflateEncoder, GzEncoder};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::{Receiver, SendError, Sender, channel};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// Uncompressed bytes per gzip member when compressing in parallel.
+const GZIP_CHUNK: usize = 1 << 20;
+/// Largest BGZF block payload (keeps the compressed block under 64 KiB).
+const BGZF_CHUNK: usize = 0xFF00;
+/// The empty block that terminates a BGZF file.
+const BGZF_EOF: [u8; 28] = [
+    0x1F, 0x8B, 0x08, 0x04, 0, 0, 0, 0, 0, 0xFF, 0x06, 0, 0x42, 0x43, 0x02, 0, 0x1B, 0, 0x03, 0, 0,
+    0, 0, 0, 0, 0, 0, 0,
+];
+
+/// Record layout of the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Fastq,
+    /// Sequence only; qualities are dropped.
+    Fasta,
+}
+
+/// Output compression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    /// Blocked gzip (`samtools`/`htslib` compatible), ends with the EOF block.
+    Bgzf,
+    /// Needs the `zstd` feature; rejected with `ErrorKind::Unsupported`
+    /// without it. Always compressed on the calling thread.
+    Zstd,
+}
+
+impl Compression {
+    /// Guess from the output file name: `.bgz`/`.bgzf` → BGZF, `.gz` → gzip,
+    /// `.zst` → zstd, anything else plain.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("bgz" | "bgzf") => Compression::Bgzf,
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    /// How the input is parsed.
+    pub reader: ReaderOptions,
+    pub format: OutputFormat,
+    /// `None` infers it from the output extension.
+    pub compression: Option<Compression>,
+    /// Compression level, 0–9 (zstd: 0 is its default level, 3).
+    pub level: u32,
+    /// Compression worker threads; 1 compresses on the calling thread.
+    pub threads: usize,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            reader: ReaderOptions::default(),
+            format: OutputFormat::Fastq,
+            compression: None,
+            level: 6,
+            threads: 1,
+        }
+    }
+}
+
+/// Totals reported by [`convert`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConvertStats {
+    pub records: u64,
+    pub bases: u64,
+}
+
+/// Stream `path_in` into `path_out` with the given layout and compression.
+/// Input errors follow `opts.reader.error_policy`; output errors abort.
+pub fn convert<P: AsRef<Path>, Q: AsRef<Path>>(
+    path_in: P,
+    path_out: Q,
+    opts: &ConvertOptions,
+) -> Result<ConvertStats, FastqError> {
+    let path_out = path_out.as_ref();
+    let label = Some(Arc::<str>::from(path_out.display().to_string()));
+    let out_err = |e| output_err(e, label.clone());
+    let compression = opts
+        .compression
+        .unwrap_or_else(|| Compression::from_path(path_out));
+    Sink::<File>::check(compression).map_err(out_err)?;
+    let reader = FastqReader::from_path(path_in, opts.reader.clone())?;
+    let file = File::create(path_out).map_err(out_err)?;
+    let sink =
+        Sink::new(file, compression, opts.level.min(9), opts.threads.max(1)).map_err(out_err)?;
+    let (stats, _) = pump(reader, sink, opts.format, &out_err)?;
+    Ok(stats)
+}
+
+/// [`convert`] into `out` (e.g. stdout) instead of a file, compressed per
+/// `opts.compression` (plain for `None`). Returns `out` once every
+/// trailer is written.
+pub fn convert_to_writer<P: AsRef<Path>, W: Write>(
+    path_in: P,
+    out: W,
+    opts: &ConvertOptions,
+) -> Result<(ConvertStats, W), FastqError> {
+    let out_err = |e| output_err(e, None);
+    let compression = opts.compression.unwrap_or(Compression::None);
+    Sink::<W>::check(compression).map_err(out_err)?;
+    let reader = FastqReader::from_path(path_in, opts.reader.clone())?;
+    let sink =
+        Sink::new(out, compression, opts.level.min(9), opts.threads.max(1)).map_err(out_err)?;
+    pump(reader, sink, opts.format, &out_err)
+}
+
+fn pump<W: Write>(
+    reader: FastqReader,
+    mut sink: Sink<W>,
+    format: OutputFormat,
+    out_err: &dyn Fn(io::Error) -> FastqError,
+) -> Result<(ConvertStats, W), FastqError> {
+    let mut stats = ConvertStats::default();
+    for rec in reader {
+        let rec = rec?;
+        match format {
+            OutputFormat::Fastq => write_fastq(&mut sink, &rec, b"\n"),
+            OutputFormat::Fasta => write_fasta(&mut sink, &rec, b"\n"),
+        }
+        .map_err(out_err)?;
+        stats.records += 1;
+        stats.bases += rec.len() as u64;
+    }
+    let out = sink.finish().map_err(out_err)?;
+    Ok((stats, out))
+}
+
+/// A write error on the output labeled `label`, without an input position.
+fn output_err(e: io::Error, label: Option<Arc<str>>) -> FastqError {
+    FastqError::io_err(
+        e,
+        IoContext {
+            byte_pos: 0,
+            line_num: 0,
+            record_num: 0,
+            record_line: 0,
+            label,
+            virtual_offset: None,
+        },
+    )
+}
+
+/// Output stream: plain, a single gzip or zstd stream, or independently
+/// compressed blocks. Shared with [`FastqWriter`](crate::writer::FastqWriter).
+pub(crate) enum Sink<W: Write> {
+    Plain(BufWriter<W>),
+    Gzip(GzEncoder<BufWriter<W>>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, BufWriter<W>>),
+    Blocks(BlockWriter<W>),
+}
+
+impl<W: Write> Sink<W> {
+    /// Fails for zstd without the `zstd` feature, before anything is
+    /// created.
+    pub(crate) fn check(compression: Compression) -> io::Result<()> {
+        if cfg!(not(feature = "zstd")) && compression == Compression::Zstd {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "zstd output needs the `zstd` feature",
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn new(
+        file: W,
+        compression: Compression,
+        level: u32,
+        threads: usize,
+    ) -> io::Result<Self> {
+        Self::check(compression)?;
+        let out = BufWriter::with_capacity(1 << 20, file);
+        Ok(match compression {
+            Compression::None => Sink::Plain(out),
+            Compression::Gzip if threads == 1 => Sink::Gzip(GzEncoder::new(out, Level::new(level))),
+            Compression::Gzip => {
+                Sink::Blocks(BlockWriter::new(out, BlockKind::Gzip, level, threads))
+            }
+            Compression::Bgzf => {
+                Sink::Blocks(BlockWriter::new(out, BlockKind::Bgzf, level, threads))
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Sink::Zstd(zstd::stream::write::Encoder::new(out, level as i32)?),
+            #[cfg(not(feature = "zstd"))]
+            Compression::Zstd => unreachable!("rejected by `check`"),
+        })
+    }
+
+    /// Flush everything (trailers included) and return the destination.
+    pub(crate) fn finish(self) -> io::Result<W> {
+        let out = match self {
+            Sink::Plain(w) => w,
+            Sink::Gzip(enc) => enc.finish()?,
+            #[cfg(feature = "zstd")]
+            Sink::Zstd(enc) => enc.finish()?,
+            Sink::Blocks(b) => b.finish()?,
+        };
+        out.into_inner().map_err(io::IntoInnerError::into_error)
+    }
+}
+
+impl<W: Write> Write for Sink<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::Plain(w) => w.write(buf),
+            Sink::Gzip(w) => w.write(buf),
+            #[cfg(feature = "zstd")]
+            Sink::Zstd(w) => w.write(buf),
+            Sink::Blocks(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Plain(w) => w.flush(),
+            Sink::Gzip(w) => w.flush(),
+            #[cfg(feature = "zstd")]
+            Sink::Zstd(w) => w.flush(),
+            Sink::Blocks(w) => w.flush(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Gzip,
+    Bgzf,
+}
+
+impl BlockKind {
+    fn chunk(self) -> usize {
+        match self {
+            BlockKind::Gzip => GZIP_CHUNK,
+            BlockKind::Bgzf => BGZF_CHUNK,
+        }
+    }
+
+    fn compress(self, data: &[u8], level: u32) -> io::Result<Vec<u8>> {
+        match self {
+            BlockKind::Gzip => {
+                let mut enc = GzEncoder::new(Vec::with_capacity(data.len() / 3), Level::new(level));
+                enc.write_all(data)?;
+                enc.finish()
+            }
+            BlockKind::Bgzf => bgzf_block(data, level),
+        }
+    }
+}
+
+fn bgzf_block(data: &[u8], level: u32) -> io::Result<Vec<u8>> {
+    let mut enc = DeflateEncoder::new(Vec::with_capacity(data.len() / 3), Level::new(level));
+    enc.write_all(data)?;
+    let mut deflated = enc.finish()?;
+    if deflated.len() + 26 > 0x10000 {
+        // incompressible data: store it
+        let mut enc = DeflateEncoder::new(Vec::new(), Level::none());
+        enc.write_all(data)?;
+        deflated = enc.finish()?;
+    }
+    let block_size = deflated.len() + 26;
+    let mut block = Vec::with_capacity(block_size);
+    block.extend_from_slice(&[
+        0x1F, 0x8B, 0x08, 0x04, 0, 0, 0, 0, 0, 0xFF, 0x06, 0, 0x42, 0x43,
+    ]);
+    block.extend_from_slice(&2u16.to_le_bytes());
+    block.extend_from_slice(&((block_size - 1) as u16).to_le_bytes());
+    block.extend_from_slice(&deflated);
+    block.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+    block.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    Ok(block)
+}
+
+type Job = (Vec<u8>, Sender<io::Result<Vec<u8>>>);
+
+/// Cuts the stream into blocks, compresses them on a worker pool and
+/// writes results in submission order.
+pub(crate) struct BlockWriter<W: Write> {
+    out: BufWriter<W>,
+    kind: BlockKind,
+    level: u32,
+    buf: Vec<u8>,
+    jobs: Sender<Job>,
+    workers: Vec<JoinHandle<()>>,
+    pending: VecDeque<Receiver<io::Result<Vec<u8>>>>,
+    max_pending: usize,
+}
+
+impl<W: Write> BlockWriter<W> {
+    fn new(out: BufWriter<W>, kind: BlockKind, level: u32, threads: usize) -> Self {
+        let (tx, rx) = channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        let workers = (0..threads)
+            .map(|_| {
+                let rx = Arc::clone(&rx);
+                thread::spawn(move || {
+                    loop {
+                        let job = rx.lock().map(|r| r.recv());
+                        let Ok(Ok((data, done))) = job else {
+                            return;
+                        };
+                        let _ = done.send(kind.compress(&data, level));
+                    }
+                })
+            })
+            .collect();
+        Self {
+            out,
+            kind,
+            level,
+            buf: Vec::with_capacity(kind.chunk()),
+            jobs: tx,
+            workers,
+            pending: VecDeque::new(),
+            max_pending: threads * 2,
+        }
+    }
+
+    fn submit(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let data = std::mem::replace(&mut self.buf, Vec::with_capacity(self.kind.chunk()));
+        let (done_tx, done_rx) = channel();
+        if let Err(SendError((data, _))) = self.jobs.send((data, done_tx)) {
+            // every worker died (panicked): keep order and compress here
+            while !self.pending.is_empty() {
+                self.write_oldest()?;
+            }
+            return self.out.write_all(&self.kind.compress(&data, self.level)?);
+        }
+        self.pending.push_back(done_rx);
+        while self.pending.len() > self.max_pending {
+            self.write_oldest()?;
+        }
+        Ok(())
+    }
+
+    fn write_oldest(&mut self) -> io::Result<()> {
+        if let Some(rx) = self.pending.pop_front() {
+            let block = rx
+                .recv()
+                .map_err(|_| io::Error::other("compression worker exited"))??;
+            self.out.write_all(&block)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<BufWriter<W>> {
+        self.submit()?;
+        while !self.pending.is_empty() {
+            self.write_oldest()?;
+        }
+        let Self {
+            mut out,
+            kind,
+            jobs,
+            workers,
+            ..
+        } = self;
+        drop(jobs);
+        for w in workers {
+            let _ = w.join();
+        }
+        if kind == BlockKind::Bgzf {
+            out.write_all(&BGZF_EOF)?;
+        }
+        Ok(out)
+    }
+}
+
+impl<W: Write> Write for BlockWriter<W> {
+    fn write(&mut self, mut data: &[u8]) -> io::Result<usize> {
+        let total = data.len();
+        let chunk = self.kind.chunk();
+        while !data.is_empty() {
+            let take = (chunk - self.buf.len()).min(data.len());
+            self.buf.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.buf.len() == chunk {
+                self.submit()?;
+            }
+        }
+        Ok(total)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
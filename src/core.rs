@@ -0,0 +1,803 @@
+//! The FASTQ record state machine, free of I/O and `std`.
+//!
+//! Everything here builds with `no_std + alloc` (disable default features);
+//! the std readers feed it lines and attach positions to its errors.
+
+use crate::policy::{
+    AllowedBytes, BlankLinePolicy, InputFormat, LineMode, QualityEncoding, ReaderOptions,
+};
+use crate::quality::QualityRebaser;
+use crate::record::{FastaRecord, FastqRecord, RefRecord, SeqRecord};
+use crate::sanitize::IdSanitizer;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::RangeInclusive;
+
+/// Why a record was rejected.
+#[derive(Debug)]
+pub enum FormatError {
+    /// A record does not start with `@`; `first_bytes` are up to four bytes
+    /// of the line found instead (after a BOM).
+    MissingHeader {
+        first_bytes: Box<[u8]>,
+    },
+    FastaHeaderDetected,
+    MissingPlus,
+    UnexpectedEof,
+    LengthMismatch {
+        seq: usize,
+        qual: usize,
+    },
+    EmptySequence,
+    /// A blank line inside a FASTQ record, or anywhere under
+    /// `BlankLinePolicy::Error`.
+    BlankLine,
+    /// A sequence byte outside `ReaderOptions::allowed_seq_bytes`;
+    /// `pos` is its offset in the sequence.
+    InvalidSequenceByte {
+        byte: u8,
+        pos: usize,
+    },
+    /// A quality byte outside the encoding's range, under
+    /// `ReaderOptions::validate_quality`; `pos` is its offset in the
+    /// quality string.
+    InvalidQualityChar {
+        byte: u8,
+        pos: usize,
+    },
+    /// A `+` line repeating something other than the record's header,
+    /// under `ReaderOptions::strict_plus_line`.
+    PlusLineMismatch {
+        plus: Box<str>,
+    },
+    /// R1 and R2 records read as a pair are not mates
+    /// (`paired::PairedFastqReader`).
+    PairDesync(Box<PairDesync>),
+}
+
+/// The two records of a [`FormatError::PairDesync`]. Positions are 1-based
+/// record ordinals and header lines in each file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairDesync {
+    pub r1_id: String,
+    pub r1_record: u64,
+    pub r1_line: u64,
+    pub r2_id: String,
+    pub r2_record: u64,
+    pub r2_line: u64,
+}
+
+impl FormatError {
+    /// [`MissingHeader`](Self::MissingHeader) for a record starting with
+    /// `line`.
+    pub(crate) fn missing_header(line: &[u8]) -> Self {
+        let line = line.strip_prefix("\u{FEFF}".as_bytes()).unwrap_or(line);
+        Self::MissingHeader {
+            first_bytes: line[..line.len().min(4)].into(),
+        }
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader { first_bytes } => {
+                f.write_str("expected header '@' at start of record")?;
+                let Some(chunk) = first_bytes.utf8_chunks().next() else {
+                    return Ok(());
+                };
+                let ch = chunk.valid().chars().next().unwrap_or('\u{FFFD}');
+                write!(f, ", got {ch:?} (U+{:04X}); first bytes:", ch as u32)?;
+                first_bytes.iter().try_for_each(|b| write!(f, " {b:02X}"))
+            }
+            Self::FastaHeaderDetected => {
+                f.write_str("found FASTA header '>' where FASTQ '@' expected")
+            }
+            Self::MissingPlus => f.write_str("missing '+' separator line"),
+            Self::UnexpectedEof => f.write_str("unexpected EOF inside record"),
+            Self::LengthMismatch { seq, qual } => write!(
+                f,
+                "quality length ({qual}) does not match sequence length ({seq})"
+            ),
+            Self::EmptySequence => f.write_str("empty sequence"),
+            Self::BlankLine => f.write_str("blank line"),
+            Self::InvalidSequenceByte { byte, pos } => write!(
+                f,
+                "byte '{}' not allowed in sequence (position {pos})",
+                byte.escape_ascii()
+            ),
+            Self::InvalidQualityChar { byte, pos } => write!(
+                f,
+                "byte '{}' is not a quality character (position {pos})",
+                byte.escape_ascii()
+            ),
+            Self::PlusLineMismatch { plus } => {
+                write!(f, "'+' line {plus:?} does not match the header")
+            }
+            Self::PairDesync(d) => write!(
+                f,
+                "mates out of sync: R1 {:?} (record {}, line {}) vs R2 {:?} (record {}, line {})",
+                d.r1_id, d.r1_record, d.r1_line, d.r2_id, d.r2_record, d.r2_line
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FormatError {}
+
+/// Records inspected by [`LineMode::Auto`] before it settles.
+pub const AUTO_LINE_MODE_RECORDS: u32 = 16;
+
+/// Kind of record completed in the scratch buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Done {
+    Fastq,
+    Fasta,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Header,
+    Seq,
+    Plus,
+    Qual,
+    /// Sequence lines of a FASTA entry (mixed streams only).
+    FastaSeq,
+    Resync,
+}
+
+/// Length and N-content thresholds from [`ReaderOptions`].
+#[derive(Debug, Clone, Copy)]
+struct RecordFilters {
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+    max_n_fraction: Option<f64>,
+}
+
+/// Record state machine fed one line at a time (terminators already removed).
+///
+/// Knows nothing about I/O or positions; readers track those and attach
+/// them to the [`FormatError`]s returned here. After an error the parser
+/// expects a new header; call [`resync`](Self::resync) instead to discard
+/// lines up to the next `@` (the Skip policy).
+///
+/// Quality lines may begin with `@` or `+`, so the sequence length decides:
+/// a `@` line too long to be the rest of the quality (or found where
+/// sequence or `+` was due) fails the current record and starts the next.
+/// A header found while resyncing is only trusted once its record
+/// completes; if the record fails, resync quietly carries on.
+#[derive(Debug, Clone)]
+pub struct RecordParser {
+    /// Mode in effect (`LineMode::Auto` parses as multi-line until settled).
+    multi_line: bool,
+    /// Records `LineMode::Auto` still inspects before settling on single-line.
+    auto_left: Option<u32>,
+    seq_lines: u32,
+    fastq_only: bool,
+    mixed_fasta: bool,
+    /// Configured or detected format; `None` while `InputFormat::Auto` has
+    /// not seen a record.
+    format: Option<InputFormat>,
+    keep_plus_line: bool,
+    strict_plus_line: bool,
+    blank_lines: BlankLinePolicy,
+    allowed_seq_bytes: Option<AllowedBytes>,
+    quality_range: Option<RangeInclusive<u8>>,
+    state: State,
+    id: String,
+    desc: String,
+    has_desc: bool,
+    seq: Vec<u8>,
+    qual: Vec<u8>,
+    /// Quality bytes seen for the current record, stored in `qual` or not.
+    qual_len: usize,
+    keep_quality: bool,
+    plus_line: Option<String>,
+    /// The scratch buffers hold a completed record; cleared on the next call.
+    done: bool,
+    rebaser: Option<QualityRebaser>,
+    sanitizer: Option<IdSanitizer>,
+    /// Header found while resyncing, not yet confirmed by a full record.
+    tentative: bool,
+    /// Header line that ended the previous record (by failing it, or by
+    /// closing a FASTA entry); it opens the next record on the following
+    /// call.
+    pending: Option<Vec<u8>>,
+    /// Header (ID and description) of the record the last error failed.
+    failed_header: Option<String>,
+    filters: RecordFilters,
+    filtered: u64,
+    lines: u64,
+    started: u64,
+    start_line: u64,
+}
+
+impl RecordParser {
+    pub fn new(opts: &ReaderOptions) -> Self {
+        Self {
+            multi_line: opts.line_mode != LineMode::Single,
+            auto_left: (opts.line_mode == LineMode::Auto).then_some(AUTO_LINE_MODE_RECORDS),
+            seq_lines: 0,
+            fastq_only: opts.fastq_only,
+            mixed_fasta: !opts.fastq_only || opts.input_format == InputFormat::Fasta,
+            format: (opts.input_format != InputFormat::Auto).then_some(opts.input_format),
+            keep_plus_line: opts.keep_plus_line,
+            strict_plus_line: opts.strict_plus_line,
+            blank_lines: opts.blank_lines,
+            allowed_seq_bytes: opts.allowed_seq_bytes,
+            quality_range: opts
+                .validate_quality
+                .then(|| opts.quality_encoding.valid_range()),
+            state: State::Header,
+            id: String::new(),
+            desc: String::new(),
+            has_desc: false,
+            seq: Vec::with_capacity(256),
+            qual: Vec::with_capacity(256),
+            qual_len: 0,
+            keep_quality: opts.keep_quality,
+            plus_line: None,
+            done: false,
+            rebaser: opts
+                .rebase_quality
+                .then(|| QualityRebaser::new(opts.quality_encoding)),
+            sanitizer: opts.sanitize_ids.map(IdSanitizer::new),
+            tentative: false,
+            pending: None,
+            failed_header: None,
+            filters: RecordFilters {
+                min_len: opts.min_len,
+                max_len: opts.max_len,
+                max_n_fraction: opts.max_n_fraction,
+            },
+            filtered: 0,
+            lines: 0,
+            started: 0,
+            start_line: 0,
+        }
+    }
+
+    /// Feed the next line. Returns a record once its last line was seen.
+    /// A FASTA entry of a mixed stream (see `ReaderOptions::fastq_only`)
+    /// fails with [`FormatError::FastaHeaderDetected`]; use
+    /// [`push_entry`](Self::push_entry) to receive it.
+    pub fn push_line(&mut self, line: &[u8]) -> Result<Option<FastqRecord>, FormatError> {
+        fastq_only(self.push_entry(line))
+    }
+
+    /// Like [`push_line`](Self::push_line), returning FASTA entries too.
+    /// A FASTA entry ends at the next header line, so it is returned when
+    /// that line is pushed (or at [`finish_entry`](Self::finish_entry)).
+    pub fn push_entry(&mut self, line: &[u8]) -> Result<Option<SeqRecord>, FormatError> {
+        let done = self.push_done(line)?;
+        Ok(done.map(|d| self.take_done(d)))
+    }
+
+    /// Zero-copy variant of [`push_line`](Self::push_line): returns `true`
+    /// when a record completed, which [`last_record`](Self::last_record)
+    /// then borrows until the next call.
+    pub fn push_record(&mut self, line: &[u8]) -> Result<bool, FormatError> {
+        match self.push_done(line)? {
+            Some(Done::Fastq) => Ok(true),
+            Some(Done::Fasta) => {
+                self.clear_done();
+                Err(FormatError::FastaHeaderDetected)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// The record completed by the last [`push_record`](Self::push_record).
+    pub fn last_record(&self) -> Option<RefRecord<'_>> {
+        (self.done && self.pending.is_none()).then(|| RefRecord {
+            id: &self.id,
+            desc: self.has_desc.then_some(self.desc.as_str()),
+            seq: &self.seq,
+            qual: &self.qual,
+            plus_line: self.plus_line.as_deref(),
+            raw: None,
+        })
+    }
+
+    /// Feed a line, leaving a completed record in the scratch buffers.
+    pub(crate) fn push_done(&mut self, line: &[u8]) -> Result<Option<Done>, FormatError> {
+        self.clear_done();
+        if let Some(header) = self.pending.take() {
+            self.begin(&header, self.lines);
+        }
+        self.lines += 1;
+        match self.step(line) {
+            Ok(rec) => {
+                if rec.is_some() {
+                    self.tentative = false;
+                }
+                Ok(rec)
+            }
+            Err(_) if self.tentative => {
+                self.started -= 1;
+                self.tentative = false;
+                self.pending = None;
+                self.reset(State::Resync);
+                if self.is_header(line) {
+                    self.begin(line, self.lines);
+                }
+                Ok(None)
+            }
+            Err(e) => {
+                self.fail();
+                Err(e)
+            }
+        }
+    }
+
+    /// Signal end of input; fails if a record was left incomplete.
+    pub fn finish(&mut self) -> Result<(), FormatError> {
+        fastq_only(self.finish_entry()).map(|_| ())
+    }
+
+    /// Like [`finish`](Self::finish), returning the FASTA entry the input
+    /// ended in, if any.
+    pub fn finish_entry(&mut self) -> Result<Option<SeqRecord>, FormatError> {
+        let done = self.finish_done()?;
+        Ok(done.map(|d| self.take_done(d)))
+    }
+
+    /// End of input; a trailing FASTA entry is left in the scratch buffers.
+    pub(crate) fn finish_done(&mut self) -> Result<Option<Done>, FormatError> {
+        self.clear_done();
+        if let Some(header) = self.pending.take() {
+            self.begin(&header, self.lines);
+        }
+        if self.state == State::FastaSeq && !self.seq.is_empty() {
+            self.tentative = false;
+            return Ok(self.finish_fasta());
+        }
+        if core::mem::take(&mut self.tentative) {
+            // a resync header cut off by EOF was never a record
+            self.started -= 1;
+            self.reset(State::Header);
+            return Ok(None);
+        }
+        if self
+            .auto_left
+            .is_some_and(|left| left < AUTO_LINE_MODE_RECORDS)
+        {
+            // fewer records than the sample, none of them wrapped
+            self.settle(false);
+        }
+        let res = match self.state {
+            State::Header | State::Resync => Ok(None),
+            State::FastaSeq => Err(FormatError::EmptySequence),
+            State::Seq if !self.multi_line => Err(FormatError::EmptySequence),
+            State::Seq | State::Qual => Err(FormatError::UnexpectedEof),
+            State::Plus => Err(FormatError::MissingPlus),
+        };
+        if res.is_err() {
+            self.fail();
+        } else {
+            self.reset(State::Header);
+        }
+        res
+    }
+
+    /// Drop the partial record and skip lines until one starts with `@`.
+    pub fn resync(&mut self) {
+        self.done = false;
+        if core::mem::take(&mut self.tentative) {
+            self.started -= 1;
+        }
+        self.reset(State::Resync);
+    }
+
+    /// True when no record is partially parsed.
+    pub fn is_between_records(&self) -> bool {
+        matches!(self.state, State::Header | State::Resync) && self.pending.is_none()
+    }
+
+    /// Records begun so far (failed ones included, unconfirmed resync
+    /// headers excluded), and how many lines before the last pushed one the
+    /// latest of them started.
+    pub fn record_start(&self) -> (u64, u64) {
+        (self.started, self.lines - self.start_line)
+    }
+
+    /// Quality encoding detected so far (only tracked with `rebase_quality`).
+    pub fn detected_quality_encoding(&self) -> Option<QualityEncoding> {
+        self.rebaser.as_ref().and_then(QualityRebaser::detected)
+    }
+
+    /// Line mode in effect: the configured one, or for [`LineMode::Auto`]
+    /// the one settled on (`None` while still undecided).
+    pub fn line_mode(&self) -> Option<LineMode> {
+        match (self.auto_left, self.multi_line) {
+            (Some(_), _) => None,
+            (None, true) => Some(LineMode::Multi),
+            (None, false) => Some(LineMode::Single),
+        }
+    }
+
+    /// Input format: the configured one, or for [`InputFormat::Auto`] the
+    /// one detected from the first record (`None` before it).
+    pub fn input_format(&self) -> Option<InputFormat> {
+        self.format
+    }
+
+    /// Records dropped by `min_len`, `max_len` or `max_n_fraction`.
+    pub fn filtered_records(&self) -> u64 {
+        self.filtered
+    }
+
+    /// Header (ID and description, without `@`) of the record the last
+    /// error failed; `None` when it failed before a header was read.
+    pub fn failed_header(&self) -> Option<&str> {
+        self.failed_header.as_deref()
+    }
+
+    /// Records whose qualities were rewritten to Phred+33.
+    pub fn rebased_records(&self) -> u64 {
+        self.rebaser.as_ref().map_or(0, QualityRebaser::rebased)
+    }
+
+    /// Move the completed record out of the scratch buffers.
+    pub(crate) fn take_done(&mut self, done: Done) -> SeqRecord {
+        let rec = match done {
+            Done::Fastq => SeqRecord::Fastq(FastqRecord::assemble(
+                &self.id,
+                self.has_desc.then_some(self.desc.as_str()),
+                &self.seq,
+                &self.qual,
+                self.plus_line.as_deref(),
+            )),
+            Done::Fasta => SeqRecord::Fasta(FastaRecord {
+                id: self.id.clone(),
+                desc: self.has_desc.then(|| self.desc.clone()),
+                seq: self.seq.clone(),
+            }),
+        };
+        self.clear_done();
+        rec
+    }
+
+    /// Copy a completed FASTQ record into `rec`, reusing its buffer.
+    #[cfg(feature = "std")]
+    pub(crate) fn take_fastq_into(&mut self, rec: &mut FastqRecord) {
+        rec.set(
+            &self.id,
+            self.has_desc.then_some(self.desc.as_str()),
+            &self.seq,
+            &self.qual,
+            self.plus_line.as_deref(),
+        );
+        self.clear_done();
+    }
+
+    /// Drop a completed record; the scratch buffers keep their capacity.
+    fn clear_done(&mut self) {
+        if core::mem::take(&mut self.done) {
+            self.reset(State::Header);
+        }
+    }
+
+    /// Drop the failed record, remembering its header.
+    fn fail(&mut self) {
+        self.failed_header = (self.state != State::Header).then(|| {
+            let mut header = core::mem::take(&mut self.id);
+            if self.has_desc {
+                header.push(' ');
+                header.push_str(&self.desc);
+            }
+            header
+        });
+        self.reset(State::Header);
+    }
+
+    fn reset(&mut self, state: State) {
+        self.state = state;
+        self.id.clear();
+        self.desc.clear();
+        self.has_desc = false;
+        self.seq.clear();
+        self.qual.clear();
+        self.qual_len = 0;
+        self.plus_line = None;
+    }
+
+    fn step(&mut self, line: &[u8]) -> Result<Option<Done>, FormatError> {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            match self.state {
+                // between records, trailing, or between FASTA sequence lines
+                State::Header | State::FastaSeq => {
+                    return match self.blank_lines {
+                        BlankLinePolicy::Tolerate => Ok(None),
+                        BlankLinePolicy::Warn => {
+                            log::warn!("skipping blank line {}", self.lines);
+                            Ok(None)
+                        }
+                        BlankLinePolicy::Error => Err(FormatError::BlankLine),
+                    };
+                }
+                State::Resync => {}
+                State::Seq if self.seq.is_empty() => return Err(FormatError::EmptySequence),
+                // skipping it would shift the record's remaining lines
+                State::Seq | State::Plus | State::Qual => return Err(FormatError::BlankLine),
+            }
+        }
+        match self.state {
+            State::Header => {
+                let line = line.strip_prefix("\u{FEFF}".as_bytes()).unwrap_or(line);
+                // a lone BOM
+                if line.is_empty() {
+                    return Ok(None);
+                }
+                if self.format.is_none() {
+                    let format = detect_format(line).unwrap_or(InputFormat::Fastq);
+                    self.mixed_fasta |= format == InputFormat::Fasta;
+                    self.format = Some(format);
+                }
+                self.started += 1;
+                self.start_line = self.lines;
+                self.header(line)?;
+                Ok(None)
+            }
+            State::Resync => {
+                if self.is_header(line) {
+                    self.begin(line, self.lines);
+                }
+                Ok(None)
+            }
+            State::FastaSeq => {
+                if self.is_header(line) {
+                    if self.seq.is_empty() {
+                        self.pending = Some(line.to_vec());
+                        return Err(FormatError::EmptySequence);
+                    }
+                    let done = self.finish_fasta();
+                    self.pending = Some(line.to_vec());
+                    return Ok(done);
+                }
+                self.push_seq(line)?;
+                Ok(None)
+            }
+            // no sequence starts with '@': the record lost its remaining lines
+            State::Seq if self.is_header(line) => {
+                self.pending = Some(line.to_vec());
+                if self.seq.is_empty() {
+                    Err(FormatError::EmptySequence)
+                } else {
+                    Err(FormatError::MissingPlus)
+                }
+            }
+            State::Seq if line.starts_with(b"+") => {
+                if self.seq.is_empty() {
+                    return Err(FormatError::EmptySequence);
+                }
+                // only multi-line records get here with a sequence
+                self.plus(line)?;
+                self.state = State::Qual;
+                Ok(None)
+            }
+            State::Seq => {
+                self.push_seq(line)?;
+                if !self.multi_line {
+                    self.state = State::Plus;
+                }
+                Ok(None)
+            }
+            State::Plus => {
+                if !line.starts_with(b"+") {
+                    if self.is_header(line) {
+                        self.pending = Some(line.to_vec());
+                    }
+                    return Err(FormatError::MissingPlus);
+                }
+                self.plus(line)?;
+                self.state = State::Qual;
+                Ok(None)
+            }
+            State::Qual => {
+                let len = self.qual_len + line.len();
+                let fits = if self.multi_line {
+                    len <= self.seq.len()
+                } else {
+                    len == self.seq.len()
+                };
+                if !fits && self.is_header(line) {
+                    // too long to be quality: the next header
+                    self.pending = Some(line.to_vec());
+                    return Err(FormatError::LengthMismatch {
+                        seq: self.seq.len(),
+                        qual: self.qual_len,
+                    });
+                }
+                if let Some(range) = &self.quality_range {
+                    if let Some(i) = line.iter().position(|b| !range.contains(b)) {
+                        return Err(FormatError::InvalidQualityChar {
+                            byte: line[i],
+                            pos: self.qual_len + i,
+                        });
+                    }
+                }
+                self.qual_len += line.len();
+                if self.keep_quality {
+                    self.qual.extend_from_slice(line);
+                }
+                if self.multi_line && self.qual_len < self.seq.len() {
+                    return Ok(None);
+                }
+                if self.qual_len != self.seq.len() {
+                    return Err(FormatError::LengthMismatch {
+                        seq: self.seq.len(),
+                        qual: self.qual_len,
+                    });
+                }
+                if let Some(left) = self.auto_left {
+                    if self.seq_lines > 1 {
+                        self.settle(true);
+                    } else if left == 1 {
+                        self.settle(false);
+                    } else {
+                        self.auto_left = Some(left - 1);
+                    }
+                }
+                if self.drop_filtered() {
+                    return Ok(None);
+                }
+                if let Some(rebaser) = &mut self.rebaser {
+                    rebaser.apply(&mut self.qual);
+                }
+                if let Some(sanitizer) = &mut self.sanitizer {
+                    sanitizer.apply(&mut self.id);
+                }
+                self.state = State::Header;
+                self.done = true;
+                Ok(Some(Done::Fastq))
+            }
+        }
+    }
+
+    fn settle(&mut self, multi_line: bool) {
+        self.auto_left = None;
+        self.multi_line = multi_line;
+    }
+
+    fn push_seq(&mut self, line: &[u8]) -> Result<(), FormatError> {
+        if let Some(allowed) = &self.allowed_seq_bytes {
+            if let Some(i) = line.iter().position(|&b| !allowed.allows(b)) {
+                return Err(FormatError::InvalidSequenceByte {
+                    byte: line[i],
+                    pos: self.seq.len() + i,
+                });
+            }
+        }
+        self.seq.extend_from_slice(line);
+        self.seq_lines += 1;
+        Ok(())
+    }
+
+    /// Open a record at `header` (line `at`), tentatively while resyncing.
+    fn begin(&mut self, header: &[u8], at: u64) {
+        self.tentative = self.state == State::Resync;
+        self.reset(State::Header);
+        self.started += 1;
+        self.start_line = at;
+        // `header` passed `is_header`, so this cannot fail
+        let _ = self.header(header);
+    }
+
+    /// A line that opens a record: `@`, or `>` in mixed streams.
+    fn is_header(&self, line: &[u8]) -> bool {
+        line.starts_with(b"@") || (self.mixed_fasta && line.starts_with(b">"))
+    }
+
+    fn finish_fasta(&mut self) -> Option<Done> {
+        if self.drop_filtered() {
+            return None;
+        }
+        if let Some(sanitizer) = &mut self.sanitizer {
+            sanitizer.apply(&mut self.id);
+        }
+        self.state = State::Header;
+        self.done = true;
+        Some(Done::Fasta)
+    }
+
+    /// Drop a complete record that fails the length or N-content filters,
+    /// counting it; `true` if it was dropped.
+    fn drop_filtered(&mut self) -> bool {
+        let len = self.seq.len();
+        let n_fraction_ok = |max: f64| {
+            let n = self.seq.iter().filter(|&&b| b == b'N' || b == b'n').count();
+            n as f64 <= max * len as f64
+        };
+        let keep = self.filters.min_len.is_none_or(|min| len >= min)
+            && self.filters.max_len.is_none_or(|max| len <= max)
+            && self.filters.max_n_fraction.is_none_or(n_fraction_ok);
+        if !keep {
+            self.filtered += 1;
+            // the record parsed, so a resync header is confirmed
+            self.tentative = false;
+            self.reset(State::Header);
+        }
+        !keep
+    }
+
+    fn plus(&mut self, line: &[u8]) -> Result<(), FormatError> {
+        if line.len() > 1 && (self.keep_plus_line || self.strict_plus_line) {
+            let text = String::from_utf8_lossy(&line[1..]);
+            if self.strict_plus_line && !self.repeats_header(&text) {
+                return Err(FormatError::PlusLineMismatch { plus: text.into() });
+            }
+            if self.keep_plus_line {
+                self.plus_line = Some(text.into_owned());
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a `+` line's text names this record: its ID, optionally
+    /// followed by its description.
+    fn repeats_header(&self, text: &str) -> bool {
+        let mut parts = text.splitn(2, char::is_whitespace);
+        if parts.next() != Some(self.id.as_str()) {
+            return false;
+        }
+        match parts.next().map(str::trim) {
+            None | Some("") => true,
+            Some(desc) => self.has_desc && desc == self.desc,
+        }
+    }
+
+    fn header(&mut self, line: &[u8]) -> Result<(), FormatError> {
+        if !self.is_header(line) {
+            if self.fastq_only && line.starts_with(b">") {
+                return Err(FormatError::FastaHeaderDetected);
+            }
+            return Err(FormatError::missing_header(line));
+        }
+        let header = String::from_utf8_lossy(&line[1..]);
+        let mut parts = header.splitn(2, char::is_whitespace);
+        self.id.clear();
+        self.id.push_str(parts.next().unwrap_or(""));
+        self.desc.clear();
+        self.has_desc = false;
+        if let Some(desc) = parts.next() {
+            self.desc.push_str(desc.trim());
+            self.has_desc = true;
+        }
+        self.seq_lines = 0;
+        self.state = if line.starts_with(b">") {
+            State::FastaSeq
+        } else {
+            State::Seq
+        };
+        Ok(())
+    }
+}
+
+/// Format of data starting with `buf` (decompressed), judged by its first
+/// byte after blank lines and a BOM: `@` is FASTQ, `>` FASTA, anything else
+/// `None`.
+pub fn detect_format(buf: &[u8]) -> Option<InputFormat> {
+    let buf = buf.strip_prefix("\u{FEFF}".as_bytes()).unwrap_or(buf);
+    let buf = buf.trim_ascii_start();
+    match buf.first() {
+        Some(b'@') => Some(InputFormat::Fastq),
+        Some(b'>') => Some(InputFormat::Fasta),
+        _ => None,
+    }
+}
+
+/// Narrow an entry result to FASTQ records for the record-only API.
+fn fastq_only(
+    res: Result<Option<SeqRecord>, FormatError>,
+) -> Result<Option<FastqRecord>, FormatError> {
+    match res? {
+        None => Ok(None),
+        Some(SeqRecord::Fastq(rec)) => Ok(Some(rec)),
+        Some(SeqRecord::Fasta(_)) => Err(FormatError::FastaHeaderDetected),
+    }
+}
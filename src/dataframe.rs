@@ -0,0 +1,62 @@
+//! Polars DataFrames of FASTQ slices (feature `polars`).
+//!
+//! [`read_to_dataframe`] reads up to `limit` records into one
+//! [`DataFrame`], for pulling a slice of a run into a notebook with one call:
+//!
+//! | column | type | |
+//! |---|---|---|
+//! | `id` | `String` | |
+//! | `desc` | `String` | null when absent |
+//! | `len` | `UInt64` | sequence length |
+//! | `mean_q` | `Float64` | mean Phred+33 score; null for an empty record |
+//! | `seq` | `String` | non-UTF-8 bytes as U+FFFD |
+
+use crate::error::FastqError;
+use crate::policy::ReaderOptions;
+use crate::reader::FastqReader;
+
+use polars::prelude::{Column, DataFrame, PolarsError};
+use std::path::Path;
+use thiserror::Error;
+
+/// Failure of [`read_to_dataframe`].
+#[derive(Debug, Error)]
+pub enum DataFrameError {
+    #[error(transparent)]
+    Read(#[from] FastqError),
+    #[error(transparent)]
+    Polars(#[from] PolarsError),
+}
+
+/// The first `limit` records of `path` (all of them for `None`) as a
+/// DataFrame with the columns above. Malformed records follow
+/// `opts.error_policy`.
+pub fn read_to_dataframe<P: AsRef<Path>>(
+    path: P,
+    opts: ReaderOptions,
+    limit: Option<usize>,
+) -> Result<DataFrame, DataFrameError> {
+    let rdr = FastqReader::from_path(path, opts)?;
+    let cap = limit.unwrap_or(0).min(1 << 20);
+    let mut id = Vec::with_capacity(cap);
+    let mut desc = Vec::with_capacity(cap);
+    let mut len = Vec::with_capacity(cap);
+    let mut mean_q = Vec::with_capacity(cap);
+    let mut seq = Vec::with_capacity(cap);
+    for rec in rdr.take(limit.unwrap_or(usize::MAX)) {
+        let rec = rec?;
+        id.push(rec.id().to_owned());
+        desc.push(rec.desc().map(str::to_owned));
+        len.push(rec.len() as u64);
+        mean_q.push(rec.mean_quality());
+        seq.push(String::from_utf8_lossy(rec.seq()).into_owned());
+    }
+    let df = DataFrame::new(vec![
+        Column::new("id".into(), id),
+        Column::new("desc".into(), desc),
+        Column::new("len".into(), len),
+        Column::new("mean_q".into(), mean_q),
+        Column::new("seq".into(), seq),
+    ])?;
+    Ok(df)
+}
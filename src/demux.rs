@@ -0,0 +1,506 @@
+//! Splitting a stream of records into per-sample files by barcode.
+//!
+//! [`Demultiplexer`] looks up each record's barcode (the index sequence at
+//! the end of a CASAVA description, or the first bases of the read) in a
+//! barcode→sample map, tolerating up to `max_mismatches` substitutions,
+//! and writes the record to that sample's [`FastqWriter`]. Records that
+//! match no sample, or several equally well, go to the undetermined
+//! output. [`Demultiplexer::finish`] returns per-sample counts.
+//!
+//! Dual-indexed runs give barcodes as `i7+i5` (`ACGTACGT+TTGGCCAA`), the
+//! form CASAVA writes; mismatches are then allowed per index. With
+//! separate index read files, [`Demultiplexer::run_indexed`] reads I1 (and
+//! I2) in lockstep with R1 (and R2) and takes the barcode from them.
+
+use crate::error::{FastqError, FormatError, IoContext};
+use crate::paired::{desync, mate_id};
+use crate::reader::FastqReader;
+use crate::record::FastqRecord;
+use crate::sample_sheet::SampleSheet;
+use crate::writer::{FastqWriter, WriterOptions};
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use thiserror::Error;
+
+/// Where a record's barcode is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BarcodeSource {
+    /// The last `:` field of the description, as written by CASAVA 1.8+ and
+    /// bcl2fastq (`1:N:0:ACGTACGT`).
+    #[default]
+    Header,
+    /// The first bases of the read, as many as the sample's barcode has;
+    /// with `trim` they are removed from the written record.
+    ReadStart { trim: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DemuxOptions {
+    pub barcode: BarcodeSource,
+    /// Substitutions allowed between a record's barcode and a sample's,
+    /// per index; `1` corrects single-base errors like bcl2fastq's
+    /// default.
+    pub max_mismatches: u32,
+}
+
+/// Two samples were given the same barcode.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("barcode {barcode} is already assigned to sample {sample:?}")]
+pub struct DuplicateBarcode {
+    pub barcode: String,
+    pub sample: String,
+}
+
+/// Records (or pairs) written for one sample.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleCount {
+    pub name: String,
+    pub barcode: String,
+    pub records: u64,
+    /// Of `records`, those whose barcode matched only after correcting
+    /// mismatches.
+    pub corrected: u64,
+}
+
+/// Outcome of a [`Demultiplexer`] run.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DemuxReport {
+    /// In the order the samples were added.
+    pub samples: Vec<SampleCount>,
+    /// Records that matched no sample, or more than one.
+    pub undetermined: u64,
+}
+
+impl DemuxReport {
+    /// Records assigned with an exact barcode match.
+    pub fn exact(&self) -> u64 {
+        self.samples.iter().map(|s| s.records - s.corrected).sum()
+    }
+
+    /// Records assigned after correcting their barcode.
+    pub fn corrected(&self) -> u64 {
+        self.samples.iter().map(|s| s.corrected).sum()
+    }
+}
+
+struct Sample<W: Write> {
+    name: String,
+    barcode: Vec<u8>,
+    out: FastqWriter<W>,
+    out_r2: Option<FastqWriter<W>>,
+    records: u64,
+    corrected: u64,
+}
+
+/// Routes records to per-sample writers; see the module docs.
+pub struct Demultiplexer<W: Write = File> {
+    opts: DemuxOptions,
+    samples: Vec<Sample<W>>,
+    by_barcode: HashMap<Vec<u8>, usize>,
+    undetermined: Option<FastqWriter<W>>,
+    undetermined_r2: Option<FastqWriter<W>>,
+    undetermined_records: u64,
+}
+
+impl Demultiplexer<File> {
+    /// Create `<dir>/<name>.fastq.gz` for every `(name, barcode)` sample
+    /// and `<dir>/Undetermined.fastq.gz`. A name listed twice is an
+    /// `InvalidInput` error rather than a truncated output.
+    pub fn create_in<P, I, S, B>(dir: P, samples: I, opts: DemuxOptions) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = (S, B)>,
+        S: Into<String>,
+        B: AsRef<[u8]>,
+    {
+        let dir = dir.as_ref();
+        let create = |name: &str| {
+            FastqWriter::from_path(
+                dir.join(format!("{name}.fastq.gz")),
+                WriterOptions::default(),
+            )
+        };
+        let mut demux = Self::new(opts, Some(create("Undetermined")?));
+        for (name, barcode) in samples {
+            let name = name.into();
+            if name == "Undetermined" || demux.samples.iter().any(|s| s.name == name) {
+                let msg = format!("sample {name:?} listed twice");
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+            }
+            let out = create(&name)?;
+            demux
+                .add_sample(name, barcode.as_ref(), out)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        }
+        Ok(demux)
+    }
+
+    /// [`create_in`](Self::create_in) for the samples of a sheet, named by
+    /// `Sample_Name`; with `lane`, only that lane's samples. Without one,
+    /// a sample listed once per lane with the same barcode is one sample;
+    /// a name whose barcode differs between lanes needs a `lane`.
+    pub fn from_sample_sheet<P: AsRef<Path>>(
+        dir: P,
+        sheet: &SampleSheet,
+        lane: Option<u32>,
+        opts: DemuxOptions,
+    ) -> io::Result<Self> {
+        let mut samples: Vec<(String, String)> = Vec::new();
+        for s in sheet
+            .samples
+            .iter()
+            .filter(|s| lane.is_none_or(|lane| s.in_lane(lane)))
+        {
+            let barcode = s.barcode();
+            match samples.iter().find(|(name, _)| *name == s.name) {
+                Some((_, seen)) if *seen == barcode => {}
+                Some(_) => {
+                    let msg = format!(
+                        "sample {:?} has a different barcode in another lane; pick a lane",
+                        s.name
+                    );
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+                }
+                None => samples.push((s.name.clone(), barcode)),
+            }
+        }
+        Self::create_in(dir, samples, opts)
+    }
+}
+
+impl<W: Write> Demultiplexer<W> {
+    /// An empty demultiplexer; records matching no sample go to
+    /// `undetermined`, or are dropped when it is `None`.
+    pub fn new(opts: DemuxOptions, undetermined: Option<FastqWriter<W>>) -> Self {
+        Self {
+            opts,
+            samples: Vec::new(),
+            by_barcode: HashMap::new(),
+            undetermined,
+            undetermined_r2: None,
+            undetermined_records: 0,
+        }
+    }
+
+    /// Where undetermined R2 records go in paired runs; dropped otherwise.
+    pub fn with_undetermined_r2(mut self, out: FastqWriter<W>) -> Self {
+        self.undetermined_r2 = Some(out);
+        self
+    }
+
+    /// Route records carrying `barcode` (case-insensitive; `i7+i5` for
+    /// dual indexes) to `out`.
+    pub fn add_sample(
+        &mut self,
+        name: impl Into<String>,
+        barcode: &[u8],
+        out: FastqWriter<W>,
+    ) -> Result<(), DuplicateBarcode> {
+        self.add(name.into(), barcode, out, None)
+    }
+
+    /// [`add_sample`](Self::add_sample) for paired runs: R1 records go to
+    /// `out_r1`, their mates to `out_r2`.
+    pub fn add_paired_sample(
+        &mut self,
+        name: impl Into<String>,
+        barcode: &[u8],
+        out_r1: FastqWriter<W>,
+        out_r2: FastqWriter<W>,
+    ) -> Result<(), DuplicateBarcode> {
+        self.add(name.into(), barcode, out_r1, Some(out_r2))
+    }
+
+    fn add(
+        &mut self,
+        name: String,
+        barcode: &[u8],
+        out: FastqWriter<W>,
+        out_r2: Option<FastqWriter<W>>,
+    ) -> Result<(), DuplicateBarcode> {
+        let barcode = barcode.to_ascii_uppercase();
+        if let Some(&i) = self.by_barcode.get(&barcode) {
+            return Err(DuplicateBarcode {
+                barcode: String::from_utf8_lossy(&barcode).into_owned(),
+                sample: self.samples[i].name.clone(),
+            });
+        }
+        self.by_barcode.insert(barcode.clone(), self.samples.len());
+        self.samples.push(Sample {
+            name,
+            barcode,
+            out,
+            out_r2,
+            records: 0,
+            corrected: 0,
+        });
+        Ok(())
+    }
+
+    /// The sample `rec` belongs to (index in the order added), or `None`
+    /// when it is undetermined.
+    pub fn assign(&self, rec: &FastqRecord) -> Option<usize> {
+        self.find(rec).map(|(i, _)| i)
+    }
+
+    /// Sample and barcode mismatches for `rec`.
+    fn find(&self, rec: &FastqRecord) -> Option<(usize, u32)> {
+        match self.opts.barcode {
+            BarcodeSource::Header => {
+                let desc = rec.desc()?;
+                let field = desc.rsplit(':').next()?.trim();
+                self.lookup(|_| Some(field.as_bytes()))
+            }
+            BarcodeSource::ReadStart { .. } => self.lookup(|len| rec.seq().get(..len)),
+        }
+    }
+
+    /// Best sample for the barcode `observed(len)` yields at each sample's
+    /// barcode length; a tie between samples is no match.
+    fn lookup<'a>(&self, observed: impl Fn(usize) -> Option<&'a [u8]>) -> Option<(usize, u32)> {
+        let mut best: Option<(usize, u32)> = None;
+        let mut tied = false;
+        for (i, sample) in self.samples.iter().enumerate() {
+            let Some(seen) = observed(sample.barcode.len()) else {
+                continue;
+            };
+            let Some(dist) = distance(seen, &sample.barcode, self.opts.max_mismatches) else {
+                continue;
+            };
+            match best {
+                Some((_, d)) if d < dist => {}
+                Some((_, d)) if d == dist => tied = true,
+                _ => {
+                    best = Some((i, dist));
+                    tied = false;
+                }
+            }
+        }
+        best.filter(|_| !tied)
+    }
+
+    /// Write `rec` to its sample's output (or the undetermined one) and
+    /// return the sample it went to.
+    pub fn write_record(&mut self, rec: &FastqRecord) -> io::Result<Option<usize>> {
+        let hit = self.find(rec);
+        let trim = match (self.opts.barcode, hit) {
+            (BarcodeSource::ReadStart { trim: true }, Some((i, _))) => {
+                self.samples[i].barcode.len()
+            }
+            _ => 0,
+        };
+        if trim == 0 {
+            return self.write(hit, rec, None);
+        }
+        // a quality shorter than the barcode (or none) trims to empty
+        let trimmed = FastqRecord::new(
+            rec.id(),
+            rec.desc(),
+            rec.seq().get(trim..).unwrap_or_default(),
+            rec.qual().get(trim..).unwrap_or_default(),
+        )
+        .with_plus_line(rec.plus_line());
+        self.write(hit, &trimmed, None)
+    }
+
+    /// Write a record (and its mate) by an index sequence read elsewhere,
+    /// e.g. the I1 read or `I1+I2`.
+    pub fn write_indexed(
+        &mut self,
+        r1: &FastqRecord,
+        r2: Option<&FastqRecord>,
+        index: &[u8],
+    ) -> io::Result<Option<usize>> {
+        let hit = self.lookup(|_| Some(index));
+        self.write(hit, r1, r2)
+    }
+
+    fn write(
+        &mut self,
+        hit: Option<(usize, u32)>,
+        r1: &FastqRecord,
+        r2: Option<&FastqRecord>,
+    ) -> io::Result<Option<usize>> {
+        let Some((i, dist)) = hit else {
+            self.undetermined_records += 1;
+            if let Some(out) = &mut self.undetermined {
+                out.write_record(r1).map_err(|e| named(e, "Undetermined"))?;
+            }
+            if let (Some(out), Some(r2)) = (&mut self.undetermined_r2, r2) {
+                out.write_record(r2).map_err(|e| named(e, "Undetermined"))?;
+            }
+            return Ok(None);
+        };
+        let sample = &mut self.samples[i];
+        sample
+            .out
+            .write_record(r1)
+            .map_err(|e| named(e, &sample.name))?;
+        if let (Some(out), Some(r2)) = (&mut sample.out_r2, r2) {
+            out.write_record(r2).map_err(|e| named(e, &sample.name))?;
+        }
+        sample.records += 1;
+        sample.corrected += u64::from(dist > 0);
+        Ok(Some(i))
+    }
+
+    /// Demultiplex every record of `records`. Read errors are returned as
+    /// they come; write errors name the sample whose output failed.
+    pub fn run<I>(&mut self, records: I) -> Result<(), FastqError>
+    where
+        I: IntoIterator<Item = Result<FastqRecord, FastqError>>,
+    {
+        for rec in records {
+            let rec = rec?;
+            self.write_record(&rec).map_err(out_err)?;
+        }
+        Ok(())
+    }
+
+    /// Demultiplex R1 (and R2) by the barcodes in separate index read
+    /// files, I1 (and I2) read in lockstep; the barcode is the I1 sequence,
+    /// or `I1+I2`. Records at the same position must share an ID (mate
+    /// markers aside): a mismatch is `FormatError::PairDesync` and a file
+    /// ending early `FormatError::UnexpectedEof`, both returned.
+    pub fn run_indexed(
+        &mut self,
+        mut r1: FastqReader,
+        mut r2: Option<FastqReader>,
+        mut i1: FastqReader,
+        mut i2: Option<FastqReader>,
+    ) -> Result<(), FastqError> {
+        let mut index = Vec::new();
+        while let Some(a) = r1.next() {
+            let a = a?;
+            let pa = FastqReader::position(&r1);
+            let b = next_mate(&a, &pa, r2.as_mut())?;
+            let x = next_mate(&a, &pa, Some(&mut i1))?.unwrap_or_default();
+            let y = next_mate(&a, &pa, i2.as_mut())?;
+            index.clear();
+            index.extend_from_slice(x.seq());
+            if let Some(y) = &y {
+                index.push(b'+');
+                index.extend_from_slice(y.seq());
+            }
+            self.write_indexed(&a, b.as_ref(), &index)
+                .map_err(out_err)?;
+        }
+        // R1 ended: a file with records left means R1 was cut short
+        for rdr in [r2.as_mut(), Some(&mut i1), i2.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(rec) = rdr.next() {
+                rec?;
+                return Err(FastqError::fmt_err(
+                    FormatError::UnexpectedEof,
+                    FastqReader::position(&r1),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts so far.
+    pub fn report(&self) -> DemuxReport {
+        DemuxReport {
+            samples: self
+                .samples
+                .iter()
+                .map(|s| SampleCount {
+                    name: s.name.clone(),
+                    barcode: String::from_utf8_lossy(&s.barcode).into_owned(),
+                    records: s.records,
+                    corrected: s.corrected,
+                })
+                .collect(),
+            undetermined: self.undetermined_records,
+        }
+    }
+
+    /// Finish every output (writing gzip trailers) and return the counts.
+    pub fn finish(self) -> io::Result<DemuxReport> {
+        let report = self.report();
+        for sample in self.samples {
+            sample.out.finish()?;
+            if let Some(out) = sample.out_r2 {
+                out.finish()?;
+            }
+        }
+        for out in [self.undetermined, self.undetermined_r2]
+            .into_iter()
+            .flatten()
+        {
+            out.finish()?;
+        }
+        Ok(report)
+    }
+}
+
+/// Substitutions between `seen` and `want`, compared index by index
+/// (split on `+`); `None` when the layouts differ or an index has more
+/// than `max`.
+fn distance(seen: &[u8], want: &[u8], max: u32) -> Option<u32> {
+    let mut seen_parts = seen.split(|&b| b == b'+');
+    let mut total = 0;
+    for want in want.split(|&b| b == b'+') {
+        let seen = seen_parts.next()?;
+        if seen.len() != want.len() {
+            return None;
+        }
+        let dist = seen
+            .iter()
+            .zip(want)
+            .filter(|(a, b)| !a.eq_ignore_ascii_case(b))
+            .count() as u32;
+        if dist > max {
+            return None;
+        }
+        total += dist;
+    }
+    seen_parts.next().is_none().then_some(total)
+}
+
+/// The record of `rdr` at the position `a` was read at in R1, checked to
+/// be its mate; `None` without a reader.
+fn next_mate(
+    a: &FastqRecord,
+    pa: &IoContext,
+    rdr: Option<&mut FastqReader>,
+) -> Result<Option<FastqRecord>, FastqError> {
+    let Some(rdr) = rdr else {
+        return Ok(None);
+    };
+    let b = match rdr.next() {
+        Some(b) => b?,
+        None => {
+            return Err(FastqError::fmt_err(
+                FormatError::UnexpectedEof,
+                FastqReader::position(rdr),
+            ));
+        }
+    };
+    if mate_id(a.id(), a.desc()).base != mate_id(b.id(), b.desc()).base {
+        return Err(desync(a, pa.clone(), &b, FastqReader::position(rdr)));
+    }
+    Ok(Some(b))
+}
+
+fn named(e: io::Error, output: &str) -> io::Error {
+    io::Error::new(e.kind(), format!("writing {output}: {e}"))
+}
+
+fn out_err(e: io::Error) -> FastqError {
+    let ctx = IoContext {
+        byte_pos: 0,
+        line_num: 0,
+        record_num: 0,
+        record_line: 0,
+        label: None,
+        virtual_offset: None,
+    };
+    FastqError::io_err(e, ctx)
+}
@@ -1,26 +1,56 @@
+pub use crate::core::FormatError;
+#[cfg(feature = "gzip")]
+pub use crate::gzip::GzipIntegrityError;
+pub use crate::limits::LimitError;
+pub use crate::paired::OrphanError;
+
+use crate::core::RecordParser;
+
 use std::io;
+use std::sync::Arc;
 use thiserror::Error;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct IoContext {
     pub byte_pos: u64,
     pub line_num: u64,
+    /// 1-based ordinal of the record being parsed, counting skipped and
+    /// failed records; 0 before the first record starts.
+    pub record_num: u64,
+    /// Line where that record started (its header line); 0 when unknown.
+    pub record_line: u64,
+    /// Input path, or the label given to `from_bufread_labeled`.
+    pub label: Option<Arc<str>>,
+    /// BGZF virtual offset of `byte_pos` (`coffset << 16 | uoffset`);
+    /// `None` unless the input is BGZF.
+    pub virtual_offset: Option<u64>,
 }
 
-#[derive(Debug, Error)]
-pub enum FormatError {
-    #[error("expected header '@' at start of record")]
-    MissingHeader,
-    #[error("found FASTA header '>' where FASTQ '@' expected")]
-    FastaHeaderDetected,
-    #[error("missing '+' separator line")]
-    MissingPlus,
-    #[error("unexpected EOF inside record")]
-    UnexpectedEof,
-    #[error("quality length ({qual}) does not match sequence length ({seq})")]
-    LengthMismatch { seq: usize, qual: usize },
-    #[error("empty sequence")]
-    EmptySequence,
+impl IoContext {
+    /// Context at a reader's position, with the record fields taken from
+    /// `parser`; `records_before` counts records that precede the parser's
+    /// input (a resumed reader).
+    pub(crate) fn at(
+        byte_pos: u64,
+        line_num: u64,
+        label: Option<Arc<str>>,
+        parser: &RecordParser,
+        records_before: u64,
+    ) -> Self {
+        let (started, back) = parser.record_start();
+        Self {
+            byte_pos,
+            line_num,
+            record_num: records_before + started,
+            record_line: if started == 0 {
+                0
+            } else {
+                line_num.saturating_sub(back)
+            },
+            label,
+            virtual_offset: None,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -37,13 +67,177 @@ pub enum FastqError {
         source: FormatError,
         ctx: IoContext,
     },
+    /// Stored gzip checksums disagree with the content
+    /// (only raised with `ReaderOptions::strict_gzip`).
+    #[cfg(feature = "gzip")]
+    #[error("gzip integrity error at {ctx:?}: {source}")]
+    GzipIntegrity {
+        #[source]
+        source: GzipIntegrityError,
+        ctx: IoContext,
+    },
+    /// A configured resource limit was hit (`max_expansion_ratio`,
+    /// `max_record_bytes`, `max_skipped`).
+    #[error("limit exceeded at {ctx:?}: {source}")]
+    LimitExceeded {
+        #[source]
+        source: LimitError,
+        ctx: IoContext,
+    },
+    /// One file of a pair ended before the other, under
+    /// `OrphanPolicy::Error`.
+    #[error("orphaned mate at {ctx:?}: {source}")]
+    Orphan {
+        #[source]
+        source: OrphanError,
+        ctx: IoContext,
+    },
 }
 
 impl FastqError {
     pub(crate) fn io_err(source: io::Error, ctx: IoContext) -> Self {
+        #[cfg(feature = "gzip")]
+        if source
+            .get_ref()
+            .is_some_and(|e| e.is::<GzipIntegrityError>())
+        {
+            let inner = source.into_inner().expect("checked above");
+            let source = *inner
+                .downcast::<GzipIntegrityError>()
+                .expect("checked above");
+            return Self::GzipIntegrity { source, ctx };
+        }
+        if source.get_ref().is_some_and(|e| e.is::<LimitError>()) {
+            let inner = source.into_inner().expect("checked above");
+            let source = *inner.downcast::<LimitError>().expect("checked above");
+            return Self::LimitExceeded { source, ctx };
+        }
         Self::Io { source, ctx }
     }
+
+    /// Whether the Skip policy may drop this error and resync; integrity
+    /// failures, limit violations and undecodable input concern the whole
+    /// input, not one record.
+    pub(crate) fn is_skippable(&self) -> bool {
+        match self {
+            Self::Io { source, .. } if source.kind() == io::ErrorKind::Unsupported => false,
+            #[cfg(feature = "gzip")]
+            Self::GzipIntegrity { .. } => false,
+            Self::LimitExceeded { .. } => false,
+            Self::Orphan { .. } => false,
+            _ => true,
+        }
+    }
+
+    /// Short `snake_case` name of the error kind (`"io"`,
+    /// `"length_mismatch"`, `"limit_exceeded"`, ...), e.g. for metric
+    /// labels; format errors are named after their [`FormatError`] variant.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Format { source, .. } => match source {
+                FormatError::MissingHeader { .. } => "missing_header",
+                FormatError::FastaHeaderDetected => "fasta_header",
+                FormatError::MissingPlus => "missing_plus",
+                FormatError::UnexpectedEof => "unexpected_eof",
+                FormatError::LengthMismatch { .. } => "length_mismatch",
+                FormatError::EmptySequence => "empty_sequence",
+                FormatError::BlankLine => "blank_line",
+                FormatError::InvalidSequenceByte { .. } => "invalid_sequence_byte",
+                FormatError::InvalidQualityChar { .. } => "invalid_quality_char",
+                FormatError::PlusLineMismatch { .. } => "plus_line_mismatch",
+                FormatError::PairDesync(_) => "pair_desync",
+            },
+            #[cfg(feature = "gzip")]
+            Self::GzipIntegrity { .. } => "gzip_integrity",
+            Self::LimitExceeded { .. } => "limit_exceeded",
+            Self::Orphan { .. } => "orphan",
+        }
+    }
+
+    /// Where the error occurred.
+    pub fn context(&self) -> &IoContext {
+        match self {
+            Self::Io { ctx, .. }
+            | Self::Format { ctx, .. }
+            | Self::LimitExceeded { ctx, .. }
+            | Self::Orphan { ctx, .. } => ctx,
+            #[cfg(feature = "gzip")]
+            Self::GzipIntegrity { ctx, .. } => ctx,
+        }
+    }
+
     pub(crate) fn fmt_err(source: FormatError, ctx: IoContext) -> Self {
         Self::Format { source, ctx }
     }
 }
+
+/// A record dropped under `ErrorPolicy::Collect`.
+#[derive(Debug)]
+pub struct SkippedRecord {
+    /// Line the record started on, or the offending line when no header
+    /// was read.
+    pub line_num: u64,
+    /// Its header (ID and description, without `@`/`>`), if one was read.
+    pub header: Option<String>,
+    pub error: FastqError,
+}
+
+/// What `ErrorPolicy::Collect` dropped: the first `limit` records in
+/// detail and a count of all of them.
+#[derive(Debug, Default)]
+pub struct SkipReport {
+    entries: Vec<SkippedRecord>,
+    total: u64,
+}
+
+impl SkipReport {
+    /// Dropped records, in input order, up to the policy's limit.
+    pub fn entries(&self) -> &[SkippedRecord] {
+        &self.entries
+    }
+
+    /// All dropped records, including those past the limit.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Whether records were dropped after the limit was reached.
+    pub fn truncated(&self) -> bool {
+        self.total > self.entries.len() as u64
+    }
+
+    pub(crate) fn push(&mut self, error: FastqError, header: Option<String>, limit: usize) {
+        self.total += 1;
+        if self.entries.len() < limit {
+            let ctx = error.context();
+            let line_num = if ctx.record_line != 0 {
+                ctx.record_line
+            } else {
+                ctx.line_num
+            };
+            self.entries.push(SkippedRecord {
+                line_num,
+                header,
+                error,
+            });
+        }
+    }
+}
+
+impl From<io::Error> for FastqError {
+    /// An I/O error without a position, e.g. from a codec's transport.
+    fn from(e: io::Error) -> Self {
+        FastqError::io_err(
+            e,
+            IoContext {
+                byte_pos: 0,
+                line_num: 0,
+                record_num: 0,
+                record_line: 0,
+                label: None,
+                virtual_offset: None,
+            },
+        )
+    }
+}
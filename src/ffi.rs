@@ -0,0 +1,241 @@
+//! C ABI for CD-HIT and other C/C++ callers (feature `ffi`).
+//!
+//! The matching header lives in `include/kira_fastq.h`. A reader handle owns
+//! the current record; pointers written by [`fq_next`] stay valid until the
+//! next `fq_next` or `fq_close` call on the same handle.
+
+use crate::error::{FastqError, FormatError};
+use crate::policy::{ErrorPolicy, LineMode, ReaderOptions};
+use crate::reader::FastqReader;
+use crate::record::FastqRecord;
+
+use std::cell::Cell;
+use std::ffi::{CStr, c_char, c_int};
+use std::ptr;
+
+pub const FQ_OK: c_int = 0;
+pub const FQ_ERR_IO: c_int = 1;
+pub const FQ_ERR_MISSING_HEADER: c_int = 2;
+pub const FQ_ERR_FASTA_HEADER: c_int = 3;
+pub const FQ_ERR_MISSING_PLUS: c_int = 4;
+pub const FQ_ERR_UNEXPECTED_EOF: c_int = 5;
+pub const FQ_ERR_LENGTH_MISMATCH: c_int = 6;
+pub const FQ_ERR_EMPTY_SEQUENCE: c_int = 7;
+pub const FQ_ERR_INVALID_ARG: c_int = 8;
+pub const FQ_ERR_GZIP_INTEGRITY: c_int = 9;
+pub const FQ_ERR_LIMIT_EXCEEDED: c_int = 10;
+pub const FQ_ERR_BLANK_LINE: c_int = 11;
+pub const FQ_ERR_INVALID_SEQUENCE: c_int = 12;
+pub const FQ_ERR_PAIR_DESYNC: c_int = 13;
+pub const FQ_ERR_ORPHAN: c_int = 14;
+pub const FQ_ERR_INVALID_QUALITY: c_int = 15;
+pub const FQ_ERR_PLUS_MISMATCH: c_int = 16;
+
+thread_local! {
+    static LAST_OPEN_ERROR: Cell<c_int> = const { Cell::new(FQ_OK) };
+}
+
+/// Opaque reader handle.
+pub struct FqReader {
+    inner: FastqReader,
+    current: Option<FastqRecord>,
+    error: c_int,
+}
+
+/// Record view filled by [`fq_next`]. `desc` is NULL when the header has no
+/// description; strings are not NUL-terminated.
+#[repr(C)]
+pub struct FqRecord {
+    pub id: *const u8,
+    pub id_len: usize,
+    pub desc: *const u8,
+    pub desc_len: usize,
+    pub seq: *const u8,
+    pub seq_len: usize,
+    pub qual: *const u8,
+    pub qual_len: usize,
+}
+
+fn error_code(err: &FastqError) -> c_int {
+    match err {
+        FastqError::Io { .. } => FQ_ERR_IO,
+        FastqError::Format { source, .. } => match source {
+            FormatError::MissingHeader { .. } => FQ_ERR_MISSING_HEADER,
+            FormatError::FastaHeaderDetected => FQ_ERR_FASTA_HEADER,
+            FormatError::MissingPlus => FQ_ERR_MISSING_PLUS,
+            FormatError::UnexpectedEof => FQ_ERR_UNEXPECTED_EOF,
+            FormatError::LengthMismatch { .. } => FQ_ERR_LENGTH_MISMATCH,
+            FormatError::EmptySequence => FQ_ERR_EMPTY_SEQUENCE,
+            FormatError::BlankLine => FQ_ERR_BLANK_LINE,
+            FormatError::InvalidSequenceByte { .. } => FQ_ERR_INVALID_SEQUENCE,
+            FormatError::InvalidQualityChar { .. } => FQ_ERR_INVALID_QUALITY,
+            FormatError::PlusLineMismatch { .. } => FQ_ERR_PLUS_MISMATCH,
+            FormatError::PairDesync(_) => FQ_ERR_PAIR_DESYNC,
+        },
+        #[cfg(feature = "gzip")]
+        FastqError::GzipIntegrity { .. } => FQ_ERR_GZIP_INTEGRITY,
+        FastqError::LimitExceeded { .. } => FQ_ERR_LIMIT_EXCEEDED,
+        FastqError::Orphan { .. } => FQ_ERR_ORPHAN,
+    }
+}
+
+/// Open a FASTQ file (plain or `.gz`).
+///
+/// `strict` selects `ErrorPolicy::Return` instead of the CD-HIT-like skip
+/// policy; `multi_line` selects `LineMode::Multi`. Returns NULL on failure;
+/// the reason is available from `fq_error_code(NULL)` on the same thread.
+///
+/// # Safety
+/// `path` must be NULL or point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fq_open(
+    path: *const c_char,
+    strict: c_int,
+    multi_line: c_int,
+) -> *mut FqReader {
+    if path.is_null() {
+        LAST_OPEN_ERROR.with(|e| e.set(FQ_ERR_INVALID_ARG));
+        return ptr::null_mut();
+    }
+    // SAFETY: caller guarantees a NUL-terminated string.
+    let path = unsafe { CStr::from_ptr(path) };
+    let Ok(path) = path.to_str() else {
+        LAST_OPEN_ERROR.with(|e| e.set(FQ_ERR_INVALID_ARG));
+        return ptr::null_mut();
+    };
+    let opts = ReaderOptions {
+        error_policy: if strict != 0 {
+            ErrorPolicy::Return
+        } else {
+            ErrorPolicy::Skip
+        },
+        line_mode: if multi_line != 0 {
+            LineMode::Multi
+        } else {
+            LineMode::Single
+        },
+        ..Default::default()
+    };
+    match FastqReader::from_path(path, opts) {
+        Ok(inner) => {
+            LAST_OPEN_ERROR.with(|e| e.set(FQ_OK));
+            Box::into_raw(Box::new(FqReader {
+                inner,
+                current: None,
+                error: FQ_OK,
+            }))
+        }
+        Err(err) => {
+            LAST_OPEN_ERROR.with(|e| e.set(error_code(&err)));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Advance to the next record and describe it in `out`.
+///
+/// Returns 1 when a record was written, 0 at EOF and -1 on error (see
+/// [`fq_error_code`]).
+///
+/// # Safety
+/// `reader` must come from [`fq_open`] and not be closed; `out` must point to
+/// writable memory for one `FqRecord`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fq_next(reader: *mut FqReader, out: *mut FqRecord) -> c_int {
+    // SAFETY: caller guarantees a live handle.
+    let Some(r) = (unsafe { reader.as_mut() }) else {
+        return -1;
+    };
+    if out.is_null() {
+        r.error = FQ_ERR_INVALID_ARG;
+        return -1;
+    }
+    match r.inner.next() {
+        None => {
+            r.current = None;
+            r.error = FQ_OK;
+            0
+        }
+        Some(Err(err)) => {
+            r.current = None;
+            r.error = error_code(&err);
+            -1
+        }
+        Some(Ok(rec)) => {
+            r.error = FQ_OK;
+            let rec = r.current.insert(rec);
+            let (desc, desc_len) = match rec.desc() {
+                Some(d) => (d.as_ptr(), d.len()),
+                None => (ptr::null(), 0),
+            };
+            // SAFETY: `out` is non-null and writable per the contract above.
+            unsafe {
+                out.write(FqRecord {
+                    id: rec.id().as_ptr(),
+                    id_len: rec.id().len(),
+                    desc,
+                    desc_len,
+                    seq: rec.seq().as_ptr(),
+                    seq_len: rec.seq().len(),
+                    qual: rec.qual().as_ptr(),
+                    qual_len: rec.qual().len(),
+                });
+            }
+            1
+        }
+    }
+}
+
+/// Error code of the last call on `reader`, or of the last failed
+/// [`fq_open`] on this thread when `reader` is NULL.
+///
+/// # Safety
+/// `reader` must be NULL or a live handle from [`fq_open`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fq_error_code(reader: *const FqReader) -> c_int {
+    // SAFETY: caller guarantees NULL or a live handle.
+    match unsafe { reader.as_ref() } {
+        Some(r) => r.error,
+        None => LAST_OPEN_ERROR.with(Cell::get),
+    }
+}
+
+/// Static, NUL-terminated description of an error code, for messages
+/// like CD-HIT's `fprintf(stderr, ...)`. Unknown codes give
+/// `"unknown error"`.
+#[unsafe(no_mangle)]
+pub extern "C" fn fq_strerror(code: c_int) -> *const c_char {
+    let msg: &'static CStr = match code {
+        FQ_OK => c"no error",
+        FQ_ERR_IO => c"I/O error",
+        FQ_ERR_MISSING_HEADER => c"expected header '@' at start of record",
+        FQ_ERR_FASTA_HEADER => c"found FASTA header '>' where FASTQ '@' expected",
+        FQ_ERR_MISSING_PLUS => c"missing '+' separator line",
+        FQ_ERR_UNEXPECTED_EOF => c"unexpected EOF inside record",
+        FQ_ERR_LENGTH_MISMATCH => c"quality length does not match sequence length",
+        FQ_ERR_EMPTY_SEQUENCE => c"empty sequence",
+        FQ_ERR_INVALID_ARG => c"invalid argument",
+        FQ_ERR_GZIP_INTEGRITY => c"gzip CRC or size mismatch",
+        FQ_ERR_LIMIT_EXCEEDED => c"configured limit exceeded",
+        FQ_ERR_BLANK_LINE => c"blank line",
+        FQ_ERR_INVALID_SEQUENCE => c"byte not allowed in sequence",
+        FQ_ERR_PAIR_DESYNC => c"mates out of sync",
+        FQ_ERR_ORPHAN => c"one mate file ended before the other",
+        FQ_ERR_INVALID_QUALITY => c"byte is not a quality character",
+        FQ_ERR_PLUS_MISMATCH => c"'+' line does not match the header",
+        _ => c"unknown error",
+    };
+    msg.as_ptr()
+}
+
+/// Release a reader handle. NULL is ignored.
+///
+/// # Safety
+/// `reader` must be NULL or a handle from [`fq_open`] that is not used again.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fq_close(reader: *mut FqReader) {
+    if !reader.is_null() {
+        // SAFETY: handle was created by `Box::into_raw` in `fq_open`.
+        drop(unsafe { Box::from_raw(reader) });
+    }
+}
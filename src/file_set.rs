@@ -0,0 +1,202 @@
+//! Many small FASTQ files read as one tagged stream.
+//!
+//! Shaped for demultiplexed runs (one small FASTQ per sample): at most
+//! `max_open` files are open at once, optionally read by several worker
+//! threads, and every record carries the file it came from.
+
+use crate::error::FastqError;
+use crate::policy::ReaderOptions;
+use crate::reader::FastqReader;
+use crate::record::FastqRecord;
+
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::thread;
+
+/// Records buffered between worker threads and the consumer.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// A record together with the file it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaggedRecord {
+    /// Index into [`FastqFileSet::paths`].
+    pub file_index: usize,
+    pub path: Arc<Path>,
+    pub record: FastqRecord,
+}
+
+/// A set of FASTQ files read as one stream.
+#[derive(Debug, Clone)]
+pub struct FastqFileSet {
+    paths: Vec<Arc<Path>>,
+    opts: ReaderOptions,
+    max_open: usize,
+    workers: usize,
+}
+
+impl FastqFileSet {
+    pub fn new<I, P>(paths: I, opts: ReaderOptions) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        Self {
+            paths: paths.into_iter().map(|p| Arc::from(p.as_ref())).collect(),
+            opts,
+            max_open: 64,
+            workers: 1,
+        }
+    }
+
+    /// Every `.fastq`, `.fq`, `.fastq.gz` and `.fq.gz` directly inside
+    /// `dir`, sorted by name.
+    pub fn from_dir<P: AsRef<Path>>(dir: P, opts: ReaderOptions) -> io::Result<Self> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            let name = name.strip_suffix(".gz").unwrap_or(name);
+            if path.is_file() && (name.ends_with(".fastq") || name.ends_with(".fq")) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(Self::new(paths, opts))
+    }
+
+    /// Upper bound on simultaneously open files (default 64).
+    pub fn max_open(mut self, n: usize) -> Self {
+        self.max_open = n.max(1);
+        self
+    }
+
+    /// Read with `n` worker threads (default 1: sequential, in path order).
+    /// Records of one file stay in order; files interleave. The number of
+    /// workers never exceeds `max_open`.
+    pub fn parallel(mut self, n: usize) -> Self {
+        self.workers = n.max(1);
+        self
+    }
+
+    pub fn paths(&self) -> &[Arc<Path>] {
+        &self.paths
+    }
+
+    /// Start reading.
+    pub fn records(&self) -> FileSetRecords {
+        let workers = self.workers.min(self.max_open).min(self.paths.len());
+        if workers <= 1 {
+            return FileSetRecords {
+                inner: Inner::Sequential {
+                    paths: self.paths.clone(),
+                    opts: Box::new(self.opts.clone()),
+                    next: 0,
+                    current: None,
+                },
+            };
+        }
+        let (tx, rx) = sync_channel(CHANNEL_CAPACITY);
+        let next = Arc::new(AtomicUsize::new(0));
+        let paths = Arc::new(self.paths.clone());
+        for _ in 0..workers {
+            let (tx, next, paths, opts) = (
+                tx.clone(),
+                Arc::clone(&next),
+                Arc::clone(&paths),
+                self.opts.clone(),
+            );
+            thread::spawn(move || worker(&paths, &opts, &next, &tx));
+        }
+        FileSetRecords {
+            inner: Inner::Parallel { rx },
+        }
+    }
+}
+
+type Item = Result<TaggedRecord, FastqError>;
+
+fn worker(paths: &[Arc<Path>], opts: &ReaderOptions, next: &AtomicUsize, tx: &SyncSender<Item>) {
+    loop {
+        let file_index = next.fetch_add(1, Ordering::Relaxed);
+        let Some(path) = paths.get(file_index) else {
+            return;
+        };
+        let rdr = match FastqReader::from_path(path, opts.clone()) {
+            Ok(rdr) => rdr,
+            Err(e) => {
+                if tx.send(Err(e)).is_err() {
+                    return;
+                }
+                continue;
+            }
+        };
+        for res in rdr {
+            let item = res.map(|record| TaggedRecord {
+                file_index,
+                path: Arc::clone(path),
+                record,
+            });
+            // consumer gone
+            if tx.send(item).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Iterator returned by [`FastqFileSet::records`]. Errors carry the file
+/// path in their `IoContext::label`.
+pub struct FileSetRecords {
+    inner: Inner,
+}
+
+enum Inner {
+    Sequential {
+        paths: Vec<Arc<Path>>,
+        opts: Box<ReaderOptions>,
+        next: usize,
+        current: Option<(usize, Box<FastqReader>)>,
+    },
+    Parallel {
+        rx: Receiver<Item>,
+    },
+}
+
+impl Iterator for FileSetRecords {
+    type Item = Item;
+
+    fn next(&mut self) -> Option<Item> {
+        match &mut self.inner {
+            Inner::Parallel { rx } => rx.recv().ok(),
+            Inner::Sequential {
+                paths,
+                opts,
+                next,
+                current,
+            } => loop {
+                if let Some((file_index, rdr)) = current {
+                    match rdr.next() {
+                        Some(res) => {
+                            let file_index = *file_index;
+                            return Some(res.map(|record| TaggedRecord {
+                                file_index,
+                                path: Arc::clone(&paths[file_index]),
+                                record,
+                            }));
+                        }
+                        None => *current = None,
+                    }
+                }
+                let path = paths.get(*next)?;
+                *next += 1;
+                match FastqReader::from_path(path, (**opts).clone()) {
+                    Ok(rdr) => *current = Some((*next - 1, Box::new(rdr))),
+                    Err(e) => return Some(Err(e)),
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,481 @@
+//! Member-by-member gzip decoding with explicit integrity checks.
+//!
+//! Used instead of `flate2::MultiGzDecoder` when
+//! `ReaderOptions::strict_gzip` is set: every member's CRC32 and ISIZE
+//! trailer (and header CRC16, when present) is verified here and a mismatch
+//! surfaces as [`FastqError::GzipIntegrity`](crate::FastqError::GzipIntegrity).
+//! With `ReaderOptions::track_gzip_members` it also reports where each
+//! member ends, which [`FastqReader`](crate::FastqReader) turns into
+//! [`GzipMember`] statistics. BGZF input (bgzip, htslib) is always decoded
+//! here, so the reader can map positions to virtual offsets.
+
+use flate2::{Decompress, FlushDecompress, Status};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+/// A gzip member whose stored checksums disagree with its content.
+/// `member` is 0-based.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GzipIntegrityError {
+    #[error("gzip member {member}: CRC32 mismatch (stored {expected:08x}, computed {actual:08x})")]
+    Crc {
+        member: u64,
+        expected: u32,
+        actual: u32,
+    },
+    #[error("gzip member {member}: ISIZE mismatch (stored {expected}, computed {actual})")]
+    Size {
+        member: u64,
+        expected: u32,
+        actual: u32,
+    },
+    #[error(
+        "gzip member {member}: header CRC16 mismatch (stored {expected:04x}, computed {actual:04x})"
+    )]
+    HeaderCrc {
+        member: u64,
+        expected: u16,
+        actual: u16,
+    },
+}
+
+/// One gzip member of a concatenated input, as reported by
+/// [`FastqReader::gzip_members`](crate::FastqReader::gzip_members).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GzipMember {
+    /// 0-based position in the file.
+    pub index: u64,
+    /// Compressed size, header and trailer included.
+    pub compressed_bytes: u64,
+    /// Offset of the member's first byte in the decompressed stream.
+    pub decompressed_offset: u64,
+    pub decompressed_bytes: u64,
+    /// Records parsed from the member (attributed by where they end).
+    pub records: u64,
+}
+
+/// A finished member as seen by the decoder: compressed size and the
+/// decompressed offset just past its end.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MemberEnd {
+    pub compressed_bytes: u64,
+    pub decompressed_end: u64,
+}
+
+/// Member ends queued by the decoder for the reader, which trails behind
+/// its read-ahead.
+pub(crate) type MemberEnds = Arc<Mutex<VecDeque<MemberEnd>>>;
+
+type MemberCallback = Box<dyn FnMut(&GzipMember) + Send>;
+
+/// Reader-side bookkeeping: attributes records to members as the parser
+/// passes the ends queued by the decoder.
+pub(crate) struct MemberTracker {
+    ends: MemberEnds,
+    done: Vec<GzipMember>,
+    /// Whether finished members are kept and reported.
+    keep: bool,
+    bgzf: bool,
+    index: u64,
+    /// Compressed and decompressed offsets of the current member.
+    cstart: u64,
+    start: u64,
+    /// Decompressed offset where the decoder started (a resumed reader).
+    base: u64,
+    records: u64,
+    on_member: Option<MemberCallback>,
+}
+
+impl MemberTracker {
+    pub(crate) fn new(ends: MemberEnds, keep: bool, bgzf: bool) -> Self {
+        Self {
+            ends,
+            done: Vec::new(),
+            keep,
+            bgzf,
+            index: 0,
+            cstart: 0,
+            start: 0,
+            base: 0,
+            records: 0,
+            on_member: None,
+        }
+    }
+
+    pub(crate) fn is_bgzf(&self) -> bool {
+        self.bgzf
+    }
+
+    /// The decoder starts at compressed offset `cstart`, which holds
+    /// decompressed offset `start`.
+    pub(crate) fn rebase(&mut self, cstart: u64, start: u64) {
+        self.cstart = cstart;
+        self.start = start;
+        self.base = start;
+    }
+
+    pub(crate) fn members(&self) -> &[GzipMember] {
+        &self.done
+    }
+
+    pub(crate) fn set_callback(&mut self, f: MemberCallback) {
+        self.on_member = Some(f);
+    }
+
+    /// A record ending just before decompressed offset `end` was parsed.
+    pub(crate) fn record(&mut self, end: u64) {
+        self.pass(end - 1);
+        self.records += 1;
+    }
+
+    /// The parser reached EOF: every queued member is complete.
+    pub(crate) fn finish(&mut self) {
+        self.pass(u64::MAX);
+    }
+
+    /// Close every member ending at or before offset `upto`.
+    fn pass(&mut self, upto: u64) {
+        loop {
+            let end = {
+                let mut ends = self.ends.lock().unwrap_or_else(|e| e.into_inner());
+                match ends.front() {
+                    Some(e) if self.base + e.decompressed_end <= upto => ends.pop_front(),
+                    _ => None,
+                }
+            };
+            let Some(end) = end else { return };
+            let member = GzipMember {
+                index: self.index,
+                compressed_bytes: end.compressed_bytes,
+                decompressed_offset: self.start,
+                decompressed_bytes: self.base + end.decompressed_end - self.start,
+                records: std::mem::take(&mut self.records),
+            };
+            self.index += 1;
+            self.cstart += end.compressed_bytes;
+            self.start = self.base + end.decompressed_end;
+            if self.keep {
+                if let Some(f) = self.on_member.as_mut() {
+                    f(&member);
+                }
+                self.done.push(member);
+            }
+        }
+    }
+
+    /// BGZF virtual offset of decompressed offset `pos`, which must not be
+    /// behind the last record passed; `None` for plain gzip.
+    pub(crate) fn virtual_offset(&self, pos: u64) -> Option<u64> {
+        if !self.bgzf {
+            return None;
+        }
+        let (mut cstart, mut start) = (self.cstart, self.start);
+        for end in self.ends.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            if self.base + end.decompressed_end > pos {
+                break;
+            }
+            cstart += end.compressed_bytes;
+            start = self.base + end.decompressed_end;
+        }
+        Some(cstart << 16 | (pos - start))
+    }
+}
+
+/// Whether `header` opens a BGZF block: a gzip member whose first extra
+/// subfield is `BC` with a 2-byte block size.
+pub(crate) fn is_bgzf(header: &[u8]) -> bool {
+    header.len() >= 16
+        && header[..4] == [0x1F, 0x8B, 8, FEXTRA]
+        && header[12..14] == *b"BC"
+        && header[14..16] == [2, 0]
+}
+
+/// BGZF virtual offset of decompressed offset `pos` in `r`, found by
+/// walking block headers and ISIZE trailers without inflating anything;
+/// `None` if `r` is not BGZF.
+pub(crate) fn bgzf_virtual_offset<R: Read + Seek>(mut r: R, pos: u64) -> io::Result<Option<u64>> {
+    let (mut coffset, mut start) = (0u64, 0u64);
+    let mut header = [0u8; 18];
+    loop {
+        r.seek(SeekFrom::Start(coffset))?;
+        let n = read_full(&mut r, &mut header)?;
+        if n == 0 && coffset > 0 {
+            if pos == start {
+                return Ok(Some(coffset << 16));
+            }
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("offset {pos} is past the end of the input ({start} bytes)"),
+            ));
+        }
+        if !is_bgzf(&header[..n]) || n < header.len() {
+            if coffset == 0 {
+                return Ok(None);
+            }
+            return Err(invalid(format!("no BGZF block at offset {coffset}")));
+        }
+        let bsize = u64::from(u16::from_le_bytes([header[16], header[17]])) + 1;
+        let mut isize = [0u8; 4];
+        r.seek(SeekFrom::Start(coffset + bsize - 4))?;
+        r.read_exact(&mut isize)?;
+        let isize = u64::from(u32::from_le_bytes(isize));
+        // an offset at the very end lands on the empty EOF block
+        if start + isize > pos || (isize == 0 && start == pos) {
+            return Ok(Some(coffset << 16 | (pos - start)));
+        }
+        coffset += bsize;
+        start += isize;
+    }
+}
+
+fn read_full<R: Read>(mut r: R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match r.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(k) => n += k,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Header,
+    Body,
+    Done,
+}
+
+/// Multi-member gzip decoder verifying each member's trailer.
+pub(crate) struct StrictGzDecoder<R> {
+    inner: Tally<R>,
+    state: State,
+    inflate: Decompress,
+    crc: crc32fast::Hasher,
+    size: u32,
+    member: u64,
+    verify: bool,
+    member_start: u64,
+    produced: u64,
+    ends: Option<MemberEnds>,
+}
+
+impl<R: BufRead> StrictGzDecoder<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner: Tally { inner, count: 0 },
+            state: State::Header,
+            inflate: Decompress::new(false),
+            crc: crc32fast::Hasher::new(),
+            size: 0,
+            member: 0,
+            verify: true,
+            member_start: 0,
+            produced: 0,
+            ends: None,
+        }
+    }
+
+    /// Decode member by member without checking checksums, queueing each
+    /// member's end on `ends`; `verify` turns the checks back on.
+    pub(crate) fn tracking(inner: R, verify: bool, ends: MemberEnds) -> Self {
+        Self {
+            verify,
+            ends: Some(ends),
+            ..Self::new(inner)
+        }
+    }
+
+    fn read_header(&mut self) -> io::Result<()> {
+        let mut header = [0u8; 10];
+        self.inner.read_exact(&mut header)?;
+        if header[..2] != [0x1F, 0x8B] {
+            return Err(invalid(format!(
+                "gzip member {}: bad magic {:02X} {:02X}",
+                self.member, header[0], header[1]
+            )));
+        }
+        if header[2] != 8 {
+            return Err(invalid(format!(
+                "gzip member {}: unsupported compression method {}",
+                self.member, header[2]
+            )));
+        }
+        let flags = header[3];
+        let mut hcrc = crc32fast::Hasher::new();
+        hcrc.update(&header);
+        if flags & FEXTRA != 0 {
+            let mut xlen = [0u8; 2];
+            self.inner.read_exact(&mut xlen)?;
+            hcrc.update(&xlen);
+            let mut extra = vec![0u8; u16::from_le_bytes(xlen) as usize];
+            self.inner.read_exact(&mut extra)?;
+            hcrc.update(&extra);
+        }
+        for flag in [FNAME, FCOMMENT] {
+            if flags & flag != 0 {
+                let mut field = Vec::new();
+                self.inner.read_until(0, &mut field)?;
+                if field.last() != Some(&0) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "truncated gzip header",
+                    ));
+                }
+                hcrc.update(&field);
+            }
+        }
+        if flags & FHCRC != 0 {
+            let mut stored = [0u8; 2];
+            self.inner.read_exact(&mut stored)?;
+            let expected = u16::from_le_bytes(stored);
+            let actual = hcrc.finalize() as u16;
+            if self.verify && expected != actual {
+                return Err(integrity(GzipIntegrityError::HeaderCrc {
+                    member: self.member,
+                    expected,
+                    actual,
+                }));
+            }
+        }
+        self.inflate.reset(false);
+        self.crc = crc32fast::Hasher::new();
+        self.size = 0;
+        Ok(())
+    }
+
+    fn finish_member(&mut self) -> io::Result<()> {
+        let mut trailer = [0u8; 8];
+        self.inner.read_exact(&mut trailer).map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                io::Error::new(e.kind(), "truncated gzip trailer")
+            } else {
+                e
+            }
+        })?;
+        let expected_crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let expected_size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        let actual_crc = std::mem::take(&mut self.crc).finalize();
+        if self.verify && expected_crc != actual_crc {
+            return Err(integrity(GzipIntegrityError::Crc {
+                member: self.member,
+                expected: expected_crc,
+                actual: actual_crc,
+            }));
+        }
+        if self.verify && expected_size != self.size {
+            return Err(integrity(GzipIntegrityError::Size {
+                member: self.member,
+                expected: expected_size,
+                actual: self.size,
+            }));
+        }
+        if let Some(ends) = &self.ends {
+            ends.lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push_back(MemberEnd {
+                    compressed_bytes: self.inner.count - self.member_start,
+                    decompressed_end: self.produced,
+                });
+        }
+        self.member_start = self.inner.count;
+        self.member += 1;
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for StrictGzDecoder<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match self.state {
+                State::Done => return Ok(0),
+                State::Header => {
+                    if self.inner.fill_buf()?.is_empty() {
+                        self.state = State::Done;
+                        return Ok(0);
+                    }
+                    self.read_header()?;
+                    self.state = State::Body;
+                }
+                State::Body => {
+                    let input = self.inner.fill_buf()?;
+                    let eof = input.is_empty();
+                    let (in_before, out_before) =
+                        (self.inflate.total_in(), self.inflate.total_out());
+                    let status = self
+                        .inflate
+                        .decompress(input, out, FlushDecompress::None)
+                        .map_err(|e| invalid(format!("gzip member {}: {e}", self.member)))?;
+                    let consumed = (self.inflate.total_in() - in_before) as usize;
+                    let produced = (self.inflate.total_out() - out_before) as usize;
+                    self.inner.consume(consumed);
+                    self.crc.update(&out[..produced]);
+                    self.produced += produced as u64;
+                    self.size = self.size.wrapping_add(produced as u32);
+
+                    if status == Status::StreamEnd {
+                        self.finish_member()?;
+                        self.state = State::Header;
+                    } else if consumed == 0 && produced == 0 {
+                        return Err(if eof {
+                            io::Error::new(io::ErrorKind::UnexpectedEof, "truncated gzip member")
+                        } else {
+                            invalid(format!(
+                                "gzip member {}: deflate stream stalled",
+                                self.member
+                            ))
+                        });
+                    }
+                    if produced > 0 {
+                        return Ok(produced);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Counts the compressed bytes the decoder takes from its input.
+struct Tally<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: BufRead> Read for Tally<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Tally<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count += amt as u64;
+        self.inner.consume(amt);
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn integrity(err: GzipIntegrityError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
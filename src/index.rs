@@ -0,0 +1,241 @@
+//! Record offset index (`.fqi`) for random access.
+//!
+//! [`FastqIndex::build`] scans a file once and samples the position of
+//! every `stride`-th record; [`FastqReader::seek_record`] then reopens the
+//! file at the nearest sample and parses forward from there. Plain files
+//! and BGZF are seeked directly; plain gzip still has to be decompressed up
+//! to the sample.
+
+use crate::error::{FastqError, IoContext};
+use crate::policy::ReaderOptions;
+use crate::reader::{FastqReader, ReaderState};
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Records between two index samples unless set otherwise.
+pub const DEFAULT_INDEX_STRIDE: u64 = 1024;
+
+const MAGIC: &[u8; 4] = b"FQI1";
+const NO_VIRTUAL_OFFSET: u64 = u64::MAX;
+
+/// Where a record starts: the reader state just before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// 0-based ordinal of the record among those the reader yields.
+    pub record: u64,
+    pub byte_offset: u64,
+    pub line_num: u64,
+    /// Records skipped under `ErrorPolicy::Skip` before it.
+    pub skipped: u64,
+    /// BGZF virtual offset, for BGZF input.
+    pub virtual_offset: Option<u64>,
+}
+
+/// Sampled record offsets of one file; see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FastqIndex {
+    stride: u64,
+    records: u64,
+    file_len: u64,
+    entries: Vec<IndexEntry>,
+}
+
+impl FastqIndex {
+    /// Scan `path` with `opts`, sampling every [`DEFAULT_INDEX_STRIDE`]-th
+    /// record. Record numbers count the records `opts` lets through, so
+    /// seek with the same options.
+    pub fn build<P: AsRef<Path>>(path: P, opts: ReaderOptions) -> Result<Self, FastqError> {
+        Self::build_with_stride(path, opts, DEFAULT_INDEX_STRIDE)
+    }
+
+    /// [`build`](Self::build) with a sample every `stride` records; smaller
+    /// strides make seeks parse less and the index larger.
+    pub fn build_with_stride<P: AsRef<Path>>(
+        path: P,
+        opts: ReaderOptions,
+        stride: u64,
+    ) -> Result<Self, FastqError> {
+        let path = path.as_ref();
+        let stride = stride.max(1);
+        let file_len = std::fs::metadata(path)
+            .map_err(|e| FastqError::io_err(e, path_ctx(path)))?
+            .len();
+        let mut fq = FastqReader::from_path(path, opts)?;
+        let mut entries = Vec::new();
+        let mut next_sample = 0;
+        let mut records = 0;
+        loop {
+            // a FASTA entry may leave the reader mid-record; sample later
+            if let Some(state) = fq.state().filter(|s| s.records >= next_sample) {
+                next_sample = state.records + stride;
+                entries.push(IndexEntry {
+                    record: state.records,
+                    byte_offset: state.byte_offset,
+                    line_num: state.line_num,
+                    skipped: state.skipped,
+                    virtual_offset: state.virtual_offset,
+                });
+            }
+            match fq.read_ref() {
+                Some(Ok(_)) => records += 1,
+                Some(Err(err)) => return Err(err),
+                None => break,
+            }
+        }
+        Ok(Self {
+            stride,
+            records,
+            file_len,
+            entries,
+        })
+    }
+
+    /// Where the index of `path` is kept: `path` with `.fqi` appended.
+    pub fn path_for<P: AsRef<Path>>(path: P) -> PathBuf {
+        let mut p = path.as_ref().as_os_str().to_owned();
+        p.push(".fqi");
+        PathBuf::from(p)
+    }
+
+    /// Load the index next to `path`, or build and save one when it is
+    /// missing or was built for a file of a different size.
+    pub fn open_or_build<P: AsRef<Path>>(path: P, opts: ReaderOptions) -> Result<Self, FastqError> {
+        let path = path.as_ref();
+        let fqi = Self::path_for(path);
+        let file_len = std::fs::metadata(path)
+            .map_err(|e| FastqError::io_err(e, path_ctx(path)))?
+            .len();
+        match Self::load(&fqi) {
+            Ok(index) if index.file_len == file_len => return Ok(index),
+            Ok(_) => log::warn!("rebuilding stale index {}", fqi.display()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => log::warn!("rebuilding unreadable index {}: {e}", fqi.display()),
+        }
+        let index = Self::build(path, opts)?;
+        index
+            .save(&fqi)
+            .map_err(|e| FastqError::io_err(e, path_ctx(&fqi)))?;
+        Ok(index)
+    }
+
+    /// Records in the file.
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    pub fn stride(&self) -> u64 {
+        self.stride
+    }
+
+    /// Size of the indexed file, used to detect a stale index.
+    pub fn file_len(&self) -> u64 {
+        self.file_len
+    }
+
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// The last sample at or before record `n`.
+    pub fn entry_before(&self, n: u64) -> Option<&IndexEntry> {
+        let i = self.entries.partition_point(|e| e.record <= n);
+        i.checked_sub(1).map(|i| &self.entries[i])
+    }
+
+    /// Reader state at the sample for record `n`, for `path`.
+    pub(crate) fn state_before(&self, path: &Path, n: u64) -> Option<ReaderState> {
+        self.entry_before(n).map(|e| ReaderState {
+            path: Some(path.to_path_buf()),
+            byte_offset: e.byte_offset,
+            line_num: e.line_num,
+            records: e.record,
+            skipped: e.skipped,
+            virtual_offset: e.virtual_offset,
+        })
+    }
+
+    /// Write the index to `path` (the `.fqi` format).
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_to(&mut w)?;
+        w.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Serialize: magic `FQI1`, then little-endian `u64`s — stride,
+    /// records, file length, entry count and five per entry.
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        for v in [
+            self.stride,
+            self.records,
+            self.file_len,
+            self.entries.len() as u64,
+        ] {
+            w.write_all(&v.to_le_bytes())?;
+        }
+        for e in &self.entries {
+            for v in [
+                e.record,
+                e.byte_offset,
+                e.line_num,
+                e.skipped,
+                e.virtual_offset.unwrap_or(NO_VIRTUAL_OFFSET),
+            ] {
+                w.write_all(&v.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(mut r: R) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a FASTQ index (.fqi)",
+            ));
+        }
+        let mut next = || -> io::Result<u64> {
+            let mut b = [0u8; 8];
+            r.read_exact(&mut b)?;
+            Ok(u64::from_le_bytes(b))
+        };
+        let (stride, records, file_len, count) = (next()?, next()?, next()?, next()?);
+        let mut entries = Vec::with_capacity(count.min(1 << 20) as usize);
+        for _ in 0..count {
+            let (record, byte_offset, line_num, skipped, voff) =
+                (next()?, next()?, next()?, next()?, next()?);
+            entries.push(IndexEntry {
+                record,
+                byte_offset,
+                line_num,
+                skipped,
+                virtual_offset: (voff != NO_VIRTUAL_OFFSET).then_some(voff),
+            });
+        }
+        Ok(Self {
+            stride,
+            records,
+            file_len,
+            entries,
+        })
+    }
+}
+
+fn path_ctx(path: &Path) -> IoContext {
+    IoContext {
+        byte_pos: 0,
+        line_num: 0,
+        record_num: 0,
+        record_line: 0,
+        label: Some(path.display().to_string().into()),
+        virtual_offset: None,
+    }
+}
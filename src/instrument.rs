@@ -0,0 +1,151 @@
+//! Reader instrumentation: counters through the `metrics` facade (feature
+//! `metrics`) and structured `tracing` events and spans (feature `tracing`).
+//! Without either, every hook is a no-op or plain `log`, and [`Meter`] is
+//! empty.
+//!
+//! | metric | kind | |
+//! |---|---|---|
+//! | `fastq_records_read` | counter | records yielded |
+//! | `fastq_records_skipped` | counter | records dropped by the skip policy |
+//! | `fastq_bytes_read` | counter | decompressed bytes parsed |
+//! | `fastq_parse_errors` | counter | errors met, labeled `kind` ([`FastqError::kind`]) |
+//!
+//! With `tracing`, each input gets a `fastq_input` span (field `path`: the
+//! source label) holding "opened" and "closed" debug events, and skip
+//! messages become warn events in it with `line`, `byte_pos`, `kind` and
+//! `path` fields.
+
+use crate::error::FastqError;
+
+/// Per-reader state of the hooks: the byte count already reported and the
+/// input's span.
+#[derive(Debug)]
+pub(crate) struct Meter {
+    #[cfg(feature = "metrics")]
+    bytes: u64,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl Meter {
+    /// State for an input labeled `label` (a path, `stdin`, ...).
+    pub(crate) fn new(label: Option<&str>) -> Self {
+        #[cfg(feature = "tracing")]
+        let span = {
+            let span = tracing::info_span!("fastq_input", path = label);
+            tracing::debug!(parent: &span, "opened");
+            span
+        };
+        #[cfg(not(feature = "tracing"))]
+        let _ = label;
+        Self {
+            #[cfg(feature = "metrics")]
+            bytes: 0,
+            #[cfg(feature = "tracing")]
+            span,
+        }
+    }
+
+    /// A chained reader moved on to the input of `next`: close the current
+    /// span and continue in `next`'s, keeping the byte count.
+    pub(crate) fn switch_to(&mut self, next: Meter) {
+        #[cfg(feature = "tracing")]
+        {
+            let mut next = next;
+            std::mem::swap(&mut self.span, &mut next.span);
+            // dropping `next` closes the previous input's span
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = next;
+    }
+
+    /// A record was yielded; `bytes` is the reader's running byte count.
+    #[inline]
+    pub(crate) fn record(&mut self, bytes: u64) {
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!("fastq_records_read").increment(1);
+            self.bytes_to(bytes);
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = bytes;
+    }
+
+    /// The input ended at `bytes`.
+    #[inline]
+    pub(crate) fn finish(&mut self, bytes: u64) {
+        #[cfg(feature = "metrics")]
+        self.bytes_to(bytes);
+        #[cfg(not(feature = "metrics"))]
+        let _ = bytes;
+    }
+
+    /// A record was dropped by the skip policy and no skip handler took
+    /// `err`: log it.
+    pub(crate) fn skip(&self, what: &str, err: &FastqError) {
+        #[cfg(feature = "tracing")]
+        skip_event_in(&self.span, what, err);
+        #[cfg(not(feature = "tracing"))]
+        skip_event(what, err);
+    }
+
+    #[cfg(feature = "metrics")]
+    fn bytes_to(&mut self, bytes: u64) {
+        // a rewound or resumed reader counts from where it is now
+        let delta = bytes.saturating_sub(self.bytes);
+        if delta > 0 {
+            metrics::counter!("fastq_bytes_read").increment(delta);
+        }
+        self.bytes = bytes;
+    }
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for Meter {
+    fn drop(&mut self) {
+        tracing::debug!(parent: &self.span, "closed");
+    }
+}
+
+/// `err` was met while parsing, whether it is then skipped or returned.
+#[inline]
+pub(crate) fn parse_error(err: &FastqError) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("fastq_parse_errors", "kind" => err.kind()).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = err;
+}
+
+/// A record was dropped by the skip policy.
+#[inline]
+pub(crate) fn skipped() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("fastq_records_skipped").increment(1);
+}
+
+/// Log "skipping {what}: {err}" outside of any reader's span.
+pub(crate) fn skip_event(what: &str, err: &FastqError) {
+    #[cfg(feature = "tracing")]
+    skip_event_in(&tracing::Span::current(), what, err);
+    #[cfg(not(feature = "tracing"))]
+    log::warn!("skipping {what}: {err}");
+}
+
+#[cfg(feature = "tracing")]
+fn skip_event_in(span: &tracing::Span, what: &str, err: &FastqError) {
+    let ctx = err.context();
+    tracing::warn!(
+        parent: span,
+        line = ctx.line_num,
+        byte_pos = ctx.byte_pos,
+        kind = err.kind(),
+        path = ctx.label.as_deref(),
+        "skipping {what}: {err}"
+    );
+}
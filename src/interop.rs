@@ -0,0 +1,120 @@
+//! Conversions between [`FastqRecord`] and other crates' record types.
+//!
+//! With the `bio` feature, `FastqRecord` converts to and from
+//! [`bio::io::fastq::Record`], so records read here (with this crate's
+//! error policy) can feed rust-bio pipelines and back. With `noodles`, the
+//! same goes for [`noodles_fastq::Record`]; its name and description are
+//! bytes, so non-UTF-8 ones come back with U+FFFD, and an empty description
+//! is `None`. With `seq_io`, a [`FastqRecord`] becomes a
+//! [`seq_io::fastq::OwnedRecord`] whose head is the ID and description
+//! joined by a space; the way back splits the head at its first space and
+//! is a `TryFrom`, failing on a head that is not UTF-8. The kept `+` text
+//! and raw input have no counterpart in any of them and are dropped.
+
+use crate::record::FastqRecord;
+
+#[cfg(feature = "bio")]
+impl From<&FastqRecord> for bio::io::fastq::Record {
+    fn from(rec: &FastqRecord) -> Self {
+        Self::with_attrs(rec.id(), rec.desc(), rec.seq(), rec.qual())
+    }
+}
+
+#[cfg(feature = "bio")]
+impl From<FastqRecord> for bio::io::fastq::Record {
+    fn from(rec: FastqRecord) -> Self {
+        Self::from(&rec)
+    }
+}
+
+#[cfg(feature = "bio")]
+impl From<&bio::io::fastq::Record> for FastqRecord {
+    fn from(rec: &bio::io::fastq::Record) -> Self {
+        FastqRecord::new(rec.id(), rec.desc(), rec.seq(), rec.qual())
+    }
+}
+
+#[cfg(feature = "bio")]
+impl From<bio::io::fastq::Record> for FastqRecord {
+    fn from(rec: bio::io::fastq::Record) -> Self {
+        Self::from(&rec)
+    }
+}
+
+#[cfg(feature = "noodles")]
+impl From<&FastqRecord> for noodles_fastq::Record {
+    fn from(rec: &FastqRecord) -> Self {
+        let definition = noodles_fastq::record::Definition::new(rec.id(), rec.desc().unwrap_or(""));
+        Self::new(definition, rec.seq(), rec.qual())
+    }
+}
+
+#[cfg(feature = "noodles")]
+impl From<FastqRecord> for noodles_fastq::Record {
+    fn from(rec: FastqRecord) -> Self {
+        Self::from(&rec)
+    }
+}
+
+#[cfg(feature = "noodles")]
+impl From<&noodles_fastq::Record> for FastqRecord {
+    fn from(rec: &noodles_fastq::Record) -> Self {
+        let id = String::from_utf8_lossy(rec.name());
+        let desc = String::from_utf8_lossy(rec.description());
+        let desc = (!desc.is_empty()).then_some(&*desc);
+        FastqRecord::new(&id, desc, rec.sequence(), rec.quality_scores())
+    }
+}
+
+#[cfg(feature = "noodles")]
+impl From<noodles_fastq::Record> for FastqRecord {
+    fn from(rec: noodles_fastq::Record) -> Self {
+        Self::from(&rec)
+    }
+}
+
+#[cfg(feature = "seq_io")]
+impl From<&FastqRecord> for seq_io::fastq::OwnedRecord {
+    fn from(rec: &FastqRecord) -> Self {
+        let mut head = rec.id().as_bytes().to_vec();
+        if let Some(desc) = rec.desc() {
+            head.push(b' ');
+            head.extend_from_slice(desc.as_bytes());
+        }
+        Self {
+            head,
+            seq: rec.seq().to_vec(),
+            qual: rec.qual().to_vec(),
+        }
+    }
+}
+
+#[cfg(feature = "seq_io")]
+impl From<FastqRecord> for seq_io::fastq::OwnedRecord {
+    fn from(rec: FastqRecord) -> Self {
+        Self::from(&rec)
+    }
+}
+
+#[cfg(feature = "seq_io")]
+impl TryFrom<&seq_io::fastq::OwnedRecord> for FastqRecord {
+    type Error = std::str::Utf8Error;
+
+    fn try_from(rec: &seq_io::fastq::OwnedRecord) -> Result<Self, Self::Error> {
+        let head = std::str::from_utf8(&rec.head)?;
+        let (id, desc) = match head.split_once(' ') {
+            Some((id, desc)) => (id, Some(desc)),
+            None => (head, None),
+        };
+        Ok(FastqRecord::new(id, desc, &rec.seq, &rec.qual))
+    }
+}
+
+#[cfg(feature = "seq_io")]
+impl TryFrom<seq_io::fastq::OwnedRecord> for FastqRecord {
+    type Error = std::str::Utf8Error;
+
+    fn try_from(rec: seq_io::fastq::OwnedRecord) -> Result<Self, Self::Error> {
+        Self::try_from(&rec)
+    }
+}
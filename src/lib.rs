@@ -2,24 +2,196 @@
 //!
 //! - Plain and `.gz` (auto-detect).
 //! - Streaming, record-by-record (no full-file buffering).
+//! - `FastqWriter` for plain, `.gz` and `.zst` output.
+//! - Zero-I/O parsing of in-memory buffers via [`parse_records`].
+//! - Mate-ID checks for paired-end data in [`paired`]; R1/R2 file discovery
+//!   in [`mate_files`].
+//! - UMI and cell-barcode extraction in [`umi`]; barcode demultiplexing in
+//!   `demux`.
+//! - Seeded subsampling of records or pairs via [`Subsample`].
 //! - CD-HIT-like error policy: skip malformed (default) or return error.
 //! - Single-line FASTQ mode by default; multi-line can be enabled via options.
+//! - Record state machine in [`core`] builds with `no_std + alloc`
+//!   (`default-features = false`).
 //! - Optional `mmap` for plain files; `zlib` feature for system-zlib parity.
-//! - Optional async API behind `async` feature.
+//! - Optional async API behind `async` feature; HTTP(S) input behind
+//!   `remote`, S3 objects behind `s3`.
+//! - Optional `kira-fastq` command-line tool behind `cli` feature.
+//! - Optional `.zip` archive input behind `zip` feature.
+//! - Optional Arrow batches and Parquet output behind `arrow` feature.
+//! - Optional Polars `DataFrame` of a FASTQ slice behind `polars` feature.
+//! - Optional `From` conversions to and from `bio::io::fastq::Record` behind
+//!   `bio` feature, `noodles_fastq::Record` behind `noodles` feature and
+//!   `seq_io::fastq::OwnedRecord` behind `seq_io` feature.
+//! - Optional reader counters through the `metrics` facade behind `metrics`
+//!   feature.
+//! - Optional structured `tracing` events and per-input spans behind
+//!   `tracing` feature.
+//! - Optional JS `Uint8Array`/`ReadableStream` sources behind `wasm` feature.
+//! - Optional C ABI (`fq_open`/`fq_next`/`fq_close`) behind `ffi` feature.
+//! - Optional Node.js addon (async `FastqReader`) behind `node` feature.
+//! - Optional Python module (`FastqReader` iterator, `FastqRecord` with
+//!   `memoryview` fields) behind `python` feature.
 
-pub mod error;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod core;
 pub mod policy;
-pub mod reader;
+pub mod quality;
 pub mod record;
+mod sanitize;
+
+#[cfg(feature = "arrow")]
+pub mod arrow_export;
+#[cfg(feature = "std")]
+pub mod checksum;
+#[cfg(feature = "codec")]
+pub mod codec;
+#[cfg(feature = "std")]
+pub mod compat;
+#[cfg(feature = "gzip")]
+pub mod convert;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+#[cfg(feature = "gzip")]
+pub mod demux;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod file_set;
+#[cfg(feature = "gzip")]
+pub mod gzip;
+#[cfg(feature = "std")]
+pub mod index;
+#[cfg(feature = "std")]
+mod instrument;
+#[cfg(any(feature = "bio", feature = "noodles", feature = "seq_io"))]
+mod interop;
+#[cfg(feature = "std")]
+pub mod limits;
+#[cfg(feature = "std")]
+pub mod mate_files;
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(feature = "std")]
+pub mod paired;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(feature = "std")]
+pub mod parser;
+#[cfg(feature = "std")]
+mod prefetch;
+#[cfg(feature = "std")]
+pub mod probe;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "std")]
+pub mod reader;
+#[cfg(feature = "serde")]
+pub mod record_serde;
+#[cfg(feature = "remote")]
+mod remote;
+#[cfg(feature = "s3")]
+pub mod s3;
+#[cfg(feature = "std")]
+pub mod sample;
+#[cfg(feature = "std")]
+pub mod sample_sheet;
+#[cfg(feature = "std")]
+mod throttle;
+#[cfg(feature = "std")]
+pub mod umi;
+#[cfg(all(feature = "uring", target_os = "linux"))]
+mod uring;
+#[cfg(feature = "std")]
 mod util;
 
 #[cfg(feature = "async")]
 pub mod async_reader;
+#[cfg(all(feature = "async", feature = "gzip"))]
+pub mod async_writer;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "gzip")]
+pub mod writer;
+#[cfg(feature = "zip")]
+pub mod zip;
+
+pub use crate::core::{FormatError, PairDesync, RecordParser, detect_format};
+pub use crate::policy::{
+    AccessAdvice, AllowedBytes, BlankLinePolicy, ChecksumAlgorithm, DEFAULT_PROGRESS_BYTES,
+    ErrorPolicy, IdSanitize, InputFormat, LineMode, MmapPolicy, Progress, ProgressSink,
+    QualityEncoding, ReaderOptions, ReaderOptionsBuilder, Throttle,
+};
+pub use crate::record::{FastaRecord, FastqParts, FastqRecord, RefRecord, SeqRecord};
 
-pub use crate::error::{FastqError, FormatError, IoContext};
-pub use crate::policy::{ErrorPolicy, LineMode, ReaderOptions};
-pub use crate::reader::{FastqReader, Source};
-pub use crate::record::FastqRecord;
+#[cfg(feature = "arrow")]
+pub use crate::arrow_export::ParquetWriter;
+#[cfg(feature = "std")]
+pub use crate::checksum::{Digest, InputChecksums};
+#[cfg(feature = "codec")]
+pub use crate::codec::FastqCodec;
+#[cfg(feature = "gzip")]
+pub use crate::convert::{
+    Compression, ConvertOptions, ConvertStats, OutputFormat, convert, convert_to_writer,
+};
+#[cfg(feature = "polars")]
+pub use crate::dataframe::{DataFrameError, read_to_dataframe};
+#[cfg(feature = "gzip")]
+pub use crate::demux::{
+    BarcodeSource, Demultiplexer, DemuxOptions, DemuxReport, DuplicateBarcode, SampleCount,
+};
+#[cfg(feature = "std")]
+pub use crate::error::{FastqError, IoContext, SkipReport, SkippedRecord};
+#[cfg(feature = "std")]
+pub use crate::file_set::{FastqFileSet, TaggedRecord};
+#[cfg(feature = "gzip")]
+pub use crate::gzip::{GzipIntegrityError, GzipMember};
+#[cfg(feature = "std")]
+pub use crate::index::{DEFAULT_INDEX_STRIDE, FastqIndex, IndexEntry};
+#[cfg(feature = "std")]
+pub use crate::limits::LimitError;
+#[cfg(feature = "std")]
+pub use crate::mate_files::{MateFileError, discover_pairs, find_mates, mate_path};
+#[cfg(feature = "std")]
+pub use crate::paired::{
+    InterleavedPairs, MateMismatch, MatePolicy, OrphanPolicy, PairedFastqReader,
+};
+#[cfg(feature = "rayon")]
+pub use crate::parallel::{DEFAULT_BATCH_RECORDS, ParProcess, ParResults};
+#[cfg(feature = "std")]
+pub use crate::parser::parse_records;
+#[cfg(feature = "std")]
+pub use crate::probe::{
+    Interleaving, ProbeReport, RecordEstimate, detect_quality_encoding, estimate_total_records,
+    probe,
+};
+#[cfg(feature = "std")]
+pub use crate::reader::{FastqReader, ReaderState, ReaderStats, RecordSpan, SkipHandler, Source};
+#[cfg(feature = "std")]
+pub use crate::sample::{SampleFraction, Subsample};
+#[cfg(feature = "std")]
+pub use crate::sample_sheet::{SampleEntry, SampleSheet, SampleSheetError};
+#[cfg(feature = "std")]
+pub use crate::umi::{
+    HeaderPart, Umi, UmiExtractor, UmiPattern, UmiPatternError, UmiSource, UmiStage,
+};
 
 #[cfg(feature = "async")]
 pub use crate::async_reader::AsyncFastqReader;
+#[cfg(all(feature = "async", feature = "gzip"))]
+pub use crate::async_writer::AsyncFastqWriter;
+#[cfg(feature = "s3")]
+pub use crate::s3::{S3Credentials, S3Object};
+#[cfg(feature = "gzip")]
+pub use crate::writer::{
+    FastqWriter, JsonlWriter, LineEnding, RecordField, TsvWriter, WriterOptions,
+};
+#[cfg(feature = "zip")]
+pub use crate::zip::{ZipEntry, ZipFastqReader};
@@ -0,0 +1,102 @@
+//! Resource limits for untrusted input (decompression bombs, runaway lines).
+//!
+//! Configured via `ReaderOptions::max_expansion_ratio`,
+//! `ReaderOptions::max_record_bytes` and `ReaderOptions::max_skipped`;
+//! violations surface as
+//! [`FastqError::LimitExceeded`](crate::FastqError::LimitExceeded).
+
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use thiserror::Error;
+
+/// Decompressed output allowed before the expansion ratio is enforced, so
+/// tiny, highly compressible inputs are not rejected.
+pub const RATIO_GRACE_BYTES: u64 = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LimitError {
+    #[error(
+        "decompressed/compressed ratio exceeds {limit} ({decompressed} bytes from {compressed})"
+    )]
+    ExpansionRatio {
+        limit: u32,
+        compressed: u64,
+        decompressed: u64,
+    },
+    #[error("record exceeds {limit} bytes")]
+    RecordTooLarge { limit: usize },
+    /// More malformed records than `ReaderOptions::max_skipped` allows.
+    #[error("more than {limit} malformed records skipped")]
+    TooManySkipped { limit: u64 },
+}
+
+impl LimitError {
+    pub(crate) fn into_io(self) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, self)
+    }
+}
+
+/// Apply `decode` to `raw`, failing reads once the output outgrows the
+/// input by more than `limit` times.
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub(crate) fn ratio_guarded(
+    raw: Box<dyn Read + Send>,
+    limit: Option<u32>,
+    decode: impl FnOnce(Box<dyn Read + Send>) -> Box<dyn Read + Send>,
+) -> Box<dyn Read + Send> {
+    let Some(limit) = limit else {
+        return decode(raw);
+    };
+    let consumed = Arc::new(AtomicU64::new(0));
+    let counted = Box::new(CountingReader {
+        inner: raw,
+        count: Arc::clone(&consumed),
+    });
+    Box::new(RatioGuard {
+        inner: decode(counted),
+        consumed,
+        produced: 0,
+        limit,
+    })
+}
+
+/// Adds the bytes read through it to a shared counter.
+pub(crate) struct CountingReader<R> {
+    pub(crate) inner: R,
+    pub(crate) count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+struct RatioGuard<R> {
+    inner: R,
+    consumed: Arc<AtomicU64>,
+    produced: u64,
+    limit: u32,
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+impl<R: Read> Read for RatioGuard<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.produced += n as u64;
+        let compressed = self.consumed.load(Ordering::Relaxed).max(1);
+        if self.produced > RATIO_GRACE_BYTES && self.produced / compressed > u64::from(self.limit) {
+            return Err(LimitError::ExpansionRatio {
+                limit: self.limit,
+                compressed,
+                decompressed: self.produced,
+            }
+            .into_io());
+        }
+        Ok(n)
+    }
+}
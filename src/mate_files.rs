@@ -0,0 +1,100 @@
+//! Finding the R2 file of an R1 file (and back) by naming convention.
+//!
+//! The mate marker is the last of `_R1_`, `_R1.`, `_1.` or `.1.` (and the
+//! `2` forms) in the file name, as in `S1_L001_R1_001.fastq.gz`,
+//! `run_1.fq.gz` or `run.1.fq`. [`find_mates`] pairs one file with its
+//! mate; [`discover_pairs`] pairs every FASTQ file in a directory.
+
+use crate::paired::Mate;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Marker pairs, most specific first.
+const MARKERS: [(&str, &str); 4] = [
+    ("_R1_", "_R2_"),
+    ("_R1.", "_R2."),
+    ("_1.", "_2."),
+    (".1.", ".2."),
+];
+
+#[derive(Debug, Error)]
+pub enum MateFileError {
+    #[error("{}: no R1/R2 marker in the file name", .0.display())]
+    NoMarker(PathBuf),
+    #[error("{}: mate file {} not found", .path.display(), .expected.display())]
+    MissingMate { path: PathBuf, expected: PathBuf },
+    #[error("listing {}: {source}", .dir.display())]
+    Io {
+        dir: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+/// The path `path`'s mate would have, and which mate `path` is; `None`
+/// when the file name carries no marker. Does not touch the filesystem.
+pub fn mate_path(path: &Path) -> Option<(PathBuf, Mate)> {
+    let name = path.file_name()?.to_str()?;
+    let (at, len, other, mate) = MARKERS
+        .iter()
+        .flat_map(|&(m1, m2)| [(m1, m2, Mate::R1), (m2, m1, Mate::R2)])
+        .find_map(|(marker, other, mate)| {
+            name.rfind(marker).map(|at| (at, marker.len(), other, mate))
+        })?;
+    let renamed = format!("{}{other}{}", &name[..at], &name[at + len..]);
+    Some((path.with_file_name(renamed), mate))
+}
+
+/// `(r1, r2)` for `path` and its mate, whichever of the two `path` is;
+/// both must exist.
+pub fn find_mates<P: AsRef<Path>>(path: P) -> Result<(PathBuf, PathBuf), MateFileError> {
+    let path = path.as_ref();
+    let (other, mate) =
+        mate_path(path).ok_or_else(|| MateFileError::NoMarker(path.to_path_buf()))?;
+    if !other.is_file() {
+        return Err(MateFileError::MissingMate {
+            path: path.to_path_buf(),
+            expected: other,
+        });
+    }
+    Ok(match mate {
+        Mate::R1 => (path.to_path_buf(), other),
+        Mate::R2 => (other, path.to_path_buf()),
+    })
+}
+
+/// Every `(r1, r2)` pair of FASTQ files (`.fq`/`.fastq`, optionally
+/// followed by `.gz`, `.bgz` or `.zst`) in `dir`, sorted by R1 path. Files without a marker are ignored; a file
+/// whose mate is missing is an error.
+pub fn discover_pairs<P: AsRef<Path>>(dir: P) -> Result<Vec<(PathBuf, PathBuf)>, MateFileError> {
+    let dir = dir.as_ref();
+    let io_err = |source| MateFileError::Io {
+        dir: dir.to_path_buf(),
+        source,
+    };
+    let mut pairs = Vec::new();
+    for entry in fs::read_dir(dir).map_err(io_err)? {
+        let path = entry.map_err(io_err)?.path();
+        let is_fastq = path.file_name().and_then(|n| n.to_str()).is_some_and(|n| {
+            let n = n.to_ascii_lowercase();
+            let n = [".gz", ".bgz", ".zst"]
+                .iter()
+                .find_map(|ext| n.strip_suffix(ext))
+                .unwrap_or(&n);
+            n.ends_with(".fq") || n.ends_with(".fastq")
+        });
+        if !is_fastq || !path.is_file() {
+            continue;
+        }
+        match find_mates(&path) {
+            Ok(pair) if pair.0 == path => pairs.push(pair),
+            Ok(_) | Err(MateFileError::NoMarker(_)) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    pairs.sort();
+    Ok(pairs)
+}
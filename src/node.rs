@@ -0,0 +1,146 @@
+//! Node.js addon (feature `node`, napi-rs).
+//!
+//! Exposes `new FastqReader(path, strict?, multiLine?)` with the same
+//! options as the Python module and `fq_open`: the CD-HIT-like skip policy
+//! unless `strict`. `next()` parses the next record on the libuv thread
+//! pool and resolves to an iterator result (`{ done, value }`), so the
+//! reader is an async iterator:
+//!
+//! ```js
+//! const rdr = new FastqReader('reads.fq.gz');
+//! for await (const rec of { [Symbol.asyncIterator]: () => rdr }) { ... }
+//! ```
+//!
+//! Records are `{ id, desc, seq, qual }` objects (`desc` is left out when
+//! the header has none; non-UTF-8 bytes come out as U+FFFD); `stats()`
+//! returns the reader's counters. Errors reject with the reader's message.
+//!
+//! Node-API symbols are resolved when the addon is loaded, so executables
+//! built with this feature (tests, `kira-fastq`) still link; debug builds of
+//! them log the missing symbols at startup.
+
+use crate::error::FastqError;
+use crate::policy::{ErrorPolicy, LineMode, ReaderOptions};
+use crate::reader::FastqReader;
+use crate::record::FastqRecord;
+
+use napi::bindgen_prelude::{AsyncTask, Error, Result, Status};
+use napi::{Env, Task};
+use napi_derive::napi;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A record as a JS object.
+#[napi(object, js_name = "FastqRecord")]
+pub struct JsFastqRecord {
+    pub id: String,
+    pub desc: Option<String>,
+    pub seq: String,
+    pub qual: String,
+}
+
+/// What `next()` resolves to: the async iterator protocol's result.
+#[napi(object, js_name = "FastqIteratorResult")]
+pub struct JsIteratorResult {
+    pub done: bool,
+    pub value: Option<JsFastqRecord>,
+}
+
+/// [`ReaderStats`](crate::ReaderStats) as a JS object.
+#[napi(object, js_name = "ReaderStats")]
+pub struct JsReaderStats {
+    pub records: i64,
+    pub skipped: i64,
+    pub filtered: i64,
+    pub raw_bytes: i64,
+    pub bytes: i64,
+    pub bases: i64,
+}
+
+/// Node `FastqReader`.
+#[napi(js_name = "FastqReader")]
+pub struct JsFastqReader {
+    inner: Arc<Mutex<FastqReader>>,
+}
+
+#[napi]
+impl JsFastqReader {
+    #[napi(constructor)]
+    pub fn new(path: String, strict: Option<bool>, multi_line: Option<bool>) -> Result<Self> {
+        let opts = ReaderOptions {
+            error_policy: if strict.unwrap_or(false) {
+                ErrorPolicy::Return
+            } else {
+                ErrorPolicy::Skip
+            },
+            line_mode: if multi_line.unwrap_or(false) {
+                LineMode::Multi
+            } else {
+                LineMode::Single
+            },
+            ..Default::default()
+        };
+        let inner = FastqReader::from_path(path, opts).map_err(to_napi_err)?;
+        Ok(Self {
+            inner: Arc::new(Mutex::new(inner)),
+        })
+    }
+
+    /// Parse the next record off the main thread.
+    #[napi(ts_return_type = "Promise<FastqIteratorResult>")]
+    pub fn next(&self) -> AsyncTask<NextRecord> {
+        AsyncTask::new(NextRecord {
+            reader: Arc::clone(&self.inner),
+        })
+    }
+
+    /// Counters so far; waits for a `next()` in flight.
+    #[napi]
+    pub fn stats(&self) -> JsReaderStats {
+        let s = lock(&self.inner).stats();
+        let n = |v: u64| i64::try_from(v).unwrap_or(i64::MAX);
+        JsReaderStats {
+            records: n(s.records),
+            skipped: n(s.skipped),
+            filtered: n(s.filtered),
+            raw_bytes: n(s.raw_bytes),
+            bytes: n(s.bytes),
+            bases: n(s.bases),
+        }
+    }
+}
+
+/// The work behind `FastqReader.next()`.
+pub struct NextRecord {
+    reader: Arc<Mutex<FastqReader>>,
+}
+
+impl Task for NextRecord {
+    type Output = Option<FastqRecord>;
+    type JsValue = JsIteratorResult;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        lock(&self.reader).next().transpose().map_err(to_napi_err)
+    }
+
+    fn resolve(&mut self, _env: Env, rec: Self::Output) -> Result<Self::JsValue> {
+        let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+        Ok(JsIteratorResult {
+            done: rec.is_none(),
+            value: rec.map(|rec| JsFastqRecord {
+                id: rec.id().to_owned(),
+                desc: rec.desc().map(str::to_owned),
+                seq: text(rec.seq()),
+                qual: text(rec.qual()),
+            }),
+        })
+    }
+}
+
+/// A reader poisoned by a panic is still consistent between records.
+fn lock(reader: &Mutex<FastqReader>) -> MutexGuard<'_, FastqReader> {
+    reader.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn to_napi_err(err: FastqError) -> Error {
+    Error::new(Status::GenericFailure, err.to_string())
+}
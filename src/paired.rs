@@ -0,0 +1,578 @@
+//! Mate-ID checks for paired-end data.
+//!
+//! Two records are mates when their IDs agree after removing the mate
+//! marker, under any of the common conventions:
+//! - `/1` / `/2` suffixes (`read7/1`, `read7/2`);
+//! - CASAVA 1.8+ descriptions (`read7 1:N:0:ACGT`, `read7 2:N:0:ACGT`);
+//! - identical bare IDs (`read7`, `read7`).
+//!
+//! Records left without a partner are handled by [`OrphanHandler`].
+//! [`PairedFastqReader`] reads R1/R2 files in lockstep on top of these,
+//! [`InterleavedPairs`] (from [`FastqReader::pairs`]) interleaved files.
+
+use crate::core::PairDesync;
+use crate::error::{FastqError, FormatError, IoContext};
+use crate::instrument;
+use crate::policy::{ErrorPolicy, ReaderOptions};
+use crate::reader::FastqReader;
+use crate::record::{FastqRecord, RefRecord};
+use std::fmt;
+use std::path::Path;
+use thiserror::Error;
+
+/// What to do when two records in a pair are not mates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatePolicy {
+    /// Stop with [`MateMismatch`].
+    #[default]
+    Error,
+    /// Drop the pair (with a warning) and continue.
+    SkipPair,
+    /// Do not compare IDs; pair records purely by position.
+    TrustOrder,
+}
+
+/// The records at the same position in R1 and R2 are not mates.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("mate IDs do not match: {r1_id:?} vs {r2_id:?}")]
+pub struct MateMismatch {
+    pub r1_id: String,
+    pub r2_id: String,
+}
+
+/// Outcome of [`check_mates`] when no error is raised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MateCheck {
+    /// Keep the pair.
+    Keep,
+    /// Drop the pair (`MatePolicy::SkipPair` on a mismatch).
+    Skip,
+}
+
+/// An ID split into the part shared by both mates and the mate number,
+/// if the ID carries one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MateId<'a> {
+    pub base: &'a str,
+    pub mate: Option<u8>,
+}
+
+/// Split `id` (and the optional description) into base ID and mate number.
+pub fn mate_id<'a>(id: &'a str, desc: Option<&str>) -> MateId<'a> {
+    if let Some(base) = id.strip_suffix("/1") {
+        return MateId {
+            base,
+            mate: Some(1),
+        };
+    }
+    if let Some(base) = id.strip_suffix("/2") {
+        return MateId {
+            base,
+            mate: Some(2),
+        };
+    }
+    let casava = desc
+        .and_then(|d| d.split_ascii_whitespace().next())
+        .and_then(|field| field.split_once(':'))
+        .and_then(|(n, _)| match n {
+            "1" => Some(1),
+            "2" => Some(2),
+            _ => None,
+        });
+    MateId {
+        base: id,
+        mate: casava,
+    }
+}
+
+/// Whether `r1` and `r2` are mates: same base ID, and if both carry mate
+/// numbers, those are different.
+pub fn are_mates(r1: RefRecord<'_>, r2: RefRecord<'_>) -> bool {
+    let (a, b) = (mate_id(r1.id, r1.desc), mate_id(r2.id, r2.desc));
+    a.base == b.base
+        && match (a.mate, b.mate) {
+            (Some(x), Some(y)) => x != y,
+            _ => true,
+        }
+}
+
+/// Apply `policy` to a candidate pair.
+pub fn check_mates(
+    r1: RefRecord<'_>,
+    r2: RefRecord<'_>,
+    policy: MatePolicy,
+) -> Result<MateCheck, MateMismatch> {
+    if policy == MatePolicy::TrustOrder || are_mates(r1, r2) {
+        return Ok(MateCheck::Keep);
+    }
+    match policy {
+        MatePolicy::SkipPair => {
+            log::warn!("skipping pair with mismatched IDs: {} vs {}", r1.id, r2.id);
+            Ok(MateCheck::Skip)
+        }
+        _ => Err(MateMismatch {
+            r1_id: r1.id.to_string(),
+            r2_id: r2.id.to_string(),
+        }),
+    }
+}
+
+/// Which file (or interleaved slot) a record came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mate {
+    R1,
+    R2,
+}
+
+/// A record whose partner is missing (EOF on the other side, or the
+/// partner was skipped as malformed).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Orphan {
+    pub mate: Mate,
+    pub record: FastqRecord,
+}
+
+/// What to do with orphaned mates.
+#[derive(Default)]
+pub enum OrphanPolicy {
+    /// Stop with [`OrphanError`].
+    #[default]
+    Error,
+    /// Drop orphans silently (they are still counted).
+    Drop,
+    /// Hand orphans to a callback, e.g. one writing them to a separate file.
+    Divert(Box<dyn FnMut(Orphan) + Send>),
+}
+
+impl OrphanPolicy {
+    pub fn divert<F: FnMut(Orphan) + Send + 'static>(f: F) -> Self {
+        Self::Divert(Box::new(f))
+    }
+}
+
+impl fmt::Debug for OrphanPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Error => "Error",
+            Self::Drop => "Drop",
+            Self::Divert(_) => "Divert(..)",
+        })
+    }
+}
+
+/// An orphaned mate under `OrphanPolicy::Error`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("orphaned {mate:?} record {id:?}: partner missing")]
+pub struct OrphanError {
+    pub mate: Mate,
+    pub id: String,
+}
+
+/// Orphans seen so far, per side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrphanCounts {
+    pub r1: u64,
+    pub r2: u64,
+}
+
+impl OrphanCounts {
+    pub fn total(&self) -> u64 {
+        self.r1 + self.r2
+    }
+}
+
+/// Applies an [`OrphanPolicy`] and keeps [`OrphanCounts`]; shared by the
+/// paired and interleaved readers.
+#[derive(Debug, Default)]
+pub struct OrphanHandler {
+    policy: OrphanPolicy,
+    counts: OrphanCounts,
+}
+
+impl OrphanHandler {
+    pub fn new(policy: OrphanPolicy) -> Self {
+        Self {
+            policy,
+            counts: OrphanCounts::default(),
+        }
+    }
+
+    /// Count `record` as an orphan from `mate` and apply the policy.
+    pub fn handle(&mut self, mate: Mate, record: FastqRecord) -> Result<(), OrphanError> {
+        match mate {
+            Mate::R1 => self.counts.r1 += 1,
+            Mate::R2 => self.counts.r2 += 1,
+        }
+        match &mut self.policy {
+            OrphanPolicy::Error => Err(OrphanError {
+                mate,
+                id: record.id().to_string(),
+            }),
+            OrphanPolicy::Drop => Ok(()),
+            OrphanPolicy::Divert(f) => {
+                f(Orphan { mate, record });
+                Ok(())
+            }
+        }
+    }
+
+    pub fn counts(&self) -> OrphanCounts {
+        self.counts
+    }
+}
+
+/// Reads R1 and R2 files (plain or `.gz`) in lockstep, yielding mate pairs.
+///
+/// Each pair must pass [`are_mates`]; a mismatch is
+/// [`FormatError::PairDesync`], returned or skipped (with a warning) per
+/// the `error_policy` of the options, or handled per a [`MatePolicy`] set
+/// with [`with_mate_policy`](Self::with_mate_policy). Records left over
+/// when one file ends first go to the [`OrphanPolicy`] (default `Error`,
+/// surfaced as [`FastqError::Orphan`]), as does the partner of a record
+/// one reader fails on.
+///
+/// When the two sides stop agreeing on the record number (a reader skipped
+/// a malformed record or filtered one out) and the records are not mates,
+/// the record that is behind is an orphan and the later one waits for its
+/// partner, so pairing resumes instead of every later pair mismatching.
+pub struct PairedFastqReader {
+    r1: FastqReader,
+    r2: FastqReader,
+    error_policy: ErrorPolicy,
+    mate_policy: Option<MatePolicy>,
+    orphans: OrphanHandler,
+    /// A second error from the same step, returned by the next call.
+    pending: Option<FastqError>,
+    /// R1 and R2 records read ahead of the other side, waiting for their
+    /// partner after a resync.
+    held: [Option<(FastqRecord, IoContext)>; 2],
+    pairs: u64,
+    skipped_pairs: u64,
+}
+
+impl PairedFastqReader {
+    /// Open both files with the same options.
+    pub fn from_paths<P, Q>(r1: P, r2: Q, opts: ReaderOptions) -> Result<Self, FastqError>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let error_policy = opts.error_policy;
+        let r1 = FastqReader::from_path(r1, opts.clone())?;
+        let r2 = FastqReader::from_path(r2, opts)?;
+        Ok(Self::new(r1, r2, error_policy))
+    }
+
+    /// Pair two already open readers; `error_policy` applies to
+    /// mismatched pairs (each reader keeps its own for malformed records).
+    pub fn new(r1: FastqReader, r2: FastqReader, error_policy: ErrorPolicy) -> Self {
+        Self {
+            r1,
+            r2,
+            error_policy,
+            mate_policy: None,
+            orphans: OrphanHandler::default(),
+            pending: None,
+            held: [None, None],
+            pairs: 0,
+            skipped_pairs: 0,
+        }
+    }
+
+    /// Replace the default `OrphanPolicy::Error`.
+    pub fn with_orphan_policy(mut self, policy: OrphanPolicy) -> Self {
+        self.orphans = OrphanHandler::new(policy);
+        self
+    }
+
+    /// Handle mismatched mates per `policy` (through [`check_mates`])
+    /// instead of the error policy: `Error` returns
+    /// [`FormatError::PairDesync`], `SkipPair` drops the pair with a
+    /// warning, `TrustOrder` pairs records by position without comparing
+    /// IDs.
+    pub fn with_mate_policy(mut self, policy: MatePolicy) -> Self {
+        self.mate_policy = Some(policy);
+        self
+    }
+
+    /// Pairs yielded so far.
+    pub fn pairs(&self) -> u64 {
+        self.pairs
+    }
+
+    /// Mismatched pairs dropped under `ErrorPolicy::Skip` or
+    /// `MatePolicy::SkipPair`.
+    pub fn skipped_pairs(&self) -> u64 {
+        self.skipped_pairs
+    }
+
+    pub fn orphans(&self) -> OrphanCounts {
+        self.orphans.counts()
+    }
+
+    /// The R1 and R2 readers, e.g. for their positions or checksums.
+    pub fn readers(&self) -> (&FastqReader, &FastqReader) {
+        (&self.r1, &self.r2)
+    }
+
+    /// The next record of `mate`'s side and where it was read.
+    fn read(&mut self, mate: Mate) -> Option<Result<(FastqRecord, IoContext), FastqError>> {
+        let (held, rdr) = match mate {
+            Mate::R1 => (&mut self.held[0], &mut self.r1),
+            Mate::R2 => (&mut self.held[1], &mut self.r2),
+        };
+        if let Some(held) = held.take() {
+            return Some(Ok(held));
+        }
+        Some(rdr.next()?.map(|rec| (rec, FastqReader::position(rdr))))
+    }
+
+    fn orphan(
+        &mut self,
+        mate: Mate,
+        record: FastqRecord,
+        ctx: IoContext,
+    ) -> Result<(), FastqError> {
+        self.orphans
+            .handle(mate, record)
+            .map_err(|source| FastqError::Orphan { source, ctx })
+    }
+}
+
+impl Iterator for PairedFastqReader {
+    type Item = Result<(FastqRecord, FastqRecord), FastqError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.take() {
+            return Some(Err(e));
+        }
+        loop {
+            let ((a, pa), (b, pb)) = match (self.read(Mate::R1), self.read(Mate::R2)) {
+                (None, None) => return None,
+                (Some(Err(e)), Some(Err(e2))) => {
+                    self.pending = Some(e2);
+                    return Some(Err(e));
+                }
+                // the failed record's partner is left without a mate
+                (Some(Err(e)), partner) => {
+                    if let Some(Ok((b, pb))) = partner {
+                        self.pending = self.orphan(Mate::R2, b, pb).err();
+                    }
+                    return Some(Err(e));
+                }
+                (partner, Some(Err(e))) => {
+                    if let Some(Ok((a, pa))) = partner {
+                        self.pending = self.orphan(Mate::R1, a, pa).err();
+                    }
+                    return Some(Err(e));
+                }
+                (Some(Ok((a, pa))), None) => match self.orphan(Mate::R1, a, pa) {
+                    Ok(()) => continue,
+                    Err(e) => return Some(Err(e)),
+                },
+                (None, Some(Ok((b, pb)))) => match self.orphan(Mate::R2, b, pb) {
+                    Ok(()) => continue,
+                    Err(e) => return Some(Err(e)),
+                },
+                (Some(Ok(a)), Some(Ok(b))) => (a, b),
+            };
+            if pa.record_num != pb.record_num && !are_mates(a.as_ref_record(), b.as_ref_record()) {
+                // one reader dropped a record the other did not: the record
+                // further back lost its partner, the later one waits for its own
+                let (behind, rec, ctx) = if pa.record_num < pb.record_num {
+                    self.held[1] = Some((b, pb));
+                    (Mate::R1, a, pa)
+                } else {
+                    self.held[0] = Some((a, pa));
+                    (Mate::R2, b, pb)
+                };
+                match self.orphan(behind, rec, ctx) {
+                    Ok(()) => continue,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            match mate_verdict(&a, &b, self.mate_policy) {
+                Verdict::Keep => {
+                    self.pairs += 1;
+                    return Some(Ok((a, b)));
+                }
+                Verdict::SkipPair => {
+                    self.skipped_pairs += 1;
+                    continue;
+                }
+                Verdict::Mismatch(strict) => {
+                    let err = desync(&a, pa, &b, pb);
+                    if strict || self.error_policy == ErrorPolicy::Return {
+                        return Some(Err(err));
+                    }
+                    instrument::skip_event("pair", &err);
+                    self.skipped_pairs += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Pairs of consecutive records from an interleaved file, from
+/// [`FastqReader::pairs`].
+///
+/// Mates are checked like in [`PairedFastqReader`]. Under
+/// `ErrorPolicy::Skip` a mismatch orphans only the first record, pairing
+/// the second with the record after it, so one missing mate does not shift
+/// every later pair. Orphans (that first record, a trailing record without
+/// a mate, or the record before one the reader fails on) go to the
+/// [`OrphanPolicy`] (default `Error`, surfaced as [`FastqError::Orphan`]).
+pub struct InterleavedPairs {
+    rdr: FastqReader,
+    error_policy: ErrorPolicy,
+    mate_policy: Option<MatePolicy>,
+    orphans: OrphanHandler,
+    /// The second record of a mismatch, waiting for a new partner.
+    held: Option<(FastqRecord, IoContext)>,
+    /// An error to return on the next call.
+    pending: Option<FastqError>,
+    pairs: u64,
+    skipped: u64,
+}
+
+impl InterleavedPairs {
+    pub(crate) fn new(rdr: FastqReader, error_policy: ErrorPolicy) -> Self {
+        Self {
+            rdr,
+            error_policy,
+            mate_policy: None,
+            orphans: OrphanHandler::default(),
+            held: None,
+            pending: None,
+            pairs: 0,
+            skipped: 0,
+        }
+    }
+
+    /// Replace the default `OrphanPolicy::Error`.
+    pub fn with_orphan_policy(mut self, policy: OrphanPolicy) -> Self {
+        self.orphans = OrphanHandler::new(policy);
+        self
+    }
+
+    /// Pairs yielded so far.
+    pub fn pairs(&self) -> u64 {
+        self.pairs
+    }
+
+    /// Handle mismatched mates per `policy` (through [`check_mates`])
+    /// instead of the error policy: `Error` returns
+    /// [`FormatError::PairDesync`], `SkipPair` drops both records with a
+    /// warning (no realigning), `TrustOrder` pairs consecutive records
+    /// without comparing IDs.
+    pub fn with_mate_policy(mut self, policy: MatePolicy) -> Self {
+        self.mate_policy = Some(policy);
+        self
+    }
+
+    /// Records dropped as mismatched pairs under `MatePolicy::SkipPair`.
+    pub fn skipped_records(&self) -> u64 {
+        self.skipped
+    }
+
+    pub fn orphans(&self) -> OrphanCounts {
+        self.orphans.counts()
+    }
+
+    /// The underlying reader, e.g. for its position or checksums.
+    pub fn reader(&self) -> &FastqReader {
+        &self.rdr
+    }
+
+    fn read(&mut self) -> Option<Result<(FastqRecord, IoContext), FastqError>> {
+        if let Some(held) = self.held.take() {
+            return Some(Ok(held));
+        }
+        Some(self.rdr.next()?.map(|rec| (rec, self.rdr.position())))
+    }
+
+    fn orphan(&mut self, record: FastqRecord, ctx: IoContext) -> Result<(), FastqError> {
+        self.orphans
+            .handle(Mate::R1, record)
+            .map_err(|source| FastqError::Orphan { source, ctx })
+    }
+}
+
+impl Iterator for InterleavedPairs {
+    type Item = Result<(FastqRecord, FastqRecord), FastqError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.take() {
+            return Some(Err(e));
+        }
+        loop {
+            let (a, pa) = match self.read()? {
+                Ok(a) => a,
+                Err(e) => return Some(Err(e)),
+            };
+            let (b, pb) = match self.read() {
+                Some(Ok(b)) => b,
+                // the record the reader failed on was `a`'s partner
+                Some(Err(e)) => {
+                    self.pending = self.orphan(a, pa).err();
+                    return Some(Err(e));
+                }
+                None => return self.orphan(a, pa).err().map(Err),
+            };
+            match mate_verdict(&a, &b, self.mate_policy) {
+                Verdict::Keep => {
+                    self.pairs += 1;
+                    return Some(Ok((a, b)));
+                }
+                Verdict::SkipPair => {
+                    self.skipped += 2;
+                    continue;
+                }
+                Verdict::Mismatch(strict) => {
+                    if strict || self.error_policy == ErrorPolicy::Return {
+                        return Some(Err(desync(&a, pa, &b, pb)));
+                    }
+                    self.held = Some((b, pb));
+                    if let Err(e) = self.orphan(a, pa) {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// What a paired reader does with a candidate pair.
+enum Verdict {
+    Keep,
+    /// `MatePolicy::SkipPair` on a mismatch.
+    SkipPair,
+    /// Not mates; `true` when `MatePolicy::Error` makes it an error
+    /// whatever the error policy.
+    Mismatch(bool),
+}
+
+fn mate_verdict(a: &FastqRecord, b: &FastqRecord, policy: Option<MatePolicy>) -> Verdict {
+    let (ra, rb) = (a.as_ref_record(), b.as_ref_record());
+    match policy.map(|p| check_mates(ra, rb, p)) {
+        None if are_mates(ra, rb) => Verdict::Keep,
+        None => Verdict::Mismatch(false),
+        Some(Ok(MateCheck::Keep)) => Verdict::Keep,
+        Some(Ok(MateCheck::Skip)) => Verdict::SkipPair,
+        Some(Err(_)) => Verdict::Mismatch(true),
+    }
+}
+
+/// [`FormatError::PairDesync`] for records read at `pa` and `pb`, reported
+/// at `pa`.
+pub(crate) fn desync(a: &FastqRecord, pa: IoContext, b: &FastqRecord, pb: IoContext) -> FastqError {
+    let source = FormatError::PairDesync(Box::new(PairDesync {
+        r1_id: a.id().to_string(),
+        r1_record: pa.record_num,
+        r1_line: pa.record_line,
+        r2_id: b.id().to_string(),
+        r2_record: pb.record_num,
+        r2_line: pb.record_line,
+    }));
+    FastqError::fmt_err(source, pa)
+}
@@ -0,0 +1,170 @@
+//! Record processing on a rayon pool
+//! ([`FastqReader::par_process`](crate::FastqReader::par_process)).
+//!
+//! One thread reads batches of records; each batch is a rayon task mapping
+//! the user function over it. Results come back per batch, either as they
+//! finish or in input order. The number of batches in flight is bounded, so
+//! a slow consumer holds the reader back instead of buffering the file.
+
+use crate::error::FastqError;
+use crate::reader::FastqReader;
+use crate::record::FastqRecord;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::thread;
+
+/// Records per batch unless set otherwise.
+pub const DEFAULT_BATCH_RECORDS: usize = 1024;
+
+type BatchResult<T> = (u64, Result<Vec<T>, FastqError>);
+
+/// Configuration of a [`FastqReader::par_process`] run; start it with
+/// [`results`](Self::results).
+pub struct ParProcess<F> {
+    reader: FastqReader,
+    f: F,
+    batch: usize,
+    ordered: bool,
+}
+
+impl<F> ParProcess<F> {
+    pub(crate) fn new(reader: FastqReader, f: F) -> Self {
+        Self {
+            reader,
+            f,
+            batch: DEFAULT_BATCH_RECORDS,
+            ordered: true,
+        }
+    }
+
+    /// Records handed to a rayon task at once (default
+    /// [`DEFAULT_BATCH_RECORDS`]).
+    pub fn batch_size(mut self, n: usize) -> Self {
+        self.batch = n.max(1);
+        self
+    }
+
+    /// Yield results in input order (default `true`); `false` yields each
+    /// batch as soon as it is done.
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// Start reading and processing. Reading stops at the first error the
+    /// reader's policy returns; it is yielded after the results of the
+    /// records before it (in ordered mode).
+    pub fn results<T>(self) -> ParResults<T>
+    where
+        F: Fn(FastqRecord) -> T + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        let in_flight = 2 * rayon::current_num_threads();
+        let (tx, rx) = sync_channel(in_flight);
+        let (permits, permit_rx) = sync_channel(in_flight);
+        for _ in 0..in_flight {
+            let _ = permits.send(());
+        }
+        let Self {
+            reader,
+            f,
+            batch,
+            ordered,
+        } = self;
+        let f = Arc::new(f);
+        thread::spawn(move || dispatch(reader, f, batch, &tx, &permit_rx));
+        ParResults {
+            rx,
+            permits,
+            ordered,
+            next_seq: 0,
+            pending: BTreeMap::new(),
+            current: Vec::new().into_iter(),
+        }
+    }
+}
+
+/// Reader thread: read batches and spawn a rayon task for each.
+fn dispatch<F, T>(
+    mut reader: FastqReader,
+    f: Arc<F>,
+    batch: usize,
+    tx: &SyncSender<BatchResult<T>>,
+    permits: &Receiver<()>,
+) where
+    F: Fn(FastqRecord) -> T + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    for seq in 0.. {
+        if permits.recv().is_err() {
+            return;
+        }
+        let records = match reader.next_batch(batch) {
+            Ok(records) if records.is_empty() => return,
+            Ok(records) => records,
+            Err(err) => {
+                let _ = tx.send((seq, Err(err)));
+                return;
+            }
+        };
+        let (f, tx) = (Arc::clone(&f), tx.clone());
+        rayon::spawn(move || {
+            let out = records.into_iter().map(&*f).collect();
+            let _ = tx.send((seq, Ok(out)));
+        });
+    }
+}
+
+/// Results of [`ParProcess::results`]: one item per record, or the error
+/// that stopped reading.
+pub struct ParResults<T> {
+    rx: Receiver<BatchResult<T>>,
+    permits: SyncSender<()>,
+    ordered: bool,
+    next_seq: u64,
+    pending: BTreeMap<u64, Result<Vec<T>, FastqError>>,
+    current: std::vec::IntoIter<T>,
+}
+
+impl<T> ParResults<T> {
+    /// The next batch to yield, waiting for it if needed.
+    fn next_batch(&mut self) -> Option<Result<Vec<T>, FastqError>> {
+        if !self.ordered {
+            return self.rx.recv().ok().map(|(_, batch)| batch);
+        }
+        loop {
+            if let Some(batch) = self.pending.remove(&self.next_seq) {
+                self.next_seq += 1;
+                return Some(batch);
+            }
+            match self.rx.recv() {
+                Ok((seq, batch)) => {
+                    self.pending.insert(seq, batch);
+                }
+                // a task died without reporting; yield what is left
+                Err(_) => return self.pending.pop_first().map(|(_, batch)| batch),
+            }
+        }
+    }
+}
+
+impl<T> Iterator for ParResults<T> {
+    type Item = Result<T, FastqError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(out) = self.current.next() {
+                return Some(Ok(out));
+            }
+            match self.next_batch()? {
+                Ok(batch) => {
+                    let _ = self.permits.try_send(());
+                    self.current = batch.into_iter();
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
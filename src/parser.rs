@@ -0,0 +1,97 @@
+//! Zero-I/O entry point over in-memory bytes, driving [`RecordParser`].
+
+use crate::core::RecordParser;
+use crate::error::{FastqError, IoContext};
+use crate::instrument;
+use crate::policy::{ErrorPolicy, ReaderOptions};
+use crate::record::FastqRecord;
+
+/// Parse FASTQ records from an in-memory buffer with the same parser and
+/// error policy as [`FastqReader`](crate::FastqReader), without any I/O.
+///
+/// The buffer must already be decompressed.
+pub fn parse_records<'a>(data: &'a [u8], opts: &ReaderOptions) -> SliceRecords<'a> {
+    SliceRecords {
+        data,
+        line_num: 0,
+        byte_pos: 0,
+        parser: RecordParser::new(opts),
+        error_policy: opts.error_policy,
+        done: false,
+    }
+}
+
+/// Iterator returned by [`parse_records`].
+#[derive(Debug, Clone)]
+pub struct SliceRecords<'a> {
+    data: &'a [u8],
+    line_num: u64,
+    byte_pos: u64,
+    parser: RecordParser,
+    error_policy: ErrorPolicy,
+    done: bool,
+}
+
+impl<'a> SliceRecords<'a> {
+    /// Unparsed remainder of the input.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.data
+    }
+
+    fn next_line(&mut self) -> Option<&'a [u8]> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let n = self
+            .data
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(self.data.len(), |p| p + 1);
+        let (mut line, rest) = self.data.split_at(n);
+        self.data = rest;
+        self.line_num += 1;
+        self.byte_pos += n as u64;
+        if let Some(l) = line.strip_suffix(b"\n") {
+            line = l;
+        }
+        if let Some(l) = line.strip_suffix(b"\r") {
+            line = l;
+        }
+        Some(line)
+    }
+
+    #[inline]
+    fn ctx(&self) -> IoContext {
+        IoContext::at(self.byte_pos, self.line_num, None, &self.parser, 0)
+    }
+}
+
+impl Iterator for SliceRecords<'_> {
+    type Item = Result<FastqRecord, FastqError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let res = match self.next_line() {
+                Some(line) => self.parser.push_line(line),
+                None => {
+                    self.done = true;
+                    self.parser.finish().map(|_| None)
+                }
+            };
+            match res {
+                Ok(Some(rec)) => return Some(Ok(rec)),
+                Ok(None) => {}
+                Err(source) => {
+                    let err = FastqError::fmt_err(source, self.ctx());
+                    if self.error_policy.skips() {
+                        instrument::skip_event("malformed record", &err);
+                        self.parser.resync();
+                    } else {
+                        return Some(Err(err));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
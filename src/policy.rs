@@ -1,26 +1,624 @@
+use alloc::sync::Arc;
+use core::fmt;
+use core::time::Duration;
+
 /// Error handling policy compatible with CD-HIT flows.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum ErrorPolicy {
     /// Skip malformed records and continue (CD-HIT-like behavior).
     Skip,
     /// Return the first error to the caller (strict).
     Return,
+    /// Skip like [`Skip`](Self::Skip), keeping the first `limit` dropped
+    /// records in the reader's `skip_report()` for QC summaries.
+    Collect { limit: usize },
+}
+
+impl ErrorPolicy {
+    /// Whether malformed records are dropped rather than returned.
+    pub fn skips(self) -> bool {
+        self != ErrorPolicy::Return
+    }
 }
 
 /// How sequence/quality lines are laid out in FASTQ.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum LineMode {
     /// Sequence and quality occupy exactly one line each (CD-HIT typical).
     Single,
     /// Sequence/quality may span multiple lines (general FASTQ).
     Multi,
+    /// Parse the first [`AUTO_LINE_MODE_RECORDS`](crate::core::AUTO_LINE_MODE_RECORDS)
+    /// records as `Multi`, then settle on `Single` unless one of them
+    /// wrapped. Later records are parsed in the chosen mode.
+    Auto,
+}
+
+/// Format of the input records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum InputFormat {
+    /// FASTQ; FASTA entries are only read with `fastq_only: false`.
+    #[default]
+    Fastq,
+    /// FASTA, as with `fastq_only: false` (FASTQ records are read too).
+    Fasta,
+    /// Decide from the first non-blank line after decompression: `>` reads
+    /// as [`Fasta`](Self::Fasta), anything else as [`Fastq`](Self::Fastq).
+    Auto,
+}
+
+/// What to do with blank (empty or whitespace-only) lines between records
+/// or trailing at the end of input. A blank line inside a FASTQ record is
+/// always `FormatError::BlankLine` (or `EmptySequence` in place of the
+/// sequence), since skipping it would take the next header for quality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BlankLinePolicy {
+    /// Skip them silently.
+    #[default]
+    Tolerate,
+    /// Skip them, logging a warning for each.
+    Warn,
+    /// Reject them with `FormatError::BlankLine`.
+    Error,
+}
+
+/// Whether plain files are memory-mapped instead of read through a buffer.
+/// Compressed files and non-regular files (FIFOs, `/dev/stdin`, sockets)
+/// are always streamed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MmapPolicy {
+    /// Map plain regular files when built with the `mmap` feature, falling
+    /// back to buffered reads if mapping fails.
+    #[default]
+    Auto,
+    /// Map plain files or fail: a non-regular file, a failed mapping or a
+    /// build without the `mmap` feature is an error.
+    Always,
+    /// Always use buffered reads.
+    Never,
+}
+
+/// Access pattern announced to the kernel for files opened by path, so it
+/// can tune read-ahead: `madvise` on memory-mapped files, `posix_fadvise`
+/// on files read through a buffer (with the `fadvise` feature). Unix
+/// only; failures are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AccessAdvice {
+    /// No hint.
+    Normal,
+    /// The file is read once, front to back.
+    #[default]
+    Sequential,
+    /// `Sequential`, and start reading the whole file into the page cache
+    /// right away; for cold files that fit in memory.
+    WillNeed,
+}
+
+/// Digest computed over the input while it is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha256,
+}
+
+/// ASCII offset of quality scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum QualityEncoding {
+    /// Sanger / Illumina 1.8+.
+    #[default]
+    Phred33,
+    /// Illumina 1.3–1.7.
+    Phred64,
+    /// Solexa / Illumina 1.0: offset 64, Solexa (odds-based) scores from
+    /// -5, which differ from Phred below 10.
+    Solexa,
+}
+
+/// Rate cap for [`ReaderOptions::throttle`]; a rate of 0 means unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Throttle {
+    /// Raw input bytes (compressed, for `.gz`) per second.
+    BytesPerSec(u64),
+    /// Records yielded per second.
+    RecordsPerSec(u64),
+}
+
+#[cfg(feature = "std")]
+impl Throttle {
+    pub(crate) fn bytes_per_sec(self) -> Option<u64> {
+        match self {
+            Throttle::BytesPerSec(n) => Some(n),
+            Throttle::RecordsPerSec(_) => None,
+        }
+    }
+
+    pub(crate) fn records_per_sec(self) -> Option<u64> {
+        match self {
+            Throttle::RecordsPerSec(n) => Some(n),
+            Throttle::BytesPerSec(_) => None,
+        }
+    }
+}
+
+/// 256-entry table of the bytes accepted in sequence lines, for
+/// [`ReaderOptions::allowed_seq_bytes`]. Stored as a bitset.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct AllowedBytes([u64; 4]);
+
+impl AllowedBytes {
+    /// Use a caller-built table: byte `b` is allowed when `table[b]` is set.
+    pub fn new(table: [bool; 256]) -> Self {
+        let mut bits = [0u64; 4];
+        for (b, &ok) in table.iter().enumerate() {
+            if ok {
+                bits[b / 64] |= 1 << (b % 64);
+            }
+        }
+        Self(bits)
+    }
+
+    /// Allow exactly the bytes in `set`.
+    pub fn from_set(set: &[u8]) -> Self {
+        Self([0; 4]).with(set)
+    }
+
+    /// `ACGTN`, either case.
+    pub fn nucleotides() -> Self {
+        Self::from_set(b"ACGTNacgtn")
+    }
+
+    /// IUPAC nucleotide codes including `U`, either case.
+    pub fn iupac() -> Self {
+        Self::from_set(b"ACGTURYSWKMBDHVNacgturyswkmbdhvn")
+    }
+
+    /// IUPAC amino-acid codes (every letter, `B`/`J`/`O`/`U`/`X`/`Z`
+    /// included), either case, and `*` for stop; for protein input as
+    /// CD-HIT clusters it.
+    pub fn protein() -> Self {
+        Self::from_set(b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz*")
+    }
+
+    /// The same table with `extra` bytes allowed too.
+    pub fn with(mut self, extra: &[u8]) -> Self {
+        for &b in extra {
+            self.0[b as usize / 64] |= 1 << (b % 64);
+        }
+        self
+    }
+
+    #[inline]
+    pub fn allows(&self, b: u8) -> bool {
+        self.0[b as usize / 64] & (1 << (b % 64)) != 0
+    }
 }
 
+/// Serialized as a string of the allowed bytes, each as the char of the
+/// same code point (`"ACGTNacgtn"`).
+#[cfg(feature = "serde")]
+impl serde::Serialize for AllowedBytes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let set: alloc::string::String = (0..=255u8)
+            .filter(|&b| self.allows(b))
+            .map(char::from)
+            .collect();
+        serializer.serialize_str(&set)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for AllowedBytes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let set = <alloc::string::String as serde::Deserialize>::deserialize(deserializer)?;
+        let mut bytes = alloc::vec::Vec::with_capacity(set.len());
+        for c in set.chars() {
+            let b = u8::try_from(c).map_err(|_| {
+                serde::de::Error::custom(format_args!("{c:?} is not a single byte"))
+            })?;
+            bytes.push(b);
+        }
+        Ok(Self::from_set(&bytes))
+    }
+}
+
+impl fmt::Debug for AllowedBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let allowed = (0..=255u8).filter(|&b| self.allows(b));
+        f.debug_set()
+            .entries(allowed.map(|b| b.escape_ascii()))
+            .finish()
+    }
+}
+
+/// Default of [`ProgressSink::every_bytes`]: 4 MiB of raw input.
+pub const DEFAULT_PROGRESS_BYTES: u64 = 4 << 20;
+
+/// Snapshot handed to a [`ProgressSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Records yielded so far.
+    pub records: u64,
+    /// Bytes read from the source (compressed for `.gz`).
+    pub raw_bytes: u64,
+    /// Size of the source, when known (files and in-memory buffers).
+    pub total_bytes: Option<u64>,
+    /// Time since the reader was opened.
+    pub elapsed: Duration,
+    /// Set on the last call, at end of input.
+    pub done: bool,
+}
+
+impl Progress {
+    /// Share of the source read, in `0.0..=1.0`.
+    pub fn fraction(&self) -> Option<f64> {
+        let total = self.total_bytes.filter(|&t| t > 0)?;
+        Some((self.raw_bytes as f64 / total as f64).min(1.0))
+    }
+
+    /// Time left at the average rate so far.
+    pub fn eta(&self) -> Option<Duration> {
+        let total = self.total_bytes?;
+        if self.raw_bytes == 0 {
+            return None;
+        }
+        let left = total.saturating_sub(self.raw_bytes) as f64 / self.raw_bytes as f64;
+        Some(self.elapsed.mul_f64(left))
+    }
+}
+
+/// Callback for [`ReaderOptions::progress`], called every
+/// [`every_bytes`](Self::every_bytes) of raw input, every
+/// [`every_records`](Self::every_records) records if set, and once at end of
+/// input.
+#[derive(Clone)]
+pub struct ProgressSink {
+    pub(crate) every_bytes: u64,
+    pub(crate) every_records: Option<u64>,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) callback: Arc<dyn Fn(&Progress) + Send + Sync>,
+}
+
+impl ProgressSink {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        Self {
+            every_bytes: DEFAULT_PROGRESS_BYTES,
+            every_records: None,
+            callback: Arc::new(f),
+        }
+    }
+
+    /// Report each time this many more raw bytes were read (default
+    /// [`DEFAULT_PROGRESS_BYTES`]).
+    pub fn every_bytes(mut self, n: u64) -> Self {
+        self.every_bytes = n.max(1);
+        self
+    }
+
+    /// Also report every `n` records.
+    pub fn every_records(mut self, n: u64) -> Self {
+        self.every_records = Some(n.max(1));
+        self
+    }
+}
+
+impl fmt::Debug for ProgressSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressSink")
+            .field("every_bytes", &self.every_bytes)
+            .field("every_records", &self.every_records)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Rules for [`ReaderOptions::sanitize_ids`]. Whitespace, `;` and `,` in
+/// IDs are always replaced with `_`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct IdSanitize {
+    /// Truncate IDs to this many bytes (suffixes included).
+    pub max_len: Option<usize>,
+    /// Append `_2`, `_3`, ... to IDs already seen. Every ID is remembered,
+    /// so memory grows with the number of records.
+    pub unique: bool,
+}
+
+/// Deserializable from configuration files with the `serde` feature:
+/// missing fields keep their defaults and unknown ones are rejected.
+/// `progress` is a callback and is neither serialized nor read.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
 pub struct ReaderOptions {
     pub error_policy: ErrorPolicy,
+    /// `false` accepts FASTA entries (`>` headers), alone or mixed in with
+    /// FASTQ records, as CD-HIT does. Read them with `next_entry` (sync and
+    /// async readers); the record iterators treat them as
+    /// `FormatError::FastaHeaderDetected`.
     pub fastq_only: bool,
+    /// Expected format; see [`InputFormat::Auto`] to sniff it.
+    pub input_format: InputFormat,
     pub line_mode: LineMode,
+    /// Digest of the bytes as stored (compressed for `.gz`), available after EOF.
+    pub checksum_raw: Option<ChecksumAlgorithm>,
+    /// Digest of the decompressed text, available after EOF.
+    pub checksum_decompressed: Option<ChecksumAlgorithm>,
+    /// Verify every gzip member's CRC32/ISIZE trailer (and header CRC16)
+    /// and fail with a dedicated error on mismatch, regardless of
+    /// `error_policy`.
+    pub strict_gzip: bool,
+    /// Decode `.gz` input member by member and record per-member record and
+    /// byte counts (`FastqReader::gzip_members`), e.g. to split a
+    /// `cat L001.gz L002.gz` file back into lanes. Sync path reader only.
+    pub track_gzip_members: bool,
+    /// Memory-map plain files; see [`MmapPolicy`].
+    pub use_mmap: MmapPolicy,
+    /// Read-ahead hint for files opened by path; see [`AccessAdvice`].
+    pub access_advice: AccessAdvice,
+    /// Read plain files through io_uring, with several reads in flight
+    /// (`uring` feature, Linux). Takes precedence over mapping under
+    /// `MmapPolicy::Auto`; where the kernel refuses a ring the file is read
+    /// as usual.
+    pub io_uring: bool,
+    /// Buffer the parser reads decompressed text from; `None` keeps the
+    /// default (256 KiB, 512 KiB over a memory map). At least 1 KiB.
+    pub read_buffer_bytes: Option<usize>,
+    /// Buffer of compressed input in front of the gzip decoder; `None`
+    /// keeps the default (256 KiB). At least 1 KiB.
+    pub decompress_buffer_bytes: Option<usize>,
+    /// Fail once decompressed output exceeds this multiple of the
+    /// compressed input (checked after the first MiB), guarding against
+    /// gzip bombs. Applies to compressed sync inputs.
+    pub max_expansion_ratio: Option<u32>,
+    /// Fail when a single record (header through quality) exceeds this many
+    /// bytes; also bounds how much one unterminated line can allocate.
+    pub max_record_bytes: Option<usize>,
+    /// Under `ErrorPolicy::Skip`/`Collect`, fail with
+    /// `LimitError::TooManySkipped` instead of dropping a record once this
+    /// many were dropped, so a corrupt or mistyped file is not consumed
+    /// silently end to end.
+    pub max_skipped: Option<u64>,
+    /// Pace reading for shared network storage or paid egress; the sync
+    /// reader sleeps the thread, the async reader awaits a timer.
+    pub throttle: Option<Throttle>,
+    /// Report reading progress (raw bytes against the source size), e.g.
+    /// to drive a progress bar or log an ETA. Sync reader only.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub progress: Option<ProgressSink>,
+    /// Encoding the input is expected to use.
+    pub quality_encoding: QualityEncoding,
+    /// Detect the encoding from the data and rewrite Phred+64 qualities to
+    /// Phred+33, so yielded records always use one encoding. Undecided
+    /// records follow `quality_encoding`.
+    pub rebase_quality: bool,
+    /// Reject quality bytes outside `quality_encoding`'s printable range
+    /// with `FormatError::InvalidQualityChar`, e.g. a binary blob
+    /// concatenated into a FASTQ file.
+    pub validate_quality: bool,
+    /// Keep whatever follows `+` in `FastqRecord::plus_line` so rewriting
+    /// tools can pass it through.
+    pub keep_plus_line: bool,
+    /// Reject a `+` line that repeats anything but the record's ID
+    /// (optionally with its description) with
+    /// `FormatError::PlusLineMismatch`; a bare `+` always passes.
+    pub strict_plus_line: bool,
+    /// Keep the exact input bytes of every FASTQ record in
+    /// `FastqRecord::raw` for lossless pass-through and debugging.
+    /// Sync reader only.
+    pub keep_raw: bool,
+    /// `false` checks each quality line's length (and bytes, under
+    /// `validate_quality`) but does not store it: records come out with an
+    /// empty quality, for consumers such as clustering that never look at
+    /// it. `rebase_quality` then has nothing to rewrite, and FASTQ output
+    /// (`FastqWriter`, `convert`, `Demultiplexer`) rejects such records
+    /// with an `InvalidInput` error; FASTA output is unaffected.
+    pub keep_quality: bool,
+    /// Blank lines between records and at the end of input; tolerated by
+    /// default.
+    pub blank_lines: BlankLinePolicy,
+    /// Rewrite record IDs so tools that split on separators or truncate
+    /// (CD-HIT among them) keep them intact and distinct.
+    pub sanitize_ids: Option<IdSanitize>,
+    /// Reject sequence lines containing a byte outside this table;
+    /// `None` accepts anything.
+    pub allowed_seq_bytes: Option<AllowedBytes>,
+    /// Drop records shorter than this many bases. Dropped records are not
+    /// errors; readers count them in their stats.
+    pub min_len: Option<usize>,
+    /// Drop records longer than this many bases.
+    pub max_len: Option<usize>,
+    /// Drop records whose share of `N`/`n` bases exceeds this (0.0–1.0).
+    pub max_n_fraction: Option<f64>,
+}
+
+/// Floor for `read_buffer_bytes` and `decompress_buffer_bytes`.
+#[cfg(feature = "std")]
+const MIN_BUFFER_BYTES: usize = 1024;
+
+#[cfg(feature = "std")]
+impl ReaderOptions {
+    /// `read_buffer_bytes`, else `default`.
+    pub(crate) fn read_buffer(&self, default: usize) -> usize {
+        self.read_buffer_bytes
+            .unwrap_or(default)
+            .max(MIN_BUFFER_BYTES)
+    }
+
+    /// `decompress_buffer_bytes`, else `default`.
+    pub(crate) fn decompress_buffer(&self, default: usize) -> usize {
+        self.decompress_buffer_bytes
+            .unwrap_or(default)
+            .max(MIN_BUFFER_BYTES)
+    }
+}
+
+impl ReaderOptions {
+    /// A [`ReaderOptionsBuilder`] starting from the defaults.
+    pub fn builder() -> ReaderOptionsBuilder {
+        ReaderOptionsBuilder::default()
+    }
+
+    /// A [`ReaderOptionsBuilder`] starting from these options, e.g. to
+    /// adjust a preset.
+    pub fn into_builder(self) -> ReaderOptionsBuilder {
+        ReaderOptionsBuilder { opts: self }
+    }
+
+    /// Input as `cd-hit` (protein) reads it: FASTQ with one sequence and
+    /// one quality line per record, or FASTA; malformed records and records
+    /// with bytes outside [`AllowedBytes::protein`] are skipped. FASTA
+    /// entries come only from `FastqReader::next_entry`; the record
+    /// iterator skips them as `FastaHeaderDetected`.
+    pub fn cd_hit() -> Self {
+        Self {
+            fastq_only: false,
+            allowed_seq_bytes: Some(AllowedBytes::protein()),
+            ..Self::default()
+        }
+    }
+
+    /// Input as `cd-hit-est` (nucleotide) reads it: as [`cd_hit`](Self::cd_hit),
+    /// with sequences limited to [`AllowedBytes::iupac`].
+    pub fn cd_hit_est() -> Self {
+        Self {
+            allowed_seq_bytes: Some(AllowedBytes::iupac()),
+            ..Self::cd_hit()
+        }
+    }
+
+    /// Reject anything questionable: the first malformed record is an
+    /// error, as are blank lines, quality bytes outside the encoding, a
+    /// `+` line naming another record, FASTA entries and damaged gzip
+    /// members.
+    pub fn strict() -> Self {
+        Self {
+            error_policy: ErrorPolicy::Return,
+            strict_gzip: true,
+            validate_quality: true,
+            strict_plus_line: true,
+            blank_lines: BlankLinePolicy::Error,
+            ..Self::default()
+        }
+    }
+
+    /// Read whatever can be read: wrapped (multi-line) records, FASTA
+    /// entries (through `next_entry`, as with [`cd_hit`](Self::cd_hit))
+    /// and Phred+64 qualities (rebased to Phred+33) are accepted, malformed
+    /// records skipped.
+    pub fn lenient() -> Self {
+        Self {
+            fastq_only: false,
+            line_mode: LineMode::Multi,
+            rebase_quality: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Builder of [`ReaderOptions`]: one setter per field, from
+/// [`ReaderOptions::builder`] or a preset's
+/// [`into_builder`](ReaderOptions::into_builder).
+///
+/// ```
+/// use kira_cdh_compat_fastq_reader::{ErrorPolicy, ReaderOptions};
+///
+/// let opts = ReaderOptions::cd_hit_est()
+///     .into_builder()
+///     .error_policy(ErrorPolicy::Return)
+///     .max_record_bytes(1 << 20)
+///     .build();
+/// assert_eq!(opts.max_record_bytes, Some(1 << 20));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ReaderOptionsBuilder {
+    opts: ReaderOptions,
+}
+
+macro_rules! setters {
+    (
+        plain { $($field:ident: $ty:ty,)* }
+        optional { $($opt:ident: $inner:ty,)* }
+    ) => {
+        $(
+            #[doc = concat!("Set [`ReaderOptions::", stringify!($field), "`].")]
+            pub fn $field(mut self, value: $ty) -> Self {
+                self.opts.$field = value;
+                self
+            }
+        )*
+        $(
+            #[doc = concat!("Set [`ReaderOptions::", stringify!($opt), "`]; takes the value or an `Option`.")]
+            pub fn $opt(mut self, value: impl Into<Option<$inner>>) -> Self {
+                self.opts.$opt = value.into();
+                self
+            }
+        )*
+    };
+}
+
+impl ReaderOptionsBuilder {
+    setters! {
+        plain {
+            error_policy: ErrorPolicy,
+            fastq_only: bool,
+            input_format: InputFormat,
+            line_mode: LineMode,
+            strict_gzip: bool,
+            track_gzip_members: bool,
+            use_mmap: MmapPolicy,
+            access_advice: AccessAdvice,
+            io_uring: bool,
+            quality_encoding: QualityEncoding,
+            rebase_quality: bool,
+            validate_quality: bool,
+            keep_plus_line: bool,
+            strict_plus_line: bool,
+            keep_raw: bool,
+            keep_quality: bool,
+            blank_lines: BlankLinePolicy,
+        }
+        optional {
+            checksum_raw: ChecksumAlgorithm,
+            checksum_decompressed: ChecksumAlgorithm,
+            read_buffer_bytes: usize,
+            decompress_buffer_bytes: usize,
+            max_expansion_ratio: u32,
+            max_record_bytes: usize,
+            max_skipped: u64,
+            throttle: Throttle,
+            progress: ProgressSink,
+            sanitize_ids: IdSanitize,
+            allowed_seq_bytes: AllowedBytes,
+            min_len: usize,
+            max_len: usize,
+            max_n_fraction: f64,
+        }
+    }
+
+    pub fn build(self) -> ReaderOptions {
+        self.opts
+    }
 }
 
 impl Default for ReaderOptions {
@@ -28,7 +626,35 @@ impl Default for ReaderOptions {
         Self {
             error_policy: ErrorPolicy::Skip,
             fastq_only: true,
+            input_format: InputFormat::Fastq,
             line_mode: LineMode::Single, // default to single-line for CD-HIT compatibility
+            checksum_raw: None,
+            checksum_decompressed: None,
+            strict_gzip: false,
+            track_gzip_members: false,
+            use_mmap: MmapPolicy::Auto,
+            access_advice: AccessAdvice::Sequential,
+            io_uring: false,
+            read_buffer_bytes: None,
+            decompress_buffer_bytes: None,
+            max_expansion_ratio: None,
+            max_record_bytes: None,
+            max_skipped: None,
+            throttle: None,
+            progress: None,
+            quality_encoding: QualityEncoding::Phred33,
+            rebase_quality: false,
+            validate_quality: false,
+            keep_plus_line: false,
+            strict_plus_line: false,
+            keep_raw: false,
+            keep_quality: true,
+            blank_lines: BlankLinePolicy::Tolerate,
+            sanitize_ids: None,
+            allowed_seq_bytes: None,
+            min_len: None,
+            max_len: None,
+            max_n_fraction: None,
         }
     }
 }
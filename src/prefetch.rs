@@ -0,0 +1,105 @@
+//! Reading and decompression on a dedicated thread
+//! ([`FastqReader::with_prefetch_thread`](crate::FastqReader::with_prefetch_thread)).
+//!
+//! The thread fills fixed-size chunks from the reader's decoded input and
+//! hands them over a bounded channel; consumed chunks travel back to be
+//! refilled, so two buffers in flight keep I/O and parsing overlapped
+//! without allocating per chunk.
+
+use std::io::{self, BufRead, Read};
+use std::sync::mpsc::{Receiver, SyncSender, TryRecvError, sync_channel};
+use std::thread;
+
+/// Decompressed bytes per chunk.
+const CHUNK_BYTES: usize = 1 << 20;
+
+/// Chunks queued ahead of the parser.
+const CHUNKS_AHEAD: usize = 2;
+
+/// Consumer side: a `BufRead` over the chunks the thread produces.
+pub(crate) struct PrefetchRead {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    recycle: SyncSender<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl PrefetchRead {
+    /// Move `src` to a new thread and read it from there.
+    pub(crate) fn spawn(src: Box<dyn BufRead + Send>) -> Self {
+        let (tx, chunks) = sync_channel(CHUNKS_AHEAD);
+        let (recycle, spent) = sync_channel(CHUNKS_AHEAD + 1);
+        thread::spawn(move || produce(src, &tx, &spent));
+        Self {
+            chunks,
+            recycle,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+/// Fill chunks until EOF, an error, or the consumer goes away.
+fn produce(
+    mut src: Box<dyn BufRead + Send>,
+    tx: &SyncSender<io::Result<Vec<u8>>>,
+    spent: &Receiver<Vec<u8>>,
+) {
+    loop {
+        let mut chunk = match spent.try_recv() {
+            Ok(chunk) => chunk,
+            Err(TryRecvError::Empty) => Vec::with_capacity(CHUNK_BYTES),
+            Err(TryRecvError::Disconnected) => return,
+        };
+        chunk.clear();
+        match (&mut src).take(CHUNK_BYTES as u64).read_to_end(&mut chunk) {
+            Ok(0) => return,
+            Ok(_) => {
+                if tx.send(Ok(chunk)).is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                // the bytes decoded before the error still hold records
+                if !chunk.is_empty() && tx.send(Ok(chunk)).is_err() {
+                    return;
+                }
+                let _ = tx.send(Err(e));
+                return;
+            }
+        }
+    }
+}
+
+impl Read for PrefetchRead {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let n = self.fill_buf()?.read(out)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for PrefetchRead {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.chunk.len() {
+            match self.chunks.recv() {
+                Ok(Ok(chunk)) => {
+                    let spent = std::mem::replace(&mut self.chunk, chunk);
+                    let _ = self.recycle.try_send(spent);
+                    self.pos = 0;
+                }
+                Ok(Err(e)) => return Err(e),
+                // the thread is done: EOF (after any error it sent)
+                Err(_) => {
+                    self.chunk.clear();
+                    self.pos = 0;
+                }
+            }
+        }
+        Ok(&self.chunk[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.chunk.len());
+    }
+}
@@ -0,0 +1,227 @@
+//! Cheap look at the start of an input before committing to a reader setup.
+
+use crate::error::{FastqError, IoContext};
+use crate::paired::are_mates;
+use crate::policy::{ErrorPolicy, LineMode, QualityEncoding, ReaderOptions};
+use crate::reader::FastqReader;
+use crate::record::FastqRecord;
+use crate::util::{looks_like_gzip, open_file};
+
+#[cfg(feature = "gzip")]
+use flate2::read::MultiGzDecoder;
+#[cfg(feature = "gzip")]
+use std::io::Read;
+use std::path::Path;
+
+/// Records read by [`probe`].
+pub const PROBE_RECORDS: usize = 64;
+
+/// Compressed bytes decoded by [`estimate_total_records`] to measure the
+/// compression ratio of a gzip input.
+pub const RATIO_SAMPLE_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Whether a single file holds interleaved paired-end reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interleaving {
+    /// Consecutive records pair up as mates (R1, R2, R1, R2, ...).
+    Interleaved,
+    /// At least one consecutive pair is not a mate pair.
+    NotInterleaved,
+    /// Fewer than two records to judge from.
+    Unknown,
+}
+
+/// What [`probe`] found at the start of an input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeReport {
+    /// Gzip by extension or magic bytes.
+    pub gzip: bool,
+    /// Records successfully parsed from the start of the input.
+    pub records_sampled: usize,
+    pub interleaving: Interleaving,
+    /// Wrapping detected by [`LineMode::Auto`]; `None` without records.
+    pub line_mode: Option<LineMode>,
+}
+
+impl ProbeReport {
+    pub fn is_interleaved(&self) -> bool {
+        self.interleaving == Interleaving::Interleaved
+    }
+}
+
+/// Read up to [`PROBE_RECORDS`] records from `path` and report on them.
+/// Parsing uses `opts` except that errors are always returned and the line
+/// mode is detected ([`LineMode::Auto`]).
+pub fn probe<P: AsRef<Path>>(path: P, opts: &ReaderOptions) -> Result<ProbeReport, FastqError> {
+    let path = path.as_ref();
+    let f = open_file(path).map_err(|e| {
+        FastqError::io_err(
+            e,
+            IoContext {
+                byte_pos: 0,
+                line_num: 0,
+                record_num: 0,
+                record_line: 0,
+                label: None,
+                virtual_offset: None,
+            },
+        )
+    })?;
+    let gzip = path.extension().and_then(|s| s.to_str()) == Some("gz")
+        || looks_like_gzip(&f).unwrap_or(false);
+
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        line_mode: LineMode::Auto,
+        ..opts.clone()
+    };
+    let mut rdr = FastqReader::from_path(path, opts)?;
+    let sample = rdr
+        .by_ref()
+        .take(PROBE_RECORDS)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(ProbeReport {
+        gzip,
+        records_sampled: sample.len(),
+        interleaving: detect_interleaving(&sample),
+        line_mode: rdr.line_mode(),
+    })
+}
+
+/// Detect the quality encoding of `path` from the qualities of its first
+/// `records` records taken together; `None` when they fit every encoding.
+/// Parsing follows `opts`, with qualities left as stored.
+pub fn detect_quality_encoding<P: AsRef<Path>>(
+    path: P,
+    opts: &ReaderOptions,
+    records: usize,
+) -> Result<Option<QualityEncoding>, FastqError> {
+    let opts = ReaderOptions {
+        rebase_quality: false,
+        ..opts.clone()
+    };
+    let sample = FastqReader::from_path(path, opts)?
+        .take(records)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(QualityEncoding::detect_many(
+        sample.iter().map(FastqRecord::qual),
+    ))
+}
+
+/// Approximate record count from [`estimate_total_records`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordEstimate {
+    /// Estimated total records in the input.
+    pub records: u64,
+    /// Records parsed to measure the mean record size.
+    pub records_sampled: usize,
+    /// Mean decompressed bytes per sampled record.
+    pub mean_record_bytes: f64,
+    /// Decompressed bytes per input byte (`1.0` for plain input).
+    pub compression_ratio: f64,
+    /// The whole input was parsed while sampling, so `records` is a count.
+    pub exact: bool,
+}
+
+/// Estimate the number of records in `path` from the mean size of the
+/// first `sample_records` records, the file size and, for gzip, the
+/// compression ratio of its first [`RATIO_SAMPLE_BYTES`]. Meant for progress
+/// reporting; parsing follows `opts`.
+pub fn estimate_total_records<P: AsRef<Path>>(
+    path: P,
+    opts: &ReaderOptions,
+    sample_records: usize,
+) -> Result<RecordEstimate, FastqError> {
+    let path = path.as_ref();
+    let ctx = || IoContext {
+        byte_pos: 0,
+        line_num: 0,
+        record_num: 0,
+        record_line: 0,
+        label: Some(path.display().to_string().into()),
+        virtual_offset: None,
+    };
+    let f = open_file(path).map_err(|e| FastqError::io_err(e, ctx()))?;
+    let file_len = f
+        .metadata()
+        .map_err(|e| FastqError::io_err(e, ctx()))?
+        .len();
+    let gzip = path.extension().and_then(|s| s.to_str()) == Some("gz")
+        || looks_like_gzip(&f).unwrap_or(false);
+
+    let mut rdr = FastqReader::from_path(path, opts.clone())?;
+    let mut sampled = 0;
+    while sampled < sample_records {
+        match rdr.next() {
+            Some(rec) => {
+                rec?;
+                sampled += 1;
+            }
+            None => break,
+        }
+    }
+    let sampled_bytes = rdr.position().byte_pos;
+    let exact = sampled < sample_records || rdr.peek_n(1).is_empty();
+    let mean_record_bytes = if sampled == 0 {
+        0.0
+    } else {
+        sampled_bytes as f64 / sampled as f64
+    };
+
+    #[cfg(feature = "gzip")]
+    let compression_ratio = if gzip { gzip_ratio(f, file_len) } else { 1.0 };
+    #[cfg(not(feature = "gzip"))]
+    let compression_ratio = {
+        debug_assert!(!gzip);
+        1.0
+    };
+
+    let records = if exact || mean_record_bytes == 0.0 {
+        sampled as u64
+    } else {
+        let total_bytes = file_len as f64 * compression_ratio;
+        ((total_bytes / mean_record_bytes).round() as u64).max(sampled as u64)
+    };
+    Ok(RecordEstimate {
+        records,
+        records_sampled: sampled,
+        mean_record_bytes,
+        compression_ratio,
+        exact,
+    })
+}
+
+/// Decompressed-to-compressed ratio over the first [`RATIO_SAMPLE_BYTES`]
+/// of a gzip file. A read error (the sample cut a member short) just ends
+/// the measurement.
+#[cfg(feature = "gzip")]
+fn gzip_ratio(f: std::fs::File, file_len: u64) -> f64 {
+    let consumed = file_len.min(RATIO_SAMPLE_BYTES);
+    if consumed == 0 {
+        return 1.0;
+    }
+    let mut dec = MultiGzDecoder::new(f.take(consumed));
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut out = 0u64;
+    while let Ok(n @ 1..) = dec.read(&mut buf) {
+        out += n as u64;
+    }
+    out as f64 / consumed as f64
+}
+
+/// Judge interleaving from leading records: every consecutive pair must be
+/// mates (`/1`–`/2`, CASAVA ` 1:`/` 2:`, or identical IDs). A trailing odd
+/// record is ignored since the sample may end mid-pair.
+pub fn detect_interleaving(records: &[FastqRecord]) -> Interleaving {
+    if records.len() < 2 {
+        return Interleaving::Unknown;
+    }
+    let all_mates = records
+        .chunks_exact(2)
+        .all(|p| are_mates(p[0].as_ref_record(), p[1].as_ref_record()));
+    if all_mates {
+        Interleaving::Interleaved
+    } else {
+        Interleaving::NotInterleaved
+    }
+}
@@ -0,0 +1,220 @@
+//! Python module (feature `python`).
+//!
+//! Exposes `FastqReader(path, strict=False, multi_line=False)`, an iterator
+//! of `(id, desc, seq, qual)` tuples with the same options as `fq_open` in
+//! the C ABI: the CD-HIT-like skip policy unless `strict`. `desc` is `None` when the header has none;
+//! bytes that are not UTF-8 come out as U+FFFD. I/O failures raise
+//! `OSError`, everything else `ValueError`.
+//!
+//! `FastqReader.records()` iterates the same records as `FastqRecord`
+//! objects instead: `id` and `desc` are strings, while `seq` and `qual` are
+//! read-only `memoryview`s over the record's own buffer, so no bytes are
+//! copied until the caller asks for them (`bytes(rec.seq)`).
+
+use crate::error::FastqError;
+use crate::policy::{ErrorPolicy, LineMode, ReaderOptions};
+use crate::reader::FastqReader;
+use crate::record::FastqRecord;
+
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::ffi;
+use pyo3::prelude::*;
+use pyo3::types::PyMemoryView;
+use std::os::raw::c_int;
+
+/// `(id, desc, seq, qual)`.
+type RecordTuple = (String, Option<String>, String, String);
+
+/// Python `FastqReader`.
+#[pyclass(
+    name = "FastqReader",
+    module = "kira_cdh_compat_fastq_reader",
+    unsendable
+)]
+pub struct PyFastqReader {
+    inner: FastqReader,
+}
+
+#[pymethods]
+impl PyFastqReader {
+    #[new]
+    #[pyo3(signature = (path, strict = false, multi_line = false))]
+    fn new(path: std::path::PathBuf, strict: bool, multi_line: bool) -> PyResult<Self> {
+        let opts = ReaderOptions {
+            error_policy: if strict {
+                ErrorPolicy::Return
+            } else {
+                ErrorPolicy::Skip
+            },
+            line_mode: if multi_line {
+                LineMode::Multi
+            } else {
+                LineMode::Single
+            },
+            ..Default::default()
+        };
+        let inner = FastqReader::from_path(path, opts).map_err(to_py_err)?;
+        Ok(Self { inner })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> PyResult<Option<RecordTuple>> {
+        let Some(rec) = self.inner.next().transpose().map_err(to_py_err)? else {
+            return Ok(None);
+        };
+        let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+        Ok(Some((
+            rec.id().to_owned(),
+            rec.desc().map(str::to_owned),
+            text(rec.seq()),
+            text(rec.qual()),
+        )))
+    }
+
+    /// Iterate the remaining records as `FastqRecord` objects.
+    fn records(slf: Py<Self>) -> PyRecordIter {
+        PyRecordIter { reader: slf }
+    }
+}
+
+/// Iterator returned by `FastqReader.records()`.
+#[pyclass(
+    name = "RecordIter",
+    module = "kira_cdh_compat_fastq_reader",
+    unsendable
+)]
+pub struct PyRecordIter {
+    reader: Py<PyFastqReader>,
+}
+
+#[pymethods]
+impl PyRecordIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<PyFastqRecord>> {
+        let mut reader = self.reader.borrow_mut(py);
+        let rec = reader.inner.next().transpose().map_err(to_py_err)?;
+        Ok(rec.map(|inner| PyFastqRecord { inner }))
+    }
+}
+
+/// Python `FastqRecord`; `seq` and `qual` are zero-copy `memoryview`s.
+#[pyclass(name = "FastqRecord", module = "kira_cdh_compat_fastq_reader", frozen)]
+pub struct PyFastqRecord {
+    inner: FastqRecord,
+}
+
+#[pymethods]
+impl PyFastqRecord {
+    #[getter]
+    fn id(&self) -> &str {
+        self.inner.id()
+    }
+
+    #[getter]
+    fn desc(&self) -> Option<&str> {
+        self.inner.desc()
+    }
+
+    #[getter]
+    fn seq<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyMemoryView>> {
+        field_view(slf, Field::Seq)
+    }
+
+    #[getter]
+    fn qual<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyMemoryView>> {
+        field_view(slf, Field::Qual)
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FastqRecord(id={:?}, len={})",
+            self.inner.id(),
+            self.inner.len()
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Seq,
+    Qual,
+}
+
+/// Exports one field of a record through the buffer protocol; keeps the
+/// record alive for as long as a `memoryview` of it exists.
+#[pyclass(module = "kira_cdh_compat_fastq_reader", frozen)]
+struct FieldView {
+    record: Py<PyFastqRecord>,
+    field: Field,
+}
+
+#[pymethods]
+impl FieldView {
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        let this = slf.get();
+        let rec = &this.record.get().inner;
+        let bytes = match this.field {
+            Field::Seq => rec.seq(),
+            Field::Qual => rec.qual(),
+        };
+        // The record is frozen, so `bytes` stays put while `view` holds a
+        // reference to `slf` (and through it, the record).
+        let rc = unsafe {
+            ffi::PyBuffer_FillInfo(
+                view,
+                slf.as_ptr(),
+                bytes.as_ptr() as *mut _,
+                bytes.len() as ffi::Py_ssize_t,
+                1,
+                flags,
+            )
+        };
+        if rc == -1 {
+            return Err(PyErr::fetch(slf.py()));
+        }
+        Ok(())
+    }
+}
+
+fn field_view<'py>(
+    slf: &Bound<'py, PyFastqRecord>,
+    field: Field,
+) -> PyResult<Bound<'py, PyMemoryView>> {
+    let view = Bound::new(
+        slf.py(),
+        FieldView {
+            record: slf.clone().unbind(),
+            field,
+        },
+    )?;
+    PyMemoryView::from(view.as_any())
+}
+
+fn to_py_err(err: FastqError) -> PyErr {
+    match err {
+        FastqError::Io { .. } => PyOSError::new_err(err.to_string()),
+        _ => PyValueError::new_err(err.to_string()),
+    }
+}
+
+/// The `kira_cdh_compat_fastq_reader` Python module.
+#[pymodule]
+pub fn kira_cdh_compat_fastq_reader(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyFastqReader>()?;
+    m.add_class::<PyFastqRecord>()?;
+    m.add_class::<PyRecordIter>()
+}
@@ -0,0 +1,158 @@
+//! Quality-score encodings and on-the-fly rebasing to Phred+33.
+//!
+//! Used by [`RecordParser`](crate::core::RecordParser) when
+//! `ReaderOptions::rebase_quality` is set.
+
+use crate::policy::QualityEncoding;
+
+use core::ops::RangeInclusive;
+
+/// Distance between the Phred+64 and Phred+33 offsets.
+const PHRED64_SHIFT: u8 = 64 - 33;
+
+/// Phred score of Solexa scores -5..=9; from 10 up the two agree.
+const SOLEXA_LOW: [u8; 15] = [1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 7, 8, 9, 10];
+
+/// Highest Phred score printable at offset 33 (`~`).
+const MAX_PHRED: usize = 93;
+
+/// `10^(-q/10)` for Phred scores `0..=MAX_PHRED`.
+const ERROR_PROBABILITY: [f64; MAX_PHRED + 1] = {
+    // 10^(-1/10)
+    const STEP: f64 = 0.794_328_234_724_281_5;
+    let mut table = [1.0; MAX_PHRED + 1];
+    let mut q = 1;
+    while q <= MAX_PHRED {
+        table[q] = table[q - 1] * STEP;
+        q += 1;
+    }
+    table
+};
+
+/// Probability that a base with Phred score `q` is wrong, `10^(-q/10)`;
+/// scores above 93 count as 93.
+pub fn phred_error_probability(q: u8) -> f64 {
+    ERROR_PROBABILITY[usize::from(q).min(MAX_PHRED)]
+}
+
+/// Phred score of a Solexa score, rounded.
+pub fn solexa_to_phred(q: i8) -> u8 {
+    match q {
+        ..=-5 => SOLEXA_LOW[0],
+        -4..=9 => SOLEXA_LOW[(q + 5) as usize],
+        _ => q as u8,
+    }
+}
+
+impl QualityEncoding {
+    /// ASCII value of quality 0.
+    pub fn offset(self) -> u8 {
+        match self {
+            QualityEncoding::Phred33 => 33,
+            QualityEncoding::Phred64 | QualityEncoding::Solexa => 64,
+        }
+    }
+
+    /// Bytes a quality string in this encoding may contain: `!`..=`~`
+    /// for Phred+33, `@`..=`~` for Phred+64 and `;`..=`~` for Solexa.
+    pub fn valid_range(self) -> RangeInclusive<u8> {
+        match self {
+            QualityEncoding::Phred33 => b'!'..=b'~',
+            QualityEncoding::Phred64 => b'@'..=b'~',
+            QualityEncoding::Solexa => b';'..=b'~',
+        }
+    }
+
+    /// Encoding implied by one quality string, or `None` when every byte
+    /// lies in the range the encodings share (`;`..=`J`).
+    pub fn detect(qual: &[u8]) -> Option<Self> {
+        Self::detect_many([qual])
+    }
+
+    /// Encoding implied by several quality strings together, e.g. those of
+    /// the first records of a file.
+    pub fn detect_many<'a, I>(quals: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        let (min, max) = quals
+            .into_iter()
+            .flatten()
+            .fold((u8::MAX, u8::MIN), |(lo, hi), &q| (lo.min(q), hi.max(q)));
+        if min < b';' {
+            // below Solexa's lowest score: only Phred+33 goes there
+            Some(QualityEncoding::Phred33)
+        } else if max > b'J' && min < b'@' {
+            // negative scores: only Solexa goes below '@' at offset 64
+            Some(QualityEncoding::Solexa)
+        } else if max > b'J' {
+            Some(QualityEncoding::Phred64)
+        } else {
+            None
+        }
+    }
+}
+
+/// Phred score of a Solexa quality byte (offset 64).
+pub(crate) fn solexa_byte_to_phred(q: u8) -> u8 {
+    solexa_to_phred((i16::from(q) - 64).clamp(-5, 93) as i8)
+}
+
+/// Tracks the encoding of a stream and rewrites Phred+64 and Solexa
+/// qualities to Phred+33. Until a record settles the encoding, the
+/// configured one is assumed; a Phred+64 guess turns into Solexa once a
+/// negative score shows up.
+#[derive(Debug, Clone)]
+pub(crate) struct QualityRebaser {
+    configured: QualityEncoding,
+    detected: Option<QualityEncoding>,
+    rebased: u64,
+}
+
+impl QualityRebaser {
+    pub(crate) fn new(configured: QualityEncoding) -> Self {
+        Self {
+            configured,
+            detected: None,
+            rebased: 0,
+        }
+    }
+
+    pub(crate) fn apply(&mut self, qual: &mut [u8]) {
+        if self.detected.is_none() {
+            self.detected = QualityEncoding::detect(qual);
+            if let Some(enc) = self.detected.filter(|&e| e != self.configured) {
+                log::info!(
+                    "quality encoding detected as {enc:?} (configured {:?}); rebasing to Phred+33",
+                    self.configured
+                );
+            }
+        }
+        if self.detected == Some(QualityEncoding::Phred64) && qual.iter().any(|&q| q < b'@') {
+            log::info!("negative quality scores: rebasing from Solexa instead of Phred+64");
+            self.detected = Some(QualityEncoding::Solexa);
+        }
+        match self.detected.unwrap_or(self.configured) {
+            QualityEncoding::Phred33 => return,
+            QualityEncoding::Phred64 => {
+                for q in qual.iter_mut() {
+                    *q = q.saturating_sub(PHRED64_SHIFT).max(b'!');
+                }
+            }
+            QualityEncoding::Solexa => {
+                for q in qual.iter_mut() {
+                    *q = solexa_byte_to_phred(*q) + 33;
+                }
+            }
+        }
+        self.rebased += 1;
+    }
+
+    pub(crate) fn detected(&self) -> Option<QualityEncoding> {
+        self.detected
+    }
+
+    pub(crate) fn rebased(&self) -> u64 {
+        self.rebased
+    }
+}
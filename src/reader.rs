@@ -1,318 +1,1747 @@
-use crate::error::{FastqError, FormatError, IoContext};
-use crate::policy::{ErrorPolicy, LineMode, ReaderOptions};
-use crate::record::FastqRecord;
-use crate::util::{looks_like_gzip, open_file};
+use crate::checksum::{ChecksumTaps, InputChecksums};
+use crate::core::{Done, RecordParser};
+use crate::error::{FastqError, FormatError, IoContext, SkipReport};
+use crate::index::FastqIndex;
+use crate::instrument::{self, Meter};
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use crate::limits::ratio_guarded;
+use crate::limits::{CountingReader, LimitError};
+use crate::paired::InterleavedPairs;
+#[cfg(feature = "rayon")]
+use crate::parallel::ParProcess;
+use crate::policy::{
+    AccessAdvice, ErrorPolicy, InputFormat, LineMode, MmapPolicy, Progress, ProgressSink,
+    QualityEncoding, ReaderOptions, Throttle,
+};
+use crate::prefetch::PrefetchRead;
+use crate::record::{FastqRecord, RefRecord, SeqRecord};
+use crate::throttle::{RateLimiter, ThrottledRead};
+#[cfg(all(feature = "uring", target_os = "linux"))]
+use crate::uring::{self, UringRead};
+#[cfg(not(feature = "zstd"))]
+use crate::util::FailingRead;
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+use crate::util::advise_map;
+use crate::util::{advise_file, looks_like_zstd, open_file, read_head};
 
 #[cfg(feature = "gzip")]
-use flate2::read::MultiGzDecoder;
-#[cfg(feature = "mmap")]
+use crate::gzip::{GzipMember, MemberTracker, StrictGzDecoder, bgzf_virtual_offset, is_bgzf};
+#[cfg(feature = "gzip")]
+use flate2::bufread::MultiGzDecoder;
+#[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
 use memmap2::Mmap;
 
-use std::io::{self, BufRead, BufReader, Read};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub enum Source {
     Path(PathBuf),
     Reader,
+    /// A reader tagged via [`FastqReader::from_bufread_labeled`].
+    Labeled(String),
+}
+
+impl Source {
+    /// The user-supplied label, for [`Source::Labeled`].
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            Source::Labeled(l) => Some(l),
+            _ => None,
+        }
+    }
+}
+
+/// Compression of an input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Codec {
+    Plain,
+    /// Only ever sniffed with the `gzip` feature.
+    Gzip,
+    /// Sniffed with or without the `zstd` feature; without it the first
+    /// read fails with `ErrorKind::Unsupported`.
+    Zstd,
+}
+
+impl Codec {
+    /// Compression of an input starting with `head`.
+    fn sniff(head: &[u8]) -> Self {
+        if cfg!(feature = "gzip") && head.starts_with(&[0x1F, 0x8B]) {
+            Self::Gzip
+        } else if looks_like_zstd(head) {
+            Self::Zstd
+        } else {
+            Self::Plain
+        }
+    }
+
+    /// Compression of the file at `path` opened as `f`: by extension
+    /// (`.gz`, `.zst`), else by magic bytes.
+    fn of_file(path: Option<&Path>, f: &File) -> Self {
+        match path.and_then(|p| p.extension()).and_then(|e| e.to_str()) {
+            Some("gz") => Self::Gzip,
+            Some("zst") => Self::Zstd,
+            _ => {
+                let mut head = [0u8; 4];
+                let n = read_head(f, &mut head).unwrap_or(0);
+                // a gzip file without the feature is still refused as gzip
+                if head[..n].starts_with(&[0x1F, 0x8B]) {
+                    Self::Gzip
+                } else {
+                    Self::sniff(&head[..n])
+                }
+            }
+        }
+    }
+}
+
+/// Thresholds and clock of `ReaderOptions::progress`.
+struct ProgressState {
+    sink: ProgressSink,
+    /// `None` on `wasm32-unknown-unknown`, where `Instant::now` panics;
+    /// progress there reports zero elapsed time.
+    started: Option<Instant>,
+    next_bytes: u64,
+    next_records: u64,
+}
+
+impl ProgressState {
+    fn new(sink: ProgressSink) -> Self {
+        Self {
+            next_bytes: sink.every_bytes,
+            next_records: sink.every_records.unwrap_or(u64::MAX),
+            started: (!cfg!(all(target_arch = "wasm32", target_os = "unknown"))).then(Instant::now),
+            sink,
+        }
+    }
 }
 
-/// Sync FASTQ reader (plain/.gz), streaming.
+/// Files still to be read by a reader built with
+/// [`FastqReader::from_paths`], and the bytes of those already read.
+struct PathChain {
+    paths: Vec<PathBuf>,
+    next: usize,
+    bytes_before: u64,
+    raw_before: u64,
+}
+
+/// Input lines read since the current record began, with where each
+/// starts.
+#[derive(Default)]
+struct RawLines {
+    buf: Vec<u8>,
+    starts: Vec<usize>,
+}
+
+impl RawLines {
+    fn clear(&mut self) {
+        self.buf.clear();
+        self.starts.clear();
+    }
+
+    fn push(&mut self, line: &[u8]) {
+        self.starts.push(self.buf.len());
+        self.buf.extend_from_slice(line);
+    }
+
+    /// The last line and the `back` lines before it.
+    fn tail(&self, back: u64) -> &[u8] {
+        let i = self.starts.len().saturating_sub(back as usize + 1);
+        &self.buf[self.starts.get(i).map_or(0, |&start| start)..]
+    }
+}
+
+/// Counters of a [`FastqReader`], from [`FastqReader::stats`]. For a
+/// resumed reader, `records`, `skipped` and `bytes` include the part before
+/// the checkpoint; `filtered` and `bases` do not.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReaderStats {
+    /// Records yielded.
+    pub records: u64,
+    /// Records dropped under `ErrorPolicy::Skip`/`Collect`.
+    pub skipped: u64,
+    /// Well-formed records dropped by `min_len`, `max_len` or
+    /// `max_n_fraction`.
+    pub filtered: u64,
+    /// Bytes read from the source (compressed for `.gz`), read-ahead
+    /// included; equals `bytes` for plain input from a caller's `BufRead`.
+    pub raw_bytes: u64,
+    /// Decompressed bytes parsed.
+    pub bytes: u64,
+    /// Sequence bytes of the records yielded.
+    pub bases: u64,
+}
+
+/// Where a record lies in the input, from
+/// [`FastqReader::last_record_span`]. Offsets are into the decompressed
+/// text; `input[byte_offset..byte_offset + byte_len]` is the record as read,
+/// line endings included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordSpan {
+    /// 0-based ordinal of the record, counting skipped and failed ones, as
+    /// `IoContext::record_num` does (1-based).
+    pub index: u64,
+    /// Line of the header, 1-based.
+    pub line: u64,
+    pub byte_offset: u64,
+    pub byte_len: u64,
+}
+
+/// Resumable position of a [`FastqReader`], taken between records with
+/// [`FastqReader::state`] and restored with [`FastqReader::resume`].
+/// Serializable with the `serde` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReaderState {
+    /// Input file; `None` for readers not opened from a path.
+    pub path: Option<PathBuf>,
+    /// Offset into the decompressed text where the next record starts.
+    pub byte_offset: u64,
+    pub line_num: u64,
+    /// Records yielded so far.
+    pub records: u64,
+    /// Records dropped under `ErrorPolicy::Skip` so far.
+    pub skipped: u64,
+    /// BGZF virtual offset of `byte_offset`; lets BGZF input resume with a
+    /// seek instead of decompressing from the start.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub virtual_offset: Option<u64>,
+}
+
+/// Callback of [`FastqReader::with_skip_handler`].
+pub type SkipHandler = Box<dyn FnMut(&FastqError) + Send>;
+
+/// Sync FASTQ reader (plain/.gz/.zst), streaming.
 pub struct FastqReader {
     src: Source,
     rdr: Box<dyn BufRead + Send>,
     opts: ReaderOptions,
     line_num: u64,
     byte_pos: u64,
-    pending_header: Option<String>,
+    records_before: u64,
+    record_bytes: usize,
+    records: u64,
+    skipped: u64,
+    record_rate: Option<RateLimiter>,
+    label: Option<Arc<str>>,
+    parser: RecordParser,
+    line: Vec<u8>,
+    checksums: ChecksumTaps,
+    eof: bool,
+    lookahead: VecDeque<FastqRecord>,
+    /// Spans of the `lookahead` records.
+    lookahead_spans: VecDeque<Option<RecordSpan>>,
+    lookahead_err: Option<FastqError>,
+    /// Offsets of the lines read since the current record began.
+    line_starts: Vec<u64>,
+    last_span: Option<RecordSpan>,
+    /// Holds a peeked record while `read_ref` lends it out.
+    ref_slot: Option<FastqRecord>,
+    index: Option<Arc<FastqIndex>>,
+    prefetch: bool,
+    on_skip: Option<SkipHandler>,
+    skip_report: SkipReport,
+    /// Bytes read from the source, for sources opened by this crate.
+    raw_read: Option<Arc<AtomicU64>>,
+    bases: u64,
+    /// Source size for progress reports, when known.
+    total_bytes: Option<u64>,
+    progress: Option<ProgressState>,
+    /// Input lines of the record being parsed, under
+    /// `ReaderOptions::keep_raw`.
+    raw: Option<RawLines>,
+    chain: Option<Box<PathChain>>,
+    meter: Meter,
+    #[cfg(feature = "gzip")]
+    members: Option<MemberTracker>,
 }
 
 impl FastqReader {
-    /// Open from a file path. Auto-detect `.gz` by extension or magic bytes.
+    /// Open from a file path. Auto-detect gzip and zstd (`.gz`, `.zst`) by
+    /// extension or magic bytes; zstd needs the `zstd` feature.
+    /// The path `-` reads standard input, as [`from_stdin`](Self::from_stdin).
     pub fn from_path<P: AsRef<Path>>(path: P, opts: ReaderOptions) -> Result<Self, FastqError> {
         let path = path.as_ref();
+        if path == Path::new("-") {
+            return Self::from_stdin(opts);
+        }
+        let label: Arc<str> = path.display().to_string().into();
         let f = open_file(path).map_err(|e| {
             FastqError::io_err(
                 e,
                 IoContext {
                     byte_pos: 0,
                     line_num: 0,
+                    record_num: 0,
+                    record_line: 0,
+                    label: Some(label.clone()),
+                    virtual_offset: None,
                 },
             )
         })?;
+        let codec = Codec::of_file(Some(path), &f);
+        Self::from_open_file(
+            f,
+            codec,
+            Source::Path(path.to_path_buf()),
+            Some(label),
+            opts,
+        )
+    }
 
-        let is_gz = path.extension().and_then(|s| s.to_str()) == Some("gz")
-            || looks_like_gzip(&f).unwrap_or(false);
+    /// Read standard input, labeled `stdin`. Gzip and zstd are sniffed
+    /// from the first bytes of the stream; without the `zstd` feature, zstd
+    /// input fails the first read with `ErrorKind::Unsupported`.
+    pub fn from_stdin(opts: ReaderOptions) -> Result<Self, FastqError> {
+        let buf = BufReader::with_capacity(opts.decompress_buffer(256 * 1024), io::stdin());
+        Ok(Self::from_sniffed(
+            buf,
+            Source::Labeled("stdin".to_owned()),
+            Some(Arc::from("stdin")),
+            opts,
+        ))
+    }
 
-        let rdr: Box<dyn BufRead + Send> = if is_gz {
-            #[cfg(feature = "gzip")]
-            {
-                let dec = MultiGzDecoder::new(f);
-                Box::new(BufReader::with_capacity(256 * 1024, dec))
+    /// Decode pipeline over a stream that cannot be rewound: the
+    /// compression is sniffed from the bytes already buffered.
+    fn from_sniffed<R>(
+        mut buf: R,
+        src: Source,
+        label: Option<Arc<str>>,
+        opts: ReaderOptions,
+    ) -> Self
+    where
+        R: BufRead + Send + 'static,
+    {
+        // a read error here resurfaces on the first record
+        let codec = Codec::sniff(buf.fill_buf().unwrap_or(&[]));
+        Self::from_raw(Box::new(buf), codec, 256 * 1024, src, label, opts)
+    }
+
+    /// Read several files (plain or gzip, mixed) as one record stream, in
+    /// order. Line numbers and byte offsets restart with each file and
+    /// errors are labeled with the file they occur in;
+    /// [`path_index`](Self::path_index) tells which file a record came
+    /// from. Checksums and gzip members cover the current file only.
+    pub fn from_paths<I, P>(paths: I, opts: ReaderOptions) -> Result<Self, FastqError>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let paths: Vec<PathBuf> = paths
+            .into_iter()
+            .map(|p| p.as_ref().to_path_buf())
+            .collect();
+        let total_bytes = paths
+            .iter()
+            .map(|p| p.metadata().map(|m| m.len()))
+            .sum::<io::Result<u64>>()
+            .ok();
+        let mut rdr = Self::from_vec(Vec::new(), opts);
+        rdr.chain = Some(Box::new(PathChain {
+            paths,
+            next: 0,
+            bytes_before: 0,
+            raw_before: 0,
+        }));
+        rdr.open_next_path()?;
+        rdr.total_bytes = total_bytes;
+        Ok(rdr)
+    }
+
+    /// Files matching a glob pattern (`data/**/*.fastq.gz`), sorted by
+    /// path, read as by [`from_paths`](Self::from_paths). A directory reads
+    /// the FASTQ files directly inside it, as
+    /// [`FastqFileSet::from_dir`](crate::FastqFileSet::from_dir) lists them.
+    /// An invalid pattern or one matching no file is an error.
+    #[cfg(feature = "glob")]
+    pub fn from_glob(pattern: &str, opts: ReaderOptions) -> Result<Self, FastqError> {
+        let ctx = IoContext {
+            byte_pos: 0,
+            line_num: 0,
+            record_num: 0,
+            record_line: 0,
+            label: Some(pattern.into()),
+            virtual_offset: None,
+        };
+        let io_err = |e: io::Error| FastqError::io_err(e, ctx.clone());
+        let paths: Vec<PathBuf> = if Path::new(pattern).is_dir() {
+            let set =
+                crate::FastqFileSet::from_dir(pattern, ReaderOptions::default()).map_err(io_err)?;
+            set.paths().iter().map(|p| p.to_path_buf()).collect()
+        } else {
+            let matches = glob::glob(pattern)
+                .map_err(|e| io_err(io::Error::new(io::ErrorKind::InvalidInput, e)))?;
+            let mut paths = Vec::new();
+            for path in matches {
+                let path = path.map_err(|e| io_err(e.into()))?;
+                if path.is_file() {
+                    paths.push(path);
+                }
             }
-            #[cfg(not(feature = "gzip"))]
-            {
-                return Err(FastqError::fmt_err(
-                    FormatError::MissingHeader,
-                    IoContext {
-                        byte_pos: 0,
-                        line_num: 0,
-                    },
-                ));
+            paths.sort();
+            paths
+        };
+        if paths.is_empty() {
+            let msg = format!("no FASTQ file matches {pattern}");
+            return Err(io_err(io::Error::new(io::ErrorKind::NotFound, msg)));
+        }
+        Self::from_paths(paths, opts)
+    }
+
+    /// Index into the [`from_paths`](Self::from_paths) list of the file
+    /// being read: the one the last record came from, unless records were
+    /// peeked. `None` for other readers.
+    pub fn path_index(&self) -> Option<usize> {
+        self.chain.as_ref().map(|c| c.next.saturating_sub(1))
+    }
+
+    /// Switch to the next file of a [`from_paths`](Self::from_paths)
+    /// reader; `false` when there is none.
+    fn open_next_path(&mut self) -> Result<bool, FastqError> {
+        let (bytes, raw_bytes) = (self.byte_pos, self.raw_bytes());
+        let Some(chain) = self.chain.as_mut() else {
+            return Ok(false);
+        };
+        let Some(path) = chain.paths.get(chain.next) else {
+            return Ok(false);
+        };
+        // a file that fails to open is reported once, then passed over
+        let path = path.clone();
+        chain.next += 1;
+        chain.bytes_before += bytes;
+        // raw_bytes() already includes raw_before
+        chain.raw_before = raw_bytes;
+        self.byte_pos = 0;
+        self.raw_read = None;
+        let next = Self::from_path(path, self.opts.clone())?;
+        self.src = next.src;
+        self.rdr = next.rdr;
+        self.label = next.label;
+        self.checksums = next.checksums;
+        self.raw_read = next.raw_read;
+        self.meter.switch_to(next.meter);
+        #[cfg(feature = "gzip")]
+        {
+            self.members = next.members;
+        }
+        self.line_num = 0;
+        self.eof = false;
+        Ok(true)
+    }
+
+    /// Read from an already opened file, sniffing gzip and zstd by magic
+    /// bytes. The file is read from its current position.
+    pub fn from_file(file: File, opts: ReaderOptions) -> Result<Self, FastqError> {
+        let codec = Codec::of_file(None, &file);
+        Self::from_open_file(file, codec, Source::Reader, None, opts)
+    }
+
+    /// Read from an in-memory buffer (plain, gzip or zstd, sniffed). The bytes are
+    /// copied; use [`parse_records`](crate::parse_records) to parse borrowed
+    /// plain text without copying.
+    pub fn from_bytes(data: &[u8], opts: ReaderOptions) -> Self {
+        Self::from_vec(data.to_vec(), opts)
+    }
+
+    /// Read from an owned buffer (plain, gzip or zstd, sniffed).
+    pub fn from_vec(data: Vec<u8>, opts: ReaderOptions) -> Self {
+        let codec = Codec::sniff(&data);
+        let total_bytes = data.len() as u64;
+        let mut rdr = Self::from_raw(
+            Box::new(Cursor::new(data)),
+            codec,
+            64 * 1024,
+            Source::Reader,
+            None,
+            opts,
+        );
+        rdr.total_bytes = Some(total_bytes);
+        rdr
+    }
+
+    /// Read from an unbuffered source (`TcpStream`, `ChildStdout`, a decoder
+    /// stream, ...). The reader is buffered internally and gzip or zstd is
+    /// sniffed from the first bytes available.
+    pub fn from_read<R>(reader: R, opts: ReaderOptions) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        let mut buf = BufReader::with_capacity(opts.decompress_buffer(256 * 1024), reader);
+        // a read error here resurfaces on the first record
+        let codec = Codec::sniff(buf.fill_buf().unwrap_or(&[]));
+        Self::from_raw(Box::new(buf), codec, 256 * 1024, Source::Reader, None, opts)
+    }
+
+    fn from_open_file(
+        f: File,
+        codec: Codec,
+        src: Source,
+        label: Option<Arc<str>>,
+        opts: ReaderOptions,
+    ) -> Result<Self, FastqError> {
+        let ctx = || IoContext {
+            byte_pos: 0,
+            line_num: 0,
+            record_num: 0,
+            record_line: 0,
+            label: label.clone(),
+            virtual_offset: None,
+        };
+        let meta = f.metadata().ok();
+        if !meta.as_ref().is_some_and(|m| m.is_file()) {
+            // FIFOs, sockets, character devices: no seeking, no mapping
+            if opts.use_mmap == MmapPolicy::Always {
+                let err = io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "cannot memory-map a file that is not a regular file",
+                );
+                return Err(FastqError::io_err(err, ctx()));
             }
+            let buf = BufReader::with_capacity(opts.decompress_buffer(256 * 1024), f);
+            return Ok(Self::from_sniffed(buf, src, label, opts));
+        }
+        let total_bytes = meta.map(|m| m.len());
+        #[cfg(not(feature = "gzip"))]
+        if codec == Codec::Gzip {
+            let err = FormatError::missing_header(&[0x1F, 0x8B]);
+            return Err(FastqError::fmt_err(err, ctx()));
+        }
+
+        let (raw, capacity) = if codec != Codec::Plain {
+            advise_file(&f, opts.access_advice);
+            (Box::new(f) as Box<dyn Read + Send>, 256 * 1024)
+        } else if let Some(uring) = Self::open_uring(&f, &opts) {
+            uring
         } else {
-            #[cfg(feature = "mmap")]
-            {
-                let mmap = unsafe { Mmap::map(&f) }.map_err(|e| {
-                    FastqError::io_err(
-                        e,
-                        IoContext {
-                            byte_pos: 0,
-                            line_num: 0,
-                        },
-                    )
-                })?;
-                let cursor = std::io::Cursor::new(mmap);
-                Box::new(BufReader::with_capacity(512 * 1024, cursor))
+            Self::map_plain(f, opts.use_mmap, opts.access_advice)
+                .map_err(|e| FastqError::io_err(e, ctx()))?
+        };
+        let mut rdr = Self::from_raw(raw, codec, capacity, src, label, opts);
+        rdr.total_bytes = total_bytes;
+        Ok(rdr)
+    }
+
+    /// `f` read through io_uring, if asked for and the kernel allows it.
+    #[cfg(all(feature = "uring", target_os = "linux"))]
+    fn open_uring(f: &File, opts: &ReaderOptions) -> Option<(Box<dyn Read + Send>, usize)> {
+        if !opts.io_uring || opts.use_mmap == MmapPolicy::Always {
+            return None;
+        }
+        advise_file(f, opts.access_advice);
+        let uring = UringRead::new(f.try_clone().ok()?).ok()?;
+        Some((Box::new(uring), uring::CHUNK))
+    }
+
+    #[cfg(not(all(feature = "uring", target_os = "linux")))]
+    fn open_uring(_f: &File, _opts: &ReaderOptions) -> Option<(Box<dyn Read + Send>, usize)> {
+        None
+    }
+
+    /// Source and buffer capacity for a plain regular file under `policy`.
+    #[cfg(all(feature = "mmap", not(target_arch = "wasm32")))]
+    fn map_plain(
+        f: File,
+        policy: MmapPolicy,
+        advice: AccessAdvice,
+    ) -> io::Result<(Box<dyn Read + Send>, usize)> {
+        if policy == MmapPolicy::Never {
+            advise_file(&f, advice);
+            return Ok((Box::new(f), 256 * 1024));
+        }
+        match unsafe { Mmap::map(&f) } {
+            Ok(mmap) => {
+                advise_map(&mmap, advice);
+                Ok((Box::new(Cursor::new(mmap)), 512 * 1024))
             }
-            #[cfg(not(feature = "mmap"))]
-            {
-                Box::new(BufReader::with_capacity(256 * 1024, f))
+            Err(_) if policy == MmapPolicy::Auto => {
+                advise_file(&f, advice);
+                Ok((Box::new(f), 256 * 1024))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg(not(all(feature = "mmap", not(target_arch = "wasm32"))))]
+    fn map_plain(
+        f: File,
+        policy: MmapPolicy,
+        advice: AccessAdvice,
+    ) -> io::Result<(Box<dyn Read + Send>, usize)> {
+        if policy == MmapPolicy::Always {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "memory mapping needs the `mmap` feature (not available on wasm32)",
+            ));
+        }
+        advise_file(&f, advice);
+        Ok((Box::new(f), 256 * 1024))
+    }
+
+    /// `raw` decompressed as zstd (concatenated frames), under the
+    /// expansion-ratio limit.
+    #[cfg(feature = "zstd")]
+    fn zstd_decoder(
+        raw: Box<dyn Read + Send>,
+        opts: &ReaderOptions,
+        capacity: usize,
+    ) -> Box<dyn Read + Send> {
+        ratio_guarded(raw, opts.max_expansion_ratio, |raw| {
+            let raw = BufReader::with_capacity(opts.decompress_buffer(capacity), raw);
+            match zstd::stream::read::Decoder::with_buffer(raw) {
+                Ok(dec) => Box::new(dec),
+                // a read error here resurfaces on the first record
+                Err(e) => Box::new(crate::util::FailingRead(Some(e))),
             }
+        })
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    fn zstd_decoder(
+        _raw: Box<dyn Read + Send>,
+        _opts: &ReaderOptions,
+        _capacity: usize,
+    ) -> Box<dyn Read + Send> {
+        let err = io::Error::new(
+            io::ErrorKind::Unsupported,
+            "zstd input needs the `zstd` feature",
+        );
+        Box::new(FailingRead(Some(err)))
+    }
+
+    /// Shared decode pipeline: raw checksum tap, optional gzip or zstd
+    /// decoder, decompressed checksum tap, buffering.
+    fn from_raw(
+        raw: Box<dyn Read + Send>,
+        codec: Codec,
+        capacity: usize,
+        src: Source,
+        label: Option<Arc<str>>,
+        opts: ReaderOptions,
+    ) -> Self {
+        let mut checksums = ChecksumTaps::default();
+        let raw_read = Arc::new(AtomicU64::new(0));
+        let raw = Box::new(CountingReader {
+            inner: raw,
+            count: Arc::clone(&raw_read),
+        });
+        let raw: Box<dyn Read + Send> = match opts.throttle.and_then(Throttle::bytes_per_sec) {
+            Some(rate) => Box::new(ThrottledRead::new(raw, rate)),
+            None => raw,
+        };
+        let raw = checksums.tap_raw(raw, opts.checksum_raw);
+        #[cfg(feature = "gzip")]
+        let mut members = None;
+        #[cfg(not(feature = "gzip"))]
+        debug_assert!(codec != Codec::Gzip);
+        let raw: Box<dyn Read + Send> = match codec {
+            Codec::Plain => raw,
+            #[cfg(feature = "gzip")]
+            Codec::Gzip => ratio_guarded(raw, opts.max_expansion_ratio, |raw| {
+                let strict = opts.strict_gzip;
+                let mut raw = BufReader::with_capacity(opts.decompress_buffer(capacity), raw);
+                // a read error here resurfaces on the first record
+                let bgzf = raw.fill_buf().is_ok_and(is_bgzf);
+                if opts.track_gzip_members || bgzf {
+                    let ends = Default::default();
+                    members = Some(MemberTracker::new(
+                        Arc::clone(&ends),
+                        opts.track_gzip_members,
+                        bgzf,
+                    ));
+                    Box::new(StrictGzDecoder::tracking(raw, strict, ends))
+                } else if strict {
+                    Box::new(StrictGzDecoder::new(raw))
+                } else {
+                    Box::new(MultiGzDecoder::new(raw))
+                }
+            }),
+            #[cfg(not(feature = "gzip"))]
+            Codec::Gzip => raw,
+            Codec::Zstd => Self::zstd_decoder(raw, &opts, capacity),
         };
+        let dec = checksums.tap_decompressed(raw, opts.checksum_decompressed);
 
-        Ok(Self {
-            src: Source::Path(path.to_path_buf()),
-            rdr,
+        let progress = opts.progress.clone().map(ProgressState::new);
+        let raw_lines = opts.keep_raw.then(RawLines::default);
+        let meter = Meter::new(label.as_deref());
+        Self {
+            src,
+            rdr: Box::new(BufReader::with_capacity(opts.read_buffer(capacity), dec)),
+            parser: RecordParser::new(&opts),
+            record_rate: opts
+                .throttle
+                .and_then(Throttle::records_per_sec)
+                .map(RateLimiter::new),
             opts,
             line_num: 0,
+            records_before: 0,
+            record_bytes: 0,
+            records: 0,
+            skipped: 0,
+            label,
             byte_pos: 0,
-            pending_header: None,
-        })
+            line: Vec::with_capacity(256),
+            checksums,
+            eof: false,
+            lookahead: VecDeque::new(),
+            lookahead_spans: VecDeque::new(),
+            lookahead_err: None,
+            line_starts: Vec::new(),
+            last_span: None,
+            ref_slot: None,
+            index: None,
+            prefetch: false,
+            on_skip: None,
+            skip_report: SkipReport::default(),
+            raw_read: Some(raw_read),
+            bases: 0,
+            total_bytes: None,
+            progress,
+            raw: raw_lines,
+            chain: None,
+            meter,
+            #[cfg(feature = "gzip")]
+            members,
+        }
     }
 
-    /// Wrap an arbitrary `BufRead` (stdin, etc.).
+    /// Wrap an arbitrary `BufRead` (stdin, etc.). Gzip and zstd input is
+    /// detected from the buffered bytes and decompressed; without the `zstd`
+    /// feature, zstd input fails the first read with
+    /// `ErrorKind::Unsupported`.
     pub fn from_bufread<R>(reader: R, opts: ReaderOptions) -> Self
     where
         R: BufRead + Send + 'static,
     {
+        Self::from_bufread_inner(reader, opts, None)
+    }
+
+    /// Like [`from_bufread`](Self::from_bufread), tagging the source with
+    /// `label` (e.g. `"stdin"`, a peer address). The label is returned by
+    /// [`Source::label`] and carried in every error's [`IoContext`].
+    pub fn from_bufread_labeled<R>(reader: R, label: impl Into<String>, opts: ReaderOptions) -> Self
+    where
+        R: BufRead + Send + 'static,
+    {
+        Self::from_bufread_inner(reader, opts, Some(label.into()))
+    }
+
+    fn from_bufread_inner<R>(mut reader: R, opts: ReaderOptions, label: Option<String>) -> Self
+    where
+        R: BufRead + Send + 'static,
+    {
+        // a read error here resurfaces on the first record
+        if Codec::sniff(reader.fill_buf().unwrap_or(&[])) != Codec::Plain {
+            let src = label.map_or(Source::Reader, Source::Labeled);
+            let label = src.label().map(Arc::from);
+            return Self::from_sniffed(reader, src, label, opts);
+        }
+        let mut checksums = ChecksumTaps::default();
+        let byte_rate = opts.throttle.and_then(Throttle::bytes_per_sec);
+        let rdr: Box<dyn BufRead + Send> = if opts.checksum_raw.is_none()
+            && opts.checksum_decompressed.is_none()
+            && byte_rate.is_none()
+        {
+            Box::new(reader)
+        } else {
+            let r: Box<dyn Read + Send> = match byte_rate {
+                Some(rate) => Box::new(ThrottledRead::new(reader, rate)),
+                None => Box::new(reader),
+            };
+            // no decompression on this path: raw and decompressed bytes coincide
+            let r = checksums.tap_raw(r, opts.checksum_raw);
+            let r = checksums.tap_decompressed(r, opts.checksum_decompressed);
+            Box::new(BufReader::with_capacity(opts.read_buffer(8 * 1024), r))
+        };
+        let progress = opts.progress.clone().map(ProgressState::new);
+        let raw_lines = opts.keep_raw.then(RawLines::default);
         Self {
-            src: Source::Reader,
-            rdr: Box::new(reader),
+            meter: Meter::new(label.as_deref()),
+            label: label.as_deref().map(Arc::from),
+            src: label.map_or(Source::Reader, Source::Labeled),
+            rdr,
+            parser: RecordParser::new(&opts),
+            record_rate: opts
+                .throttle
+                .and_then(Throttle::records_per_sec)
+                .map(RateLimiter::new),
             opts,
             line_num: 0,
+            records_before: 0,
+            record_bytes: 0,
+            records: 0,
+            skipped: 0,
             byte_pos: 0,
-            pending_header: None,
+            line: Vec::with_capacity(256),
+            checksums,
+            eof: false,
+            lookahead: VecDeque::new(),
+            lookahead_spans: VecDeque::new(),
+            lookahead_err: None,
+            line_starts: Vec::new(),
+            last_span: None,
+            ref_slot: None,
+            index: None,
+            prefetch: false,
+            on_skip: None,
+            skip_report: SkipReport::default(),
+            raw_read: None,
+            bases: 0,
+            total_bytes: None,
+            progress,
+            raw: raw_lines,
+            chain: None,
+            #[cfg(feature = "gzip")]
+            members: None,
         }
     }
 
-    pub fn next(&mut self) -> Option<Result<FastqRecord, FastqError>> {
-        loop {
-            match self.read_one() {
-                Ok(Some(rec)) => return Some(Ok(rec)),
-                Ok(None) => return None,
-                Err(err) => {
-                    if self.opts.error_policy == ErrorPolicy::Skip {
-                        log::warn!("skipping malformed record: {err}");
-                        if !self.resync_to_next_header() {
-                            return None;
-                        }
-                        continue;
-                    } else {
-                        return Some(Err(err));
-                    }
-                }
+    /// Input checksums requested via `ReaderOptions::checksum_raw` /
+    /// `checksum_decompressed`. `None` until the input has been read to EOF
+    /// (or when no checksum was requested).
+    pub fn checksums(&self) -> Option<InputChecksums> {
+        (self.eof && !self.checksums.is_empty()).then(|| self.checksums.result())
+    }
+
+    /// Counters so far; see [`ReaderStats`].
+    pub fn stats(&self) -> ReaderStats {
+        ReaderStats {
+            records: self.records,
+            skipped: self.skipped,
+            filtered: self.parser.filtered_records(),
+            raw_bytes: self.raw_bytes(),
+            bytes: self.bytes(),
+            bases: self.bases,
+        }
+    }
+
+    /// Decompressed bytes parsed, across chained files.
+    fn bytes(&self) -> u64 {
+        self.byte_pos + self.chain.as_ref().map_or(0, |c| c.bytes_before)
+    }
+
+    fn raw_bytes(&self) -> u64 {
+        let before = self.chain.as_ref().map_or(0, |c| c.raw_before);
+        before
+            + self
+                .raw_read
+                .as_ref()
+                .map_or(self.byte_pos, |n| n.load(Ordering::Relaxed))
+    }
+
+    /// Checkpoint of the current position, or `None` while a record is
+    /// only partially read (it never is between calls to `next`) or records
+    /// are held back by [`peek_n`](Self::peek_n). Always `None` for
+    /// [`from_paths`](Self::from_paths) readers.
+    pub fn state(&self) -> Option<ReaderState> {
+        let idle =
+            self.lookahead.is_empty() && self.lookahead_err.is_none() && self.chain.is_none();
+        (idle && self.parser.is_between_records()).then(|| ReaderState {
+            path: match &self.src {
+                Source::Path(p) => Some(p.clone()),
+                _ => None,
+            },
+            byte_offset: self.byte_pos,
+            line_num: self.line_num,
+            records: self.records,
+            skipped: self.skipped,
+            virtual_offset: self.virtual_offset(),
+        })
+    }
+
+    /// Reopen `state.path` and continue where `state` was taken. Plain files
+    /// and BGZF (with `state.virtual_offset`) are seeked; other compressed
+    /// (gzip, zstd)
+    /// input is decompressed and discarded up to the offset. Input checksums
+    /// then cover only the resumed part.
+    pub fn resume(state: &ReaderState, opts: ReaderOptions) -> Result<Self, FastqError> {
+        let ctx = IoContext {
+            byte_pos: state.byte_offset,
+            line_num: state.line_num,
+            record_num: state.records + state.skipped,
+            record_line: 0,
+            label: state.path.as_ref().map(|p| p.display().to_string().into()),
+            virtual_offset: None,
+        };
+        let Some(path) = &state.path else {
+            return Err(FastqError::io_err(
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "reader state has no path to resume from",
+                ),
+                ctx,
+            ));
+        };
+        let mut f = open_file(path).map_err(|e| FastqError::io_err(e, ctx.clone()))?;
+        let codec = Codec::of_file(Some(path), &f);
+
+        let mut rdr = if let (Codec::Gzip, Some(voff)) = (codec, state.virtual_offset) {
+            Self::resume_bgzf(f, path, voff, state.byte_offset, &ctx, opts)?
+        } else if codec != Codec::Plain {
+            let mut rdr = Self::from_path(path, opts)?;
+            let skipped = io::copy(
+                &mut Read::take(&mut *rdr.rdr, state.byte_offset),
+                &mut io::sink(),
+            )
+            .map_err(|e| FastqError::io_err(e, ctx.clone()))?;
+            if skipped < state.byte_offset {
+                return Err(FastqError::io_err(io::ErrorKind::UnexpectedEof.into(), ctx));
             }
+            rdr
+        } else {
+            f.seek(SeekFrom::Start(state.byte_offset))
+                .map_err(|e| FastqError::io_err(e, ctx.clone()))?;
+            Self::from_raw(
+                Box::new(f),
+                Codec::Plain,
+                256 * 1024,
+                Source::Path(path.clone()),
+                ctx.label.clone(),
+                opts,
+            )
+        };
+        rdr.byte_pos = state.byte_offset;
+        rdr.line_num = state.line_num;
+        rdr.records_before = state.records + state.skipped;
+        rdr.records = state.records;
+        rdr.skipped = state.skipped;
+        Ok(rdr)
+    }
+
+    /// Open `path` and start parsing at `byte_offset` of the decompressed
+    /// text, which must be a record boundary (such as a
+    /// [`RecordSpan::byte_offset`] saved by an earlier run). Plain files are
+    /// seeked, BGZF files are located by their block sizes; other gzip input
+    /// is decompressed and discarded up to the offset. Line and record
+    /// numbers count from the offset.
+    pub fn resume_at<P: AsRef<Path>>(
+        path: P,
+        byte_offset: u64,
+        opts: ReaderOptions,
+    ) -> Result<Self, FastqError> {
+        let path = path.as_ref();
+        #[cfg(feature = "gzip")]
+        let virtual_offset = {
+            let ctx = IoContext {
+                byte_pos: byte_offset,
+                line_num: 0,
+                record_num: 0,
+                record_line: 0,
+                label: Some(path.display().to_string().into()),
+                virtual_offset: None,
+            };
+            let f = open_file(path).map_err(|e| FastqError::io_err(e, ctx.clone()))?;
+            bgzf_virtual_offset(BufReader::new(f), byte_offset)
+                .map_err(|e| FastqError::io_err(e, ctx))?
+        };
+        #[cfg(not(feature = "gzip"))]
+        let virtual_offset = None;
+        let state = ReaderState {
+            path: Some(path.to_path_buf()),
+            byte_offset,
+            line_num: 0,
+            records: 0,
+            skipped: 0,
+            virtual_offset,
+        };
+        Self::resume(&state, opts)
+    }
+
+    /// Seek `f` to the BGZF block of `voff` and skip to its in-block offset,
+    /// which is `byte_offset` in the decompressed text.
+    #[cfg(feature = "gzip")]
+    fn resume_bgzf(
+        mut f: File,
+        path: &Path,
+        voff: u64,
+        byte_offset: u64,
+        ctx: &IoContext,
+        opts: ReaderOptions,
+    ) -> Result<Self, FastqError> {
+        let (coffset, uoffset) = (voff >> 16, voff & 0xFFFF);
+        f.seek(SeekFrom::Start(coffset))
+            .map_err(|e| FastqError::io_err(e, ctx.clone()))?;
+        let mut rdr = Self::from_raw(
+            Box::new(f),
+            Codec::Gzip,
+            256 * 1024,
+            Source::Path(path.to_path_buf()),
+            ctx.label.clone(),
+            opts,
+        );
+        match rdr.members.as_mut() {
+            Some(members) if members.is_bgzf() => {
+                members.rebase(coffset, byte_offset.saturating_sub(uoffset));
+            }
+            _ => {
+                let msg = "virtual offset does not address a BGZF block";
+                return Err(FastqError::io_err(
+                    io::Error::new(io::ErrorKind::InvalidData, msg),
+                    ctx.clone(),
+                ));
+            }
+        }
+        let skipped = io::copy(&mut Read::take(&mut *rdr.rdr, uoffset), &mut io::sink())
+            .map_err(|e| FastqError::io_err(e, ctx.clone()))?;
+        if skipped < uoffset {
+            return Err(FastqError::io_err(
+                io::ErrorKind::UnexpectedEof.into(),
+                ctx.clone(),
+            ));
         }
+        Ok(rdr)
     }
 
-    fn read_line(&mut self, buf: &mut String) -> io::Result<usize> {
+    #[cfg(not(feature = "gzip"))]
+    fn resume_bgzf(
+        _f: File,
+        _path: &Path,
+        _voff: u64,
+        _byte_offset: u64,
+        ctx: &IoContext,
+        _opts: ReaderOptions,
+    ) -> Result<Self, FastqError> {
+        let err = FormatError::missing_header(&[0x1F, 0x8B]);
+        Err(FastqError::fmt_err(err, ctx.clone()))
+    }
+
+    /// Where this reader's bytes come from.
+    pub fn source(&self) -> &Source {
+        &self.src
+    }
+
+    fn read_line(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
         buf.clear();
-        let n = self.rdr.read_line(buf)?;
+        let n = match self.opts.max_record_bytes {
+            None => self.rdr.read_until(b'\n', buf)?,
+            Some(limit) => {
+                let budget = limit.saturating_sub(self.record_bytes);
+                let n = Read::take(&mut *self.rdr, budget as u64 + 1).read_until(b'\n', buf)?;
+                if n > budget {
+                    return Err(LimitError::RecordTooLarge { limit }.into_io());
+                }
+                n
+            }
+        };
+        self.record_bytes += n;
         if n > 0 {
+            self.line_starts.push(self.byte_pos);
+            if let Some(raw) = &mut self.raw {
+                raw.push(buf);
+            }
             self.line_num += 1;
             self.byte_pos += n as u64;
-            if buf.ends_with('\n') {
+            if buf.ends_with(b"\n") {
                 buf.pop();
             }
-            if buf.ends_with('\r') {
+            if buf.ends_with(b"\r") {
                 buf.pop();
             }
         }
         Ok(n)
     }
 
-    fn read_one(&mut self) -> Result<Option<FastqRecord>, FastqError> {
-        let header = if let Some(h) = self.pending_header.take() {
-            h
-        } else {
-            let mut h = String::with_capacity(128);
-            loop {
-                let n = self
-                    .read_line(&mut h)
-                    .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                if n == 0 {
-                    return Ok(None);
+    fn read_one(&mut self) -> Result<Option<Done>, FastqError> {
+        let mut line = std::mem::take(&mut self.line);
+        let res = self.read_one_with(&mut line);
+        self.line = line;
+        if let Ok(Some(done)) = &res {
+            self.last_span = matches!(done, Done::Fastq).then(|| self.record_span());
+        }
+        #[cfg(feature = "gzip")]
+        if let Some(members) = self.members.as_mut() {
+            match &res {
+                Ok(Some(_)) => members.record(self.byte_pos),
+                Ok(None) => members.finish(),
+                Err(_) => {}
+            }
+        }
+        res
+    }
+
+    fn read_one_with(&mut self, line: &mut Vec<u8>) -> Result<Option<Done>, FastqError> {
+        loop {
+            if self.parser.is_between_records() {
+                self.record_bytes = 0;
+                self.line_starts.clear();
+                if let Some(raw) = &mut self.raw {
+                    raw.clear();
                 }
-                if !h.is_empty() {
-                    if h.starts_with('\u{FEFF}') {
-                        h.drain(..'\u{FEFF}'.len_utf8());
-                        if h.is_empty() {
-                            continue;
-                        }
-                    }
-                    break;
+            }
+            let n = self
+                .read_line(line)
+                .map_err(|e| FastqError::io_err(e, self.ctx()))?;
+            if n == 0 {
+                self.eof = true;
+                return self
+                    .parser
+                    .finish_done()
+                    .map_err(|e| FastqError::fmt_err(e, self.ctx()));
+            }
+            match self.parser.push_done(line) {
+                Ok(Some(done)) => {
+                    // a FASTA entry ends on the next header, which is not its own
+                    self.record_bytes = 0;
+                    return Ok(Some(done));
                 }
+                Ok(None) => {}
+                Err(e) => return Err(FastqError::fmt_err(e, self.ctx())),
             }
-            h
-        };
+        }
+    }
 
-        if !header.starts_with('@') {
-            if self.opts.fastq_only && header.starts_with('>') {
-                return Err(FastqError::fmt_err(
-                    FormatError::FastaHeaderDetected,
-                    self.ctx(),
-                ));
-            }
-            let ch = header.chars().next().unwrap_or('\0');
-            let bytes = header.as_bytes();
-            let hex = bytes[..bytes.len().min(4)]
-                .iter()
-                .map(|b| format!("{:02X}", b))
-                .collect::<Vec<_>>()
-                .join(" ");
-            let msg = format!(
-                "expected header '@' at start of record, got {:?} (U+{:04X}); first bytes: {}",
-                ch, ch as u32, hex
-            );
-            return Err(FastqError::io_err(
-                io::Error::new(io::ErrorKind::InvalidData, msg),
-                self.ctx(),
-            ));
+    /// Span of the FASTQ record just completed in the parser.
+    fn record_span(&self) -> RecordSpan {
+        let (started, back) = self.parser.record_start();
+        let first = self.line_starts.len().saturating_sub(back as usize + 1);
+        let start = self
+            .line_starts
+            .get(first)
+            .copied()
+            .unwrap_or(self.byte_pos);
+        RecordSpan {
+            index: (self.records_before + started).saturating_sub(1),
+            line: self.line_num - back,
+            byte_offset: start,
+            byte_len: self.byte_pos - start,
         }
+    }
 
-        let mut parts = header[1..].splitn(2, char::is_whitespace);
-        let id = parts.next().unwrap_or("").to_string();
-        let desc = parts.next().map(|s| s.trim().to_string());
+    /// Where the record last returned lies in the input: its index, header
+    /// line and byte range. `None` before the first record and after a
+    /// FASTA entry.
+    pub fn last_record_span(&self) -> Option<RecordSpan> {
+        self.last_span
+    }
 
-        let mut line = String::with_capacity(256);
+    /// Take the next peeked record, restoring its span.
+    fn pop_lookahead(&mut self) -> Option<FastqRecord> {
+        let rec = self.lookahead.pop_front()?;
+        self.last_span = self.lookahead_spans.pop_front().flatten();
+        Some(rec)
+    }
 
-        match self.opts.line_mode {
-            LineMode::Single => {
-                let n = self
-                    .read_line(&mut line)
-                    .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                if n == 0 || line.is_empty() {
-                    return Err(FastqError::fmt_err(FormatError::EmptySequence, self.ctx()));
+    fn resync_to_next_header(&mut self) -> bool {
+        self.parser.resync();
+        let mut buf = Vec::with_capacity(256);
+        loop {
+            // discarded lines are bounded one at a time
+            self.record_bytes = 0;
+            if self.parser.is_between_records() {
+                self.line_starts.clear();
+            }
+            if let Some(raw) = self
+                .raw
+                .as_mut()
+                .filter(|_| self.parser.is_between_records())
+            {
+                raw.clear();
+            }
+            match self.read_line(&mut buf) {
+                Ok(0) => {
+                    self.eof = true;
+                    return false;
                 }
-                let seq = line.as_bytes().to_vec();
-
-                let n = self
-                    .read_line(&mut line)
-                    .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                if n == 0 || !line.starts_with('+') {
-                    return Err(FastqError::fmt_err(FormatError::MissingPlus, self.ctx()));
+                Err(_) => return false,
+                Ok(_) => {
+                    // only an '@' line ends the resync, and it always parses as a header
+                    let _ = self.parser.push_line(&buf);
+                    if !self.parser.is_between_records() {
+                        return true;
+                    }
                 }
+            }
+        }
+    }
 
-                let n = self
-                    .read_line(&mut line)
-                    .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                if n == 0 {
-                    return Err(FastqError::fmt_err(FormatError::UnexpectedEof, self.ctx()));
+    /// The next `k` records, parsed but not consumed; `next` yields them
+    /// first. Fewer are returned at EOF or when an error comes first — the
+    /// error is then returned by `next` after the peeked records.
+    /// [`position`](Self::position) already includes the peeked records.
+    pub fn peek_n(&mut self, k: usize) -> &[FastqRecord] {
+        let yielded = self.last_span;
+        while self.lookahead.len() < k && self.lookahead_err.is_none() {
+            match self.advance() {
+                Some(Ok(rec)) => {
+                    self.lookahead.push_back(rec);
+                    self.lookahead_spans.push_back(self.last_span);
                 }
-                let qual = line.as_bytes().to_vec();
-
-                if qual.len() != seq.len() {
-                    return Err(FastqError::fmt_err(
-                        FormatError::LengthMismatch {
-                            seq: seq.len(),
-                            qual: qual.len(),
-                        },
-                        self.ctx(),
-                    ));
-                }
-                Ok(Some(FastqRecord {
-                    id,
-                    desc,
-                    seq,
-                    qual,
-                }))
-            }
-            LineMode::Multi => {
-                let mut seq = Vec::with_capacity(256);
-                loop {
-                    let n = self
-                        .read_line(&mut line)
-                        .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                    if n == 0 {
-                        return Err(FastqError::fmt_err(FormatError::UnexpectedEof, self.ctx()));
-                    }
-                    if line.starts_with('+') {
-                        break;
-                    }
-                    seq.extend_from_slice(line.as_bytes());
+                Some(Err(err)) => self.lookahead_err = Some(err),
+                None => break,
+            }
+        }
+        self.last_span = yielded;
+        let n = k.min(self.lookahead.len());
+        &self.lookahead.make_contiguous()[..n]
+    }
+
+    /// Next entry of a FASTA/FASTQ stream (`ReaderOptions::fastq_only: false`);
+    /// without that option every entry is FASTQ. Shares position, counters
+    /// and lookahead with the record iterator.
+    pub fn next_entry(&mut self) -> Option<Result<SeqRecord, FastqError>> {
+        let rec = match self.pop_lookahead() {
+            Some(rec) => SeqRecord::Fastq(rec),
+            None => match self.lookahead_err.take() {
+                Some(err) => return Some(Err(err)),
+                None => match self.advance_entry()? {
+                    Ok(rec) => rec,
+                    Err(err) => return Some(Err(err)),
+                },
+            },
+        };
+        self.yielded(rec.seq().len());
+        Some(Ok(rec))
+    }
+
+    /// Next record as a view into reader-owned buffers, valid until the
+    /// next call; avoids the per-record allocations of the iterator. Shares
+    /// position, counters, error policy and lookahead with `next`.
+    pub fn read_ref(&mut self) -> Option<Result<RefRecord<'_>, FastqError>> {
+        if let Some(rec) = self.pop_lookahead() {
+            self.yielded(rec.seq().len());
+            let slot = self.ref_slot.insert(rec);
+            return Some(Ok(slot.as_ref_record()));
+        }
+        if let Some(err) = self.lookahead_err.take() {
+            return Some(Err(err));
+        }
+        if let Err(err) = self.advance_fastq()? {
+            return Some(Err(err));
+        }
+        self.yielded(self.parser.last_record().map_or(0, |rec| rec.seq.len()));
+        let raw = self.last_raw();
+        self.parser
+            .last_record()
+            .map(|rec| Ok(RefRecord { raw, ..rec }))
+    }
+
+    /// Read the next record into `rec`, reusing its buffer; `Ok(false)` at
+    /// EOF. The allocation-free counterpart of `next` for tight loops.
+    pub fn read_record_into(&mut self, rec: &mut FastqRecord) -> Result<bool, FastqError> {
+        if let Some(next) = self.pop_lookahead() {
+            *rec = next;
+        } else if let Some(err) = self.lookahead_err.take() {
+            return Err(err);
+        } else {
+            match self.advance_fastq() {
+                Some(Ok(())) => self.take_fastq_into(rec),
+                Some(Err(err)) => return Err(err),
+                None => return Ok(false),
+            }
+        }
+        self.yielded(rec.seq().len());
+        Ok(true)
+    }
+
+    /// Pass over the next `n` records without building them: each is
+    /// parsed into the reader's scratch buffers only. Returns how many were
+    /// passed over, fewer than `n` at EOF. They count as read in
+    /// [`stats`](Self::stats); malformed ones are handled by the error
+    /// policy as in `next`.
+    pub fn skip_records(&mut self, n: u64) -> Result<u64, FastqError> {
+        for done in 0..n {
+            if let Some(rec) = self.pop_lookahead() {
+                self.yielded(rec.seq().len());
+                continue;
+            }
+            if let Some(err) = self.lookahead_err.take() {
+                return Err(err);
+            }
+            match self.advance_fastq() {
+                Some(Ok(())) => {
+                    self.yielded(self.parser.last_record().map_or(0, |rec| rec.seq.len()))
                 }
-                if seq.is_empty() {
-                    return Err(FastqError::fmt_err(FormatError::EmptySequence, self.ctx()));
+                Some(Err(err)) => return Err(err),
+                None => return Ok(done),
+            }
+        }
+        Ok(n)
+    }
+
+    /// The next `n` records (fewer at EOF); the reader stays usable for the
+    /// rest. With [`skip_records`](Self::skip_records), picks chunk `k` of a
+    /// file: `skip_records(k * len)?` then `take_records(len)`.
+    pub fn take_records(
+        &mut self,
+        n: u64,
+    ) -> impl Iterator<Item = Result<FastqRecord, FastqError>> + '_ {
+        let mut left = n;
+        std::iter::from_fn(move || {
+            left = left.checked_sub(1)?;
+            self.next()
+        })
+    }
+
+    /// `(id, desc)` of each record, for ID inventories, pairing checks and
+    /// duplicate-ID scans. Sequence and quality are validated in the
+    /// parser's reused buffers but never copied into records, so this
+    /// allocates only the header strings. Errors and the error policy are
+    /// as for [`next`](Iterator::next).
+    pub fn headers(
+        &mut self,
+    ) -> impl Iterator<Item = Result<(String, Option<String>), FastqError>> + '_ {
+        std::iter::from_fn(move || {
+            if let Some(rec) = self.pop_lookahead() {
+                self.yielded(rec.seq().len());
+                return Some(Ok((rec.id().to_owned(), rec.desc().map(str::to_owned))));
+            }
+            if let Some(err) = self.lookahead_err.take() {
+                return Some(Err(err));
+            }
+            if let Err(err) = self.advance_fastq()? {
+                return Some(Err(err));
+            }
+            let rec = self.parser.last_record()?;
+            let (header, bases) = ((rec.id.to_owned(), rec.desc.map(str::to_owned)), rec.len());
+            self.yielded(bases);
+            Some(Ok(header))
+        })
+    }
+
+    /// Read and decompress the input on a dedicated thread, which hands
+    /// decoded chunks to this one over a bounded channel so I/O overlaps
+    /// parsing. Kept across [`seek_record`](Self::seek_record) and
+    /// [`rewind`](Self::rewind).
+    pub fn with_prefetch_thread(mut self) -> Self {
+        if !self.prefetch {
+            let src = std::mem::replace(&mut self.rdr, Box::new(io::empty()));
+            self.rdr = Box::new(PrefetchRead::spawn(src));
+            self.prefetch = true;
+        }
+        self
+    }
+
+    /// Hand every record dropped under `ErrorPolicy::Skip` (FASTA entries
+    /// skipped by the record iterator included) to `f` instead of logging
+    /// it, e.g. to count or persist them. Kept across
+    /// [`seek_record`](Self::seek_record) and [`rewind`](Self::rewind).
+    pub fn with_skip_handler<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&FastqError) + Send + 'static,
+    {
+        self.on_skip = Some(Box::new(f));
+        self
+    }
+
+    /// Records dropped so far under `ErrorPolicy::Collect`; empty under
+    /// the other policies.
+    pub fn skip_report(&self) -> &SkipReport {
+        &self.skip_report
+    }
+
+    /// Up to `n` records; empty at EOF. An error after some records is
+    /// held back and returned by the next call, so every record before it
+    /// is delivered first.
+    pub fn next_batch(&mut self, n: usize) -> Result<Vec<FastqRecord>, FastqError> {
+        let mut batch = Vec::with_capacity(n);
+        while batch.len() < n {
+            match self.next() {
+                Some(Ok(rec)) => batch.push(rec),
+                Some(Err(err)) if batch.is_empty() => return Err(err),
+                Some(Err(err)) => {
+                    self.lookahead_err = Some(err);
+                    break;
                 }
+                None => break,
+            }
+        }
+        Ok(batch)
+    }
 
-                let mut qual = Vec::with_capacity(seq.len());
-                while qual.len() < seq.len() {
-                    let n = self
-                        .read_line(&mut line)
-                        .map_err(|e| FastqError::io_err(e, self.ctx()))?;
-                    if n == 0 {
-                        return Err(FastqError::fmt_err(FormatError::UnexpectedEof, self.ctx()));
+    /// Use `index` for [`seek_record`](Self::seek_record) instead of the
+    /// `.fqi` file next to the input.
+    pub fn with_index(mut self, index: FastqIndex) -> Self {
+        self.index = Some(Arc::new(index));
+        self
+    }
+
+    /// Reposition so that the next record is record `n` (0-based, counting
+    /// the records this reader's options let through). Reopens the input
+    /// at the nearest index sample and parses forward from it; the index is
+    /// the one given to [`with_index`](Self::with_index), else the `.fqi`
+    /// next to the input (as written by [`FastqIndex::open_or_build`]).
+    /// Only for readers opened from a path.
+    pub fn seek_record(&mut self, n: u64) -> Result<(), FastqError> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let Source::Path(path) = &self.src else {
+            let err = invalid("seek_record needs a reader opened from a path".into());
+            return Err(FastqError::io_err(err, self.ctx()));
+        };
+        let path = path.clone();
+        let index = match &self.index {
+            Some(index) => Arc::clone(index),
+            None => {
+                let fqi = FastqIndex::path_for(&path);
+                let index = FastqIndex::load(&fqi).map_err(|e| {
+                    let msg = format!("cannot load index {}: {e}", fqi.display());
+                    FastqError::io_err(io::Error::new(e.kind(), msg), self.ctx())
+                })?;
+                Arc::new(index)
+            }
+        };
+        if n > index.records() {
+            let msg = format!("record {n} is past the end ({} records)", index.records());
+            return Err(FastqError::io_err(invalid(msg), self.ctx()));
+        }
+        let Some(state) = index.state_before(&path, n) else {
+            let err = invalid("the index has no entries".into());
+            return Err(FastqError::io_err(err, self.ctx()));
+        };
+        let mut rdr = Self::resume(&state, self.opts.clone())?;
+        rdr.index = Some(index);
+        if self.prefetch {
+            rdr = rdr.with_prefetch_thread();
+        }
+        for _ in state.records..n {
+            match rdr.read_ref() {
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err),
+                None => break,
+            }
+        }
+        rdr.on_skip = self.on_skip.take();
+        rdr.skip_report = std::mem::take(&mut self.skip_report);
+        *self = rdr;
+        Ok(())
+    }
+
+    /// Records `range.start..range.end`, after
+    /// [`seek_record(range.start)`](Self::seek_record).
+    pub fn records_range(
+        &mut self,
+        range: Range<u64>,
+    ) -> Result<impl Iterator<Item = Result<FastqRecord, FastqError>> + '_, FastqError> {
+        self.seek_record(range.start)?;
+        let n = range.end.saturating_sub(range.start);
+        Ok(self.take(usize::try_from(n).unwrap_or(usize::MAX)))
+    }
+
+    /// Start over from the first record, e.g. for a second pass after
+    /// counting. Reopens the input with the same options; counters, stats,
+    /// checksums and the skip report start from zero. Only for readers
+    /// opened from a path ([`from_paths`](Self::from_paths) readers go back
+    /// to the first file).
+    pub fn rewind(&mut self) -> Result<(), FastqError> {
+        let mut rdr = match (&self.chain, &self.src) {
+            (Some(chain), _) => Self::from_paths(&chain.paths, self.opts.clone())?,
+            (None, Source::Path(path)) => Self::from_path(path, self.opts.clone())?,
+            _ => {
+                let err = io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "rewind needs a reader opened from a path",
+                );
+                return Err(FastqError::io_err(err, self.ctx()));
+            }
+        };
+        rdr.index = self.index.take();
+        if self.prefetch {
+            rdr = rdr.with_prefetch_thread();
+        }
+        rdr.on_skip = self.on_skip.take();
+        *self = rdr;
+        Ok(())
+    }
+
+    /// Process records with `f` on the rayon pool, reading batches on a
+    /// dedicated thread; configure the returned [`ParProcess`] and call
+    /// [`results`](ParProcess::results). Requires the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_process<F, T>(self, f: F) -> ParProcess<F>
+    where
+        F: Fn(FastqRecord) -> T + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        ParProcess::new(self, f)
+    }
+
+    /// Read an interleaved paired file as mate pairs (R1, R2, R1, R2, ...);
+    /// see [`InterleavedPairs`].
+    pub fn pairs(self) -> InterleavedPairs {
+        let policy = self.opts.error_policy;
+        InterleavedPairs::new(self, policy)
+    }
+
+    /// Iterate over [`next_entry`](Self::next_entry).
+    pub fn entries(&mut self) -> impl Iterator<Item = Result<SeqRecord, FastqError>> + '_ {
+        std::iter::from_fn(move || self.next_entry())
+    }
+
+    /// Parse the next FASTQ record, applying the error policy; FASTA
+    /// entries count as malformed records here.
+    fn advance(&mut self) -> Option<Result<FastqRecord, FastqError>> {
+        Some(self.advance_fastq()?.map(|()| {
+            let mut rec = FastqRecord::default();
+            self.take_fastq_into(&mut rec);
+            rec
+        }))
+    }
+
+    /// Like [`advance`](Self::advance), but leaves the record in the
+    /// parser's buffers.
+    fn advance_fastq(&mut self) -> Option<Result<(), FastqError>> {
+        loop {
+            match self.advance_done()? {
+                Ok(Done::Fastq) => return Some(Ok(())),
+                Ok(done @ Done::Fasta) => {
+                    let entry = self.parser.take_done(done);
+                    let err = FastqError::fmt_err(FormatError::FastaHeaderDetected, self.ctx());
+                    instrument::parse_error(&err);
+                    if self.opts.error_policy == ErrorPolicy::Return {
+                        return Some(Err(err));
+                    }
+                    let header = match entry.desc() {
+                        Some(desc) => format!("{} {desc}", entry.id()),
+                        None => entry.id().to_owned(),
+                    };
+                    if let Err(err) = self.report_skip(err, "FASTA entry", Some(header)) {
+                        return Some(Err(err));
                     }
-                    qual.extend_from_slice(line.as_bytes());
                 }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
 
-                if qual.len() != seq.len() {
-                    return Err(FastqError::fmt_err(
-                        FormatError::LengthMismatch {
-                            seq: seq.len(),
-                            qual: qual.len(),
-                        },
-                        self.ctx(),
-                    ));
-                }
-                Ok(Some(FastqRecord {
-                    id,
-                    desc,
-                    seq,
-                    qual,
-                }))
+    /// Parse the next entry, applying the error policy.
+    fn advance_entry(&mut self) -> Option<Result<SeqRecord, FastqError>> {
+        Some(self.advance_done()?.map(|done| {
+            let mut entry = self.parser.take_done(done);
+            if let SeqRecord::Fastq(rec) = &mut entry {
+                rec.set_raw(self.last_raw());
             }
+            entry
+        }))
+    }
+
+    /// Move the record completed in the parser into `rec`, with its raw
+    /// input under `ReaderOptions::keep_raw`.
+    fn take_fastq_into(&mut self, rec: &mut FastqRecord) {
+        self.parser.take_fastq_into(rec);
+        if self.raw.is_some() {
+            rec.set_raw(self.last_raw());
         }
     }
 
-    fn resync_to_next_header(&mut self) -> bool {
-        let mut buf = String::with_capacity(256);
+    /// Input lines of the record just completed in the parser.
+    fn last_raw(&self) -> Option<&[u8]> {
+        let back = self.parser.record_start().1;
+        self.raw.as_ref().map(|raw| raw.tail(back))
+    }
+
+    /// Complete the next entry in the parser's buffers, applying the error
+    /// policy.
+    fn advance_done(&mut self) -> Option<Result<Done, FastqError>> {
         loop {
-            match self.read_line(&mut buf) {
-                Ok(0) => return false,
-                Ok(_) if buf.starts_with('@') => {
-                    self.pending_header = Some(buf.clone());
-                    return true;
+            match self.read_one() {
+                Ok(Some(done)) => return Some(Ok(done)),
+                Ok(None) => match self.open_next_path() {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        self.meter.finish(self.bytes());
+                        self.report_progress();
+                        return None;
+                    }
+                    Err(err) => return Some(Err(err)),
+                },
+                Err(err) => {
+                    instrument::parse_error(&err);
+                    if self.opts.error_policy.skips() && err.is_skippable() {
+                        let header = matches!(err, FastqError::Format { .. })
+                            .then(|| self.parser.failed_header().map(str::to_owned))
+                            .flatten();
+                        if let Err(err) = self.report_skip(err, "malformed record", header) {
+                            return Some(Err(err));
+                        }
+                        if !self.resync_to_next_header() {
+                            if self.eof {
+                                // the next file starts clean
+                                match self.open_next_path() {
+                                    Ok(true) => continue,
+                                    Ok(false) => {}
+                                    Err(err) => return Some(Err(err)),
+                                }
+                            }
+                            self.meter.finish(self.bytes());
+                            self.report_progress();
+                            return None;
+                        }
+                    } else {
+                        return Some(Err(err));
+                    }
                 }
-                Ok(_) => {}
-                Err(_) => return false,
             }
         }
     }
 
+    /// Count a dropped record, pass it to the skip handler (or log it) and
+    /// add it to the skip report under `ErrorPolicy::Collect`. Past
+    /// `ReaderOptions::max_skipped` the record is not dropped: the limit
+    /// error is returned instead.
+    fn report_skip(
+        &mut self,
+        err: FastqError,
+        what: &str,
+        header: Option<String>,
+    ) -> Result<(), FastqError> {
+        if let Some(limit) = self.opts.max_skipped.filter(|&max| self.skipped >= max) {
+            let source = LimitError::TooManySkipped { limit };
+            return Err(FastqError::LimitExceeded {
+                source,
+                ctx: self.ctx(),
+            });
+        }
+        self.skipped += 1;
+        instrument::skipped();
+        match &mut self.on_skip {
+            Some(f) => f(&err),
+            None => self.meter.skip(what, &err),
+        }
+        if let ErrorPolicy::Collect { limit } = self.opts.error_policy {
+            self.skip_report.push(err, header, limit);
+        }
+        Ok(())
+    }
+
+    /// Call the `ReaderOptions::progress` sink if a threshold was crossed,
+    /// and a last time at EOF.
+    fn report_progress(&mut self) {
+        if self.progress.is_none() {
+            return;
+        }
+        let (raw_bytes, records, done) = (self.raw_bytes(), self.records, self.eof);
+        let Some(p) = &mut self.progress else {
+            return;
+        };
+        if !done && raw_bytes < p.next_bytes && records < p.next_records {
+            return;
+        }
+        let every = p.sink.every_bytes;
+        p.next_bytes = (raw_bytes / every + 1).saturating_mul(every);
+        if let Some(n) = p.sink.every_records {
+            p.next_records = records.saturating_add(n);
+        }
+        (p.sink.callback)(&Progress {
+            records,
+            raw_bytes,
+            total_bytes: self.total_bytes,
+            elapsed: p.started.map_or(Duration::ZERO, |t| t.elapsed()),
+            done,
+        });
+        if done {
+            self.progress = None;
+        }
+    }
+
+    /// Count a record of `bases` handed to the caller, pacing under
+    /// `Throttle::RecordsPerSec`.
+    fn yielded(&mut self, bases: usize) {
+        self.records += 1;
+        self.bases += bases as u64;
+        self.meter.record(self.bytes());
+        self.report_progress();
+        if let Some(pause) = self.record_rate.as_mut().and_then(|r| r.record(1)) {
+            std::thread::sleep(pause);
+        }
+    }
+
+    /// Records whose qualities were rebased to Phred+33 under
+    /// `ReaderOptions::rebase_quality`.
+    pub fn rebased_records(&self) -> u64 {
+        self.parser.rebased_records()
+    }
+
+    /// Quality encoding detected so far under `rebase_quality`.
+    pub fn detected_quality_encoding(&self) -> Option<QualityEncoding> {
+        self.parser.detected_quality_encoding()
+    }
+
+    /// Line mode in effect; under `LineMode::Auto`, `None` until the first
+    /// records settled it.
+    pub fn line_mode(&self) -> Option<LineMode> {
+        self.parser.line_mode()
+    }
+
+    /// Input format; under `InputFormat::Auto`, `None` until the first
+    /// record was read.
+    pub fn input_format(&self) -> Option<InputFormat> {
+        self.parser.input_format()
+    }
+
+    /// Gzip members the reader has moved past, in file order, under
+    /// `ReaderOptions::track_gzip_members`; all of them once EOF is reached.
+    /// Empty for plain input or without the option.
+    #[cfg(feature = "gzip")]
+    pub fn gzip_members(&self) -> &[GzipMember] {
+        self.members.as_ref().map_or(&[], MemberTracker::members)
+    }
+
+    /// Call `f` each time the reader moves past the end of a gzip member,
+    /// before the first record of the next one is returned. Only fires
+    /// under `ReaderOptions::track_gzip_members`.
+    #[cfg(feature = "gzip")]
+    pub fn on_gzip_member<F>(&mut self, f: F)
+    where
+        F: FnMut(&GzipMember) + Send + 'static,
+    {
+        if let Some(members) = self.members.as_mut() {
+            members.set_callback(Box::new(f));
+        }
+    }
+
+    /// Current position: lines and bytes consumed so far, plus the source
+    /// label. The same context is attached to errors.
+    pub fn position(&self) -> IoContext {
+        self.ctx()
+    }
+
     #[inline]
     fn ctx(&self) -> IoContext {
         IoContext {
-            byte_pos: self.byte_pos,
-            line_num: self.line_num,
+            virtual_offset: self.virtual_offset(),
+            ..IoContext::at(
+                self.byte_pos,
+                self.line_num,
+                self.label.clone(),
+                &self.parser,
+                self.records_before,
+            )
         }
     }
+
+    #[cfg(feature = "gzip")]
+    fn virtual_offset(&self) -> Option<u64> {
+        let members = self.members.as_ref()?;
+        members.virtual_offset(self.byte_pos)
+    }
+
+    #[cfg(not(feature = "gzip"))]
+    fn virtual_offset(&self) -> Option<u64> {
+        None
+    }
 }
 
 impl Iterator for FastqReader {
     type Item = Result<FastqRecord, FastqError>;
+
     fn next(&mut self) -> Option<Self::Item> {
-        FastqReader::next(self)
+        let rec = match self.pop_lookahead() {
+            Some(rec) => rec,
+            None => match self.lookahead_err.take() {
+                Some(err) => return Some(Err(err)),
+                None => match self.advance()? {
+                    Ok(rec) => rec,
+                    Err(err) => return Some(Err(err)),
+                },
+            },
+        };
+        self.yielded(rec.seq().len());
+        Some(Ok(rec))
     }
 }
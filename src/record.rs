@@ -1,12 +1,318 @@
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::policy::QualityEncoding;
+use crate::quality::{phred_error_probability, solexa_byte_to_phred};
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A FASTQ record. ID, description, sequence, quality, the kept `+`
+/// text and the kept raw input live in one buffer (a single allocation per record); the
+/// accessors slice it. Use [`into_parts`](Self::into_parts) for separately
+/// owned fields. The default is an empty record, e.g. to pass to
+/// `FastqReader::read_record_into`.
+#[derive(Clone, PartialEq, Eq, Default)]
 pub struct FastqRecord {
+    /// `id | desc | seq | qual | plus | raw`; the text parts are valid
+    /// UTF-8.
+    buf: Vec<u8>,
+    id_end: usize,
+    desc_end: Option<usize>,
+    seq_end: usize,
+    qual_end: usize,
+    plus_end: Option<usize>,
+    raw_end: Option<usize>,
+}
+
+/// The fields of a [`FastqRecord`] as separately owned values.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FastqParts {
     pub id: String,
     pub desc: Option<String>,
     pub seq: Vec<u8>,
     pub qual: Vec<u8>,
+    pub plus_line: Option<String>,
 }
 
 impl FastqRecord {
+    pub fn new(id: &str, desc: Option<&str>, seq: &[u8], qual: &[u8]) -> Self {
+        Self::assemble(id, desc, seq, qual, None)
+    }
+
+    pub(crate) fn assemble(
+        id: &str,
+        desc: Option<&str>,
+        seq: &[u8],
+        qual: &[u8],
+        plus_line: Option<&str>,
+    ) -> Self {
+        let mut rec = Self::default();
+        rec.set(id, desc, seq, qual, plus_line);
+        rec
+    }
+
+    /// Overwrite every field, reusing the buffer's capacity.
+    pub(crate) fn set(
+        &mut self,
+        id: &str,
+        desc: Option<&str>,
+        seq: &[u8],
+        qual: &[u8],
+        plus_line: Option<&str>,
+    ) {
+        let len = id.len()
+            + desc.map_or(0, str::len)
+            + seq.len()
+            + qual.len()
+            + plus_line.map_or(0, str::len);
+        let buf = &mut self.buf;
+        buf.clear();
+        buf.reserve_exact(len);
+        buf.extend_from_slice(id.as_bytes());
+        self.id_end = buf.len();
+        self.desc_end = desc.map(|d| {
+            buf.extend_from_slice(d.as_bytes());
+            buf.len()
+        });
+        buf.extend_from_slice(seq);
+        self.seq_end = buf.len();
+        buf.extend_from_slice(qual);
+        self.qual_end = buf.len();
+        self.plus_end = plus_line.map(|p| {
+            buf.extend_from_slice(p.as_bytes());
+            buf.len()
+        });
+        self.raw_end = None;
+    }
+
+    /// Attach the input bytes the record was parsed from.
+    pub(crate) fn set_raw(&mut self, raw: Option<&[u8]>) {
+        self.buf.truncate(self.raw_start());
+        self.raw_end = raw.map(|r| {
+            self.buf.extend_from_slice(r);
+            self.buf.len()
+        });
+    }
+
+    /// The same record with `plus_line` as its `+` text. The kept raw
+    /// input no longer describes it and is dropped.
+    pub fn with_plus_line(mut self, plus_line: Option<&str>) -> Self {
+        self.buf.truncate(self.qual_end);
+        self.plus_end = plus_line.map(|p| {
+            self.buf.extend_from_slice(p.as_bytes());
+            self.buf.len()
+        });
+        self.raw_end = None;
+        self
+    }
+
+    #[inline]
+    pub fn id(&self) -> &str {
+        self.text(0, self.id_end)
+    }
+
+    #[inline]
+    pub fn desc(&self) -> Option<&str> {
+        self.desc_end.map(|end| self.text(self.id_end, end))
+    }
+
+    #[inline]
+    pub fn seq(&self) -> &[u8] {
+        &self.buf[self.seq_start()..self.seq_end]
+    }
+
+    #[inline]
+    pub fn qual(&self) -> &[u8] {
+        &self.buf[self.seq_end..self.qual_end]
+    }
+
+    /// Text after the `+` separator, kept only with
+    /// `ReaderOptions::keep_plus_line` (`None` when the line is a bare `+`).
+    #[inline]
+    pub fn plus_line(&self) -> Option<&str> {
+        self.plus_end.map(|end| self.text(self.qual_end, end))
+    }
+
+    /// The exact input bytes of the record, from its header line through
+    /// the end of its last quality line (terminator included), kept only
+    /// with `ReaderOptions::keep_raw`.
+    #[inline]
+    pub fn raw(&self) -> Option<&[u8]> {
+        self.raw_end.map(|end| &self.buf[self.raw_start()..end])
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.seq_end - self.seq_start()
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Quality scores: each quality byte minus `offset` (33 for Sanger /
+    /// Illumina 1.8+, 64 for Illumina 1.3–1.7).
+    pub fn phred_scores(&self, offset: u8) -> Vec<u8> {
+        self.qual()
+            .iter()
+            .map(|q| q.saturating_sub(offset))
+            .collect()
+    }
+
+    /// Phred scores of qualities stored in `encoding`; Solexa scores are
+    /// converted to Phred.
+    pub fn phred_scores_in(&self, encoding: QualityEncoding) -> Vec<u8> {
+        match encoding {
+            QualityEncoding::Solexa => self
+                .qual()
+                .iter()
+                .map(|&q| solexa_byte_to_phred(q))
+                .collect(),
+            _ => self.phred_scores(encoding.offset()),
+        }
+    }
+
+    /// Mean Phred+33 score of the bases; `None` for an empty record.
+    pub fn mean_quality(&self) -> Option<f64> {
+        let qual = self.qual();
+        let sum: u64 = qual.iter().map(|&q| u64::from(q.saturating_sub(33))).sum();
+        (!qual.is_empty()).then(|| sum as f64 / qual.len() as f64)
+    }
+
+    /// Per-base error probabilities of Phred+33 qualities.
+    pub fn error_probabilities(&self) -> Vec<f64> {
+        self.qual()
+            .iter()
+            .map(|&q| phred_error_probability(q.saturating_sub(33)))
+            .collect()
+    }
+
+    /// Borrow this record as a [`RefRecord`].
+    #[inline]
+    pub fn as_ref_record(&self) -> RefRecord<'_> {
+        RefRecord {
+            id: self.id(),
+            desc: self.desc(),
+            seq: self.seq(),
+            qual: self.qual(),
+            plus_line: self.plus_line(),
+            raw: self.raw(),
+        }
+    }
+
+    /// Split into separately owned fields.
+    pub fn into_parts(self) -> FastqParts {
+        FastqParts {
+            id: self.id().to_string(),
+            desc: self.desc().map(str::to_string),
+            seq: self.seq().to_vec(),
+            qual: self.qual().to_vec(),
+            plus_line: self.plus_line().map(str::to_string),
+        }
+    }
+
+    #[inline]
+    fn seq_start(&self) -> usize {
+        self.desc_end.unwrap_or(self.id_end)
+    }
+
+    #[inline]
+    fn raw_start(&self) -> usize {
+        self.plus_end.unwrap_or(self.qual_end)
+    }
+
+    #[inline]
+    fn text(&self, start: usize, end: usize) -> &str {
+        // SAFETY: the id, desc and plus ranges were copied from `&str`s
+        unsafe { core::str::from_utf8_unchecked(&self.buf[start..end]) }
+    }
+}
+
+impl From<FastqParts> for FastqRecord {
+    fn from(p: FastqParts) -> Self {
+        Self::assemble(
+            &p.id,
+            p.desc.as_deref(),
+            &p.seq,
+            &p.qual,
+            p.plus_line.as_deref(),
+        )
+    }
+}
+
+impl fmt::Debug for FastqRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FastqRecord")
+            .field("id", &self.id())
+            .field("desc", &self.desc())
+            .field("seq", &self.seq())
+            .field("qual", &self.qual())
+            .field("plus_line", &self.plus_line())
+            .field("raw", &self.raw().map(<[u8]>::escape_ascii))
+            .finish()
+    }
+}
+
+/// A FASTA entry read from a mixed FASTA/FASTQ stream
+/// (`ReaderOptions::fastq_only: false`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FastaRecord {
+    pub id: String,
+    pub desc: Option<String>,
+    pub seq: Vec<u8>,
+}
+
+/// One entry of a mixed FASTA/FASTQ stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeqRecord {
+    Fastq(FastqRecord),
+    Fasta(FastaRecord),
+}
+
+impl SeqRecord {
+    pub fn id(&self) -> &str {
+        match self {
+            SeqRecord::Fastq(r) => r.id(),
+            SeqRecord::Fasta(r) => &r.id,
+        }
+    }
+
+    pub fn desc(&self) -> Option<&str> {
+        match self {
+            SeqRecord::Fastq(r) => r.desc(),
+            SeqRecord::Fasta(r) => r.desc.as_deref(),
+        }
+    }
+
+    pub fn seq(&self) -> &[u8] {
+        match self {
+            SeqRecord::Fastq(r) => r.seq(),
+            SeqRecord::Fasta(r) => &r.seq,
+        }
+    }
+
+    /// Qualities, for FASTQ entries.
+    pub fn qual(&self) -> Option<&[u8]> {
+        match self {
+            SeqRecord::Fastq(r) => Some(r.qual()),
+            SeqRecord::Fasta(_) => None,
+        }
+    }
+}
+
+/// Borrowed view of a FASTQ record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefRecord<'a> {
+    pub id: &'a str,
+    pub desc: Option<&'a str>,
+    pub seq: &'a [u8],
+    pub qual: &'a [u8],
+    /// Text after `+`, as in [`FastqRecord::plus_line`].
+    pub plus_line: Option<&'a str>,
+    /// Input bytes, as in [`FastqRecord::raw`].
+    pub raw: Option<&'a [u8]>,
+}
+
+impl RefRecord<'_> {
     #[inline]
     pub fn len(&self) -> usize {
         self.seq.len()
@@ -15,4 +321,19 @@ impl FastqRecord {
     pub fn is_empty(&self) -> bool {
         self.seq.is_empty()
     }
+
+    /// Copy the borrowed data into an owned [`FastqRecord`].
+    pub fn to_owned_record(&self) -> FastqRecord {
+        let mut rec =
+            FastqRecord::assemble(self.id, self.desc, self.seq, self.qual, self.plus_line);
+        rec.set_raw(self.raw);
+        rec
+    }
+}
+
+impl<'a> From<&'a FastqRecord> for RefRecord<'a> {
+    #[inline]
+    fn from(rec: &'a FastqRecord) -> Self {
+        rec.as_ref_record()
+    }
 }
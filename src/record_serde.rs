@@ -0,0 +1,117 @@
+//! `Serialize`/`Deserialize` for [`FastqRecord`] (feature `serde`).
+//!
+//! A record is a map of `id`, `desc` (omitted when absent), `seq`, `qual`
+//! and `plus_line` (omitted when absent); the kept raw input is not
+//! included. By default `seq` and `qual` are plain strings, which needs
+//! them to be UTF-8 (FASTQ text always is). For byte-exact transport of
+//! arbitrary input, [`base64`] encodes them as base64 instead:
+//!
+//! ```
+//! # use kira_cdh_compat_fastq_reader::FastqRecord;
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Message {
+//!     #[serde(with = "kira_cdh_compat_fastq_reader::record_serde::base64")]
+//!     record: FastqRecord,
+//! }
+//! ```
+
+use crate::record::FastqRecord;
+
+use ::base64::Engine as _;
+use ::base64::engine::general_purpose::STANDARD;
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Serialize)]
+struct RecordRef<'a, T> {
+    id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    desc: Option<&'a str>,
+    seq: T,
+    qual: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plus_line: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecordOwned {
+    id: String,
+    #[serde(default)]
+    desc: Option<String>,
+    seq: String,
+    qual: String,
+    #[serde(default)]
+    plus_line: Option<String>,
+}
+
+impl RecordOwned {
+    fn to_record(&self, seq: &[u8], qual: &[u8]) -> FastqRecord {
+        FastqRecord::new(&self.id, self.desc.as_deref(), seq, qual)
+            .with_plus_line(self.plus_line.as_deref())
+    }
+}
+
+impl<'a, T> RecordRef<'a, T> {
+    fn new(rec: &'a FastqRecord, encode: impl Fn(&'a [u8]) -> T) -> Self {
+        Self {
+            id: rec.id(),
+            desc: rec.desc(),
+            seq: encode(rec.seq()),
+            qual: encode(rec.qual()),
+            plus_line: rec.plus_line(),
+        }
+    }
+}
+
+impl Serialize for FastqRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let text = |field: &'static str, bytes| {
+            std::str::from_utf8(bytes)
+                .map_err(|_| S::Error::custom(format_args!("{field} is not UTF-8; use base64")))
+        };
+        RecordRef {
+            id: self.id(),
+            desc: self.desc(),
+            seq: text("seq", self.seq())?,
+            qual: text("qual", self.qual())?,
+            plus_line: self.plus_line(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FastqRecord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rec = RecordOwned::deserialize(deserializer)?;
+        Ok(rec.to_record(rec.seq.as_bytes(), rec.qual.as_bytes()))
+    }
+}
+
+/// `#[serde(with = "...")]` module serializing a [`FastqRecord`] with
+/// base64 (standard alphabet, padded) `seq` and `qual`.
+pub mod base64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(rec: &FastqRecord, serializer: S) -> Result<S::Ok, S::Error> {
+        RecordRef::new(rec, encode).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<FastqRecord, D::Error> {
+        let rec = RecordOwned::deserialize(deserializer)?;
+        let seq = decode(&rec.seq).map_err(|e| D::Error::custom(format_args!("seq: {e}")))?;
+        let qual = decode(&rec.qual).map_err(|e| D::Error::custom(format_args!("qual: {e}")))?;
+        Ok(rec.to_record(&seq, &qual))
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    STANDARD.encode(bytes)
+}
+
+fn decode(text: &str) -> Result<Vec<u8>, ::base64::DecodeError> {
+    STANDARD.decode(text)
+}
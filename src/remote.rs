@@ -0,0 +1,216 @@
+//! HTTP(S) input for
+//! [`AsyncFastqReader::from_url`](crate::async_reader::AsyncFastqReader::from_url)
+//! and `from_s3`.
+//!
+//! A background task downloads the body into a bounded channel. When the
+//! connection drops mid-body, the rest is requested again with a `Range`
+//! header, up to [`MAX_RETRIES`] times with exponential backoff. S3 objects
+//! are fetched part by part with ranged GETs.
+
+#[cfg(feature = "s3")]
+use crate::s3::{S3Object, amz_date_now};
+
+use bytes::Bytes;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::sync::mpsc;
+
+/// Consecutive failed attempts tolerated before the error is returned.
+const MAX_RETRIES: u32 = 5;
+const RETRY_DELAY: Duration = Duration::from_millis(250);
+/// Body chunks buffered ahead of the parser.
+const CHANNEL_CAPACITY: usize = 16;
+
+/// What to download.
+pub(crate) enum Target {
+    Url(String),
+    #[cfg(feature = "s3")]
+    S3(S3Object),
+}
+
+impl Target {
+    /// GET of the bytes from `start` on: to the end for a URL (the whole
+    /// body when `start` is 0), one part for S3.
+    fn get(&self, client: &Client, start: u64) -> RequestBuilder {
+        match self {
+            Target::Url(url) if start == 0 => client.get(url),
+            Target::Url(url) => client.get(url).header(RANGE, format!("bytes={start}-")),
+            #[cfg(feature = "s3")]
+            Target::S3(obj) => {
+                let end = start + obj.part_size.max(1) - 1;
+                let range = format!("bytes={start}-{end}");
+                obj.signed_headers(&range, &amz_date_now())
+                    .into_iter()
+                    .fold(client.get(obj.url()), |req, (name, value)| {
+                        req.header(name, value)
+                    })
+            }
+        }
+    }
+
+    fn is_ranged(&self, start: u64) -> bool {
+        match self {
+            Target::Url(_) => start > 0,
+            #[cfg(feature = "s3")]
+            Target::S3(_) => true,
+        }
+    }
+
+    pub(crate) fn label(&self) -> String {
+        match self {
+            Target::Url(url) => url.clone(),
+            #[cfg(feature = "s3")]
+            Target::S3(obj) => obj.uri(),
+        }
+    }
+}
+
+/// The body of `target` as a byte stream. Fails if the first request does
+/// (after retrying transient errors).
+pub(crate) async fn open(target: Target) -> io::Result<Body> {
+    let client = Client::new();
+    let mut retries = 0;
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    // `None`: an empty object, which has no byte 0 to range over
+    if let Some(resp) = request(&client, &target, 0, &mut retries).await? {
+        tokio::spawn(download(client, target, resp, tx));
+    }
+    Ok(Body {
+        rx,
+        chunk: Bytes::new(),
+    })
+}
+
+/// Read side of the download task.
+pub(crate) struct Body {
+    rx: mpsc::Receiver<io::Result<Bytes>>,
+    chunk: Bytes,
+}
+
+impl AsyncRead for Body {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        while this.chunk.is_empty() {
+            match ready!(this.rx.poll_recv(cx)) {
+                Some(Ok(chunk)) => this.chunk = chunk,
+                Some(Err(err)) => return Poll::Ready(Err(err)),
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+        let n = buf.remaining().min(this.chunk.len());
+        buf.put_slice(&this.chunk.split_to(n));
+        Poll::Ready(Ok(()))
+    }
+}
+
+async fn download(
+    client: Client,
+    target: Target,
+    mut resp: Response,
+    tx: mpsc::Sender<io::Result<Bytes>>,
+) {
+    let mut offset = 0u64;
+    let mut retries = 0;
+    let mut total = object_size(&resp);
+    loop {
+        let next = match resp.chunk().await {
+            Ok(Some(chunk)) => {
+                offset += chunk.len() as u64;
+                retries = 0;
+                if tx.send(Ok(chunk)).await.is_err() {
+                    // reader dropped
+                    return;
+                }
+                continue;
+            }
+            // next part
+            Ok(None) if total.is_some_and(|total| offset < total) => {
+                request(&client, &target, offset, &mut retries).await
+            }
+            Ok(None) => return,
+            // hyper reports a cut-off body as a decode error; resume either way
+            Err(_) if retries < MAX_RETRIES => {
+                backoff(&mut retries).await;
+                request(&client, &target, offset, &mut retries).await
+            }
+            Err(err) => Err(io::Error::other(err)),
+        };
+        match next {
+            Ok(Some(next)) => {
+                total = total.or_else(|| object_size(&next));
+                resp = next;
+            }
+            Ok(None) => return,
+            Err(err) => {
+                let _ = tx.send(Err(err)).await;
+                return;
+            }
+        }
+    }
+}
+
+/// Object size from a ranged response's `Content-Range: bytes a-b/size`.
+fn object_size(resp: &Response) -> Option<u64> {
+    let value = resp.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    value.rsplit_once('/')?.1.parse().ok()
+}
+
+/// GET `target` from byte `offset` on, retrying transient failures;
+/// `None` when a ranged GET of byte 0 finds the object empty.
+async fn request(
+    client: &Client,
+    target: &Target,
+    offset: u64,
+    retries: &mut u32,
+) -> io::Result<Option<Response>> {
+    let ranged = target.is_ranged(offset);
+    loop {
+        let err = match target.get(client, offset).send().await {
+            Ok(resp)
+                if ranged && offset == 0 && resp.status() == StatusCode::RANGE_NOT_SATISFIABLE =>
+            {
+                return Ok(None);
+            }
+            Ok(resp) => match resp.error_for_status() {
+                Ok(resp) if ranged && resp.status() != StatusCode::PARTIAL_CONTENT => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        format!(
+                            "{}: server ignored the range request, cannot resume",
+                            target.label()
+                        ),
+                    ));
+                }
+                Ok(resp) => return Ok(Some(resp)),
+                Err(err) => err,
+            },
+            Err(err) => err,
+        };
+        if !is_transient(&err) || *retries >= MAX_RETRIES {
+            return Err(io::Error::other(err));
+        }
+        backoff(retries).await;
+    }
+}
+
+async fn backoff(retries: &mut u32) {
+    tokio::time::sleep(RETRY_DELAY * 2u32.pow(*retries)).await;
+    *retries += 1;
+}
+
+/// Dropped connections, timeouts and 5xx/429 responses.
+fn is_transient(err: &reqwest::Error) -> bool {
+    match err.status() {
+        Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        None => err.is_timeout() || err.is_connect() || err.is_body() || err.is_request(),
+    }
+}
@@ -0,0 +1,192 @@
+//! S3 objects as input for
+//! [`AsyncFastqReader::from_s3`](crate::async_reader::AsyncFastqReader::from_s3).
+//!
+//! Objects are fetched in ranged parts of [`S3Object::part_size`] bytes,
+//! each request signed with AWS Signature Version 4 (or sent unsigned for
+//! public buckets). Any S3-compatible endpoint (MinIO, Ceph, R2, ...) can
+//! be set with [`S3Object::endpoint`]; it is addressed path-style.
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// SHA-256 of the empty payload of a GET.
+const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Access keys for signing requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// For temporary credentials.
+    pub session_token: Option<String>,
+}
+
+impl S3Credentials {
+    /// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+    /// `AWS_SESSION_TOKEN`; `None` unless both keys are set.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            access_key_id: env::var("AWS_ACCESS_KEY_ID").ok()?,
+            secret_access_key: env::var("AWS_SECRET_ACCESS_KEY").ok()?,
+            session_token: env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+}
+
+/// One object and how to reach it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct S3Object {
+    pub bucket: String,
+    pub key: String,
+    pub region: String,
+    /// Base URL of an S3-compatible service (`http://localhost:9000`);
+    /// `None` for AWS.
+    pub endpoint: Option<String>,
+    /// `None` sends unsigned requests.
+    pub credentials: Option<S3Credentials>,
+    /// Bytes per ranged GET (default 8 MiB).
+    pub part_size: u64,
+}
+
+impl S3Object {
+    /// `bucket`/`key` with region, endpoint and credentials from the
+    /// environment: `AWS_REGION` (or `AWS_DEFAULT_REGION`, else
+    /// `us-east-1`), `AWS_ENDPOINT_URL` and [`S3Credentials::from_env`].
+    pub fn new(bucket: impl Into<String>, key: impl Into<String>) -> Self {
+        Self {
+            bucket: bucket.into(),
+            key: key.into(),
+            region: env::var("AWS_REGION")
+                .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+                .unwrap_or_else(|_| "us-east-1".to_owned()),
+            endpoint: env::var("AWS_ENDPOINT_URL").ok(),
+            credentials: S3Credentials::from_env(),
+            part_size: 8 << 20,
+        }
+    }
+
+    /// `s3://bucket/key`, the label carried in errors.
+    pub fn uri(&self) -> String {
+        format!("s3://{}/{}", self.bucket, self.key)
+    }
+
+    /// URL of the object: path-style on a custom endpoint, virtual-hosted
+    /// on AWS.
+    pub fn url(&self) -> String {
+        let path = uri_encode_path(&self.key);
+        match &self.endpoint {
+            Some(base) => format!("{}/{}/{path}", base.trim_end_matches('/'), self.bucket),
+            None if self.region == "us-east-1" => {
+                format!("https://{}.s3.amazonaws.com/{path}", self.bucket)
+            }
+            None => format!(
+                "https://{}.s3.{}.amazonaws.com/{path}",
+                self.bucket, self.region
+            ),
+        }
+    }
+
+    /// Headers of a signed GET of `range` (an HTTP `Range` value) at
+    /// `amz_date` (`YYYYMMDDTHHMMSSZ`), `Authorization` last. Only `Range`
+    /// without credentials.
+    pub fn signed_headers(&self, range: &str, amz_date: &str) -> Vec<(&'static str, String)> {
+        let mut headers = vec![("range", range.to_owned())];
+        let Some(creds) = &self.credentials else {
+            return headers;
+        };
+        headers.push(("x-amz-content-sha256", EMPTY_SHA256.to_owned()));
+        headers.push(("x-amz-date", amz_date.to_owned()));
+        if let Some(token) = &creds.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+
+        let url = self.url();
+        let after_scheme = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+        let (host, path) = after_scheme.split_once('/').unwrap_or((after_scheme, ""));
+        let mut canonical_headers = format!("host:{host}\n");
+        let mut signed = String::from("host");
+        for (name, value) in &headers {
+            canonical_headers.push_str(&format!("{name}:{}\n", value.trim()));
+            signed.push(';');
+            signed.push_str(name);
+        }
+        let canonical_request =
+            format!("GET\n/{path}\n\n{canonical_headers}\n{signed}\n{EMPTY_SHA256}");
+
+        let date = &amz_date[..amz_date.len().min(8)];
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex(&sha256(canonical_request.as_bytes()))
+        );
+        let mut key = hmac_sha256(
+            format!("AWS4{}", creds.secret_access_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part.as_bytes());
+        }
+        let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+        headers.push((
+            "authorization",
+            format!(
+                "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed}, Signature={signature}",
+                creds.access_key_id
+            ),
+        ));
+        headers
+    }
+}
+
+/// The current time as `YYYYMMDDTHHMMSSZ`.
+pub(crate) fn amz_date_now() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // civil-from-days, after Howard Hinnant
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        rem / 3600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+/// Percent-encode a key as SigV4 wants it: everything but unreserved
+/// characters and `/`.
+fn uri_encode_path(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
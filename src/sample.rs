@@ -0,0 +1,93 @@
+//! Seeded random subsampling of a record stream.
+//!
+//! [`Subsample`] is implemented for every iterator of
+//! `Result<T, FastqError>`: a [`FastqReader`](crate::FastqReader) (records),
+//! a [`PairedFastqReader`](crate::PairedFastqReader) or
+//! [`FastqReader::pairs`](crate::FastqReader::pairs) (pairs, so R1 and R2
+//! are kept or dropped together). One random draw is made per item, so the
+//! same seed over the same input always selects the same items.
+
+use crate::error::FastqError;
+
+/// Stream subsampling; see the module docs.
+pub trait Subsample<T>: Iterator<Item = Result<T, FastqError>> + Sized {
+    /// Keep each item with probability `fraction` (clamped to `0..=1`).
+    /// Errors pass through.
+    fn sample_fraction(self, fraction: f64, seed: u64) -> SampleFraction<Self> {
+        SampleFraction {
+            inner: self,
+            fraction: fraction.clamp(0.0, 1.0),
+            rng: SplitMix64(seed),
+        }
+    }
+
+    /// `n` items drawn uniformly from the whole stream (reservoir
+    /// sampling, `n` items in memory), in input order; all of them when
+    /// there are fewer. Stops at the first error.
+    fn sample_n(self, n: usize, seed: u64) -> Result<Vec<T>, FastqError> {
+        let mut rng = SplitMix64(seed);
+        let mut reservoir: Vec<(u64, T)> = Vec::with_capacity(n.min(1 << 16));
+        for (i, item) in (0u64..).zip(self) {
+            let item = item?;
+            if reservoir.len() < n {
+                reservoir.push((i, item));
+            } else {
+                let j = rng.below(i + 1);
+                if let Some(slot) = reservoir.get_mut(j as usize) {
+                    *slot = (i, item);
+                }
+            }
+        }
+        reservoir.sort_unstable_by_key(|&(i, _)| i);
+        Ok(reservoir.into_iter().map(|(_, item)| item).collect())
+    }
+}
+
+impl<T, I> Subsample<T> for I where I: Iterator<Item = Result<T, FastqError>> {}
+
+/// Iterator of [`Subsample::sample_fraction`].
+pub struct SampleFraction<I> {
+    inner: I,
+    fraction: f64,
+    rng: SplitMix64,
+}
+
+impl<T, I> Iterator for SampleFraction<I>
+where
+    I: Iterator<Item = Result<T, FastqError>>,
+{
+    type Item = Result<T, FastqError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next()? {
+                Ok(item) if self.rng.unit() < self.fraction => return Some(Ok(item)),
+                Ok(_) => {}
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// SplitMix64; any seed, zero included, gives a full-period stream.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `0..n`.
+    fn below(&mut self, n: u64) -> u64 {
+        ((u128::from(self.next_u64()) * u128::from(n)) >> 64) as u64
+    }
+}
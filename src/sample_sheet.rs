@@ -0,0 +1,186 @@
+//! Sample sheets describing a demultiplexing run.
+//!
+//! [`SampleSheet::parse`] reads the `[Data]` (or `[BCLConvert_Data]`)
+//! section of an Illumina `SampleSheet.csv` — columns `Sample_ID`,
+//! `Sample_Name`, `index`, `index2` and `Lane` — or a plain tab-separated
+//! list of `name  index  [index2]` lines (`#` starts a comment). The
+//! result can configure a [`Demultiplexer`](crate::demux::Demultiplexer)
+//! or be used on its own.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SampleSheetError {
+    #[error("reading sample sheet: {0}")]
+    Io(#[from] io::Error),
+    #[error("sample sheet has no {0} column")]
+    MissingColumn(&'static str),
+    #[error("sample sheet line {line}: {msg}")]
+    Invalid { line: usize, msg: String },
+}
+
+/// One sample of a sheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleEntry {
+    pub id: String,
+    /// `Sample_Name`, or the ID when the sheet has none.
+    pub name: String,
+    /// i7 index.
+    pub index: String,
+    /// i5 index, for dual-indexed runs.
+    pub index2: Option<String>,
+    /// `None` when the sheet has no `Lane` column: the sample is in every
+    /// lane.
+    pub lane: Option<u32>,
+}
+
+impl SampleEntry {
+    /// Whether the sample was sequenced in `lane`.
+    pub fn in_lane(&self, lane: u32) -> bool {
+        self.lane.is_none_or(|l| l == lane)
+    }
+
+    /// Barcode as the demultiplexer takes it: `index`, or `index+index2`.
+    pub fn barcode(&self) -> String {
+        match &self.index2 {
+            Some(i5) => format!("{}+{i5}", self.index),
+            None => self.index.clone(),
+        }
+    }
+}
+
+/// Parsed sample sheet; see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SampleSheet {
+    pub samples: Vec<SampleEntry>,
+}
+
+impl SampleSheet {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, SampleSheetError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse an Illumina CSV sheet, or a TSV list when the text has no
+    /// `[Data]` section or `Sample_ID` header.
+    pub fn parse(text: &str) -> Result<Self, SampleSheetError> {
+        if text
+            .lines()
+            .any(|l| l.trim_start().starts_with('[') || l.starts_with("Sample_ID"))
+        {
+            Self::parse_csv(text)
+        } else {
+            Self::parse_tsv(text)
+        }
+    }
+
+    /// Samples of `lane`, plus those listed for every lane.
+    pub fn lane(&self, lane: u32) -> impl Iterator<Item = &SampleEntry> {
+        self.samples.iter().filter(move |s| s.in_lane(lane))
+    }
+
+    fn parse_csv(text: &str) -> Result<Self, SampleSheetError> {
+        let has_sections = text.lines().any(|l| l.trim_start().starts_with('['));
+        let mut in_data = !has_sections;
+        let mut columns: Option<Columns> = None;
+        let mut samples = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.starts_with('[') {
+                let section = line.trim_end_matches(',');
+                in_data = matches!(section, "[Data]" | "[BCLConvert_Data]");
+                columns = None;
+                continue;
+            }
+            let fields: Vec<&str> = line
+                .split(',')
+                .map(|f| f.trim().trim_matches('"'))
+                .collect();
+            if !in_data || fields.iter().all(|f| f.is_empty()) {
+                continue;
+            }
+            let Some(cols) = &columns else {
+                columns = Some(Columns::new(&fields)?);
+                continue;
+            };
+            let get = |i: Option<usize>| {
+                i.and_then(|i| fields.get(i))
+                    .filter(|f| !f.is_empty())
+                    .map(|f| f.to_string())
+            };
+            let invalid = |msg: &str| SampleSheetError::Invalid {
+                line: n + 1,
+                msg: msg.to_owned(),
+            };
+            let id = get(Some(cols.id)).ok_or_else(|| invalid("empty Sample_ID"))?;
+            let index = get(Some(cols.index)).ok_or_else(|| invalid("empty index"))?;
+            let lane = match get(cols.lane) {
+                Some(l) => Some(l.parse().map_err(|_| invalid("Lane is not a number"))?),
+                None => None,
+            };
+            samples.push(SampleEntry {
+                name: get(cols.name).unwrap_or_else(|| id.clone()),
+                id,
+                index,
+                index2: get(cols.index2),
+                lane,
+            });
+        }
+        if columns.is_none() {
+            return Err(SampleSheetError::MissingColumn("Sample_ID"));
+        }
+        Ok(Self { samples })
+    }
+
+    fn parse_tsv(text: &str) -> Result<Self, SampleSheetError> {
+        let mut samples = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+            let [name, index, rest @ ..] = fields.as_slice() else {
+                return Err(SampleSheetError::Invalid {
+                    line: n + 1,
+                    msg: "expected name and index separated by a tab".to_owned(),
+                });
+            };
+            samples.push(SampleEntry {
+                id: name.to_string(),
+                name: name.to_string(),
+                index: index.to_string(),
+                index2: rest
+                    .first()
+                    .filter(|i| !i.is_empty())
+                    .map(|i| i.to_string()),
+                lane: None,
+            });
+        }
+        Ok(Self { samples })
+    }
+}
+
+/// Column positions in a `[Data]` header.
+struct Columns {
+    id: usize,
+    name: Option<usize>,
+    index: usize,
+    index2: Option<usize>,
+    lane: Option<usize>,
+}
+
+impl Columns {
+    fn new(header: &[&str]) -> Result<Self, SampleSheetError> {
+        let find = |name: &str| header.iter().position(|h| h.eq_ignore_ascii_case(name));
+        Ok(Self {
+            id: find("Sample_ID").ok_or(SampleSheetError::MissingColumn("Sample_ID"))?,
+            name: find("Sample_Name"),
+            index: find("index").ok_or(SampleSheetError::MissingColumn("index"))?,
+            index2: find("index2"),
+            lane: find("Lane"),
+        })
+    }
+}
@@ -0,0 +1,68 @@
+//! Read-ID sanitization for downstream tools that split or truncate IDs.
+//!
+//! Used by [`RecordParser`](crate::core::RecordParser) when
+//! `ReaderOptions::sanitize_ids` is set.
+
+use crate::policy::IdSanitize;
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+
+/// Applies an [`IdSanitize`] to each record ID in turn.
+#[derive(Debug, Clone)]
+pub(crate) struct IdSanitizer {
+    rules: IdSanitize,
+    seen: BTreeSet<String>,
+}
+
+impl IdSanitizer {
+    pub(crate) fn new(rules: IdSanitize) -> Self {
+        Self {
+            rules,
+            seen: BTreeSet::new(),
+        }
+    }
+
+    pub(crate) fn apply(&mut self, id: &mut String) {
+        if id.contains(|c: char| c.is_whitespace() || c == ';' || c == ',') {
+            *id = id
+                .chars()
+                .map(|c| {
+                    if c.is_whitespace() || c == ';' || c == ',' {
+                        '_'
+                    } else {
+                        c
+                    }
+                })
+                .collect();
+        }
+        let max_len = self.rules.max_len.unwrap_or(usize::MAX);
+        truncate(id, max_len);
+        if !self.rules.unique || self.seen.insert(id.clone()) {
+            return;
+        }
+        let base = core::mem::take(id);
+        for n in 2u64.. {
+            let suffix = format!("_{n}");
+            let mut candidate = base.clone();
+            truncate(&mut candidate, max_len.saturating_sub(suffix.len()));
+            candidate.push_str(&suffix);
+            if self.seen.insert(candidate.clone()) {
+                *id = candidate;
+                return;
+            }
+        }
+    }
+}
+
+/// Cut `s` to at most `max` bytes without splitting a character.
+fn truncate(s: &mut String, max: usize) {
+    if s.len() > max {
+        let mut end = max;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+    }
+}
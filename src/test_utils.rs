@@ -0,0 +1,267 @@
+//! Deterministic FASTQ generator and corruptor for downstream tests
+//! (feature `test-utils`).
+//!
+//! Everything here is seeded, so a failing test reproduces byte-for-byte.
+
+use crate::record::FastqRecord;
+
+/// Small xorshift64* generator; deterministic and dependency-free.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // zero is a fixed point of xorshift
+        Self(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform value in `lo..=hi`.
+    pub fn range(&mut self, lo: u64, hi: u64) -> u64 {
+        debug_assert!(lo <= hi);
+        lo + self.next_u64() % (hi - lo + 1)
+    }
+}
+
+/// Distribution of Phred scores (before the +33 offset).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityProfile {
+    /// Every base gets the same score.
+    Constant(u8),
+    /// Scores drawn uniformly from `min..=max`.
+    Uniform { min: u8, max: u8 },
+    /// Scores falling linearly from `start` to `end` along the read
+    /// (typical Illumina 3' degradation), with +/-2 jitter.
+    Degrading { start: u8, end: u8 },
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratorConfig {
+    pub seed: u64,
+    pub min_len: usize,
+    pub max_len: usize,
+    pub quality: QualityProfile,
+    /// Fraction of bases replaced with `N`.
+    pub n_fraction: f64,
+    /// Attach a `desc` field to every record.
+    pub with_desc: bool,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            seed: 42,
+            min_len: 50,
+            max_len: 150,
+            quality: QualityProfile::Uniform { min: 2, max: 40 },
+            n_fraction: 0.0,
+            with_desc: false,
+        }
+    }
+}
+
+/// Produces an endless, reproducible stream of valid records.
+#[derive(Debug, Clone)]
+pub struct RecordGenerator {
+    cfg: GeneratorConfig,
+    rng: Rng,
+    index: u64,
+}
+
+impl RecordGenerator {
+    pub fn new(cfg: GeneratorConfig) -> Self {
+        let rng = Rng::new(cfg.seed);
+        Self { cfg, rng, index: 0 }
+    }
+
+    pub fn next_record(&mut self) -> FastqRecord {
+        let len = self.rng.range(
+            self.cfg.min_len as u64,
+            self.cfg.max_len.max(self.cfg.min_len) as u64,
+        ) as usize;
+        let n_cutoff = (self.cfg.n_fraction.clamp(0.0, 1.0) * u32::MAX as f64) as u64;
+        let seq: Vec<u8> = (0..len)
+            .map(|_| {
+                if n_cutoff > 0 && (self.rng.next_u64() & 0xFFFF_FFFF) < n_cutoff {
+                    b'N'
+                } else {
+                    b"ACGT"[(self.rng.next_u64() % 4) as usize]
+                }
+            })
+            .collect();
+        let qual: Vec<u8> = (0..len)
+            .map(|i| {
+                let q = match self.cfg.quality {
+                    QualityProfile::Constant(q) => q,
+                    QualityProfile::Uniform { min, max } => {
+                        self.rng.range(min as u64, max.max(min) as u64) as u8
+                    }
+                    QualityProfile::Degrading { start, end } => {
+                        let t = if len > 1 {
+                            i as f64 / (len - 1) as f64
+                        } else {
+                            0.0
+                        };
+                        let base = start as f64 + (end as f64 - start as f64) * t;
+                        let jitter = self.rng.range(0, 4) as f64 - 2.0;
+                        (base + jitter).clamp(0.0, 93.0) as u8
+                    }
+                };
+                q.min(93) + 33
+            })
+            .collect();
+        let id = format!("read{}", self.index);
+        let desc = self
+            .cfg
+            .with_desc
+            .then(|| format!("sample=gen idx={}", self.index));
+        self.index += 1;
+        FastqRecord::new(&id, desc.as_deref(), &seq, &qual)
+    }
+
+    /// Generate `n` records.
+    pub fn take_records(&mut self, n: usize) -> Vec<FastqRecord> {
+        (0..n).map(|_| self.next_record()).collect()
+    }
+}
+
+impl Iterator for RecordGenerator {
+    type Item = FastqRecord;
+
+    fn next(&mut self) -> Option<FastqRecord> {
+        Some(self.next_record())
+    }
+}
+
+/// Serialize records as FASTQ; `wrap` splits seq/qual into lines of that
+/// width (multi-line FASTQ), `None` keeps them on one line.
+pub fn to_fastq_bytes(records: &[FastqRecord], wrap: Option<usize>) -> Vec<u8> {
+    let mut out = Vec::new();
+    for rec in records {
+        write_record(&mut out, rec, wrap);
+    }
+    out
+}
+
+fn write_record(out: &mut Vec<u8>, rec: &FastqRecord, wrap: Option<usize>) {
+    out.push(b'@');
+    out.extend_from_slice(rec.id().as_bytes());
+    if let Some(desc) = rec.desc() {
+        out.push(b' ');
+        out.extend_from_slice(desc.as_bytes());
+    }
+    out.push(b'\n');
+    write_wrapped(out, rec.seq(), wrap);
+    out.push(b'+');
+    if let Some(plus) = rec.plus_line() {
+        out.extend_from_slice(plus.as_bytes());
+    }
+    out.push(b'\n');
+    write_wrapped(out, rec.qual(), wrap);
+}
+
+fn write_wrapped(out: &mut Vec<u8>, data: &[u8], wrap: Option<usize>) {
+    match wrap {
+        Some(w) if w > 0 && !data.is_empty() => {
+            for chunk in data.chunks(w) {
+                out.extend_from_slice(chunk);
+                out.push(b'\n');
+            }
+        }
+        _ => {
+            out.extend_from_slice(data);
+            out.push(b'\n');
+        }
+    }
+}
+
+/// A specific malformation to inject into one record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corruption {
+    /// Cut the stream in the middle of the record (only meaningful last).
+    Truncate,
+    /// Drop the last quality byte.
+    LengthMismatch,
+    /// Replace the `+` separator with a sequence-looking line.
+    BadPlusLine,
+    /// Replace `@` on the header with `#`.
+    MissingHeader,
+    /// Replace `@` on the header with FASTA's `>`.
+    FastaHeader,
+    /// Emit an empty sequence and quality.
+    EmptySequence,
+}
+
+/// Serializes records, applying corruptions at chosen record indices.
+#[derive(Debug, Clone, Default)]
+pub struct Corruptor {
+    plan: Vec<(usize, Corruption)>,
+}
+
+impl Corruptor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Corrupt the record at `index` (0-based) with `kind`.
+    pub fn at(mut self, index: usize, kind: Corruption) -> Self {
+        self.plan.push((index, kind));
+        self
+    }
+
+    /// Serialize single-line FASTQ with the planned corruptions applied.
+    /// A `Truncate` stops output after the affected record.
+    pub fn apply(&self, records: &[FastqRecord]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (i, rec) in records.iter().enumerate() {
+            let kind = self.plan.iter().find(|(idx, _)| *idx == i).map(|(_, k)| *k);
+            match kind {
+                None => write_record(&mut out, rec, None),
+                Some(Corruption::Truncate) => {
+                    let mut buf = Vec::new();
+                    write_record(&mut buf, rec, None);
+                    let header_len = buf.iter().position(|&b| b == b'\n').map_or(0, |p| p + 1);
+                    out.extend_from_slice(&buf[..header_len + rec.len() / 2]);
+                    return out;
+                }
+                Some(Corruption::LengthMismatch) => {
+                    let mut bad = rec.clone().into_parts();
+                    bad.qual.pop();
+                    write_record(&mut out, &bad.into(), None);
+                }
+                Some(Corruption::BadPlusLine) => {
+                    let mut buf = Vec::new();
+                    write_record(&mut buf, rec, None);
+                    let plus = buf
+                        .windows(3)
+                        .position(|w| w == b"\n+\n")
+                        .expect("serialized record");
+                    buf[plus + 1] = b'N';
+                    out.extend_from_slice(&buf);
+                }
+                Some(Corruption::MissingHeader) | Some(Corruption::FastaHeader) => {
+                    let start = out.len();
+                    write_record(&mut out, rec, None);
+                    out[start] = if kind == Some(Corruption::FastaHeader) {
+                        b'>'
+                    } else {
+                        b'#'
+                    };
+                }
+                Some(Corruption::EmptySequence) => {
+                    let bad = FastqRecord::new(rec.id(), rec.desc(), b"", b"");
+                    write_record(&mut out, &bad, None);
+                }
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,115 @@
+//! Rate limiting for readers on shared storage (`ReaderOptions::throttle`).
+//!
+//! Byte limits wrap the raw input (before decompression), so they cap what
+//! is actually pulled from disk or network; record limits pace the
+//! iterator.
+
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+/// Tracks work done against a rate and says how long to wait to stay
+/// under it. A rate of 0 disables limiting.
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimiter {
+    per_sec: u64,
+    start: Option<Instant>,
+    done: u64,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(per_sec: u64) -> Self {
+        Self {
+            per_sec,
+            start: None,
+            done: 0,
+        }
+    }
+
+    /// Account for `n` units; returns the pause needed before continuing.
+    pub(crate) fn record(&mut self, n: u64) -> Option<Duration> {
+        if self.per_sec == 0 {
+            return None;
+        }
+        let start = *self.start.get_or_insert_with(Instant::now);
+        self.done += n;
+        let due = Duration::from_secs_f64(self.done as f64 / self.per_sec as f64);
+        due.checked_sub(start.elapsed()).filter(|d| !d.is_zero())
+    }
+}
+
+/// `Read` adapter sleeping the thread to hold a bytes/s rate.
+pub(crate) struct ThrottledRead<R> {
+    inner: R,
+    limiter: RateLimiter,
+}
+
+impl<R> ThrottledRead<R> {
+    pub(crate) fn new(inner: R, bytes_per_sec: u64) -> Self {
+        Self {
+            inner,
+            limiter: RateLimiter::new(bytes_per_sec),
+        }
+    }
+}
+
+impl<R: Read> Read for ThrottledRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Some(pause) = self.limiter.record(n as u64) {
+            std::thread::sleep(pause);
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "async")]
+pub(crate) use self::async_impl::ThrottledAsyncRead;
+
+#[cfg(feature = "async")]
+mod async_impl {
+    use super::RateLimiter;
+    use std::future::Future;
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll, ready};
+    use tokio::io::{AsyncRead, ReadBuf};
+    use tokio::time::Sleep;
+
+    /// `AsyncRead` adapter holding a bytes/s rate with a timer instead of
+    /// blocking the executor.
+    pub(crate) struct ThrottledAsyncRead<R> {
+        inner: R,
+        limiter: RateLimiter,
+        pause: Option<Pin<Box<Sleep>>>,
+    }
+
+    impl<R> ThrottledAsyncRead<R> {
+        pub(crate) fn new(inner: R, bytes_per_sec: u64) -> Self {
+            Self {
+                inner,
+                limiter: RateLimiter::new(bytes_per_sec),
+                pause: None,
+            }
+        }
+    }
+
+    impl<R: AsyncRead + Unpin> AsyncRead for ThrottledAsyncRead<R> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            if let Some(pause) = self.pause.as_mut() {
+                ready!(pause.as_mut().poll(cx));
+                self.pause = None;
+            }
+            let before = buf.filled().len();
+            ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+            let n = buf.filled().len() - before;
+            if let Some(d) = self.limiter.record(n as u64) {
+                self.pause = Some(Box::pin(tokio::time::sleep(d)));
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+}
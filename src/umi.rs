@@ -0,0 +1,227 @@
+//! UMI and cell-barcode extraction, in the manner of `umi_tools extract`.
+//!
+//! A [`UmiPattern`] such as `CCCCNNNNNNXX` marks, base by base, the cell
+//! barcode (`C`), UMI (`N`) and bases to keep (`X`) at one end of the read.
+//! [`UmiExtractor`] moves the `C` and `N` bases (and their qualities) out
+//! of the record and appends them to its ID (`read7_ACGT_GGATCC`), or reads
+//! a UMI another tool already put in a header field. Use it per record with
+//! [`UmiExtractor::extract`] or as a streaming stage over a reader with
+//! [`UmiExtractor::stage`].
+
+use crate::error::FastqError;
+use crate::record::FastqRecord;
+use thiserror::Error;
+
+/// A pattern string with a byte other than `C`, `N` or `X`, or without a
+/// `C` or `N`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid UMI pattern {pattern:?}: expected C, N and X with at least one C or N")]
+pub struct UmiPatternError {
+    pub pattern: String,
+}
+
+/// Per-base layout of the read end that carries barcode and UMI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UmiPattern {
+    pattern: Vec<u8>,
+}
+
+impl UmiPattern {
+    /// Parse a pattern of `C` (cell barcode), `N` (UMI) and `X` (kept)
+    /// bases, e.g. `NNNNNNNN` for an 8-base UMI prefix.
+    pub fn new(pattern: &str) -> Result<Self, UmiPatternError> {
+        let bytes = pattern.as_bytes();
+        let valid = bytes.iter().all(|b| b"CNX".contains(b))
+            && bytes.iter().any(|&b| b == b'C' || b == b'N');
+        if !valid {
+            return Err(UmiPatternError {
+                pattern: pattern.to_owned(),
+            });
+        }
+        Ok(Self {
+            pattern: bytes.to_vec(),
+        })
+    }
+
+    /// Bases the pattern covers; shorter reads cannot be extracted.
+    pub fn len(&self) -> usize {
+        self.pattern.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pattern.is_empty()
+    }
+}
+
+/// Part of the header holding a UMI put there by another tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderPart {
+    Id,
+    Desc,
+}
+
+/// Where the UMI and barcode bases come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UmiSource {
+    /// The first bases of the read, laid out by the pattern.
+    ReadStart(UmiPattern),
+    /// The last bases of the read (`umi_tools --3prime`), the pattern read
+    /// left to right.
+    ReadEnd(UmiPattern),
+    /// Field `index` (0-based) of the ID or description split on `sep`,
+    /// e.g. `HeaderField { part: HeaderPart::Id, sep: ':', index: 7 }` for
+    /// bcl2fastq's `M:R:F:L:T:X:Y:UMI`. The record is left unchanged.
+    HeaderField {
+        part: HeaderPart,
+        sep: char,
+        index: usize,
+    },
+}
+
+/// Bases and qualities taken out of one record. Qualities are empty for
+/// [`UmiSource::HeaderField`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Umi {
+    pub umi: Vec<u8>,
+    pub umi_qual: Vec<u8>,
+    pub barcode: Vec<u8>,
+    pub barcode_qual: Vec<u8>,
+}
+
+/// Moves UMI and barcode bases out of records; see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UmiExtractor {
+    source: UmiSource,
+    id_sep: Option<char>,
+}
+
+impl UmiExtractor {
+    /// Extract from `source`, appending `_BARCODE_UMI` (parts that are
+    /// present) to read-sourced IDs.
+    pub fn new(source: UmiSource) -> Self {
+        Self {
+            source,
+            id_sep: Some('_'),
+        }
+    }
+
+    /// Separator before the barcode and UMI appended to the ID (default
+    /// `_`); `None` leaves the ID as is.
+    pub fn id_separator(mut self, sep: Option<char>) -> Self {
+        self.id_sep = sep;
+        self
+    }
+
+    /// Take the UMI and barcode out of `rec`, trimming its sequence and
+    /// quality. `None` when the read is shorter than the pattern, its
+    /// quality is neither empty nor as long as the sequence, or the header
+    /// lacks the field; `rec` is then unchanged. A record without quality
+    /// (`keep_quality: false`) gives a UMI and barcode without quality.
+    pub fn extract(&self, rec: &mut FastqRecord) -> Option<Umi> {
+        let (pattern, at_end) = match &self.source {
+            UmiSource::ReadStart(p) => (p, false),
+            UmiSource::ReadEnd(p) => (p, true),
+            UmiSource::HeaderField { part, sep, index } => {
+                let text = match part {
+                    HeaderPart::Id => rec.id(),
+                    HeaderPart::Desc => rec.desc()?,
+                };
+                let field = text.split(*sep).nth(*index).filter(|f| !f.is_empty())?;
+                return Some(Umi {
+                    umi: field.as_bytes().to_vec(),
+                    ..Umi::default()
+                });
+            }
+        };
+        let n = pattern.len();
+        let (seq, qual) = (rec.seq(), rec.qual());
+        if seq.len() < n || !(qual.is_empty() || qual.len() == seq.len()) {
+            return None;
+        }
+        let start = if at_end { seq.len() - n } else { 0 };
+        // an empty quality stays empty in every part
+        let qual_of = |range: std::ops::Range<usize>| qual.get(range).unwrap_or_default();
+        let mut umi = Umi::default();
+        let mut kept_seq = Vec::with_capacity(seq.len());
+        let mut kept_qual = Vec::with_capacity(qual.len());
+        if at_end {
+            kept_seq.extend_from_slice(&seq[..start]);
+            kept_qual.extend_from_slice(qual_of(0..start));
+        }
+        for (i, &kind) in pattern.pattern.iter().enumerate() {
+            let (base, q) = (seq[start + i], qual_of(start + i..start + i + 1));
+            let (bases, quals) = match kind {
+                b'N' => (&mut umi.umi, &mut umi.umi_qual),
+                b'C' => (&mut umi.barcode, &mut umi.barcode_qual),
+                _ => (&mut kept_seq, &mut kept_qual),
+            };
+            bases.push(base);
+            quals.extend_from_slice(q);
+        }
+        if !at_end {
+            kept_seq.extend_from_slice(&seq[n..]);
+            kept_qual.extend_from_slice(qual_of(n..seq.len()));
+        }
+        let mut id = rec.id().to_owned();
+        if let Some(sep) = self.id_sep {
+            for part in [&umi.barcode, &umi.umi] {
+                if !part.is_empty() {
+                    id.push(sep);
+                    id.push_str(&String::from_utf8_lossy(part));
+                }
+            }
+        }
+        *rec = FastqRecord::new(&id, rec.desc(), &kept_seq, &kept_qual)
+            .with_plus_line(rec.plus_line());
+        Some(umi)
+    }
+
+    /// Streaming stage: extract from every record of `records`, dropping
+    /// those [`extract`](Self::extract) cannot handle (counted by
+    /// [`UmiStage::dropped`]). Errors pass through.
+    pub fn stage<I>(self, records: I) -> UmiStage<I>
+    where
+        I: Iterator<Item = Result<FastqRecord, FastqError>>,
+    {
+        UmiStage {
+            inner: records,
+            extractor: self,
+            dropped: 0,
+        }
+    }
+}
+
+/// Iterator of [`UmiExtractor::stage`]: each record with what was taken
+/// out of it.
+pub struct UmiStage<I> {
+    inner: I,
+    extractor: UmiExtractor,
+    dropped: u64,
+}
+
+impl<I> UmiStage<I> {
+    /// Records dropped so far for being too short or lacking the field.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+impl<I> Iterator for UmiStage<I>
+where
+    I: Iterator<Item = Result<FastqRecord, FastqError>>,
+{
+    type Item = Result<(FastqRecord, Umi), FastqError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut rec = match self.inner.next()? {
+                Ok(rec) => rec,
+                Err(err) => return Some(Err(err)),
+            };
+            match self.extractor.extract(&mut rec) {
+                Some(umi) => return Some(Ok((rec, umi))),
+                None => self.dropped += 1,
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! io_uring file reads for `ReaderOptions::io_uring` (Linux, `uring`
+//! feature).
+//!
+//! [`UringRead`] keeps [`DEPTH`] reads of [`CHUNK`] bytes in flight on
+//! consecutive file offsets, into buffers registered with the kernel
+//! (plain reads when registration is refused, e.g. under a low
+//! `RLIMIT_MEMLOCK`). Chunks are handed out in file order; a drained
+//! buffer is immediately re-posted for the next chunk past the ones in
+//! flight, so the parser rarely waits on the device.
+
+use io_uring::{IoUring, opcode, types};
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::fd::AsRawFd;
+
+/// Reads in flight.
+const DEPTH: usize = 8;
+/// Bytes per read.
+pub(crate) const CHUNK: usize = 256 * 1024;
+
+/// One buffer of the ring and the chunk it holds.
+struct Slot {
+    buf: Box<[u8]>,
+    offset: u64,
+    /// Bytes of the chunk; 0 past the end of the file.
+    want: usize,
+    got: usize,
+    in_flight: bool,
+    err: Option<io::Error>,
+}
+
+/// A regular file read through io_uring.
+pub(crate) struct UringRead {
+    ring: IoUring,
+    file: File,
+    slots: Vec<Slot>,
+    fixed: bool,
+    /// File size when opened; reads stop there.
+    len: u64,
+    /// Offset of the next chunk to post.
+    next_offset: u64,
+    /// Slot being consumed, and how far.
+    cur: usize,
+    pos: usize,
+}
+
+impl UringRead {
+    /// Set up a ring over `file` and post the first reads. Fails where the
+    /// kernel (or a seccomp filter) refuses io_uring.
+    pub(crate) fn new(file: File) -> io::Result<Self> {
+        let len = file.metadata()?.len();
+        let ring = IoUring::new(DEPTH as u32)?;
+        let mut slots: Vec<Slot> = (0..DEPTH)
+            .map(|_| Slot {
+                buf: vec![0; CHUNK].into_boxed_slice(),
+                offset: 0,
+                want: 0,
+                got: 0,
+                in_flight: false,
+                err: None,
+            })
+            .collect();
+        let iovecs: Vec<libc::iovec> = slots
+            .iter_mut()
+            .map(|slot| libc::iovec {
+                iov_base: slot.buf.as_mut_ptr().cast(),
+                iov_len: CHUNK,
+            })
+            .collect();
+        // SAFETY: the buffers are boxed, never resized, and dropped only
+        // after the ring (field order) with no read in flight (`Drop`).
+        let fixed = unsafe { ring.submitter().register_buffers(&iovecs) }.is_ok();
+        let mut this = Self {
+            ring,
+            file,
+            slots,
+            fixed,
+            len,
+            next_offset: 0,
+            cur: 0,
+            pos: 0,
+        };
+        for i in 0..DEPTH {
+            this.post(i)?;
+        }
+        Ok(this)
+    }
+
+    /// Give slot `i` the next chunk of the file and post its read.
+    fn post(&mut self, i: usize) -> io::Result<()> {
+        let want = (self.len - self.next_offset).min(CHUNK as u64) as usize;
+        let slot = &mut self.slots[i];
+        slot.offset = self.next_offset;
+        slot.want = want;
+        slot.got = 0;
+        self.next_offset += want as u64;
+        if want == 0 {
+            return Ok(());
+        }
+        self.push_read(i)
+    }
+
+    /// Post a read of the missing part of slot `i`'s chunk.
+    fn push_read(&mut self, i: usize) -> io::Result<()> {
+        let fd = types::Fd(self.file.as_raw_fd());
+        let slot = &mut self.slots[i];
+        let buf = slot.buf[slot.got..].as_mut_ptr();
+        let len = (slot.want - slot.got) as u32;
+        let offset = slot.offset + slot.got as u64;
+        let entry = if self.fixed {
+            opcode::ReadFixed::new(fd, buf, len, i as u16)
+                .offset(offset)
+                .build()
+        } else {
+            opcode::Read::new(fd, buf, len).offset(offset).build()
+        };
+        // SAFETY: the buffer is not touched again until the completion
+        // tagged `i` is reaped; at most `DEPTH` entries are ever queued.
+        unsafe { self.ring.submission().push(&entry.user_data(i as u64)) }
+            .map_err(|_| io::Error::other("io_uring submission queue is full"))?;
+        slot.in_flight = true;
+        self.ring.submit()?;
+        Ok(())
+    }
+
+    /// Block until slot `i`'s chunk is complete (or failed), reaping and
+    /// re-posting other slots' short reads on the way.
+    fn wait(&mut self, i: usize) -> io::Result<()> {
+        while self.slots[i].in_flight {
+            self.ring.submit_and_wait(1)?;
+            let done: Vec<(usize, i32)> = self
+                .ring
+                .completion()
+                .map(|cqe| (cqe.user_data() as usize, cqe.result()))
+                .collect();
+            for (j, res) in done {
+                let slot = &mut self.slots[j];
+                slot.in_flight = false;
+                match res {
+                    0 => slot.want = slot.got, // the file shrank
+                    n if n > 0 => slot.got += n as usize,
+                    n if -n == libc::EINTR || -n == libc::EAGAIN => {}
+                    n => {
+                        slot.err = Some(io::Error::from_raw_os_error(-n));
+                        continue;
+                    }
+                }
+                if slot.got < slot.want {
+                    self.push_read(j)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Read for UringRead {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let i = self.cur;
+            self.wait(i)?;
+            let slot = &mut self.slots[i];
+            if let Some(err) = slot.err.take() {
+                return Err(err);
+            }
+            if slot.want == 0 {
+                return Ok(0);
+            }
+            if self.pos < slot.got {
+                let n = out.len().min(slot.got - self.pos);
+                out[..n].copy_from_slice(&slot.buf[self.pos..self.pos + n]);
+                self.pos += n;
+                return Ok(n);
+            }
+            // drained: reuse the buffer for the chunk after those in flight
+            self.pos = 0;
+            self.post(i)?;
+            self.cur = (i + 1) % DEPTH;
+        }
+    }
+}
+
+impl Drop for UringRead {
+    fn drop(&mut self) {
+        // the kernel must be done with every buffer before it is freed
+        while self.slots.iter().any(|s| s.in_flight) {
+            if self.ring.submit_and_wait(1).is_err() {
+                // cannot tell when the reads finish; leak the buffers
+                for slot in &mut self.slots {
+                    std::mem::forget(std::mem::take(&mut slot.buf));
+                }
+                return;
+            }
+            for cqe in self.ring.completion() {
+                if let Some(slot) = self.slots.get_mut(cqe.user_data() as usize) {
+                    slot.in_flight = false;
+                }
+            }
+        }
+    }
+}
@@ -1,14 +1,81 @@
+use crate::policy::AccessAdvice;
+
 use std::fs::File;
 use std::io::{self, Read, Seek, SeekFrom};
 
-pub fn looks_like_gzip<R: Read + Seek>(mut r: R) -> io::Result<bool> {
+pub fn looks_like_gzip<R: Read + Seek>(r: R) -> io::Result<bool> {
     let mut magic = [0u8; 2];
-    let pos = r.seek(SeekFrom::Current(0))?;
-    let n = r.read(&mut magic)?;
-    r.seek(SeekFrom::Start(pos))?;
+    let n = read_head(r, &mut magic)?;
     Ok(n >= 2 && magic == [0x1F, 0x8B])
 }
 
+/// Read up to `buf.len()` bytes and seek back to where `r` was.
+pub fn read_head<R: Read + Seek>(mut r: R, buf: &mut [u8]) -> io::Result<usize> {
+    let pos = r.stream_position()?;
+    let n = r.read(buf)?;
+    r.seek(SeekFrom::Start(pos))?;
+    Ok(n)
+}
+
+/// Whether `head` starts with the zstd frame magic.
+pub fn looks_like_zstd(head: &[u8]) -> bool {
+    head.starts_with(&[0x28, 0xB5, 0x2F, 0xFD])
+}
+
+/// Fails the first read with the given error, then reads as empty; stands
+/// in for input that cannot be decoded.
+pub struct FailingRead(pub Option<io::Error>);
+
+impl Read for FailingRead {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        self.0.take().map_or(Ok(0), Err)
+    }
+}
+
+/// Announce `advice` for the whole of `f` with `posix_fadvise`. A no-op
+/// without the `fadvise` feature and where the call does not exist.
+#[cfg(all(
+    feature = "fadvise",
+    any(target_os = "linux", target_os = "android", target_os = "freebsd")
+))]
+pub fn advise_file(f: &File, advice: AccessAdvice) {
+    use std::os::fd::AsRawFd;
+
+    let hints: &[libc::c_int] = match advice {
+        AccessAdvice::Normal => &[],
+        AccessAdvice::Sequential => &[libc::POSIX_FADV_SEQUENTIAL],
+        AccessAdvice::WillNeed => &[libc::POSIX_FADV_SEQUENTIAL, libc::POSIX_FADV_WILLNEED],
+    };
+    for &hint in hints {
+        // SAFETY: the descriptor stays open for the borrow of `f`; the
+        // call takes no pointers. Failure only loses the hint.
+        unsafe { libc::posix_fadvise(f.as_raw_fd(), 0, 0, hint) };
+    }
+}
+
+#[cfg(not(all(
+    feature = "fadvise",
+    any(target_os = "linux", target_os = "android", target_os = "freebsd")
+)))]
+pub fn advise_file(_f: &File, _advice: AccessAdvice) {}
+
+/// Announce `advice` for a mapped file with `madvise`.
+#[cfg(all(feature = "mmap", unix))]
+pub fn advise_map(map: &memmap2::Mmap, advice: AccessAdvice) {
+    use memmap2::Advice;
+
+    // failures only lose the hint
+    if advice != AccessAdvice::Normal {
+        let _ = map.advise(Advice::Sequential);
+    }
+    if advice == AccessAdvice::WillNeed {
+        let _ = map.advise(Advice::WillNeed);
+    }
+}
+
+#[cfg(all(feature = "mmap", not(unix), not(target_arch = "wasm32")))]
+pub fn advise_map(_map: &memmap2::Mmap, _advice: AccessAdvice) {}
+
 pub fn open_file(path: &std::path::Path) -> io::Result<File> {
     std::fs::File::open(path)
 }
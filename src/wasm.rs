@@ -0,0 +1,59 @@
+//! JS input sources for `wasm32-unknown-unknown` (feature `wasm`).
+//!
+//! [`FastqReader::from_uint8_array`] reads a `Uint8Array` (e.g. an
+//! uploaded `File`'s `arrayBuffer()`), [`FastqReader::from_readable_stream`]
+//! a `ReadableStream` of `Uint8Array` chunks (e.g. `file.stream()` or a
+//! `fetch` body). Both sniff gzip like [`FastqReader::from_vec`]; the
+//! reader itself is synchronous, so a stream is drained into wasm memory
+//! before parsing starts.
+
+use crate::error::FastqError;
+use crate::policy::ReaderOptions;
+use crate::reader::FastqReader;
+
+use js_sys::{Reflect, Uint8Array};
+use std::io;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{ReadableStream, ReadableStreamDefaultReader};
+
+impl FastqReader {
+    /// Read a JS `Uint8Array` (plain or gzip, sniffed). The bytes are
+    /// copied into wasm memory.
+    pub fn from_uint8_array(data: &Uint8Array, opts: ReaderOptions) -> Self {
+        Self::from_vec(data.to_vec(), opts)
+    }
+
+    /// Read a JS `ReadableStream` whose chunks are `Uint8Array`s (plain or
+    /// gzip, sniffed). The stream is read to its end before the reader is
+    /// returned; a rejected read or a chunk of another type is an I/O
+    /// error.
+    pub async fn from_readable_stream(
+        stream: &ReadableStream,
+        opts: ReaderOptions,
+    ) -> Result<Self, FastqError> {
+        let reader = ReadableStreamDefaultReader::new(stream).map_err(js_err)?;
+        let mut data = Vec::new();
+        loop {
+            let chunk = JsFuture::from(reader.read()).await.map_err(js_err)?;
+            let done = Reflect::get(&chunk, &JsValue::from_str("done")).map_err(js_err)?;
+            if done.is_truthy() {
+                break;
+            }
+            let value = Reflect::get(&chunk, &JsValue::from_str("value")).map_err(js_err)?;
+            let bytes = value.dyn_into::<Uint8Array>().map_err(|v| {
+                let msg = format!("ReadableStream chunk is not a Uint8Array: {v:?}");
+                FastqError::from(io::Error::new(io::ErrorKind::InvalidData, msg))
+            })?;
+            data.extend_from_slice(&bytes.to_vec());
+        }
+        reader.release_lock();
+        Ok(Self::from_vec(data, opts))
+    }
+}
+
+/// A thrown JS value as an I/O error without a position.
+fn js_err(v: JsValue) -> FastqError {
+    let msg = v.as_string().unwrap_or_else(|| format!("{v:?}"));
+    FastqError::from(io::Error::other(msg))
+}
@@ -0,0 +1,365 @@
+//! Writing records back out as FASTQ.
+//!
+//! [`FastqWriter`] is the counterpart of [`FastqReader`](crate::FastqReader):
+//! plain, gzip, BGZF or zstd (feature `zstd`) output (inferred from the
+//! file extension or set explicitly), with a configurable compression level
+//! and line ending.
+//! [`JsonlWriter`] and [`TsvWriter`] write one line per record with a
+//! chosen set of [`RecordField`]s, for `jq`/`awk` QC pipelines.
+
+use crate::convert::{Compression, Sink};
+use crate::record::FastqRecord;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Line terminator written after every line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    pub(crate) fn as_bytes(self) -> &'static [u8] {
+        match self {
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WriterOptions {
+    /// `None` infers it from the extension in [`FastqWriter::from_path`]
+    /// and means plain output for [`FastqWriter::from_writer`].
+    pub compression: Option<Compression>,
+    /// Compression level, 0–9 (zstd: 0 is its default level, 3).
+    pub level: u32,
+    /// Compression worker threads; 1 compresses on the calling thread.
+    /// Ignored for zstd.
+    pub threads: usize,
+    pub line_ending: LineEnding,
+}
+
+impl Default for WriterOptions {
+    fn default() -> Self {
+        Self {
+            compression: None,
+            level: 6,
+            threads: 1,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+/// Streaming FASTQ writer. Call [`finish`](Self::finish) when done:
+/// compressed output is only complete once its trailer is written.
+pub struct FastqWriter<W: Write = File> {
+    sink: Sink<W>,
+    eol: &'static [u8],
+    records: u64,
+}
+
+impl FastqWriter<File> {
+    /// Create (or truncate) `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P, opts: WriterOptions) -> io::Result<Self> {
+        let path = path.as_ref();
+        let compression = opts
+            .compression
+            .unwrap_or_else(|| Compression::from_path(path));
+        let opts = WriterOptions {
+            compression: Some(compression),
+            ..opts
+        };
+        Sink::<File>::check(compression)?;
+        Self::from_writer(File::create(path)?, opts)
+    }
+}
+
+impl<W: Write> FastqWriter<W> {
+    /// Write to any destination (stdout, a socket, a `Vec<u8>`, ...).
+    /// `Compression::Zstd` without the `zstd` feature is an
+    /// `ErrorKind::Unsupported` error.
+    pub fn from_writer(out: W, opts: WriterOptions) -> io::Result<Self> {
+        let compression = opts.compression.unwrap_or(Compression::None);
+        Ok(Self {
+            sink: Sink::new(out, compression, opts.level.min(9), opts.threads.max(1))?,
+            eol: opts.line_ending.as_bytes(),
+            records: 0,
+        })
+    }
+
+    /// Write one record, including its kept `+` text. A record whose
+    /// quality is not as long as its sequence is an `InvalidInput` error.
+    pub fn write_record(&mut self, rec: &FastqRecord) -> io::Result<()> {
+        write_fastq(&mut self.sink, rec, self.eol)?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Records written so far.
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Flush buffered data, finish compression and return the destination.
+    pub fn finish(self) -> io::Result<W> {
+        self.sink.finish()
+    }
+}
+
+/// Writes nothing and fails with `ErrorKind::InvalidInput` when the quality
+/// does not match the sequence in length (e.g. a record read with
+/// `keep_quality: false`, or from FASTA), as that would not be FASTQ.
+pub(crate) fn write_fastq<W: Write>(w: &mut W, rec: &FastqRecord, eol: &[u8]) -> io::Result<()> {
+    if rec.qual().len() != rec.seq().len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "record {}: {} quality bytes for {} bases",
+                rec.id(),
+                rec.qual().len(),
+                rec.seq().len()
+            ),
+        ));
+    }
+    write_header(w, b'@', rec, eol)?;
+    w.write_all(rec.seq())?;
+    w.write_all(eol)?;
+    w.write_all(b"+")?;
+    if let Some(plus) = rec.plus_line() {
+        w.write_all(plus.as_bytes())?;
+    }
+    w.write_all(eol)?;
+    w.write_all(rec.qual())?;
+    w.write_all(eol)
+}
+
+pub(crate) fn write_fasta<W: Write>(w: &mut W, rec: &FastqRecord, eol: &[u8]) -> io::Result<()> {
+    write_header(w, b'>', rec, eol)?;
+    w.write_all(rec.seq())?;
+    w.write_all(eol)
+}
+
+fn write_header<W: Write>(w: &mut W, marker: u8, rec: &FastqRecord, eol: &[u8]) -> io::Result<()> {
+    w.write_all(&[marker])?;
+    w.write_all(rec.id().as_bytes())?;
+    if let Some(desc) = rec.desc() {
+        w.write_all(b" ")?;
+        w.write_all(desc.as_bytes())?;
+    }
+    w.write_all(eol)
+}
+
+/// A column of [`JsonlWriter`] and [`TsvWriter`] output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordField {
+    Id,
+    /// Header text after the ID; `null` in JSON, empty in TSV when absent.
+    Desc,
+    /// Sequence length.
+    Length,
+    /// [`FastqRecord::mean_quality`]; `null`/empty for empty records.
+    MeanQuality,
+    Seq,
+    Qual,
+}
+
+impl RecordField {
+    /// Every field, in the order above.
+    pub const ALL: [RecordField; 6] = [
+        RecordField::Id,
+        RecordField::Desc,
+        RecordField::Length,
+        RecordField::MeanQuality,
+        RecordField::Seq,
+        RecordField::Qual,
+    ];
+
+    /// JSON key and TSV header name.
+    pub fn name(self) -> &'static str {
+        match self {
+            RecordField::Id => "id",
+            RecordField::Desc => "desc",
+            RecordField::Length => "length",
+            RecordField::MeanQuality => "mean_quality",
+            RecordField::Seq => "seq",
+            RecordField::Qual => "qual",
+        }
+    }
+}
+
+/// One JSON object per line, keyed by [`RecordField::name`]. Bytes that
+/// are not UTF-8 are written as U+FFFD.
+pub struct JsonlWriter<W: Write = File> {
+    out: BufWriter<W>,
+    fields: Vec<RecordField>,
+    records: u64,
+}
+
+impl JsonlWriter<File> {
+    /// Create (or truncate) `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P, fields: &[RecordField]) -> io::Result<Self> {
+        Ok(Self::from_writer(File::create(path)?, fields))
+    }
+}
+
+impl<W: Write> JsonlWriter<W> {
+    pub fn from_writer(out: W, fields: &[RecordField]) -> Self {
+        Self {
+            out: BufWriter::new(out),
+            fields: fields.to_vec(),
+            records: 0,
+        }
+    }
+
+    pub fn write_record(&mut self, rec: &FastqRecord) -> io::Result<()> {
+        self.out.write_all(b"{")?;
+        for (i, &field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                self.out.write_all(b",")?;
+            }
+            write!(self.out, "\"{}\":", field.name())?;
+            match field {
+                RecordField::Id => write_json_str(&mut self.out, rec.id().as_bytes())?,
+                RecordField::Desc => match rec.desc() {
+                    Some(desc) => write_json_str(&mut self.out, desc.as_bytes())?,
+                    None => self.out.write_all(b"null")?,
+                },
+                RecordField::Length => write!(self.out, "{}", rec.len())?,
+                RecordField::MeanQuality => match rec.mean_quality() {
+                    Some(q) => write!(self.out, "{q}")?,
+                    None => self.out.write_all(b"null")?,
+                },
+                RecordField::Seq => write_json_str(&mut self.out, rec.seq())?,
+                RecordField::Qual => write_json_str(&mut self.out, rec.qual())?,
+            }
+        }
+        self.out.write_all(b"}\n")?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Records written so far.
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Flush and return the destination.
+    pub fn finish(self) -> io::Result<W> {
+        self.out
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)
+    }
+}
+
+/// Tab-separated lines, with a header row of [`RecordField::name`]s
+/// unless [`without_header`](Self::without_header). Tabs and line breaks
+/// inside a description are written as spaces.
+pub struct TsvWriter<W: Write = File> {
+    out: BufWriter<W>,
+    fields: Vec<RecordField>,
+    header: bool,
+    records: u64,
+}
+
+impl TsvWriter<File> {
+    /// Create (or truncate) `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P, fields: &[RecordField]) -> io::Result<Self> {
+        Ok(Self::from_writer(File::create(path)?, fields))
+    }
+}
+
+impl<W: Write> TsvWriter<W> {
+    pub fn from_writer(out: W, fields: &[RecordField]) -> Self {
+        Self {
+            out: BufWriter::new(out),
+            fields: fields.to_vec(),
+            header: true,
+            records: 0,
+        }
+    }
+
+    /// Skip the header row (e.g. when appending to an existing table).
+    pub fn without_header(mut self) -> Self {
+        self.header = false;
+        self
+    }
+
+    pub fn write_record(&mut self, rec: &FastqRecord) -> io::Result<()> {
+        if self.header {
+            self.write_names()?;
+        }
+        for (i, &field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                self.out.write_all(b"\t")?;
+            }
+            match field {
+                RecordField::Id => self.out.write_all(rec.id().as_bytes())?,
+                RecordField::Desc => {
+                    let desc = rec.desc().unwrap_or_default().as_bytes();
+                    for &b in desc {
+                        let b = if matches!(b, b'\t' | b'\n' | b'\r') {
+                            b' '
+                        } else {
+                            b
+                        };
+                        self.out.write_all(&[b])?;
+                    }
+                }
+                RecordField::Length => write!(self.out, "{}", rec.len())?,
+                RecordField::MeanQuality => {
+                    if let Some(q) = rec.mean_quality() {
+                        write!(self.out, "{q}")?;
+                    }
+                }
+                RecordField::Seq => self.out.write_all(rec.seq())?,
+                RecordField::Qual => self.out.write_all(rec.qual())?,
+            }
+        }
+        self.out.write_all(b"\n")?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Records written so far.
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// Flush (writing the header if no record was) and return the
+    /// destination.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.header {
+            self.write_names()?;
+        }
+        self.out
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)
+    }
+
+    fn write_names(&mut self) -> io::Result<()> {
+        self.header = false;
+        let names: Vec<_> = self.fields.iter().map(|f| f.name()).collect();
+        writeln!(self.out, "{}", names.join("\t"))
+    }
+}
+
+fn write_json_str<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
+    w.write_all(b"\"")?;
+    for c in String::from_utf8_lossy(bytes).chars() {
+        match c {
+            '"' => w.write_all(b"\\\"")?,
+            '\\' => w.write_all(b"\\\\")?,
+            '\n' => w.write_all(b"\\n")?,
+            '\r' => w.write_all(b"\\r")?,
+            '\t' => w.write_all(b"\\t")?,
+            c if c < ' ' => write!(w, "\\u{:04x}", c as u32)?,
+            c => write!(w, "{c}")?,
+        }
+    }
+    w.write_all(b"\"")
+}
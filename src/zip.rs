@@ -0,0 +1,216 @@
+//! FASTQ records from `.zip` archives (feature `zip`).
+//!
+//! The central directory is parsed by the `zip` crate (ZIP64 and data
+//! descriptors included); entries are streamed straight from the file.
+//! Stored and deflated entries are supported, others and encrypted ones are
+//! `ErrorKind::Unsupported`; entries that are themselves `.gz` are
+//! decompressed as usual.
+
+use crate::error::{FastqError, IoContext};
+use crate::gzip::StrictGzDecoder;
+use crate::limits::ratio_guarded;
+use crate::policy::ReaderOptions;
+use crate::reader::FastqReader;
+use crate::record::FastqRecord;
+
+use ::zip::{CompressionMethod, ZipArchive};
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// One file inside the archive, as listed in the central directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipEntry {
+    pub name: String,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    method: CompressionMethod,
+    encrypted: bool,
+    data_start: u64,
+}
+
+/// Reads every entry whose name matches a glob pattern, in archive order,
+/// as one continuous record stream.
+pub struct ZipFastqReader {
+    path: PathBuf,
+    file: File,
+    opts: ReaderOptions,
+    entries: Vec<ZipEntry>,
+    next_entry: usize,
+    current: Option<FastqReader>,
+}
+
+impl ZipFastqReader {
+    /// Open `path` and select entries matching `pattern` (`*` and `?`
+    /// wildcards, matched against the full entry name, e.g. `"*_R1*.fastq*"`).
+    /// Directory entries are ignored.
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+        pattern: &str,
+        opts: ReaderOptions,
+    ) -> Result<Self, FastqError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(open_err)?;
+        let entries = read_central_directory(&file, pattern).map_err(open_err)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            opts,
+            entries,
+            next_entry: 0,
+            current: None,
+        })
+    }
+
+    /// Entries selected by the pattern, in the order they will be read.
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    /// Name of the entry currently being read.
+    pub fn current_entry(&self) -> Option<&str> {
+        self.current
+            .as_ref()
+            .map(|_| self.entries[self.next_entry - 1].name.as_str())
+    }
+
+    /// Path of the archive.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn open_entry(&self, entry: &ZipEntry) -> io::Result<FastqReader> {
+        if entry.encrypted {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("zip entry {:?} is encrypted", entry.name),
+            ));
+        }
+        if ![CompressionMethod::STORE, CompressionMethod::DEFLATE].contains(&entry.method) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "zip entry {:?}: compression method {}",
+                    entry.name, entry.method
+                ),
+            ));
+        }
+        let mut f = self.file.try_clone()?;
+        f.seek(SeekFrom::Start(entry.data_start))?;
+        let data = BufReader::with_capacity(
+            self.opts.decompress_buffer(256 * 1024),
+            f.take(entry.compressed_size),
+        );
+
+        let (deflated, gz, strict) = (
+            entry.method == CompressionMethod::DEFLATE,
+            entry.name.ends_with(".gz"),
+            self.opts.strict_gzip,
+        );
+        let dec = ratio_guarded(Box::new(data), self.opts.max_expansion_ratio, |raw| {
+            let raw: Box<dyn Read + Send> = if deflated {
+                Box::new(DeflateDecoder::new(raw))
+            } else {
+                raw
+            };
+            match (gz, strict) {
+                (false, _) => raw,
+                (true, true) => Box::new(StrictGzDecoder::new(BufReader::new(raw))),
+                (true, false) => Box::new(MultiGzDecoder::new(raw)),
+            }
+        });
+        Ok(FastqReader::from_bufread_labeled(
+            BufReader::with_capacity(self.opts.read_buffer(256 * 1024), dec),
+            format!("{}:{}", self.path.display(), entry.name),
+            self.opts.clone(),
+        ))
+    }
+}
+
+impl Iterator for ZipFastqReader {
+    type Item = Result<FastqRecord, FastqError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(rdr) = self.current.as_mut() {
+                match rdr.next() {
+                    Some(item) => return Some(item),
+                    None => self.current = None,
+                }
+            }
+            let entry = self.entries.get(self.next_entry)?.clone();
+            self.next_entry += 1;
+            match self.open_entry(&entry) {
+                Ok(rdr) => self.current = Some(rdr),
+                Err(e) => return Some(Err(open_err(e))),
+            }
+        }
+    }
+}
+
+fn open_err(e: io::Error) -> FastqError {
+    FastqError::io_err(
+        e,
+        IoContext {
+            byte_pos: 0,
+            line_num: 0,
+            record_num: 0,
+            record_line: 0,
+            label: None,
+            virtual_offset: None,
+        },
+    )
+}
+
+/// Entries of the archive in `f` whose names match `pattern`, in
+/// central-directory order, without directories.
+fn read_central_directory(f: &File, pattern: &str) -> io::Result<Vec<ZipEntry>> {
+    let mut archive = ZipArchive::new(f.try_clone()?)?;
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let name = archive.name_for_index(i).unwrap_or_default();
+        if name.ends_with('/') || !glob_match(pattern, name) {
+            continue;
+        }
+        // reads the local header, so only for selected entries
+        let entry = archive.by_index_raw(i)?;
+        entries.push(ZipEntry {
+            name: entry.name().to_owned(),
+            compressed_size: entry.compressed_size(),
+            uncompressed_size: entry.size(),
+            method: entry.compression(),
+            encrypted: entry.encrypted(),
+            data_start: entry.data_start(),
+        });
+    }
+    Ok(entries)
+}
+
+/// `*` matches any run of characters (including `/`), `?` exactly one.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut pi, mut ni) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        match p.get(pi) {
+            Some('*') => {
+                backtrack = Some((pi, ni));
+                pi += 1;
+            }
+            Some(&c) if c == '?' || c == n[ni] => {
+                pi += 1;
+                ni += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bn)) => {
+                    pi = bp + 1;
+                    ni = bn + 1;
+                    backtrack = Some((bp, bn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,78 @@
+#[cfg(feature = "arrow")]
+mod t {
+    use arrow_array::{Array, LargeBinaryArray, StringArray};
+    use kira_cdh_compat_fastq_reader::FastqRecord;
+    use kira_cdh_compat_fastq_reader::ParquetWriter;
+    use kira_cdh_compat_fastq_reader::arrow_export::{record_batch, schema};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use tempfile::tempdir;
+
+    fn records() -> Vec<FastqRecord> {
+        vec![
+            FastqRecord::new("r1", Some("lane=1"), b"ACGT", b"IIII"),
+            FastqRecord::new("r2", None, b"GG", b"!!"),
+            FastqRecord::new("r3", None, b"", b""),
+        ]
+    }
+
+    #[test]
+    fn record_batch_columns() {
+        let batch = record_batch(&records()).unwrap();
+        assert_eq!(batch.schema(), schema());
+        assert_eq!(batch.num_rows(), 3);
+        let desc = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(desc.value(0), "lane=1");
+        assert!(desc.is_null(1));
+        let seq = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<LargeBinaryArray>()
+            .unwrap();
+        assert_eq!(seq.value(0), b"ACGT");
+        assert_eq!(seq.value(2), b"");
+    }
+
+    #[test]
+    fn parquet_round_trips_across_row_groups() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("reads.parquet");
+        let mut w = ParquetWriter::from_path(&path).unwrap().with_batch_rows(2);
+        for rec in &records() {
+            w.write_record(rec).unwrap();
+        }
+        w.write_batch(&record_batch(&records()[..1]).unwrap())
+            .unwrap();
+        assert_eq!(w.records(), 4);
+        w.finish().unwrap();
+
+        let builder =
+            ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(builder.metadata().num_row_groups(), 3);
+        let mut back = Vec::new();
+        for batch in builder.build().unwrap() {
+            let batch = batch.unwrap();
+            let col = |i: usize| batch.column(i).clone();
+            let (id, desc, seq, qual) = (col(0), col(1), col(2), col(3));
+            let id = id.as_any().downcast_ref::<StringArray>().unwrap();
+            let desc = desc.as_any().downcast_ref::<StringArray>().unwrap();
+            let seq = seq.as_any().downcast_ref::<LargeBinaryArray>().unwrap();
+            let qual = qual.as_any().downcast_ref::<LargeBinaryArray>().unwrap();
+            for i in 0..batch.num_rows() {
+                let d = (!desc.is_null(i)).then(|| desc.value(i));
+                back.push(FastqRecord::new(
+                    id.value(i),
+                    d,
+                    seq.value(i),
+                    qual.value(i),
+                ));
+            }
+        }
+        let mut want = records();
+        want.push(records()[0].clone());
+        assert_eq!(back, want);
+    }
+}
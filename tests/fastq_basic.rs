@@ -1,4 +1,6 @@
-use kira_cdh_compat_fastq_reader::{ErrorPolicy, FastqReader, LineMode, ReaderOptions};
+use kira_cdh_compat_fastq_reader::{
+    ErrorPolicy, FastqReader, LineMode, QualityEncoding, ReaderOptions,
+};
 use std::io::BufReader;
 
 const SAMPLE: &str = "\
@@ -20,20 +22,21 @@ fn parse_two_records_single_line() {
             error_policy: ErrorPolicy::Return,
             fastq_only: true,
             line_mode: LineMode::Single,
+            ..Default::default()
         },
     );
 
     let r1 = fq.next().unwrap().unwrap();
-    assert_eq!(r1.id, "read1");
-    assert_eq!(r1.desc.as_deref(), Some("desc"));
-    assert_eq!(r1.seq, b"ACGTN");
-    assert_eq!(r1.qual, b"!!!!!");
+    assert_eq!(r1.id(), "read1");
+    assert_eq!(r1.desc(), Some("desc"));
+    assert_eq!(r1.seq(), b"ACGTN");
+    assert_eq!(r1.qual(), b"!!!!!");
 
     let r2 = fq.next().unwrap().unwrap();
-    assert_eq!(r2.id, "read2");
-    assert_eq!(r2.desc, None);
-    assert_eq!(r2.seq, b"ACGT");
-    assert_eq!(r2.qual, b"####");
+    assert_eq!(r2.id(), "read2");
+    assert_eq!(r2.desc(), None);
+    assert_eq!(r2.seq(), b"ACGT");
+    assert_eq!(r2.qual(), b"####");
 
     assert!(fq.next().is_none());
 }
@@ -54,6 +57,7 @@ T
             error_policy: ErrorPolicy::Return,
             fastq_only: true,
             line_mode: LineMode::Single,
+            ..Default::default()
         },
     );
     // second seq line will cause MissingPlus error
@@ -82,13 +86,1354 @@ A
             error_policy: ErrorPolicy::Skip,
             fastq_only: true,
             line_mode: LineMode::Single,
+            ..Default::default()
         },
     );
 
     // r1 malformed -> resync to @r2
     let r = fq.next().unwrap().unwrap();
-    assert_eq!(r.id, "r2");
-    assert_eq!(r.seq, b"A");
-    assert_eq!(r.qual, b"#");
+    assert_eq!(r.id(), "r2");
+    assert_eq!(r.seq(), b"A");
+    assert_eq!(r.qual(), b"#");
     assert!(fq.next().is_none());
 }
+
+#[test]
+fn skip_handler_receives_dropped_records() {
+    use std::sync::{Arc, Mutex};
+
+    let bad = "@r1\nACGT\n+\n###\n@r2\nA\n+\n#\n>f\nAC\n@r3\nGG\n+\nII\n";
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    let opts = ReaderOptions {
+        fastq_only: false,
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bytes(bad.as_bytes(), opts)
+        .with_skip_handler(move |err| sink.lock().unwrap().push(err.to_string()));
+    let ids: Vec<_> = fq.by_ref().map(|r| r.unwrap().id().to_string()).collect();
+    assert_eq!(ids, ["r2", "r3"]);
+    assert_eq!(fq.state().unwrap().skipped, 2);
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    assert!(seen[1].contains("FASTA"), "{}", seen[1]);
+}
+
+#[test]
+fn collect_policy_reports_dropped_records() {
+    use kira_cdh_compat_fastq_reader::{FastqError, FormatError};
+
+    let bad = "@r1 one\nACGT\n+\n###\n@r2\nA\n+\n#\nxx\n@r3\nGG\n+\nII\n>f desc\nAC\n";
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Collect { limit: 2 },
+        fastq_only: false,
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bytes(bad.as_bytes(), opts);
+    let ids: Vec<_> = fq.by_ref().map(|r| r.unwrap().id().to_string()).collect();
+    assert_eq!(ids, ["r2", "r3"]);
+    let report = fq.skip_report();
+    assert_eq!(report.total(), 3);
+    assert!(report.truncated());
+    let [first, second] = report.entries() else {
+        panic!("expected two entries");
+    };
+    assert_eq!(
+        (first.line_num, first.header.as_deref()),
+        (1, Some("r1 one"))
+    );
+    assert!(matches!(
+        first.error,
+        FastqError::Format {
+            source: FormatError::LengthMismatch { .. },
+            ..
+        }
+    ));
+    assert_eq!((second.line_num, second.header.as_deref()), (9, None));
+    assert_eq!(second.error.context().line_num, 9);
+}
+
+#[test]
+fn max_skipped_turns_skips_into_an_error() {
+    use kira_cdh_compat_fastq_reader::{FastqError, LimitError};
+
+    let data = "@a\nAC\n+\nI\n@b\nAC\n+\nII\n@c\nA\n+\nII\n@d\nGG\n+\nII\n";
+    let limited = |max| ReaderOptions {
+        max_skipped: Some(max),
+        ..Default::default()
+    };
+    let ids: Vec<_> = FastqReader::from_bytes(data.as_bytes(), limited(2))
+        .map(|r| r.unwrap().id().to_string())
+        .collect();
+    assert_eq!(ids, ["b", "d"]);
+
+    let mut fq = FastqReader::from_bytes(data.as_bytes(), limited(1));
+    assert_eq!(fq.next().unwrap().unwrap().id(), "b");
+    let err = fq.next().unwrap().unwrap_err();
+    assert!(matches!(
+        err,
+        FastqError::LimitExceeded {
+            source: LimitError::TooManySkipped { limit: 1 },
+            ..
+        }
+    ));
+    assert_eq!(err.context().record_line, 9);
+}
+
+#[test]
+fn parse_records_from_slice_matches_reader() {
+    let data = b"\
+@r1
+ACGT
++
+###
+@r2 two
+AC
++
+##
+";
+    let opts = ReaderOptions::default();
+    let from_slice: Vec<_> = kira_cdh_compat_fastq_reader::parse_records(data, &opts)
+        .map(Result::unwrap)
+        .collect();
+    let from_reader: Vec<_> = FastqReader::from_bufread(BufReader::new(&data[..]), opts.clone())
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(from_slice, from_reader);
+    assert_eq!(from_slice.len(), 1);
+    assert_eq!(from_slice[0].desc(), Some("two"));
+
+    let strict = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..opts
+    };
+    let err = kira_cdh_compat_fastq_reader::parse_records(data, &strict)
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(err.to_string().contains("line_num: 4"), "{err}");
+}
+
+#[test]
+fn checksums_span_many_blocks() {
+    let mut data = b"@r\n".to_vec();
+    data.extend(std::iter::repeat_n(b'A', 1000));
+    data.extend_from_slice(b"\n+\n");
+    data.extend(std::iter::repeat_n(b'I', 1000));
+    data.push(b'\n');
+    let opts = ReaderOptions {
+        checksum_raw: Some(kira_cdh_compat_fastq_reader::ChecksumAlgorithm::Sha256),
+        checksum_decompressed: Some(kira_cdh_compat_fastq_reader::ChecksumAlgorithm::Md5),
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bufread(
+        BufReader::with_capacity(100, std::io::Cursor::new(data)),
+        opts,
+    );
+    assert_eq!(fq.by_ref().count(), 1);
+    let sums = fq.checksums().unwrap();
+    assert_eq!(
+        sums.raw.unwrap().to_hex(),
+        "c46075821d6edc0641e19b8cd479c36cf6d9c327e13db0d21274149b0e716188"
+    );
+    assert_eq!(
+        sums.decompressed.unwrap().to_hex(),
+        "230e49b469627eacf1d9711a34b7e2c5"
+    );
+}
+
+#[test]
+fn labeled_source_appears_in_errors_and_position() {
+    use kira_cdh_compat_fastq_reader::FastqError;
+
+    let data = "@r1\nACGT\n+\nIIII\n@r2\nACGT\n+\nII\n";
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bufread_labeled(BufReader::new(data.as_bytes()), "lane-3", opts);
+    assert_eq!(fq.source().label(), Some("lane-3"));
+    fq.next().unwrap().unwrap();
+    assert_eq!(fq.position().line_num, 4);
+
+    match fq.next().unwrap().unwrap_err() {
+        FastqError::Format { ctx, .. } => assert_eq!(ctx.label.as_deref(), Some("lane-3")),
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[test]
+fn convenience_constructors_from_bytes_and_file() {
+    let ids = |fq: FastqReader| fq.map(|r| r.unwrap().id().to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        ids(FastqReader::from_bytes(
+            SAMPLE.as_bytes(),
+            ReaderOptions::default()
+        )),
+        ["read1", "read2"]
+    );
+    assert_eq!(
+        ids(FastqReader::from_vec(
+            SAMPLE.as_bytes().to_vec(),
+            ReaderOptions::default()
+        )),
+        ["read1", "read2"]
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("reads.txt");
+    std::fs::write(&path, SAMPLE).unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+    let fq = FastqReader::from_file(file, ReaderOptions::default()).unwrap();
+    assert_eq!(ids(fq), ["read1", "read2"]);
+}
+
+#[test]
+fn rebase_phred64_qualities_to_phred33() {
+    // the first record is ambiguous; the second settles Phred+64
+    let data = b"@r1\nACGT\n+\n@DHJ\n@r2\nACGT\n+\nhhh@\n";
+    let mut fq = FastqReader::from_bytes(
+        data,
+        ReaderOptions {
+            error_policy: ErrorPolicy::Return,
+            rebase_quality: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(fq.next().unwrap().unwrap().qual(), b"@DHJ");
+    assert_eq!(fq.next().unwrap().unwrap().qual(), b"III!");
+    assert_eq!(
+        fq.detected_quality_encoding(),
+        Some(QualityEncoding::Phred64)
+    );
+    assert_eq!(fq.rebased_records(), 1);
+
+    // configured Phred+64 also rebases undecided records
+    let mut fq = FastqReader::from_bytes(
+        b"@r1\nACGT\n+\n@DHJ\n",
+        ReaderOptions {
+            quality_encoding: QualityEncoding::Phred64,
+            rebase_quality: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(fq.next().unwrap().unwrap().qual(), b"!%)+");
+    assert_eq!(fq.detected_quality_encoding(), None);
+}
+
+#[test]
+fn solexa_qualities_detected_and_rebased() {
+    use kira_cdh_compat_fastq_reader::detect_quality_encoding;
+    use kira_cdh_compat_fastq_reader::quality::solexa_to_phred;
+
+    assert_eq!(
+        QualityEncoding::detect(b"hhhh"),
+        Some(QualityEncoding::Phred64)
+    );
+    assert_eq!(
+        QualityEncoding::detect(b";@h"),
+        Some(QualityEncoding::Solexa)
+    );
+    assert_eq!(
+        QualityEncoding::detect_many([&b"hhhh"[..], b"?ABC"]),
+        Some(QualityEncoding::Solexa)
+    );
+    assert_eq!(
+        (solexa_to_phred(-5), solexa_to_phred(0), solexa_to_phred(30)),
+        (1, 3, 30)
+    );
+
+    // a Phred+64 guess is revised once a negative score appears
+    let data = b"@r1\nACG\n+\nhhh\n@r2\nACG\n+\n;@h\n";
+    let opts = ReaderOptions {
+        rebase_quality: true,
+        ..Default::default()
+    };
+    let quals: Vec<_> = FastqReader::from_bytes(data, opts.clone())
+        .map(|r| r.unwrap().qual().to_vec())
+        .collect();
+    assert_eq!(quals, [&b"III"[..], b"\"$I"]);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("solexa.fastq");
+    std::fs::write(&path, data).unwrap();
+    let detect = |n| detect_quality_encoding(&path, &opts, n).unwrap();
+    assert_eq!(detect(1), Some(QualityEncoding::Phred64));
+    assert_eq!(detect(100), Some(QualityEncoding::Solexa));
+}
+
+#[test]
+fn phred_score_accessors() {
+    use kira_cdh_compat_fastq_reader::FastqRecord;
+
+    let rec = FastqRecord::new("r", None, b"ACGT", b"!+5I");
+    assert_eq!(rec.phred_scores(33), [0, 10, 20, 40]);
+    assert_eq!(rec.mean_quality(), Some(17.5));
+    let probs = rec.error_probabilities();
+    let expected = [1.0, 0.1, 0.01, 0.0001];
+    assert!(
+        probs
+            .iter()
+            .zip(expected)
+            .all(|(p, e)| (p - e).abs() < 1e-12 * e.max(1.0))
+    );
+
+    let illumina = FastqRecord::new("r", None, b"AC", b"@h");
+    assert_eq!(illumina.phred_scores(64), [0, 40]);
+    assert_eq!(illumina.phred_scores_in(QualityEncoding::Phred64), [0, 40]);
+    let solexa = FastqRecord::new("r", None, b"ACG", b";@h");
+    assert_eq!(solexa.phred_scores_in(QualityEncoding::Solexa), [1, 3, 40]);
+    assert_eq!(FastqRecord::new("r", None, b"", b"").mean_quality(), None);
+}
+
+#[test]
+fn length_and_n_filters_drop_records() {
+    let data = b"@short\nAC\n+\nII\n@ok\nACGT\n+\nIIII\n@ns\nANNT\n+\nIIII\n@long\nACGTACGT\n+\nIIIIIIII\n";
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        min_len: Some(3),
+        max_len: Some(6),
+        max_n_fraction: Some(0.25),
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bytes(data, opts);
+    let ids: Vec<_> = fq.by_ref().map(|r| r.unwrap().id().to_string()).collect();
+    assert_eq!(ids, ["ok"]);
+    let stats = fq.stats();
+    assert_eq!((stats.records, stats.filtered, stats.skipped), (1, 3, 0));
+}
+
+#[test]
+fn peek_n_does_not_consume_records() {
+    let mut fq = FastqReader::from_bytes(SAMPLE.as_bytes(), ReaderOptions::default());
+
+    let ids: Vec<_> = fq.peek_n(1).iter().map(|r| r.id()).collect();
+    assert_eq!(ids, ["read1"]);
+    let ids: Vec<_> = fq.peek_n(5).iter().map(|r| r.id()).collect();
+    assert_eq!(ids, ["read1", "read2"]);
+    assert!(fq.state().is_none());
+
+    assert_eq!(fq.next().unwrap().unwrap().id(), "read1");
+    assert_eq!(fq.peek_n(1)[0].id(), "read2");
+    assert_eq!(fq.next().unwrap().unwrap().id(), "read2");
+    assert!(fq.peek_n(1).is_empty());
+    assert!(fq.next().is_none());
+    assert_eq!(fq.state().unwrap().records, 2);
+}
+
+#[test]
+fn peek_n_defers_errors_until_reached() {
+    let data = "@r1\nACGT\n+\nIIII\nbad\n";
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bytes(data.as_bytes(), opts);
+    assert_eq!(fq.peek_n(3).len(), 1);
+    assert_eq!(fq.next().unwrap().unwrap().id(), "r1");
+    assert!(fq.next().unwrap().is_err());
+}
+
+#[test]
+fn errors_report_record_ordinal_and_start_line() {
+    use kira_cdh_compat_fastq_reader::FastqError;
+
+    let data = "@r1\nACGT\n+\nIIII\n@r2\nACGT\n+\nII\n";
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bytes(data.as_bytes(), opts.clone());
+    fq.next().unwrap().unwrap();
+    match fq.next().unwrap().unwrap_err() {
+        FastqError::Format { ctx, .. } => {
+            assert_eq!((ctx.record_num, ctx.record_line, ctx.line_num), (2, 5, 8));
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+    let err = kira_cdh_compat_fastq_reader::parse_records(data.as_bytes(), &opts)
+        .nth(1)
+        .unwrap()
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("record_num: 2, record_line: 5"),
+        "{err}"
+    );
+
+    // the skipped record and its junk still count; r3 starts on line 8
+    let data = "@r1\nACGT\n+\nIIII\n@r2\nACGT\njunk\n@r3\nGG\n+\nII\n";
+    let mut fq = FastqReader::from_bytes(data.as_bytes(), ReaderOptions::default());
+    let ids: Vec<_> = fq.by_ref().map(|r| r.unwrap().id().to_string()).collect();
+    assert_eq!(ids, ["r1", "r3"]);
+    let pos = fq.position();
+    assert_eq!((pos.record_num, pos.record_line), (3, 8));
+}
+
+#[test]
+fn multi_line_uses_sequence_length_for_at_and_plus_lines() {
+    let multi = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        line_mode: LineMode::Multi,
+        ..Default::default()
+    };
+    let data = "@r1\nACGT\nAC\n+\n@III\n+I\n@r2\nGG\n+\n@@\n";
+    let recs: Vec<_> = FastqReader::from_bytes(data.as_bytes(), multi.clone())
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(recs.len(), 2);
+    assert_eq!(recs[0].qual(), b"@III+I");
+    assert_eq!(recs[1].qual(), b"@@");
+
+    // "@r2" would overrun r1's quality, so it opens the next record
+    let data = "@r1\nACGT\n+\nII\n@r2\nGG\n+\nII\n";
+    let mut fq = FastqReader::from_bytes(data.as_bytes(), multi);
+    assert!(fq.next().unwrap().is_err());
+    assert_eq!(fq.next().unwrap().unwrap().id(), "r2");
+    assert!(fq.next().is_none());
+}
+
+#[test]
+fn resync_does_not_trust_quality_lines_starting_with_at() {
+    let data = "@r1\nACGT\nXX\n+\n@@II\n@r2\nAC\n+\nII\n";
+    for recs in [
+        FastqReader::from_bytes(data.as_bytes(), ReaderOptions::default())
+            .map(Result::unwrap)
+            .collect::<Vec<_>>(),
+        kira_cdh_compat_fastq_reader::parse_records(data.as_bytes(), &ReaderOptions::default())
+            .map(Result::unwrap)
+            .collect(),
+    ] {
+        let ids: Vec<_> = recs.iter().map(|r| r.id()).collect();
+        assert_eq!(ids, ["r2"]);
+    }
+
+    let mut fq = FastqReader::from_bytes(data.as_bytes(), ReaderOptions::default());
+    fq.next().unwrap().unwrap();
+    let pos = fq.position();
+    assert_eq!((pos.record_num, pos.record_line), (2, 6));
+}
+
+#[test]
+fn blank_line_policy() {
+    use kira_cdh_compat_fastq_reader::{BlankLinePolicy, FastqError, FormatError};
+
+    let data = "\n@r1\nACGT\n+\nIIII\n\n \n@r2\nGG\n+\nII\n\n\n";
+    for blank_lines in [BlankLinePolicy::Tolerate, BlankLinePolicy::Warn] {
+        let opts = ReaderOptions {
+            error_policy: ErrorPolicy::Return,
+            blank_lines,
+            ..Default::default()
+        };
+        let ids: Vec<_> = FastqReader::from_bytes(data.as_bytes(), opts)
+            .map(|r| r.unwrap().id().to_string())
+            .collect();
+        assert_eq!(ids, ["r1", "r2"]);
+    }
+
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        blank_lines: BlankLinePolicy::Error,
+        ..Default::default()
+    };
+    let data = "@r1\nACGT\n+\nIIII\n\n";
+    let mut fq = FastqReader::from_bytes(data.as_bytes(), opts);
+    fq.next().unwrap().unwrap();
+    match fq.next().unwrap().unwrap_err() {
+        FastqError::Format {
+            source: FormatError::BlankLine,
+            ctx,
+        } => assert_eq!(ctx.line_num, 5),
+        other => panic!("unexpected error: {other}"),
+    }
+
+    // inside a record a blank line is an error whatever the policy: read
+    // as quality, the next header would swallow record s
+    let data = "@r\nAC\n+\n\n@s\nAC\n+\nII\n@t\nGG\n+\nII\n";
+    let mut fq = FastqReader::from_bytes(data.as_bytes(), ReaderOptions::default());
+    let ids: Vec<_> = fq.by_ref().map(|r| r.unwrap().id().to_string()).collect();
+    assert_eq!(ids, ["s", "t"]);
+    assert_eq!(fq.stats().skipped, 1);
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let err = FastqReader::from_bytes(data.as_bytes(), opts)
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        FastqError::Format {
+            source: FormatError::BlankLine,
+            ..
+        }
+    ));
+
+    // an empty record is still reported as such, not as a blank line
+    let data = "@r1\n\n+\n\n";
+    let err = FastqReader::from_bytes(
+        data.as_bytes(),
+        ReaderOptions {
+            error_policy: ErrorPolicy::Return,
+            ..Default::default()
+        },
+    )
+    .next()
+    .unwrap()
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        FastqError::Format {
+            source: FormatError::EmptySequence,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn sanitize_ids_replaces_separators_truncates_and_dedups() {
+    use kira_cdh_compat_fastq_reader::IdSanitize;
+
+    let data = "@a;b,c\nA\n+\nI\n@a;b,c\nA\n+\nI\n@longname1\nA\n+\nI\n@longname2\nA\n+\nI\n";
+    let opts = ReaderOptions {
+        sanitize_ids: Some(IdSanitize {
+            max_len: Some(8),
+            unique: true,
+        }),
+        ..Default::default()
+    };
+    let ids: Vec<_> = FastqReader::from_bytes(data.as_bytes(), opts)
+        .map(|r| r.unwrap().id().to_string())
+        .collect();
+    assert_eq!(ids, ["a_b_c", "a_b_c_2", "longname", "longna_2"]);
+
+    let opts = ReaderOptions {
+        sanitize_ids: Some(IdSanitize::default()),
+        ..Default::default()
+    };
+    let ids: Vec<_> = FastqReader::from_bytes(data.as_bytes(), opts)
+        .map(|r| r.unwrap().id().to_string())
+        .collect();
+    assert_eq!(ids, ["a_b_c", "a_b_c", "longname1", "longname2"]);
+}
+
+#[test]
+fn custom_allowed_byte_tables() {
+    use kira_cdh_compat_fastq_reader::{AllowedBytes, FastqError, FormatError};
+
+    let data = "@r1\nacgtn\n+\nIIIII\n@r2\nACGT\n+\nIIII\n@r3\nAC*T\n+\nIIII\n";
+    let strict = |allowed| ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        allowed_seq_bytes: Some(allowed),
+        ..Default::default()
+    };
+
+    // masked-lowercase-only alphabet
+    let mut fq = FastqReader::from_bytes(data.as_bytes(), strict(AllowedBytes::from_set(b"acgtn")));
+    assert_eq!(fq.next().unwrap().unwrap().id(), "r1");
+    match fq.next().unwrap().unwrap_err() {
+        FastqError::Format {
+            source: FormatError::InvalidSequenceByte { byte: b'A', pos: 0 },
+            ctx,
+        } => assert_eq!(ctx.line_num, 6),
+        other => panic!("unexpected error: {other}"),
+    }
+
+    let mut table = [false; 256];
+    for &b in b"ACGTacgtn*" {
+        table[b as usize] = true;
+    }
+    let ids: Vec<_> = FastqReader::from_bytes(data.as_bytes(), strict(AllowedBytes::new(table)))
+        .map(|r| r.unwrap().id().to_string())
+        .collect();
+    assert_eq!(ids, ["r1", "r2", "r3"]);
+
+    // the Skip policy drops just the offending record
+    let opts = ReaderOptions {
+        allowed_seq_bytes: Some(AllowedBytes::nucleotides()),
+        ..Default::default()
+    };
+    let ids: Vec<_> = FastqReader::from_bytes(data.as_bytes(), opts)
+        .map(|r| r.unwrap().id().to_string())
+        .collect();
+    assert_eq!(ids, ["r1", "r2"]);
+    assert!(AllowedBytes::iupac().with(b"*").allows(b'*'));
+
+    let protein = "@p1\nMKVLA*\n+\nIIIIII\n@p2\nMK-LA\n+\nIIIII\n";
+    let mut fq = FastqReader::from_bytes(protein.as_bytes(), strict(AllowedBytes::protein()));
+    assert_eq!(fq.next().unwrap().unwrap().seq(), b"MKVLA*");
+    assert!(matches!(
+        fq.next().unwrap().unwrap_err(),
+        FastqError::Format {
+            source: FormatError::InvalidSequenceByte { byte: b'-', pos: 2 },
+            ..
+        }
+    ));
+}
+
+#[test]
+fn validate_quality_rejects_out_of_range_bytes() {
+    use kira_cdh_compat_fastq_reader::{FastqError, FormatError};
+
+    let data = "@r1\nACGT\n+\nII#I\n@r2\nACGT\n+\nII\x01I\n@r3\nAC\n+\nII\n";
+    let opts = |encoding, error_policy| ReaderOptions {
+        error_policy,
+        quality_encoding: encoding,
+        validate_quality: true,
+        ..Default::default()
+    };
+
+    let mut fq = FastqReader::from_bytes(
+        data.as_bytes(),
+        opts(QualityEncoding::Phred33, ErrorPolicy::Return),
+    );
+    assert_eq!(fq.next().unwrap().unwrap().id(), "r1");
+    match fq.next().unwrap().unwrap_err() {
+        FastqError::Format {
+            source: FormatError::InvalidQualityChar { byte: 1, pos: 2 },
+            ctx,
+        } => assert_eq!(ctx.line_num, 8),
+        other => panic!("unexpected error: {other}"),
+    }
+
+    // '#' is below the Phred+64 range; Skip drops both bad records
+    let ids: Vec<_> = FastqReader::from_bytes(
+        data.as_bytes(),
+        opts(QualityEncoding::Phred64, ErrorPolicy::Skip),
+    )
+    .map(|r| r.unwrap().id().to_string())
+    .collect();
+    assert_eq!(ids, ["r3"]);
+
+    // off by default
+    let ids: Vec<_> = FastqReader::from_bytes(data.as_bytes(), ReaderOptions::default())
+        .map(|r| r.unwrap().id().to_string())
+        .collect();
+    assert_eq!(ids, ["r1", "r2", "r3"]);
+}
+
+#[test]
+fn strict_plus_line_checks_the_repeated_header() {
+    use kira_cdh_compat_fastq_reader::{FastqError, FormatError};
+
+    let data = "@r1 lane=1\nAC\n+r1\nII\n@r2 lane=1\nAC\n+r2 lane=1\nII\n@r3\nAC\n+\nII\n@r4\nAC\n+r5\nII\n";
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        strict_plus_line: true,
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bytes(data.as_bytes(), opts.clone());
+    for id in ["r1", "r2", "r3"] {
+        assert_eq!(fq.next().unwrap().unwrap().id(), id);
+    }
+    match fq.next().unwrap().unwrap_err() {
+        FastqError::Format {
+            source: FormatError::PlusLineMismatch { plus },
+            ctx,
+        } => {
+            assert_eq!(&*plus, "r5");
+            assert_eq!(ctx.line_num, 15);
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+
+    let bad_desc = "@r1 lane=1\nAC\n+r1 lane=2\nII\n";
+    assert!(
+        FastqReader::from_bytes(bad_desc.as_bytes(), opts)
+            .next()
+            .unwrap()
+            .is_err()
+    );
+    assert!(
+        FastqReader::from_bytes(bad_desc.as_bytes(), ReaderOptions::default())
+            .next()
+            .unwrap()
+            .is_ok()
+    );
+}
+
+#[test]
+fn keep_raw_returns_exact_record_bytes() {
+    let data = b"@r1 x\r\nAC\r\n+r1\r\nII\r\n\n@bad\nACGT\n+\nII\n@r2\nGG\n+\n!!";
+    let opts = ReaderOptions {
+        keep_raw: true,
+        ..Default::default()
+    };
+    let recs: Vec<_> = FastqReader::from_bytes(data, opts.clone())
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(recs.len(), 2);
+    assert_eq!(recs[0].raw(), Some(&b"@r1 x\r\nAC\r\n+r1\r\nII\r\n"[..]));
+    assert_eq!(recs[0].seq(), b"AC");
+    assert_eq!(recs[1].raw(), Some(&b"@r2\nGG\n+\n!!"[..]));
+
+    // the zero-copy and peeking paths agree
+    let mut fq = FastqReader::from_bytes(data, opts);
+    assert_eq!(fq.peek_n(1)[0].raw(), recs[0].raw());
+    assert_eq!(fq.read_ref().unwrap().unwrap().raw, recs[0].raw());
+    let r2 = fq.read_ref().unwrap().unwrap();
+    assert_eq!(r2.raw, recs[1].raw());
+    assert_eq!(r2.to_owned_record(), recs[1]);
+
+    // off by default
+    let rec = FastqReader::from_bytes(data, ReaderOptions::default()).next();
+    assert_eq!(rec.unwrap().unwrap().raw(), None);
+}
+
+#[test]
+fn mixed_fasta_fastq_stream() {
+    use kira_cdh_compat_fastq_reader::{FastqError, FormatError, SeqRecord};
+
+    let data = ">f1 first\nACGT\nAC\n@q1\nGG\n+\nII\n>f2\nTT\n@q2\nA\n+\n>\n>f3\nCC\n";
+    let mixed = ReaderOptions {
+        fastq_only: false,
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bytes(data.as_bytes(), mixed.clone());
+    let entries: Vec<_> = fq.entries().map(Result::unwrap).collect();
+    let ids: Vec<_> = entries.iter().map(SeqRecord::id).collect();
+    assert_eq!(ids, ["f1", "q1", "f2", "q2", "f3"]);
+    match &entries[0] {
+        SeqRecord::Fasta(f) => {
+            assert_eq!(f.seq, b"ACGTAC");
+            assert_eq!(f.desc.as_deref(), Some("first"));
+        }
+        other => panic!("expected FASTA, got {other:?}"),
+    }
+    assert_eq!(entries[3].qual(), Some(&b">"[..]));
+    assert_eq!(entries[4].qual(), None);
+
+    // the record iterator drops FASTA entries under Skip, or reports them
+    let skip = ReaderOptions {
+        error_policy: ErrorPolicy::Skip,
+        ..mixed.clone()
+    };
+    let ids: Vec<_> = FastqReader::from_bytes(data.as_bytes(), skip)
+        .map(|r| r.unwrap().id().to_string())
+        .collect();
+    assert_eq!(ids, ["q1", "q2"]);
+    let err = FastqReader::from_bytes(data.as_bytes(), mixed)
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        FastqError::Format {
+            source: FormatError::FastaHeaderDetected,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn fasta_input_without_mixed_flag() {
+    use kira_cdh_compat_fastq_reader::SeqRecord;
+
+    let opts = ReaderOptions {
+        fastq_only: false,
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bytes(&b">a x\nAC\nGT\n>b\nTT\n"[..], opts);
+    let entries: Vec<_> = fq.entries().map(Result::unwrap).collect();
+    assert_eq!(entries.len(), 2);
+    match &entries[0] {
+        SeqRecord::Fasta(f) => assert_eq!((f.id.as_str(), &f.seq[..]), ("a", &b"ACGT"[..])),
+        other => panic!("expected FASTA, got {other:?}"),
+    }
+    assert_eq!(entries[1].seq(), b"TT");
+}
+
+#[test]
+fn missing_header_is_a_format_error() {
+    use kira_cdh_compat_fastq_reader::{FastqError, FormatError};
+
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let input = "\u{FEFF}xyz\nACGT\n+\nIIII\n";
+    let mut fq = FastqReader::from_bytes(input.as_bytes(), opts);
+    let err = fq.next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), "missing_header");
+    assert!(matches!(
+        &err,
+        FastqError::Format { source: FormatError::MissingHeader { first_bytes }, .. }
+            if &first_bytes[..] == b"xyz"
+    ));
+    assert!(
+        err.to_string()
+            .contains("got 'x' (U+0078); first bytes: 78 79 7A"),
+        "{err}"
+    );
+}
+
+#[test]
+fn input_format_auto_detects_fasta_and_fastq() {
+    use kira_cdh_compat_fastq_reader::{InputFormat, SeqRecord, detect_format};
+
+    assert_eq!(detect_format(b"\n\n>x\nAC\n"), Some(InputFormat::Fasta));
+    assert_eq!(
+        detect_format("\u{FEFF}@r\n".as_bytes()),
+        Some(InputFormat::Fastq)
+    );
+    assert_eq!(detect_format(b"ACGT\n"), None);
+    assert_eq!(detect_format(b""), None);
+
+    let auto = ReaderOptions {
+        input_format: InputFormat::Auto,
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bytes(&b"\n>a\nAC\n>b\nGT\n"[..], auto.clone());
+    assert_eq!(fq.input_format(), None);
+    let entries: Vec<_> = fq.entries().map(Result::unwrap).collect();
+    assert_eq!(fq.input_format(), Some(InputFormat::Fasta));
+    assert!(matches!(
+        &entries[..],
+        [SeqRecord::Fasta(_), SeqRecord::Fasta(_)]
+    ));
+
+    let mut fq = FastqReader::from_bytes(&b"@a\nAC\n+\nII\n>b\nGT\n"[..], auto);
+    assert_eq!(fq.next().unwrap().unwrap().id(), "a");
+    assert_eq!(fq.input_format(), Some(InputFormat::Fastq));
+    assert!(fq.next().unwrap().is_err());
+}
+
+#[test]
+fn auto_line_mode_settles_from_first_records() {
+    use kira_cdh_compat_fastq_reader::core::AUTO_LINE_MODE_RECORDS;
+
+    let auto = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        line_mode: LineMode::Auto,
+        ..Default::default()
+    };
+    let wrapped = "@w1\nACGT\nAC\n+\nIIII\nII\n@w2\nGG\n+\nII\n";
+    let mut fq = FastqReader::from_bytes(wrapped.as_bytes(), auto.clone());
+    assert_eq!(fq.line_mode(), None);
+    assert_eq!(fq.next().unwrap().unwrap().seq(), b"ACGTAC");
+    assert_eq!(fq.line_mode(), Some(LineMode::Multi));
+    assert_eq!(fq.count(), 1);
+
+    let mut single = String::new();
+    for i in 0..AUTO_LINE_MODE_RECORDS + 1 {
+        single.push_str(&format!("@s{i}\nACGT\n+\nIIII\n"));
+    }
+    let mut fq = FastqReader::from_bytes(single.as_bytes(), auto.clone());
+    for _ in 0..AUTO_LINE_MODE_RECORDS {
+        fq.next().unwrap().unwrap();
+    }
+    assert_eq!(fq.line_mode(), Some(LineMode::Single));
+    fq.next().unwrap().unwrap();
+
+    // a short file settles at EOF
+    let mut fq = FastqReader::from_bytes(b"@a\nAC\n+\nII\n", auto);
+    assert_eq!(fq.by_ref().count(), 1);
+    assert_eq!(fq.line_mode(), Some(LineMode::Single));
+}
+
+#[test]
+fn record_accessors_and_into_parts() {
+    use kira_cdh_compat_fastq_reader::{FastqParts, FastqRecord};
+
+    let opts = ReaderOptions {
+        keep_plus_line: true,
+        ..Default::default()
+    };
+    let data = b"@r1 lane=1\nACGT\n+r1\nIIII\n@r2\nGG\n+\n!!\n";
+    let mut fq = FastqReader::from_bytes(data, opts);
+    let r1 = fq.next().unwrap().unwrap();
+    assert_eq!(r1.id(), "r1");
+    assert_eq!(r1.desc(), Some("lane=1"));
+    assert_eq!(r1.seq(), b"ACGT");
+    assert_eq!(r1.qual(), b"IIII");
+    assert_eq!(r1.plus_line(), Some("r1"));
+    assert_eq!(r1.len(), 4);
+
+    let parts = r1.clone().into_parts();
+    assert_eq!(
+        parts,
+        FastqParts {
+            id: "r1".into(),
+            desc: Some("lane=1".into()),
+            seq: b"ACGT".to_vec(),
+            qual: b"IIII".to_vec(),
+            plus_line: Some("r1".into()),
+        }
+    );
+    assert_eq!(FastqRecord::from(parts), r1);
+
+    let r2 = fq.next().unwrap().unwrap();
+    assert_eq!(r2, FastqRecord::new("r2", None, b"GG", b"!!"));
+    assert_eq!(r2.desc(), None);
+    assert_eq!(r2.plus_line(), None);
+    let r2 = r2.with_plus_line(Some("r2"));
+    assert_eq!(r2.plus_line(), Some("r2"));
+    assert_eq!(r2.qual(), b"!!");
+
+    // the zero-copy view keeps the `+` text too
+    let opts = ReaderOptions {
+        keep_plus_line: true,
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bytes(data, opts);
+    let r1_ref = fq.read_ref().unwrap().unwrap();
+    assert_eq!(r1_ref.plus_line, Some("r1"));
+    assert_eq!(r1_ref.to_owned_record(), r1);
+    assert_eq!(fq.read_ref().unwrap().unwrap().plus_line, None);
+}
+
+#[test]
+fn read_ref_matches_owned_records() {
+    let data: &[u8] = b"@r1 x\nACGT\n+\nIIII\n@bad\nAC\n+\nI\n@r2\nGG\n+\n!!\n@r3\nT\n+\n#\n";
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Skip,
+        ..Default::default()
+    };
+    let owned: Vec<_> = FastqReader::from_bytes(data, opts.clone())
+        .map(Result::unwrap)
+        .collect();
+
+    let mut fq = FastqReader::from_bytes(data, opts);
+    let mut got = Vec::new();
+    let rec = fq.read_ref().unwrap().unwrap();
+    assert_eq!((rec.id, rec.desc, rec.seq), ("r1", Some("x"), &b"ACGT"[..]));
+    got.push(rec.to_owned_record());
+    // a peeked record is handed out by read_ref first
+    assert_eq!(fq.peek_n(1)[0].id(), "r2");
+    while let Some(rec) = fq.read_ref() {
+        got.push(rec.unwrap().to_owned_record());
+    }
+    assert_eq!(got, owned);
+    assert_eq!(fq.state().unwrap().skipped, 1);
+}
+
+#[test]
+fn read_record_into_reuses_buffer() {
+    use kira_cdh_compat_fastq_reader::FastqRecord;
+
+    let data: &[u8] = b"@r1 x\nACGTACGT\n+\nIIIIIIII\n@r2\nGG\n+\n!!\n@r3\nTTT\n+\n###\n";
+    let owned: Vec<_> = FastqReader::from_bytes(data, ReaderOptions::default())
+        .map(Result::unwrap)
+        .collect();
+
+    let mut fq = FastqReader::from_bytes(data, ReaderOptions::default());
+    let mut rec = FastqRecord::default();
+    let mut got = Vec::new();
+    while fq.read_record_into(&mut rec).unwrap() {
+        got.push(rec.clone());
+    }
+    assert_eq!(got, owned);
+    assert!(!fq.read_record_into(&mut rec).unwrap());
+}
+
+#[test]
+fn non_utf8_header_bytes_are_decoded_lossily() {
+    // Latin-1 description; only header text is ever decoded
+    let data: &[u8] = b"@r1 caf\xE9\nACGT\n+\nIIII\n";
+    let rec = FastqReader::from_bytes(data, ReaderOptions::default())
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(rec.id(), "r1");
+    assert_eq!(rec.desc(), Some("caf\u{FFFD}"));
+    assert_eq!(rec.seq(), b"ACGT");
+}
+
+#[test]
+fn next_batch_delivers_records_before_an_error() {
+    let data: &[u8] = b"@r1\nA\n+\nI\n@r2\nC\n+\nI\n@r3\nG\n+\nI\n@bad\nAC\n+\nI\n@r4\nT\n+\nI\n";
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let mut fq = FastqReader::from_bytes(data, opts);
+    let ids = |b: Vec<kira_cdh_compat_fastq_reader::FastqRecord>| {
+        b.iter().map(|r| r.id().to_string()).collect::<Vec<_>>()
+    };
+    assert_eq!(ids(fq.next_batch(2).unwrap()), ["r1", "r2"]);
+    assert_eq!(ids(fq.next_batch(2).unwrap()), ["r3"]);
+    assert!(fq.next_batch(2).is_err());
+    assert_eq!(ids(fq.next_batch(2).unwrap()), ["r4"]);
+    assert!(fq.next_batch(2).unwrap().is_empty());
+}
+
+#[test]
+fn skip_and_take_records_pick_a_chunk() {
+    let text: String = (0..10).map(|i| format!("@r{i}\nACGT\n+\nIIII\n")).collect();
+    let mut fq = FastqReader::from_bytes(text.as_bytes(), ReaderOptions::default());
+    assert_eq!(fq.peek_n(1)[0].id(), "r0");
+    assert_eq!(fq.skip_records(3).unwrap(), 3);
+    let chunk: Vec<_> = fq
+        .take_records(4)
+        .map(|r| r.unwrap().id().to_owned())
+        .collect();
+    assert_eq!(chunk, ["r3", "r4", "r5", "r6"]);
+    assert_eq!(fq.stats().records, 7);
+    assert_eq!(fq.skip_records(10).unwrap(), 3);
+    assert_eq!(fq.take_records(2).count(), 0);
+
+    let bad = b"@a\nAC\n+\nI\n@b\nAC\n+\nII\n";
+    let strict = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..ReaderOptions::default()
+    };
+    assert!(
+        FastqReader::from_bytes(bad, strict)
+            .skip_records(2)
+            .is_err()
+    );
+    let mut fq = FastqReader::from_bytes(bad, ReaderOptions::default());
+    assert_eq!(fq.skip_records(2).unwrap(), 1);
+}
+
+#[test]
+fn last_record_span_slices_the_input() {
+    use kira_cdh_compat_fastq_reader::RecordSpan;
+
+    let data = b"@a\r\nACGT\r\n+\r\nIIII\r\n@bad\nAC\n+\nI\n@c d\nAC\nGT\n+\nII\nII\n";
+    let opts = ReaderOptions {
+        line_mode: LineMode::Multi,
+        ..ReaderOptions::default()
+    };
+    let mut fq = FastqReader::from_bytes(data, opts);
+    assert_eq!(fq.last_record_span(), None);
+    assert_eq!(fq.peek_n(2).len(), 2);
+    assert_eq!(fq.last_record_span(), None);
+
+    let slice = |span: RecordSpan| {
+        let start = span.byte_offset as usize;
+        &data[start..start + span.byte_len as usize]
+    };
+    fq.next().unwrap().unwrap();
+    let a = fq.last_record_span().unwrap();
+    assert_eq!((a.index, a.line), (0, 1));
+    assert_eq!(slice(a), b"@a\r\nACGT\r\n+\r\nIIII\r\n");
+
+    let c = fq.next().unwrap().unwrap();
+    assert_eq!(c.id(), "c");
+    let span = fq.last_record_span().unwrap();
+    assert_eq!((span.index, span.line), (2, 9));
+    assert_eq!(slice(span), b"@c d\nAC\nGT\n+\nII\nII\n");
+    assert!(fq.next().is_none());
+}
+
+#[test]
+fn rewind_restarts_path_readers() {
+    let dir = tempfile::tempdir().unwrap();
+    let (a, b) = (dir.path().join("a.fq"), dir.path().join("b.fq"));
+    std::fs::write(&a, SAMPLE).unwrap();
+    std::fs::write(&b, "@x\nA\n+\nI\n").unwrap();
+
+    let mut fq = FastqReader::from_path(&a, ReaderOptions::default()).unwrap();
+    let n = fq.by_ref().count();
+    fq.rewind().unwrap();
+    assert_eq!(fq.stats().records, 0);
+    assert_eq!(fq.by_ref().count(), n);
+    assert_eq!(fq.stats().records, n as u64);
+
+    let mut chain = FastqReader::from_paths([&a, &b], ReaderOptions::default()).unwrap();
+    let first: Vec<_> = chain
+        .by_ref()
+        .map(|r| r.unwrap().id().to_string())
+        .collect();
+    chain.rewind().unwrap();
+    assert_eq!(chain.path_index(), Some(0));
+    let second: Vec<_> = chain.map(|r| r.unwrap().id().to_string()).collect();
+    assert_eq!(first, second);
+
+    let mut mem = FastqReader::from_bytes(SAMPLE.as_bytes(), ReaderOptions::default());
+    assert!(mem.rewind().is_err());
+}
+
+#[test]
+fn headers_yield_ids_and_descriptions() {
+    let data = b"@a one\nACGT\n+\nIIII\n@bad\nAC\n+\nI\n@b\nAC\n+\nII\n@c x y\nG\n+\nI\n";
+    let mut fq = FastqReader::from_bytes(data, ReaderOptions::default());
+    assert_eq!(fq.peek_n(1).len(), 1);
+    let headers: Vec<_> = fq.headers().map(Result::unwrap).collect();
+    assert_eq!(
+        headers,
+        [
+            ("a".to_owned(), Some("one".to_owned())),
+            ("b".to_owned(), None),
+            ("c".to_owned(), Some("x y".to_owned())),
+        ]
+    );
+    let stats = fq.stats();
+    assert_eq!((stats.records, stats.bases, stats.skipped), (3, 7, 1));
+
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..ReaderOptions::default()
+    };
+    let mut fq = FastqReader::from_bytes(data, opts);
+    let mut headers = fq.headers();
+    assert_eq!(headers.next().unwrap().unwrap().0, "a");
+    assert!(headers.next().unwrap().is_err());
+}
+
+#[test]
+fn keep_quality_false_drops_quality_but_checks_length() {
+    let data = b"@a\nACGT\n+\nIIII\n@bad\nACGT\n+\nIII\n@b\nACGT\n+\nIIII\n";
+    let opts = ReaderOptions {
+        keep_quality: false,
+        ..ReaderOptions::default()
+    };
+    let mut fq = FastqReader::from_bytes(data, opts);
+    let recs: Vec<_> = fq.by_ref().map(Result::unwrap).collect();
+    assert_eq!(recs.len(), 2);
+    assert_eq!((recs[1].id(), recs[1].seq()), ("b", &b"ACGT"[..]));
+    assert!(recs.iter().all(|rec| rec.qual().is_empty()));
+    assert_eq!(fq.stats().skipped, 1);
+}
+
+#[test]
+fn mmap_policy_on_regular_files() {
+    use kira_cdh_compat_fastq_reader::MmapPolicy;
+
+    let ids = |fq: FastqReader| fq.map(|r| r.unwrap().id().to_string()).collect::<Vec<_>>();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("reads.fq");
+    std::fs::write(&path, SAMPLE).unwrap();
+    let open = |path: &std::path::Path, use_mmap| {
+        let opts = ReaderOptions {
+            use_mmap,
+            ..ReaderOptions::default()
+        };
+        FastqReader::from_path(path, opts)
+    };
+    assert_eq!(
+        ids(open(&path, MmapPolicy::Auto).unwrap()),
+        ["read1", "read2"]
+    );
+    assert_eq!(
+        ids(open(&path, MmapPolicy::Never).unwrap()),
+        ["read1", "read2"]
+    );
+    let always = open(&path, MmapPolicy::Always);
+    if cfg!(feature = "mmap") {
+        assert_eq!(ids(always.unwrap()), ["read1", "read2"]);
+    } else {
+        assert!(always.is_err());
+    }
+
+    // a character device is streamed, or refused under `Always`
+    #[cfg(unix)]
+    {
+        let null = std::path::Path::new("/dev/null");
+        assert_eq!(open(null, MmapPolicy::Auto).unwrap().count(), 0);
+        assert!(open(null, MmapPolicy::Always).is_err());
+    }
+}
+
+#[test]
+fn access_advice_is_only_a_hint() {
+    use kira_cdh_compat_fastq_reader::{AccessAdvice, MmapPolicy};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("reads.fq");
+    std::fs::write(&path, SAMPLE).unwrap();
+    for access_advice in [
+        AccessAdvice::Normal,
+        AccessAdvice::Sequential,
+        AccessAdvice::WillNeed,
+    ] {
+        for use_mmap in [MmapPolicy::Auto, MmapPolicy::Never] {
+            let opts = ReaderOptions {
+                access_advice,
+                use_mmap,
+                ..ReaderOptions::default()
+            };
+            assert_eq!(FastqReader::from_path(&path, opts).unwrap().count(), 2);
+        }
+    }
+}
+
+#[cfg(all(unix, feature = "gzip"))]
+#[test]
+fn gzip_fifo_input_is_sniffed_and_streamed() {
+    use std::io::Write;
+
+    let dir = tempfile::tempdir().unwrap();
+    let fifo = dir.path().join("reads.fifo");
+    let made = std::process::Command::new("mkfifo").arg(&fifo).status();
+    if !made.is_ok_and(|s| s.success()) {
+        return;
+    }
+    let writer = {
+        let fifo = fifo.clone();
+        std::thread::spawn(move || {
+            let out = std::fs::OpenOptions::new().write(true).open(fifo).unwrap();
+            let mut enc = flate2::write::GzEncoder::new(out, flate2::Compression::fast());
+            enc.write_all(SAMPLE.as_bytes()).unwrap();
+            enc.finish().unwrap();
+        })
+    };
+    let fq = FastqReader::from_path(&fifo, ReaderOptions::default()).unwrap();
+    let ids: Vec<_> = fq.map(|r| r.unwrap().id().to_string()).collect();
+    assert_eq!(ids, ["read1", "read2"]);
+    writer.join().unwrap();
+}
+
+#[test]
+fn io_uring_reads_match_buffered_reads() {
+    use std::io::Write;
+
+    // several times the ring's buffers, with a ragged last chunk
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("reads.fq");
+    let mut text = Vec::new();
+    for i in 0..60_001 {
+        writeln!(text, "@r{i}\nACGTACGTACGTACGTACGT\n+\nIIIIIIIIIIIIIIIIIIII").unwrap();
+    }
+    std::fs::write(&path, &text).unwrap();
+
+    let opts = ReaderOptions {
+        io_uring: true,
+        ..ReaderOptions::default()
+    };
+    let mut fq = FastqReader::from_path(&path, opts).unwrap();
+    let mut n = 0;
+    for (i, rec) in fq.by_ref().enumerate() {
+        assert_eq!(rec.unwrap().id(), format!("r{i}"));
+        n += 1;
+    }
+    assert_eq!(n, 60_001);
+    assert_eq!(fq.stats().bytes, text.len() as u64);
+}
+
+#[test]
+fn option_presets_and_builder() {
+    use kira_cdh_compat_fastq_reader::BlankLinePolicy;
+
+    let opts = ReaderOptions::builder()
+        .line_mode(LineMode::Multi)
+        .blank_lines(BlankLinePolicy::Warn)
+        .max_record_bytes(4096)
+        .min_len(None)
+        .build();
+    assert_eq!(opts.line_mode, LineMode::Multi);
+    assert_eq!(opts.blank_lines, BlankLinePolicy::Warn);
+    assert_eq!(opts.max_record_bytes, Some(4096));
+    assert_eq!(opts.error_policy, ErrorPolicy::Skip);
+
+    // cd-hit-est drops a protein record, cd-hit keeps it
+    let data = b"@n\nACGTN\n+\nIIIII\n@p\nMKVLQ\n+\nIIIII\n";
+    let count = |opts| FastqReader::from_bytes(data, opts).count();
+    assert_eq!(count(ReaderOptions::cd_hit()), 2);
+    assert_eq!(count(ReaderOptions::cd_hit_est()), 1);
+
+    // FASTA under the cd-hit preset: entries through next_entry only
+    let fasta = b">p1\nMKV\n>p2\nMKL\n";
+    assert_eq!(
+        FastqReader::from_bytes(fasta, ReaderOptions::cd_hit()).count(),
+        0
+    );
+    let mut fq = FastqReader::from_bytes(fasta, ReaderOptions::cd_hit());
+    let ids: Vec<_> = std::iter::from_fn(|| fq.next_entry())
+        .map(|e| e.unwrap().id().to_owned())
+        .collect();
+    assert_eq!(ids, ["p1", "p2"]);
+
+    // a wrapped Phred+64 record
+    let data = b"@w\nACGT\nAC\n+\nhhhh\nhh\n";
+    let mut fq = FastqReader::from_bytes(data, ReaderOptions::lenient());
+    assert_eq!(fq.next().unwrap().unwrap().qual(), b"IIIIII");
+    assert!(
+        FastqReader::from_bytes(data, ReaderOptions::strict())
+            .next()
+            .unwrap()
+            .is_err()
+    );
+
+    let blank = b"@a\nAC\n+\nII\n\n@b\nAC\n+\nII\n";
+    assert_eq!(
+        FastqReader::from_bytes(blank, ReaderOptions::cd_hit()).count(),
+        2
+    );
+    let mut fq = FastqReader::from_bytes(blank, ReaderOptions::strict());
+    assert!(fq.next().unwrap().is_ok());
+    assert!(fq.next().unwrap().is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn reader_options_load_from_json() {
+    use kira_cdh_compat_fastq_reader::{AllowedBytes, Throttle};
+
+    let opts: ReaderOptions = serde_json::from_str(
+        r#"{
+            "error_policy": { "collect": { "limit": 10 } },
+            "line_mode": "multi",
+            "quality_encoding": "phred64",
+            "throttle": { "bytes_per_sec": 1000000 },
+            "allowed_seq_bytes": "ACGTN",
+            "max_record_bytes": 65536
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(opts.error_policy, ErrorPolicy::Collect { limit: 10 });
+    assert_eq!(opts.line_mode, LineMode::Multi);
+    assert_eq!(opts.quality_encoding, QualityEncoding::Phred64);
+    assert_eq!(opts.throttle, Some(Throttle::BytesPerSec(1_000_000)));
+    assert_eq!(
+        opts.allowed_seq_bytes,
+        Some(AllowedBytes::from_set(b"ACGTN"))
+    );
+    assert_eq!(opts.max_record_bytes, Some(65536));
+    // the rest keeps its defaults
+    assert!(opts.fastq_only);
+
+    let json = serde_json::to_string(&ReaderOptions::cd_hit_est()).unwrap();
+    let back: ReaderOptions = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        back.allowed_seq_bytes,
+        ReaderOptions::cd_hit_est().allowed_seq_bytes
+    );
+    assert_eq!(back.error_policy, ErrorPolicy::Skip);
+
+    assert!(serde_json::from_str::<ReaderOptions>(r#"{"line_mod": "multi"}"#).is_err());
+    assert!(serde_json::from_str::<ReaderOptions>(r#"{"allowed_seq_bytes": "Aé€"}"#).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn fastq_record_serde_text_and_base64() {
+    use kira_cdh_compat_fastq_reader::FastqRecord;
+
+    let rec = FastqRecord::new("r1", Some("lane=2"), b"ACGTN", b"II#II");
+    let json = serde_json::to_string(&rec).unwrap();
+    assert_eq!(
+        json,
+        r#"{"id":"r1","desc":"lane=2","seq":"ACGTN","qual":"II#II"}"#
+    );
+    assert_eq!(serde_json::from_str::<FastqRecord>(&json).unwrap(), rec);
+    let bare: FastqRecord =
+        serde_json::from_str(r#"{"id":"r2","seq":"AC","qual":"II","plus_line":"r2"}"#).unwrap();
+    assert_eq!((bare.desc(), bare.plus_line()), (None, Some("r2")));
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Message {
+        #[serde(with = "kira_cdh_compat_fastq_reader::record_serde::base64")]
+        record: FastqRecord,
+    }
+    for seq in [&b""[..], b"A", b"AC", b"ACG", b"ACGT\xff"] {
+        let qual = vec![b'I'; seq.len()];
+        let msg = Message {
+            record: FastqRecord::new("b", None, seq, &qual),
+        };
+        let json = serde_json::to_string(&msg).unwrap();
+        let back: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.record, msg.record);
+    }
+    let json = serde_json::to_string(&Message { record: rec }).unwrap();
+    assert!(json.contains(r#""seq":"QUNHVE4=""#), "{json}");
+
+    let binary = FastqRecord::new("x", None, b"\xff", b"I");
+    assert!(serde_json::to_string(&binary).is_err());
+    let bad = r#"{"record":{"id":"b","seq":"QU=H","qual":""}}"#;
+    assert!(serde_json::from_str::<Message>(bad).is_err());
+}
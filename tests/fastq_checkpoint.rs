@@ -0,0 +1,122 @@
+use kira_cdh_compat_fastq_reader::{FastqReader, ReaderOptions};
+use std::io::Write;
+
+const SAMPLE: &str = "@r1\nACGT\n+\n!!!!\n@r2\nGG\n+\n##\nbroken\n@r3\nTTT\n+\n$$$\n@r4\nA\n+\n%\n";
+
+fn ids(rdr: FastqReader) -> Vec<String> {
+    rdr.map(|r| r.unwrap().id().to_string()).collect()
+}
+
+#[test]
+fn resume_plain_file_from_state() {
+    let mut f = tempfile::Builder::new().suffix(".fq").tempfile().unwrap();
+    f.write_all(SAMPLE.as_bytes()).unwrap();
+
+    let mut rdr = FastqReader::from_path(f.path(), ReaderOptions::default()).unwrap();
+    rdr.next().unwrap().unwrap();
+    rdr.next().unwrap().unwrap();
+    let state = rdr.state().unwrap();
+    assert_eq!(state.records, 2);
+    assert_eq!(state.byte_offset, 28);
+
+    let resumed = FastqReader::resume(&state, ReaderOptions::default()).unwrap();
+    assert_eq!(ids(resumed), ["r3", "r4"]);
+    assert_eq!(ids(rdr), ["r3", "r4"]);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn resume_gzip_file_after_skip() {
+    let f = tempfile::Builder::new()
+        .suffix(".fq.gz")
+        .tempfile()
+        .unwrap();
+    let mut enc = flate2::write::GzEncoder::new(f.reopen().unwrap(), flate2::Compression::fast());
+    enc.write_all(SAMPLE.as_bytes()).unwrap();
+    enc.finish().unwrap();
+
+    let mut rdr = FastqReader::from_path(f.path(), ReaderOptions::default()).unwrap();
+    for _ in 0..3 {
+        rdr.next().unwrap().unwrap();
+    }
+    let state = rdr.state().unwrap();
+    assert_eq!((state.records, state.skipped), (3, 1));
+
+    let mut resumed = FastqReader::resume(&state, ReaderOptions::default()).unwrap();
+    assert_eq!(resumed.next().unwrap().unwrap().id(), "r4");
+    let after = resumed.state().unwrap();
+    assert_eq!((after.records, after.line_num), (4, 17));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn state_round_trips_through_json() {
+    let mut f = tempfile::Builder::new().suffix(".fq").tempfile().unwrap();
+    f.write_all(SAMPLE.as_bytes()).unwrap();
+    let mut rdr = FastqReader::from_path(f.path(), ReaderOptions::default()).unwrap();
+    rdr.next().unwrap().unwrap();
+
+    let json = serde_json::to_string(&rdr.state().unwrap()).unwrap();
+    let state = serde_json::from_str(&json).unwrap();
+    let resumed = FastqReader::resume(&state, ReaderOptions::default()).unwrap();
+    assert_eq!(ids(resumed), ["r2", "r3", "r4"]);
+}
+
+#[test]
+fn resume_at_record_span_offset() {
+    let mut f = tempfile::Builder::new().suffix(".fq").tempfile().unwrap();
+    f.write_all(SAMPLE.as_bytes()).unwrap();
+
+    let mut rdr = FastqReader::from_path(f.path(), ReaderOptions::default()).unwrap();
+    rdr.next().unwrap().unwrap();
+    rdr.next().unwrap().unwrap();
+    let r3 = rdr.next().unwrap().unwrap();
+    let span = rdr.last_record_span().unwrap();
+    assert_eq!(r3.id(), "r3");
+
+    let resumed = FastqReader::resume_at(f.path(), span.byte_offset, ReaderOptions::default());
+    assert_eq!(ids(resumed.unwrap()), ["r3", "r4"]);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn resume_at_seeks_bgzf_blocks() {
+    use kira_cdh_compat_fastq_reader::{Compression, FastqWriter, WriterOptions};
+
+    let mut text = Vec::new();
+    for i in 0..4000 {
+        write!(text, "@read{i}\nACGTACGTAC\n+\nIIIIIIIIII\n").unwrap();
+    }
+    let opts = WriterOptions {
+        compression: Some(Compression::Bgzf),
+        ..Default::default()
+    };
+    let f = tempfile::Builder::new()
+        .suffix(".fq.gz")
+        .tempfile()
+        .unwrap();
+    let mut w = FastqWriter::from_writer(f.reopen().unwrap(), opts).unwrap();
+    for rec in FastqReader::from_bytes(&text, ReaderOptions::default()) {
+        w.write_record(&rec.unwrap()).unwrap();
+    }
+    w.finish().unwrap();
+
+    let mut rdr = FastqReader::from_path(f.path(), ReaderOptions::default()).unwrap();
+    let mut spans = Vec::new();
+    while let Some(rec) = rdr.next() {
+        rec.unwrap();
+        spans.push(rdr.last_record_span().unwrap());
+    }
+    for i in [0, 1, 2999, 3999] {
+        let mut resumed =
+            FastqReader::resume_at(f.path(), spans[i].byte_offset, ReaderOptions::default())
+                .unwrap();
+        assert_eq!(resumed.next().unwrap().unwrap().id(), format!("read{i}"));
+        assert_eq!(resumed.count(), 3999 - i);
+    }
+    // the end of the input resumes to nothing
+    let end = spans[3999].byte_offset + spans[3999].byte_len;
+    let resumed = FastqReader::resume_at(f.path(), end, ReaderOptions::default()).unwrap();
+    assert_eq!(resumed.count(), 0);
+    assert!(FastqReader::resume_at(f.path(), end + 1, ReaderOptions::default()).is_err());
+}
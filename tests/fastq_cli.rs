@@ -0,0 +1,113 @@
+#[cfg(feature = "cli")]
+mod t {
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    #[test]
+    fn cli_stats_and_validate() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.fastq");
+        std::fs::write(&path, "@a\nACGT\n+\nIIII\n@b\nAC\n+\nI\n").unwrap();
+        let bin = env!("CARGO_BIN_EXE_kira-fastq");
+
+        let out = Command::new(bin).arg("stats").arg(&path).output().unwrap();
+        assert!(out.status.success());
+        let text = String::from_utf8(out.stdout).unwrap();
+        assert!(text.contains("records\t1\n"), "{text}");
+
+        let out = Command::new(bin)
+            .arg("validate")
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(!out.status.success());
+    }
+
+    #[test]
+    fn cli_reads_gzip_from_stdin() {
+        use std::io::Write;
+        use std::process::Stdio;
+
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        enc.write_all(b"@a\nACGT\n+\nIIII\n@b\nAC\n+\nII\n")
+            .unwrap();
+        let gz = enc.finish().unwrap();
+        let bin = env!("CARGO_BIN_EXE_kira-fastq");
+        let mut child = Command::new(bin)
+            .args(["stats", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(&gz).unwrap();
+        let out = child.wait_with_output().unwrap();
+        assert!(out.status.success());
+        let text = String::from_utf8(out.stdout).unwrap();
+        assert!(text.contains("records\t2\n"), "{text}");
+
+        #[cfg(not(feature = "zstd"))]
+        {
+            let mut child = Command::new(bin)
+                .args(["stats", "-"])
+                .stdin(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let zstd_frame = [0x28, 0xB5, 0x2F, 0xFD, 0, 0];
+            child.stdin.take().unwrap().write_all(&zstd_frame).unwrap();
+            let out = child.wait_with_output().unwrap();
+            assert!(!out.status.success());
+            assert!(String::from_utf8_lossy(&out.stderr).contains("zstd"));
+        }
+    }
+
+    #[test]
+    fn cli_convert_head_and_inspect() {
+        use kira_cdh_compat_fastq_reader::{FastqReader, ReaderOptions};
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.fastq");
+        std::fs::write(&path, "@a x\nACGT\n+a x\nIIII\n@b\nAC\n+\nII\n").unwrap();
+        let bin = env!("CARGO_BIN_EXE_kira-fastq");
+
+        // the gzip trailer is written: the output reads back in full
+        let gz = dir.path().join("out.fq.gz");
+        let out = Command::new(bin)
+            .arg("convert")
+            .args([&path, &gz])
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{out:?}");
+        let ids: Vec<_> = FastqReader::from_path(&gz, ReaderOptions::default())
+            .unwrap()
+            .map(|r| r.unwrap().id().to_owned())
+            .collect();
+        assert_eq!(ids, ["a", "b"]);
+
+        let out = Command::new(bin)
+            .args(["convert", "--to", "fasta"])
+            .arg(&path)
+            .arg("-")
+            .output()
+            .unwrap();
+        assert_eq!(out.stdout, b">a x\nACGT\n>b\nAC\n");
+
+        let out = Command::new(bin)
+            .args(["head", "-n", "1"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert_eq!(out.stdout, b"@a x\nACGT\n+a x\nIIII\n");
+
+        // zstd input is named as such, whatever the extension
+        let zst = dir.path().join("b.dat");
+        std::fs::write(&zst, [0x28, 0xB5, 0x2F, 0xFD, 0, 0]).unwrap();
+        let out = Command::new(bin)
+            .args(["inspect", "-n", "0"])
+            .arg(&zst)
+            .output()
+            .unwrap();
+        let text = String::from_utf8(out.stdout).unwrap();
+        assert!(text.contains("compression: zstd"), "{text}");
+    }
+}
@@ -0,0 +1,66 @@
+#[cfg(feature = "codec")]
+mod t {
+    use futures_util::StreamExt;
+    use kira_cdh_compat_fastq_reader::{
+        ErrorPolicy, FastqCodec, FastqError, FastqReader, LimitError, ReaderOptions,
+    };
+    use tokio::io::AsyncWriteExt;
+    use tokio_util::codec::FramedRead;
+
+    const DATA: &[u8] = b"@r1\nACGT\n+\nIIII\n@bad\nAC\n+\nI\n@r2\r\nGG\r\n+\r\nII\r\n@r3\nT\n+\n#";
+
+    #[tokio::test]
+    async fn framed_read_matches_reader() {
+        for error_policy in [ErrorPolicy::Skip, ErrorPolicy::Return] {
+            let opts = ReaderOptions {
+                error_policy,
+                ..Default::default()
+            };
+            let expected: Vec<_> = FastqReader::from_bytes(DATA, opts.clone())
+                .map(|r| r.map(|r| r.id().to_string()).ok())
+                .collect();
+
+            // deliver the bytes in small pieces, as a socket would
+            let (mut tx, rx) = tokio::io::duplex(7);
+            tokio::spawn(async move {
+                for chunk in DATA.chunks(5) {
+                    tx.write_all(chunk).await.unwrap();
+                }
+            });
+            let mut framed = FramedRead::new(rx, FastqCodec::new(opts));
+            let mut got = Vec::new();
+            while let Some(r) = framed.next().await {
+                let failed = r.is_err();
+                got.push(r.map(|r| r.id().to_string()).ok());
+                if failed {
+                    break;
+                }
+            }
+            assert_eq!(got, expected[..got.len()]);
+            if error_policy == ErrorPolicy::Skip {
+                assert_eq!(got.len(), 3);
+                assert_eq!(framed.decoder().skipped(), 1);
+            } else {
+                assert_eq!(got, [Some("r1".to_string()), None]);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn record_limit_applies_to_unterminated_lines() {
+        let opts = ReaderOptions {
+            max_record_bytes: Some(16),
+            ..Default::default()
+        };
+        let data = [b"@r1\n".as_slice(), &[b'A'; 64]].concat();
+        let mut framed = FramedRead::new(data.as_slice(), FastqCodec::new(opts));
+        let err = framed.next().await.unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            FastqError::LimitExceeded {
+                source: LimitError::RecordTooLarge { limit: 16 },
+                ..
+            }
+        ));
+    }
+}
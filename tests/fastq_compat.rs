@@ -0,0 +1,132 @@
+use kira_cdh_compat_fastq_reader::compat::Record;
+use kira_cdh_compat_fastq_reader::{FastqReader, ReaderOptions, RefRecord};
+use std::io::BufReader;
+
+fn head_of<R: Record>(rec: &R) -> Vec<u8> {
+    rec.head().into_owned()
+}
+
+#[test]
+fn seq_io_style_accessors_on_owned_and_borrowed() {
+    let data = "@r1 lane=1\nACGT\n+\nIIII\n";
+    let mut fq =
+        FastqReader::from_bufread(BufReader::new(data.as_bytes()), ReaderOptions::default());
+    let rec = fq.next().unwrap().unwrap();
+
+    assert_eq!(Record::id(&rec).unwrap(), "r1");
+    assert_eq!(Record::desc(&rec).unwrap().unwrap(), "lane=1");
+    assert_eq!(Record::seq(&rec), b"ACGT");
+    assert_eq!(Record::qual(&rec), b"IIII");
+    assert_eq!(head_of(&rec), b"r1 lane=1");
+
+    let view: RefRecord<'_> = rec.as_ref_record();
+    assert_eq!(Record::id(&view).unwrap(), "r1");
+    assert_eq!(head_of(&view), b"r1 lane=1");
+    assert_eq!(view.to_owned_record(), rec);
+}
+
+#[test]
+fn needletail_style_sequence_record() {
+    use kira_cdh_compat_fastq_reader::compat::SequenceRecord;
+
+    let data = "@r1 x\nacgtUN\n+\nIIIIII\n";
+    let mut fq =
+        FastqReader::from_bufread(BufReader::new(data.as_bytes()), ReaderOptions::default());
+    let rec = fq.next().unwrap().unwrap();
+    let sr = SequenceRecord::from(&rec);
+
+    assert_eq!(&*sr.id(), b"r1 x");
+    assert_eq!(sr.num_bases(), 6);
+    assert_eq!(&*sr.normalize(false), b"ACGTTN");
+    assert_eq!(sr.kmers(4).count(), 3);
+
+    let norm = sr.normalize(false).into_owned();
+    let rec2 =
+        kira_cdh_compat_fastq_reader::FastqRecord::new(rec.id(), rec.desc(), &norm, rec.qual());
+    let sr2 = SequenceRecord::from(&rec2);
+    let rc = sr2.reverse_complement();
+    assert_eq!(rc, b"NAACGT");
+    let canon: Vec<_> = sr2.canonical_kmers(3, &rc).collect();
+    // ACG/CGT are each other's reverse complements; GTT->AAC; TTN skipped
+    assert_eq!(
+        canon,
+        vec![
+            (0, &b"ACG"[..], false),
+            (1, &b"ACG"[..], true),
+            (2, &b"AAC"[..], true)
+        ]
+    );
+}
+
+#[cfg(feature = "bio")]
+#[test]
+fn bio_record_conversions_round_trip() {
+    use kira_cdh_compat_fastq_reader::FastqRecord;
+
+    let rec = FastqRecord::new("r1", Some("lane=1"), b"ACGT", b"II#I");
+    let bio_rec = bio::io::fastq::Record::from(&rec);
+    assert_eq!(
+        (bio_rec.id(), bio_rec.desc(), bio_rec.seq(), bio_rec.qual()),
+        ("r1", Some("lane=1"), &b"ACGT"[..], &b"II#I"[..])
+    );
+    assert!(bio_rec.check().is_ok());
+    assert_eq!(FastqRecord::from(bio_rec), rec);
+
+    let bare = FastqRecord::new("r2", None, b"", b"");
+    let back: FastqRecord = bio::io::fastq::Record::from(bare.clone()).into();
+    assert_eq!(back, bare);
+}
+
+#[cfg(feature = "noodles")]
+#[test]
+fn noodles_record_conversions_round_trip() {
+    use kira_cdh_compat_fastq_reader::FastqRecord;
+
+    let rec = FastqRecord::new("r1", Some("lane=1"), b"ACGT", b"II#I");
+    let nd = noodles_fastq::Record::from(&rec);
+    assert_eq!(nd.name(), "r1");
+    assert_eq!(nd.description(), "lane=1");
+    assert_eq!(
+        (nd.sequence(), nd.quality_scores()),
+        (&b"ACGT"[..], &b"II#I"[..])
+    );
+    assert_eq!(FastqRecord::from(nd), rec);
+
+    let bare = FastqRecord::new("r2", None, b"", b"");
+    let back: FastqRecord = noodles_fastq::Record::from(bare.clone()).into();
+    assert_eq!(back, bare);
+
+    // records read by noodles take this crate's types from there on
+    let mut nd_reader = noodles_fastq::io::Reader::new(&b"@r3 x\nAC\n+\nII\n"[..]);
+    let recs: Vec<FastqRecord> = nd_reader.records().map(|r| r.unwrap().into()).collect();
+    assert_eq!(recs, [FastqRecord::new("r3", Some("x"), b"AC", b"II")]);
+}
+
+#[cfg(feature = "seq_io")]
+#[test]
+fn seq_io_owned_record_conversions_round_trip() {
+    use kira_cdh_compat_fastq_reader::FastqRecord;
+    use seq_io::fastq::OwnedRecord;
+
+    let rec = FastqRecord::new("r1", Some("lane=1 x"), b"ACGT", b"II#I");
+    let owned = OwnedRecord::from(&rec);
+    assert_eq!(
+        (&owned.head[..], &owned.seq[..], &owned.qual[..]),
+        (&b"r1 lane=1 x"[..], &b"ACGT"[..], &b"II#I"[..])
+    );
+    assert_eq!(FastqRecord::try_from(owned).unwrap(), rec);
+
+    let bare = FastqRecord::new("r2", None, b"", b"");
+    assert_eq!(OwnedRecord::from(bare.clone()).head, b"r2");
+    assert_eq!(
+        FastqRecord::try_from(OwnedRecord::from(&bare)).unwrap(),
+        bare
+    );
+
+    let bad = OwnedRecord {
+        head: b"r\xff".to_vec(),
+        seq: b"A".to_vec(),
+        qual: b"I".to_vec(),
+    };
+    assert!(FastqRecord::try_from(&bad).is_err());
+}
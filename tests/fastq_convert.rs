@@ -0,0 +1,101 @@
+#[cfg(feature = "gzip")]
+mod t {
+    use kira_cdh_compat_fastq_reader::{
+        Compression, ConvertOptions, FastqReader, OutputFormat, ReaderOptions, convert,
+    };
+    use tempfile::tempdir;
+
+    fn sample(n: usize) -> String {
+        (0..n)
+            .map(|i| format!("@r{i} d\n{}\n+\n{}\n", "ACGT".repeat(20), "I".repeat(80)))
+            .collect()
+    }
+
+    #[test]
+    fn parallel_bgzf_and_gzip_round_trip() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("in.fastq");
+        std::fs::write(&input, sample(5000)).unwrap();
+
+        for (name, compression) in [
+            ("out.bgz", Compression::Bgzf),
+            ("out.fq.gz", Compression::Gzip),
+        ] {
+            let out = dir.path().join(name);
+            let opts = ConvertOptions {
+                threads: 4,
+                ..Default::default()
+            };
+            let stats = convert(&input, &out, &opts).unwrap();
+            assert_eq!(stats.records, 5000);
+
+            let bytes = std::fs::read(&out).unwrap();
+            if compression == Compression::Bgzf {
+                assert_eq!(&bytes[12..14], b"BC");
+                assert_eq!(bytes[bytes.len() - 28..][..4], [0x1F, 0x8B, 0x08, 0x04]);
+            }
+            let back: Vec<_> = FastqReader::from_path(&out, ReaderOptions::default())
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect();
+            assert_eq!(back.len(), 5000);
+            assert_eq!(back[4999].id(), "r4999");
+            assert_eq!(back[4999].desc(), Some("d"));
+        }
+    }
+
+    #[test]
+    fn fasta_and_zstd_output() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("in.fastq");
+        std::fs::write(&input, sample(2)).unwrap();
+
+        let out = dir.path().join("out.fa");
+        let opts = ConvertOptions {
+            format: OutputFormat::Fasta,
+            ..Default::default()
+        };
+        convert(&input, &out, &opts).unwrap();
+        let text = std::fs::read_to_string(&out).unwrap();
+        assert!(text.starts_with(">r0 d\nACGT"));
+        assert_eq!(text.lines().count(), 4);
+
+        let zst = dir.path().join("out.fq.zst");
+        let res = convert(&input, &zst, &ConvertOptions::default());
+        if cfg!(feature = "zstd") {
+            assert_eq!(res.unwrap().records, 2);
+            let back = FastqReader::from_path(&zst, ReaderOptions::default()).unwrap();
+            assert_eq!(back.count(), 2);
+        } else {
+            assert!(res.is_err());
+            assert!(!zst.exists());
+        }
+    }
+
+    #[test]
+    fn plus_line_content_survives_rewrite() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("in.fastq");
+        std::fs::write(&input, "@r0\nACGT\n+r0 run=7\nIIII\n@r1\nGG\n+\nII\n").unwrap();
+
+        let out = dir.path().join("out.fastq");
+        let opts = ConvertOptions {
+            reader: ReaderOptions {
+                keep_plus_line: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        convert(&input, &out, &opts).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            std::fs::read_to_string(&input).unwrap()
+        );
+
+        let recs: Vec<_> = FastqReader::from_path(&out, ReaderOptions::default())
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(recs[0].plus_line(), None);
+    }
+}
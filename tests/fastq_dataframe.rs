@@ -0,0 +1,35 @@
+#[cfg(feature = "polars")]
+mod t {
+    use kira_cdh_compat_fastq_reader::{ReaderOptions, read_to_dataframe};
+    use polars::prelude::DataType;
+    use tempfile::tempdir;
+
+    #[test]
+    fn dataframe_has_one_row_per_record_up_to_the_limit() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("in.fq");
+        std::fs::write(
+            &path,
+            "@r1 lane=1\nACGT\n+\nIIII\n@bad\nAC\n+\nI\n@r2\nGG\n+\n!!\n@r3\nT\n+\n5\n",
+        )
+        .unwrap();
+
+        let df = read_to_dataframe(&path, ReaderOptions::default(), None).unwrap();
+        assert_eq!(
+            df.get_column_names(),
+            ["id", "desc", "len", "mean_q", "seq"]
+        );
+        assert_eq!(df.height(), 3);
+        let col = |name: &str| df.column(name).unwrap();
+        assert_eq!(col("len").dtype(), &DataType::UInt64);
+        assert_eq!(col("id").str().unwrap().get(1), Some("r2"));
+        assert_eq!(col("desc").str().unwrap().get(0), Some("lane=1"));
+        assert_eq!(col("desc").str().unwrap().get(1), None);
+        assert_eq!(col("len").u64().unwrap().get(0), Some(4));
+        assert_eq!(col("mean_q").f64().unwrap().get(0), Some(40.0));
+        assert_eq!(col("seq").str().unwrap().get(2), Some("T"));
+
+        let head = read_to_dataframe(&path, ReaderOptions::default(), Some(2)).unwrap();
+        assert_eq!(head.height(), 2);
+    }
+}
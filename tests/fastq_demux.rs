@@ -0,0 +1,289 @@
+use kira_cdh_compat_fastq_reader::{
+    BarcodeSource, Demultiplexer, DemuxOptions, FastqError, FastqReader, FastqWriter, FormatError,
+    ReaderOptions, WriterOptions,
+};
+
+fn out() -> FastqWriter<Vec<u8>> {
+    FastqWriter::from_writer(Vec::new(), WriterOptions::default()).unwrap()
+}
+
+const DATA: &str = "\
+@r1 1:N:0:ACGTAC
+AAAA
++
+IIII
+@r2 1:N:0:TTGGCA
+CCCC
++
+IIII
+@r3 1:N:0:ACGTAA
+GGGG
++
+IIII
+@r4 1:N:0:NNNNNN
+TTTT
++
+IIII
+";
+
+#[test]
+fn routes_records_by_header_barcode() {
+    let opts = DemuxOptions {
+        barcode: BarcodeSource::Header,
+        max_mismatches: 1,
+    };
+    let mut demux = Demultiplexer::new(opts, Some(out()));
+    demux.add_sample("s1", b"acgtac", out()).unwrap();
+    demux.add_sample("s2", b"TTGGCA", out()).unwrap();
+    assert!(demux.add_sample("s3", b"ACGTAC", out()).is_err());
+
+    demux
+        .run(FastqReader::from_bytes(
+            DATA.as_bytes(),
+            ReaderOptions::default(),
+        ))
+        .unwrap();
+    let report = demux.finish().unwrap();
+    let counts: Vec<_> = report
+        .samples
+        .iter()
+        .map(|s| (s.name.as_str(), s.barcode.as_str(), s.records))
+        .collect();
+    assert_eq!(counts, [("s1", "ACGTAC", 2), ("s2", "TTGGCA", 1)]);
+    assert_eq!(report.undetermined, 1);
+}
+
+#[test]
+fn ties_and_distant_barcodes_are_undetermined() {
+    let exact = DemuxOptions::default();
+    let mut demux = Demultiplexer::new(exact, None);
+    demux.add_sample("s1", b"ACGTAC", out()).unwrap();
+    let fq = FastqReader::from_bytes(DATA.as_bytes(), ReaderOptions::default());
+    let routed: Vec<_> = fq
+        .map(|r| demux.write_record(&r.unwrap()).unwrap())
+        .collect();
+    assert_eq!(routed, [Some(0), None, None, None]);
+
+    // ACGTAA is one substitution from both samples
+    let opts = DemuxOptions {
+        max_mismatches: 1,
+        ..exact
+    };
+    let mut demux = Demultiplexer::new(opts, None);
+    demux.add_sample("s1", b"ACGTAC", out()).unwrap();
+    demux.add_sample("s2", b"ACGTAG", out()).unwrap();
+    let rec = FastqReader::from_bytes(DATA.as_bytes(), ReaderOptions::default())
+        .nth(2)
+        .unwrap()
+        .unwrap();
+    assert_eq!(demux.assign(&rec), None);
+}
+
+#[test]
+fn read_start_barcodes_are_trimmed() {
+    let data = b"@r1\nACGTTTTT\n+\nABCDEFGH\n@r2\nGGGGAAAA\n+\nIIIIIIII\n";
+    let opts = DemuxOptions {
+        barcode: BarcodeSource::ReadStart { trim: true },
+        max_mismatches: 0,
+    };
+    let mut demux = Demultiplexer::new(opts, Some(out()));
+    demux.add_sample("s1", b"ACGT", out()).unwrap();
+    demux
+        .run(FastqReader::from_bytes(data, ReaderOptions::default()))
+        .unwrap();
+    assert_eq!(demux.report().samples[0].records, 1);
+    assert_eq!(demux.report().undetermined, 1);
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut demux =
+        Demultiplexer::create_in(dir.path(), [("s1", "ACGT"), ("s2", "GGGG")], opts).unwrap();
+    demux
+        .run(FastqReader::from_bytes(data, ReaderOptions::default()))
+        .unwrap();
+    demux.finish().unwrap();
+    let s1: Vec<_> =
+        FastqReader::from_path(dir.path().join("s1.fastq.gz"), ReaderOptions::default())
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+    assert_eq!(s1.len(), 1);
+    assert_eq!(s1[0].seq(), b"TTTT");
+    assert_eq!(s1[0].qual(), b"EFGH");
+    assert!(dir.path().join("Undetermined.fastq.gz").exists());
+}
+
+#[test]
+fn read_start_trim_with_a_quality_shorter_than_the_barcode() {
+    use kira_cdh_compat_fastq_reader::FastqRecord;
+
+    let opts = DemuxOptions {
+        barcode: BarcodeSource::ReadStart { trim: true },
+        max_mismatches: 0,
+    };
+    let mut demux = Demultiplexer::new(opts, None);
+    demux.add_sample("s1", b"ACGT", out()).unwrap();
+    // the trimmed record still lacks a full quality, so the writer refuses it
+    for qual in [&b""[..], b"II"] {
+        let rec = FastqRecord::new("r1", None, b"ACGTTT", qual);
+        let err = demux.write_record(&rec).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+    let rec = FastqRecord::new("r2", None, b"ACGTTT", b"IIIIII");
+    assert_eq!(demux.write_record(&rec).unwrap(), Some(0));
+    let report = demux.finish().unwrap();
+    assert_eq!(report.samples[0].records, 1);
+}
+
+fn fq(records: &[(&str, &str)]) -> FastqReader {
+    let text: String = records
+        .iter()
+        .map(|(id, seq)| format!("@{id}\n{seq}\n+\n{}\n", "I".repeat(seq.len())))
+        .collect();
+    FastqReader::from_vec(text.into_bytes(), ReaderOptions::default())
+}
+
+#[test]
+fn dual_index_reads_in_lockstep_with_correction() {
+    let r1 = fq(&[
+        ("a/1", "AAAA"),
+        ("b/1", "CCCC"),
+        ("c/1", "GGGG"),
+        ("d/1", "TTTT"),
+    ]);
+    let r2 = fq(&[
+        ("a/2", "TTTT"),
+        ("b/2", "GGGG"),
+        ("c/2", "CCCC"),
+        ("d/2", "AAAA"),
+    ]);
+    let i1 = fq(&[("a", "ACGT"), ("b", "ACGA"), ("c", "TTTT"), ("d", "ACGT")]);
+    let i2 = fq(&[("a", "GGCC"), ("b", "GGCC"), ("c", "GGCC"), ("d", "GGAA")]);
+    let opts = DemuxOptions {
+        max_mismatches: 1,
+        ..Default::default()
+    };
+    let mut demux = Demultiplexer::new(opts, Some(out())).with_undetermined_r2(out());
+    demux
+        .add_paired_sample("s1", b"ACGT+GGCC", out(), out())
+        .unwrap();
+    demux.run_indexed(r1, Some(r2), i1, Some(i2)).unwrap();
+    let report = demux.finish().unwrap();
+    // a exact, b one mismatch in i7; c and d (two in i5) undetermined
+    assert_eq!(report.samples[0].records, 2);
+    assert_eq!(report.samples[0].corrected, 1);
+    assert_eq!((report.exact(), report.corrected()), (1, 1));
+    assert_eq!(report.undetermined, 2);
+
+    // a single I1 file; records must line up
+    let mut demux = Demultiplexer::new(DemuxOptions::default(), None);
+    demux.add_sample("s1", b"ACGT", out()).unwrap();
+    let err = demux
+        .run_indexed(
+            fq(&[("a", "AA"), ("b", "CC")]),
+            None,
+            fq(&[("a", "ACGT"), ("x", "ACGT")]),
+            None,
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        FastqError::Format {
+            source: FormatError::PairDesync(_),
+            ..
+        }
+    ));
+    let err = demux
+        .run_indexed(
+            fq(&[("a", "AA")]),
+            None,
+            fq(&[("a", "ACGT"), ("b", "ACGT")]),
+            None,
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        FastqError::Format {
+            source: FormatError::UnexpectedEof,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn sample_sheets_configure_the_demultiplexer() {
+    use kira_cdh_compat_fastq_reader::{SampleSheet, SampleSheetError};
+
+    let csv = "\
+[Header],,
+IEMFileVersion,4,
+[Reads],,
+151,,
+[Data],,,,
+Lane,Sample_ID,Sample_Name,index,index2
+1,S1,alpha,ACGTAC,TTGGCA
+1,S2,,GGGGGG,AAAAAA
+2,S3,gamma,ACGTAC,TTGGCA
+,,,,
+";
+    let sheet = SampleSheet::parse(csv).unwrap();
+    assert_eq!(sheet.samples.len(), 3);
+    let s2 = &sheet.samples[1];
+    assert_eq!((s2.id.as_str(), s2.name.as_str()), ("S2", "S2"));
+    assert_eq!(s2.lane, Some(1));
+    assert_eq!(sheet.samples[0].barcode(), "ACGTAC+TTGGCA");
+    let lane2: Vec<_> = sheet.lane(2).map(|s| s.name.as_str()).collect();
+    assert_eq!(lane2, ["gamma"]);
+
+    let tsv = "# name\tindex\nalpha\tACGTAC\n\nbeta\tGGGGGG\tAAAAAA\n";
+    let sheet = SampleSheet::parse(tsv).unwrap();
+    assert_eq!(sheet.samples[0].index2, None);
+    assert_eq!(sheet.samples[1].barcode(), "GGGGGG+AAAAAA");
+    assert!(matches!(
+        SampleSheet::parse("alpha ACGT\n"),
+        Err(SampleSheetError::Invalid { line: 1, .. })
+    ));
+    assert!(matches!(
+        SampleSheet::parse("[Data]\nSample_ID,Lane\nS1,1\n"),
+        Err(SampleSheetError::MissingColumn("index"))
+    ));
+
+    // lane 1 of the CSV sheet, routed by CASAVA headers
+    let dir = tempfile::tempdir().unwrap();
+    let sheet = SampleSheet::parse(csv).unwrap();
+    let mut demux =
+        Demultiplexer::from_sample_sheet(dir.path(), &sheet, Some(1), DemuxOptions::default())
+            .unwrap();
+    let data = b"@r1 1:N:0:ACGTAC+TTGGCA\nA\n+\nI\n@r2 1:N:0:GGGGGG+AAAAAA\nC\n+\nI\n";
+    demux
+        .run(FastqReader::from_bytes(data, ReaderOptions::default()))
+        .unwrap();
+    let report = demux.finish().unwrap();
+    let counts: Vec<_> = report
+        .samples
+        .iter()
+        .map(|s| (s.name.as_str(), s.records))
+        .collect();
+    assert_eq!(counts, [("alpha", 1), ("S2", 1)]);
+    assert!(dir.path().join("alpha.fastq.gz").exists());
+
+    // without a lane, a sample listed once per lane is one sample
+    let dir = tempfile::tempdir().unwrap();
+    let sheet = SampleSheet::parse("[Data]\nLane,Sample_ID,index\n1,S1,ACGT\n2,S1,ACGT\n").unwrap();
+    let demux = Demultiplexer::from_sample_sheet(dir.path(), &sheet, None, DemuxOptions::default())
+        .unwrap();
+    let report = demux.finish().unwrap();
+    assert_eq!(report.samples.len(), 1);
+    let sheet = SampleSheet::parse("[Data]\nLane,Sample_ID,index\n1,S1,ACGT\n2,S1,TTTT\n").unwrap();
+    let err = Demultiplexer::from_sample_sheet(dir.path(), &sheet, None, DemuxOptions::default())
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    let err = Demultiplexer::create_in(
+        dir.path(),
+        [("S1", "ACGT"), ("S1", "TTTT")],
+        DemuxOptions::default(),
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
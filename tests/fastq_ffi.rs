@@ -0,0 +1,60 @@
+#[cfg(feature = "ffi")]
+mod t {
+    use kira_cdh_compat_fastq_reader::ffi::*;
+    use std::ffi::{CStr, CString};
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn ffi_reads_records_and_reports_errors() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.fastq");
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(b"@r1 d\nACGT\n+\n!!!!\n@r2\nAC\n+\n!\n")
+            .unwrap();
+        let cpath = CString::new(path.to_str().unwrap()).unwrap();
+
+        unsafe {
+            let r = fq_open(cpath.as_ptr(), 1, 0);
+            assert!(!r.is_null());
+            let mut rec = std::mem::MaybeUninit::<FqRecord>::uninit();
+            assert_eq!(fq_next(r, rec.as_mut_ptr()), 1);
+            let rec = rec.assume_init();
+            assert_eq!(std::slice::from_raw_parts(rec.id, rec.id_len), b"r1");
+            assert_eq!(std::slice::from_raw_parts(rec.desc, rec.desc_len), b"d");
+            assert_eq!(std::slice::from_raw_parts(rec.seq, rec.seq_len), b"ACGT");
+
+            let mut rec = std::mem::MaybeUninit::<FqRecord>::uninit();
+            assert_eq!(fq_next(r, rec.as_mut_ptr()), -1);
+            assert_eq!(fq_error_code(r), FQ_ERR_LENGTH_MISMATCH);
+            assert_eq!(
+                CStr::from_ptr(fq_strerror(fq_error_code(r))),
+                c"quality length does not match sequence length"
+            );
+            fq_close(r);
+
+            let missing = CString::new("/nonexistent/x.fastq").unwrap();
+            assert!(fq_open(missing.as_ptr(), 0, 0).is_null());
+            assert_eq!(fq_error_code(std::ptr::null()), FQ_ERR_IO);
+            assert_eq!(CStr::from_ptr(fq_strerror(-7)), c"unknown error");
+        }
+    }
+
+    #[test]
+    fn ffi_reports_missing_header() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("b.fastq");
+        std::fs::write(&path, b"xyz\nACGT\n+\nIIII\n").unwrap();
+        let cpath = CString::new(path.to_str().unwrap()).unwrap();
+
+        unsafe {
+            let r = fq_open(cpath.as_ptr(), 1, 0);
+            assert!(!r.is_null());
+            let mut rec = std::mem::MaybeUninit::<FqRecord>::uninit();
+            assert_eq!(fq_next(r, rec.as_mut_ptr()), -1);
+            assert_eq!(fq_error_code(r), FQ_ERR_MISSING_HEADER);
+            fq_close(r);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use kira_cdh_compat_fastq_reader::{FastqFileSet, ReaderOptions};
+use std::collections::BTreeMap;
+use tempfile::tempdir;
+
+fn write_samples(dir: &std::path::Path, files: usize, records: usize) {
+    for f in 0..files {
+        let mut text = String::new();
+        for r in 0..records {
+            text.push_str(&format!("@s{f}_r{r}\nACGT\n+\nIIII\n"));
+        }
+        std::fs::write(dir.join(format!("sample{f:03}.fastq")), text).unwrap();
+    }
+    std::fs::write(dir.join("SampleSheet.csv"), "not fastq").unwrap();
+}
+
+#[test]
+fn sequential_stream_is_tagged_and_ordered() {
+    let dir = tempdir().unwrap();
+    write_samples(dir.path(), 3, 2);
+    let set = FastqFileSet::from_dir(dir.path(), ReaderOptions::default()).unwrap();
+    assert_eq!(set.paths().len(), 3);
+
+    let got: Vec<_> = set
+        .records()
+        .map(|r| {
+            let r = r.unwrap();
+            (r.file_index, r.record.id().to_string())
+        })
+        .collect();
+    assert_eq!(got[0], (0, "s0_r0".to_string()));
+    assert_eq!(got[5], (2, "s2_r1".to_string()));
+}
+
+#[test]
+fn parallel_reading_covers_every_file() {
+    let dir = tempdir().unwrap();
+    write_samples(dir.path(), 40, 5);
+    let set = FastqFileSet::from_dir(dir.path(), ReaderOptions::default())
+        .unwrap()
+        .max_open(3)
+        .parallel(8);
+
+    let mut per_file: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for rec in set.records() {
+        let rec = rec.unwrap();
+        assert!(
+            rec.path
+                .ends_with(format!("sample{:03}.fastq", rec.file_index))
+        );
+        per_file
+            .entry(rec.file_index)
+            .or_default()
+            .push(rec.record.id().to_string());
+    }
+    assert_eq!(per_file.len(), 40);
+    // order within a file is preserved
+    assert_eq!(per_file[&7], ["s7_r0", "s7_r1", "s7_r2", "s7_r3", "s7_r4"]);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn from_paths_chains_plain_and_gzip_files() {
+    use kira_cdh_compat_fastq_reader::{ErrorPolicy, FastqReader};
+    use std::io::Write;
+
+    let dir = tempdir().unwrap();
+    let a = dir.path().join("a.fastq");
+    std::fs::write(&a, "@a1\nAC\n+\nII\n@a2\nGT\n+\nII\n").unwrap();
+    let b = dir.path().join("b.fastq.gz");
+    let mut enc = flate2::write::GzEncoder::new(
+        std::fs::File::create(&b).unwrap(),
+        flate2::Compression::fast(),
+    );
+    enc.write_all(b"@b1\nAAA\n+\nIII\n@b2\nCC\n+\nI\n").unwrap();
+    enc.finish().unwrap();
+
+    let mut fq = FastqReader::from_paths([&a, &b], ReaderOptions::default()).unwrap();
+    let mut got = Vec::new();
+    while let Some(res) = fq.next() {
+        match res {
+            Ok(rec) => got.push((fq.path_index(), rec.id().to_owned())),
+            Err(err) => {
+                let ctx = err.context();
+                assert_eq!(ctx.line_num, 8);
+                assert!(ctx.label.as_deref().unwrap().ends_with("b.fastq.gz"));
+            }
+        }
+    }
+    let want = [(0, "a1"), (0, "a2"), (1, "b1")];
+    assert_eq!(got, want.map(|(i, id)| (Some(i), id.to_owned())));
+    assert_eq!(fq.stats().records, 3);
+    assert!(fq.state().is_none());
+
+    // a bad record at the end of one file does not swallow the next
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Skip,
+        ..ReaderOptions::default()
+    };
+    let ids: Vec<_> = FastqReader::from_paths([&b, &a], opts)
+        .unwrap()
+        .map(|r| r.unwrap().id().to_owned())
+        .collect();
+    assert_eq!(ids, ["b1", "a1", "a2"]);
+    assert!(
+        FastqReader::from_paths([dir.path().join("missing.fq")], ReaderOptions::default()).is_err()
+    );
+}
+
+#[cfg(feature = "glob")]
+#[test]
+fn from_glob_expands_and_sorts() {
+    use kira_cdh_compat_fastq_reader::FastqReader;
+
+    let dir = tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("lane2")).unwrap();
+    std::fs::create_dir(dir.path().join("lane1")).unwrap();
+    std::fs::write(dir.path().join("lane2/x.fastq"), "@c\nA\n+\nI\n").unwrap();
+    std::fs::write(dir.path().join("lane1/y.fastq"), "@b\nA\n+\nI\n").unwrap();
+    std::fs::write(dir.path().join("a.fastq"), "@a\nA\n+\nI\n").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "x").unwrap();
+
+    let ids = |fq: FastqReader| -> Vec<String> { fq.map(|r| r.unwrap().id().to_owned()).collect() };
+    let pattern = format!("{}/**/*.fastq", dir.path().display());
+    let fq = FastqReader::from_glob(&pattern, ReaderOptions::default()).unwrap();
+    assert_eq!(ids(fq), ["a", "b", "c"]);
+    let lane = dir.path().join("lane1");
+    let fq = FastqReader::from_glob(lane.to_str().unwrap(), ReaderOptions::default()).unwrap();
+    assert_eq!(ids(fq), ["b"]);
+
+    let none = format!("{}/*.fq.gz", dir.path().display());
+    assert!(FastqReader::from_glob(&none, ReaderOptions::default()).is_err());
+    assert!(FastqReader::from_glob("[", ReaderOptions::default()).is_err());
+}
@@ -1,4 +1,4 @@
-use kira_cdh_compat_fastq_reader::{ErrorPolicy, LineMode, ReaderOptions};
+use kira_cdh_compat_fastq_reader::{ChecksumAlgorithm, ErrorPolicy, LineMode, ReaderOptions};
 use std::fs::File;
 use std::io::Write;
 use tempfile::tempdir;
@@ -24,13 +24,537 @@ fn parse_gz_file_single_line() {
             error_policy: ErrorPolicy::Return,
             fastq_only: true,
             line_mode: LineMode::Single,
+            ..Default::default()
         },
     )
     .expect("open gz");
 
     let rec = fq.next().unwrap().unwrap();
-    assert_eq!(rec.id, "x");
-    assert_eq!(rec.seq, b"ACGT");
-    assert_eq!(rec.qual, b"!!!!");
+    assert_eq!(rec.id(), "x");
+    assert_eq!(rec.seq(), b"ACGT");
+    assert_eq!(rec.qual(), b"!!!!");
     assert!(fq.next().is_none());
 }
+
+#[cfg(feature = "gzip")]
+#[test]
+fn checksums_of_decompressed_gz_content() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("sample.fastq.gz");
+    {
+        let f = File::create(&path).unwrap();
+        let mut enc = flate2::write::GzEncoder::new(f, flate2::Compression::fast());
+        enc.write_all(b"@x\nACGT\n+\n!!!!\n").unwrap();
+        enc.finish().unwrap();
+    }
+    let opts = ReaderOptions {
+        checksum_raw: Some(ChecksumAlgorithm::Sha256),
+        checksum_decompressed: Some(ChecksumAlgorithm::Md5),
+        ..Default::default()
+    };
+    let mut fq = kira_cdh_compat_fastq_reader::FastqReader::from_path(&path, opts).unwrap();
+    assert!(fq.checksums().is_none());
+    assert_eq!(fq.by_ref().count(), 1);
+
+    let sums = fq.checksums().expect("available after EOF");
+    assert_eq!(
+        sums.decompressed.unwrap().to_hex(),
+        "0c52def3cacd141ccf629565c0d5cf16"
+    );
+    assert_eq!(sums.raw.unwrap().bytes.len(), 32);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn strict_gzip_reports_corrupted_trailer() {
+    use kira_cdh_compat_fastq_reader::{FastqError, FastqReader, GzipIntegrityError};
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("bad.fastq.gz");
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    enc.write_all(b"@x\nACGT\n+\n!!!!\n@y\nGG\n+\nII\n")
+        .unwrap();
+    let mut gz = enc.finish().unwrap();
+    let crc_at = gz.len() - 8;
+    gz[crc_at] ^= 0xFF;
+    std::fs::write(&path, &gz).unwrap();
+
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Skip,
+        strict_gzip: true,
+        ..Default::default()
+    };
+    let results: Vec<_> = FastqReader::from_path(&path, opts).unwrap().collect();
+    let err = results
+        .into_iter()
+        .find_map(Result::err)
+        .expect("integrity error surfaces even under Skip");
+    assert!(matches!(
+        err,
+        FastqError::GzipIntegrity {
+            source: GzipIntegrityError::Crc { member: 0, .. },
+            ..
+        }
+    ));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn from_vec_and_from_file_sniff_gzip() {
+    use kira_cdh_compat_fastq_reader::FastqReader;
+
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    enc.write_all(b"@x\nACGT\n+\n!!!!\n").unwrap();
+    let gz = enc.finish().unwrap();
+
+    let rec = FastqReader::from_vec(gz.clone(), ReaderOptions::default())
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(rec.seq(), b"ACGT");
+
+    // no .gz extension: detection relies on the magic bytes
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("reads.bin");
+    std::fs::write(&path, &gz).unwrap();
+    let mut fq =
+        FastqReader::from_file(File::open(&path).unwrap(), ReaderOptions::default()).unwrap();
+    assert_eq!(fq.next().unwrap().unwrap().id(), "x");
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn from_read_buffers_and_sniffs_gzip() {
+    use kira_cdh_compat_fastq_reader::FastqReader;
+    use std::io::Read;
+
+    /// Hands out at most 3 bytes per call, like a slow socket.
+    struct Trickle(std::io::Cursor<Vec<u8>>);
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(3);
+            self.0.read(&mut buf[..n])
+        }
+    }
+
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    enc.write_all(b"@x\nACGT\n+\n!!!!\n@y\nGG\n+\n!!\n")
+        .unwrap();
+    let gz = enc.finish().unwrap();
+
+    let fq = FastqReader::from_read(Trickle(std::io::Cursor::new(gz)), ReaderOptions::default());
+    let ids: Vec<_> = fq.map(|r| r.unwrap().id().to_string()).collect();
+    assert_eq!(ids, ["x", "y"]);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn limits_stop_gzip_bombs_and_huge_records() {
+    use kira_cdh_compat_fastq_reader::{FastqError, FastqReader, LimitError};
+
+    // 8 MiB of one repeated line compresses by far more than 100x
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    enc.write_all(b"@bomb\n").unwrap();
+    enc.write_all(&vec![b'A'; 8 << 20]).unwrap();
+    let bomb = enc.finish().unwrap();
+
+    let opts = ReaderOptions {
+        max_expansion_ratio: Some(100),
+        ..Default::default()
+    };
+    let err = FastqReader::from_vec(bomb.clone(), opts)
+        .find_map(Result::err)
+        .expect("ratio limit under Skip");
+    assert!(matches!(
+        err,
+        FastqError::LimitExceeded {
+            source: LimitError::ExpansionRatio { limit: 100, .. },
+            ..
+        }
+    ));
+
+    let opts = ReaderOptions {
+        max_record_bytes: Some(1 << 16),
+        ..Default::default()
+    };
+    let err = FastqReader::from_vec(bomb, opts)
+        .find_map(Result::err)
+        .expect("record limit under Skip");
+    assert!(matches!(
+        err,
+        FastqError::LimitExceeded {
+            source: LimitError::RecordTooLarge { limit: 65536 },
+            ..
+        }
+    ));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn estimate_total_records_plain_and_gz() {
+    use kira_cdh_compat_fastq_reader::estimate_total_records;
+
+    let dir = tempdir().unwrap();
+    let plain = dir.path().join("many.fastq");
+    let gz = dir.path().join("many.fastq.gz");
+    let mut text = String::new();
+    for i in 0..5000 {
+        text.push_str(&format!("@r{i:05}\nACGTACGTAC\n+\nIIIIIIIIII\n"));
+    }
+    std::fs::write(&plain, &text).unwrap();
+    let mut enc =
+        flate2::write::GzEncoder::new(File::create(&gz).unwrap(), flate2::Compression::default());
+    enc.write_all(text.as_bytes()).unwrap();
+    enc.finish().unwrap();
+
+    let opts = ReaderOptions::default();
+    let est = estimate_total_records(&plain, &opts, 100).unwrap();
+    assert_eq!(est.records, 5000);
+    assert_eq!(est.records_sampled, 100);
+    assert_eq!(est.compression_ratio, 1.0);
+    assert!(!est.exact);
+
+    let est = estimate_total_records(&gz, &opts, 100).unwrap();
+    assert!(est.compression_ratio > 1.0);
+    assert!((4900..=5100).contains(&est.records), "{est:?}");
+
+    let est = estimate_total_records(&gz, &opts, 10_000).unwrap();
+    assert!(est.exact);
+    assert_eq!(est.records, 5000);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn gzip_members_of_concatenated_lanes() {
+    use kira_cdh_compat_fastq_reader::{FastqReader, GzipMember};
+    use std::sync::{Arc, Mutex};
+
+    let gz = |text: &[u8]| {
+        let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        enc.write_all(text).unwrap();
+        enc.finish().unwrap()
+    };
+    let lane1 = gz(b"@a\nACGT\n+\n!!!!\n@b\nGG\n+\nII\n");
+    let lane2 = gz(b"@c\nTTT\n+\n###\n");
+    let mut all = lane1.clone();
+    all.extend_from_slice(&lane2);
+
+    for strict in [false, true] {
+        let opts = ReaderOptions {
+            track_gzip_members: true,
+            strict_gzip: strict,
+            ..Default::default()
+        };
+        let mut fq = FastqReader::from_vec(all.clone(), opts);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        fq.on_gzip_member(move |m| sink.lock().unwrap().push(m.index));
+
+        assert_eq!(fq.next().unwrap().unwrap().id(), "a");
+        assert_eq!(fq.next().unwrap().unwrap().id(), "b");
+        assert!(fq.gzip_members().is_empty());
+        assert_eq!(fq.next().unwrap().unwrap().id(), "c");
+        assert_eq!(*seen.lock().unwrap(), [0]);
+        assert!(fq.next().is_none());
+        assert_eq!(*seen.lock().unwrap(), [0, 1]);
+
+        assert_eq!(
+            fq.gzip_members(),
+            [
+                GzipMember {
+                    index: 0,
+                    compressed_bytes: lane1.len() as u64,
+                    decompressed_offset: 0,
+                    decompressed_bytes: 26,
+                    records: 2,
+                },
+                GzipMember {
+                    index: 1,
+                    compressed_bytes: lane2.len() as u64,
+                    decompressed_offset: 26,
+                    decompressed_bytes: 13,
+                    records: 1,
+                },
+            ]
+        );
+    }
+
+    // off by default
+    let mut fq = FastqReader::from_vec(all, ReaderOptions::default());
+    assert_eq!(fq.by_ref().count(), 3);
+    assert!(fq.gzip_members().is_empty());
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn bgzf_virtual_offsets_point_at_records() {
+    use kira_cdh_compat_fastq_reader::{Compression, FastqReader, FastqWriter, WriterOptions};
+    use std::io::Read;
+
+    let mut text = Vec::new();
+    for i in 0..4000 {
+        write!(text, "@read{i}\nACGTACGTAC\n+\nIIIIIIIIII\n").unwrap();
+    }
+    let opts = WriterOptions {
+        compression: Some(Compression::Bgzf),
+        ..Default::default()
+    };
+    let mut w = FastqWriter::from_writer(Vec::new(), opts).unwrap();
+    for rec in FastqReader::from_bytes(&text, ReaderOptions::default()) {
+        w.write_record(&rec.unwrap()).unwrap();
+    }
+    let bgz = w.finish().unwrap();
+
+    let mut fq = FastqReader::from_vec(bgz.clone(), ReaderOptions::default());
+    assert_eq!(fq.position().virtual_offset, Some(0));
+    let mut offsets = Vec::new();
+    while let Some(rec) = fq.next() {
+        let voff = fq.position().virtual_offset.unwrap();
+        offsets.push((rec.unwrap().id().to_string(), voff));
+    }
+    assert!(offsets.iter().any(|(_, v)| v >> 16 > 0), "one block only");
+
+    // the offset after each record addresses the next one
+    for pair in offsets.windows(2).step_by(37) {
+        let (coffset, uoffset) = (pair[0].1 >> 16, pair[0].1 & 0xFFFF);
+        let next = format!("@{}\n", pair[1].0);
+        let mut text = Vec::new();
+        flate2::read::MultiGzDecoder::new(&bgz[coffset as usize..])
+            .take(uoffset + next.len() as u64)
+            .read_to_end(&mut text)
+            .unwrap();
+        assert_eq!(&text[uoffset as usize..], next.as_bytes());
+    }
+
+    // plain gzip has no virtual offsets
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    enc.write_all(&text).unwrap();
+    let mut fq = FastqReader::from_vec(enc.finish().unwrap(), ReaderOptions::default());
+    fq.next().unwrap().unwrap();
+    assert_eq!(fq.position().virtual_offset, None);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn prefetch_thread_matches_inline_reading() {
+    use kira_cdh_compat_fastq_reader::FastqReader;
+
+    let mut text = Vec::new();
+    for i in 0..50_000 {
+        write!(text, "@r{i}\nACGTACGTAC\n+\nIIIIIIIIII\n").unwrap();
+    }
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    enc.write_all(&text).unwrap();
+    let gz = enc.finish().unwrap();
+    let opts = ReaderOptions {
+        checksum_decompressed: Some(ChecksumAlgorithm::Md5),
+        ..Default::default()
+    };
+
+    let inline: Vec<_> = FastqReader::from_vec(gz.clone(), opts.clone())
+        .map(Result::unwrap)
+        .collect();
+    let mut fq = FastqReader::from_vec(gz.clone(), opts).with_prefetch_thread();
+    let prefetched: Vec<_> = fq.by_ref().map(Result::unwrap).collect();
+    assert_eq!(prefetched, inline);
+    assert_eq!(fq.position().byte_pos, text.len() as u64);
+    assert!(fq.checksums().unwrap().decompressed.is_some());
+
+    // decoder errors cross the thread, after the records decoded before them
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    enc.write_all(&text[..text.len() / 25]).unwrap();
+    let gz = enc.finish().unwrap();
+    let truncated = gz[..gz.len() - 100].to_vec();
+    let fail_fast = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let read = |fq: FastqReader| {
+        let mut ok = 0;
+        for r in fq {
+            match r {
+                Ok(_) => ok += 1,
+                Err(_) => return Some(ok),
+            }
+        }
+        None
+    };
+    let inline = read(FastqReader::from_vec(truncated.clone(), fail_fast.clone()));
+    assert!(inline.unwrap() > 1900);
+    let prefetched = read(FastqReader::from_vec(truncated, fail_fast).with_prefetch_thread());
+    assert_eq!(prefetched, inline);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn stats_count_records_bytes_and_bases() {
+    use kira_cdh_compat_fastq_reader::FastqReader;
+
+    let text = b"@a\nACGT\n+\n!!!!\n@b\nAC\n+\n!\n@c\nGGG\n+\n!!!\n";
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    enc.write_all(text).unwrap();
+    let gz = enc.finish().unwrap();
+
+    let mut fq = FastqReader::from_vec(gz.clone(), ReaderOptions::default());
+    // opening may read ahead (gzip header sniffing), but parses nothing
+    assert_eq!(fq.stats().bytes, 0);
+    assert_eq!(fq.by_ref().count(), 2);
+    let stats = fq.stats();
+    assert_eq!((stats.records, stats.skipped, stats.bases), (2, 1, 7));
+    assert_eq!(stats.bytes, text.len() as u64);
+    assert_eq!(stats.raw_bytes, gz.len() as u64);
+
+    let mut plain = FastqReader::from_bytes(&text[..], ReaderOptions::default());
+    plain.by_ref().for_each(drop);
+    let stats = plain.stats();
+    assert_eq!(
+        (stats.raw_bytes, stats.bytes),
+        (text.len() as u64, text.len() as u64)
+    );
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn progress_sink_reports_raw_bytes_against_size() {
+    use kira_cdh_compat_fastq_reader::{FastqReader, ProgressSink};
+    use std::sync::{Arc, Mutex};
+
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    for i in 0..100 {
+        writeln!(enc, "@r{i}\nACGT\n+\nIIII").unwrap();
+    }
+    let gz = enc.finish().unwrap();
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    let opts = ReaderOptions {
+        progress: Some(ProgressSink::new(move |p| sink.lock().unwrap().push(*p)).every_records(40)),
+        ..Default::default()
+    };
+    let fq = FastqReader::from_vec(gz.clone(), opts);
+    assert_eq!(fq.count(), 100);
+
+    let seen = seen.lock().unwrap();
+    let records: Vec<_> = seen.iter().map(|p| p.records).collect();
+    assert_eq!(records, [40, 80, 100]);
+    let last = seen.last().unwrap();
+    assert!(last.done && seen[..2].iter().all(|p| !p.done));
+    assert_eq!(last.total_bytes, Some(gz.len() as u64));
+    assert_eq!(last.raw_bytes, gz.len() as u64);
+    assert_eq!(last.fraction(), Some(1.0));
+    assert_eq!(last.eta(), Some(std::time::Duration::ZERO));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn from_bufread_sniffs_compression() {
+    use kira_cdh_compat_fastq_reader::FastqReader;
+    use std::io::{BufReader, Cursor};
+
+    let text = b"@a\nACGT\n+\nIIII\n@b\nAC\n+\nII\n";
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    enc.write_all(text).unwrap();
+    let gz = enc.finish().unwrap();
+
+    let mut fq = FastqReader::from_bufread_labeled(
+        BufReader::new(Cursor::new(gz.clone())),
+        "curl",
+        ReaderOptions::default(),
+    );
+    let ids: Vec<_> = fq.by_ref().map(|r| r.unwrap().id().to_owned()).collect();
+    assert_eq!(ids, ["a", "b"]);
+    let stats = fq.stats();
+    assert_eq!(
+        (stats.bytes, stats.raw_bytes),
+        (text.len() as u64, gz.len() as u64)
+    );
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn zstd_input_without_feature_is_unsupported() {
+    use kira_cdh_compat_fastq_reader::{FastqError, FastqReader};
+    use std::io::Cursor;
+
+    let zstd = Cursor::new(vec![0x28, 0xB5, 0x2F, 0xFD, 0x00]);
+    let err = FastqReader::from_bufread(zstd, ReaderOptions::default())
+        .next()
+        .unwrap()
+        .unwrap_err();
+    match err {
+        FastqError::Io { source, .. } => {
+            assert_eq!(source.kind(), std::io::ErrorKind::Unsupported)
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn zstd_input_from_path_bufread_and_bytes() {
+    use kira_cdh_compat_fastq_reader::FastqReader;
+    use std::io::{BufReader, Cursor};
+
+    let text = b"@a\nACGT\n+\nIIII\n@b\nGG\n+\n!!\n";
+    // two concatenated frames, like `zstd -c a b`
+    let mut zst = zstd::encode_all(&text[..16], 3).unwrap();
+    zst.extend(zstd::encode_all(&text[16..], 3).unwrap());
+    let ids =
+        |rdr: FastqReader| -> Vec<String> { rdr.map(|r| r.unwrap().id().to_owned()).collect() };
+
+    let dir = tempdir().unwrap();
+    let named = dir.path().join("reads.fastq.zst");
+    std::fs::write(&named, &zst).unwrap();
+    let sniffed = dir.path().join("reads.fq");
+    std::fs::write(&sniffed, &zst).unwrap();
+    for path in [&named, &sniffed] {
+        let rdr = FastqReader::from_path(path, ReaderOptions::default()).unwrap();
+        assert_eq!(ids(rdr), ["a", "b"], "{}", path.display());
+    }
+
+    let mut rdr = FastqReader::from_bufread(
+        BufReader::new(Cursor::new(zst.clone())),
+        ReaderOptions::default(),
+    );
+    assert_eq!(rdr.by_ref().count(), 2);
+    let stats = rdr.stats();
+    assert_eq!(
+        (stats.bytes, stats.raw_bytes),
+        (text.len() as u64, zst.len() as u64)
+    );
+
+    assert_eq!(
+        ids(FastqReader::from_bytes(&zst, ReaderOptions::default())),
+        ["a", "b"]
+    );
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn tiny_buffers_still_parse_long_records() {
+    use kira_cdh_compat_fastq_reader::FastqReader;
+
+    let seq = "ACGT".repeat(1000);
+    let qual = "I".repeat(4000);
+    let text = format!("@a\n{seq}\n+\n{qual}\n@b\n{seq}\n+\n{qual}\n");
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    enc.write_all(text.as_bytes()).unwrap();
+    let gz = enc.finish().unwrap();
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("reads.fq.gz");
+    std::fs::write(&path, &gz).unwrap();
+    let opts = ReaderOptions {
+        read_buffer_bytes: Some(1),
+        decompress_buffer_bytes: Some(2048),
+        ..ReaderOptions::default()
+    };
+    for fq in [
+        FastqReader::from_path(&path, opts.clone()).unwrap(),
+        FastqReader::from_read(std::io::Cursor::new(gz), opts),
+    ] {
+        let recs: Vec<_> = fq.map(Result::unwrap).collect();
+        assert_eq!(recs.len(), 2);
+        assert_eq!(recs[1].seq(), seq.as_bytes());
+    }
+}
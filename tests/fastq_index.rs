@@ -0,0 +1,106 @@
+use kira_cdh_compat_fastq_reader::{
+    ErrorPolicy, FastqError, FastqIndex, FastqReader, FastqRecord, ReaderOptions,
+};
+use tempfile::tempdir;
+
+fn fastq(records: usize) -> String {
+    let mut text = String::new();
+    for i in 0..records {
+        text.push_str(&format!("@r{i}\nACGT\n+\nIIII\n"));
+        if i == 20 {
+            text.push_str("@broken\nACGT\n+\nII\n");
+        }
+    }
+    text
+}
+
+fn ids(iter: impl Iterator<Item = Result<FastqRecord, FastqError>>) -> Vec<String> {
+    iter.map(|r| r.unwrap().id().to_string()).collect()
+}
+
+#[test]
+fn seek_record_through_sidecar_index() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("reads.fastq");
+    std::fs::write(&path, fastq(50)).unwrap();
+    let opts = ReaderOptions {
+        error_policy: ErrorPolicy::Skip,
+        ..Default::default()
+    };
+
+    let index = FastqIndex::build_with_stride(&path, opts.clone(), 8).unwrap();
+    assert_eq!(index.records(), 50);
+    assert_eq!(index.entries().len(), 7);
+    index.save(FastqIndex::path_for(&path)).unwrap();
+    assert_eq!(
+        FastqIndex::open_or_build(&path, opts.clone()).unwrap(),
+        index
+    );
+
+    let mut fq = FastqReader::from_path(&path, opts).unwrap();
+    fq.seek_record(37).unwrap();
+    let rec = fq.next().unwrap().unwrap();
+    assert_eq!(rec.id(), "r37");
+    // the skipped record before the sample is still counted
+    assert_eq!(fq.position().record_num, 39);
+
+    assert_eq!(
+        ids(fq.records_range(19..23).unwrap()),
+        ["r19", "r20", "r21", "r22"]
+    );
+    assert_eq!(ids(fq.records_range(48..60).unwrap()), ["r48", "r49"]);
+    fq.seek_record(50).unwrap();
+    assert!(fq.next().is_none());
+    assert!(fq.seek_record(51).is_err());
+}
+
+#[test]
+fn seek_record_without_index_fails() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("reads.fastq");
+    std::fs::write(&path, fastq(3)).unwrap();
+    let mut fq = FastqReader::from_path(&path, ReaderOptions::default()).unwrap();
+    assert!(fq.seek_record(1).is_err());
+
+    let index = FastqIndex::build(&path, ReaderOptions::default()).unwrap();
+    let mut fq = FastqReader::from_path(&path, ReaderOptions::default())
+        .unwrap()
+        .with_index(index);
+    fq.seek_record(2).unwrap();
+    assert_eq!(fq.next().unwrap().unwrap().id(), "r2");
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn bgzf_index_seeks_by_virtual_offset() {
+    use kira_cdh_compat_fastq_reader::{Compression, FastqWriter, WriterOptions};
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("reads.fastq.bgz");
+    let opts = WriterOptions {
+        compression: Some(Compression::Bgzf),
+        ..Default::default()
+    };
+    let mut w = FastqWriter::from_path(&path, opts).unwrap();
+    for rec in FastqReader::from_bytes(fastq(5000).as_bytes(), ReaderOptions::default()) {
+        w.write_record(&rec.unwrap()).unwrap();
+    }
+    w.finish().unwrap();
+
+    let index = FastqIndex::open_or_build(&path, ReaderOptions::default()).unwrap();
+    assert!(FastqIndex::path_for(&path).exists());
+    assert_eq!(index.records(), 5000);
+    let last = index.entries().last().unwrap();
+    assert!(last.virtual_offset.unwrap() >> 16 > 0);
+
+    let mut fq = FastqReader::from_path(&path, ReaderOptions::default()).unwrap();
+    fq.seek_record(4321).unwrap();
+    assert_eq!(fq.next().unwrap().unwrap().id(), "r4321");
+    assert_eq!(
+        ids(fq.records_range(4998..5000).unwrap()),
+        ["r4998", "r4999"]
+    );
+    // offsets stay absolute after the seek
+    let voff = fq.position().virtual_offset.unwrap();
+    assert!(voff >> 16 >= last.virtual_offset.unwrap() >> 16);
+}
@@ -0,0 +1,77 @@
+#[cfg(feature = "metrics")]
+mod t {
+    use kira_cdh_compat_fastq_reader::{FastqReader, ReaderOptions};
+    use metrics::{
+        Counter, CounterFn, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+    };
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    /// Counters by `name{label=value,...}`.
+    #[derive(Default)]
+    struct Counters(Mutex<BTreeMap<String, Arc<Cell>>>);
+
+    #[derive(Default)]
+    struct Cell(AtomicU64);
+
+    impl CounterFn for Cell {
+        fn increment(&self, value: u64) {
+            self.0.fetch_add(value, Ordering::Relaxed);
+        }
+
+        fn absolute(&self, value: u64) {
+            self.0.fetch_max(value, Ordering::Relaxed);
+        }
+    }
+
+    impl Counters {
+        fn get(&self, name: &str) -> u64 {
+            let map = self.0.lock().unwrap();
+            map.get(name).map_or(0, |c| c.0.load(Ordering::Relaxed))
+        }
+    }
+
+    impl Recorder for Counters {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            let labels: Vec<String> = key
+                .labels()
+                .map(|l| format!("{}={}", l.key(), l.value()))
+                .collect();
+            let name = match labels.is_empty() {
+                true => key.name().to_owned(),
+                false => format!("{}{{{}}}", key.name(), labels.join(",")),
+            };
+            let mut map = self.0.lock().unwrap();
+            Counter::from_arc(Arc::clone(map.entry(name).or_default()))
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn reader_counts_records_skips_bytes_and_errors_by_kind() {
+        let data = "@r1\nACGT\n+\nIIII\n@bad\nAC\n+\nI\n@r2\nGG\n+\n!!\nno header\n";
+        let counters = Counters::default();
+        let n = metrics::with_local_recorder(&counters, || {
+            FastqReader::from_bytes(data.as_bytes(), ReaderOptions::default()).count()
+        });
+        assert_eq!(n, 2);
+        assert_eq!(counters.get("fastq_records_read"), 2);
+        assert_eq!(counters.get("fastq_records_skipped"), 2);
+        assert_eq!(counters.get("fastq_bytes_read"), data.len() as u64);
+        assert_eq!(counters.get("fastq_parse_errors{kind=length_mismatch}"), 1);
+        assert_eq!(counters.get("fastq_parse_errors{kind=missing_header}"), 1);
+        assert_eq!(counters.get("fastq_parse_errors{kind=io}"), 0);
+    }
+}
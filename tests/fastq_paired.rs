@@ -0,0 +1,451 @@
+use kira_cdh_compat_fastq_reader::paired::{MateCheck, are_mates, check_mates, mate_id};
+use kira_cdh_compat_fastq_reader::{MatePolicy, RefRecord};
+
+fn rec<'a>(id: &'a str, desc: Option<&'a str>) -> RefRecord<'a> {
+    RefRecord {
+        id,
+        desc,
+        seq: b"A",
+        qual: b"I",
+        plus_line: None,
+        raw: None,
+    }
+}
+
+#[test]
+fn recognizes_common_mate_conventions() {
+    assert!(are_mates(rec("r7/1", None), rec("r7/2", None)));
+    assert!(are_mates(
+        rec("M1:1:FC:2:1:5:9", Some("1:N:0:ACGT")),
+        rec("M1:1:FC:2:1:5:9", Some("2:N:0:ACGT"))
+    ));
+    assert!(are_mates(rec("r7", None), rec("r7", Some("lane=1"))));
+
+    assert!(!are_mates(rec("r7/1", None), rec("r8/2", None)));
+    assert!(!are_mates(rec("r7/1", None), rec("r7/1", None)));
+    assert_eq!(mate_id("r7/2", None).mate, Some(2));
+    assert_eq!(mate_id("r7", Some("1:Y:18:ACGT")).base, "r7");
+}
+
+#[test]
+fn mismatch_policies() {
+    let (a, b) = (rec("r1/1", None), rec("r2/2", None));
+    let err = check_mates(a, b, MatePolicy::Error).unwrap_err();
+    assert_eq!((err.r1_id.as_str(), err.r2_id.as_str()), ("r1/1", "r2/2"));
+    assert_eq!(check_mates(a, b, MatePolicy::SkipPair), Ok(MateCheck::Skip));
+    assert_eq!(
+        check_mates(a, b, MatePolicy::TrustOrder),
+        Ok(MateCheck::Keep)
+    );
+}
+
+#[test]
+fn orphan_policies_count_and_divert() {
+    use kira_cdh_compat_fastq_reader::OrphanPolicy;
+    use kira_cdh_compat_fastq_reader::paired::{Mate, OrphanHandler};
+    use std::sync::{Arc, Mutex};
+
+    let orphan = || rec("lonely/1", None).to_owned_record();
+
+    let mut strict = OrphanHandler::new(OrphanPolicy::Error);
+    let err = strict.handle(Mate::R1, orphan()).unwrap_err();
+    assert_eq!(err.id, "lonely/1");
+    assert_eq!(strict.counts().r1, 1);
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    let mut divert = OrphanHandler::new(OrphanPolicy::divert(move |o| {
+        sink.lock().unwrap().push(o.record.id().to_string())
+    }));
+    divert.handle(Mate::R1, orphan()).unwrap();
+    divert.handle(Mate::R2, orphan()).unwrap();
+    assert_eq!(divert.counts().total(), 2);
+    assert_eq!(seen.lock().unwrap().len(), 2);
+
+    let mut drop = OrphanHandler::new(OrphanPolicy::Drop);
+    drop.handle(Mate::R2, orphan()).unwrap();
+    assert_eq!(drop.counts().r2, 1);
+}
+
+#[test]
+fn probe_detects_interleaved_files() {
+    use kira_cdh_compat_fastq_reader::{Interleaving, ReaderOptions, probe};
+
+    let dir = tempfile::tempdir().unwrap();
+    let inter = dir.path().join("inter.fastq");
+    std::fs::write(
+        &inter,
+        "@a/1\nAC\n+\nII\n@a/2\nGT\n+\nII\n@b/1\nAC\n+\nII\n@b/2\nGT\n+\nII\n",
+    )
+    .unwrap();
+    let single = dir.path().join("single.fastq");
+    std::fs::write(&single, "@a\nAC\n+\nII\n@b\nGT\n+\nII\n").unwrap();
+
+    let report = probe(&inter, &ReaderOptions::default()).unwrap();
+    assert!(report.is_interleaved());
+    assert_eq!(report.records_sampled, 4);
+    assert!(!report.gzip);
+    assert_eq!(
+        report.line_mode,
+        Some(kira_cdh_compat_fastq_reader::LineMode::Single)
+    );
+    assert_eq!(
+        probe(&single, &ReaderOptions::default())
+            .unwrap()
+            .interleaving,
+        Interleaving::NotInterleaved
+    );
+}
+
+#[test]
+fn paired_reader_yields_mates_and_reports_desync() {
+    use kira_cdh_compat_fastq_reader::{
+        ErrorPolicy, FastqError, FormatError, PairedFastqReader, ReaderOptions,
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let r1 = dir.path().join("r1.fastq");
+    let r2 = dir.path().join("r2.fastq");
+    std::fs::write(
+        &r1,
+        "@a/1\nAC\n+\nII\n@b 1:N:0:ACGT\nAC\n+\nII\n@c/1\nAC\n+\nII\n@d/1\nAC\n+\nII\n",
+    )
+    .unwrap();
+    std::fs::write(
+        &r2,
+        "@a/2\nGT\n+\nII\n@b 2:N:0:ACGT\nGT\n+\nII\n@x/2\nGT\n+\nII\n@d/2\nGT\n+\nII\n",
+    )
+    .unwrap();
+
+    let strict = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let mut rdr = PairedFastqReader::from_paths(&r1, &r2, strict).unwrap();
+    let (a1, a2) = rdr.next().unwrap().unwrap();
+    assert_eq!((a1.id(), a2.id()), ("a/1", "a/2"));
+    assert_eq!(rdr.next().unwrap().unwrap().1.desc(), Some("2:N:0:ACGT"));
+    let err = rdr.next().unwrap().unwrap_err();
+    let FastqError::Format {
+        source: FormatError::PairDesync(d),
+        ..
+    } = err
+    else {
+        panic!("expected PairDesync, got {err:?}");
+    };
+    assert_eq!((d.r1_id.as_str(), d.r2_id.as_str()), ("c/1", "x/2"));
+    assert_eq!(
+        (d.r1_record, d.r1_line, d.r2_record, d.r2_line),
+        (3, 9, 3, 9)
+    );
+
+    let mut rdr = PairedFastqReader::from_paths(&r1, &r2, ReaderOptions::default()).unwrap();
+    let ids: Vec<_> = rdr
+        .by_ref()
+        .map(|p| p.unwrap().0.id().to_string())
+        .collect();
+    assert_eq!(ids, ["a/1", "b", "d/1"]);
+    assert_eq!((rdr.pairs(), rdr.skipped_pairs()), (3, 1));
+}
+
+#[test]
+fn paired_reader_orphans_when_one_file_ends_first() {
+    use kira_cdh_compat_fastq_reader::{
+        ErrorPolicy, FastqError, FastqReader, OrphanPolicy, PairedFastqReader, ReaderOptions,
+    };
+
+    let open = || {
+        let r1 =
+            FastqReader::from_bytes(b"@a/1\nA\n+\nI\n@b/1\nC\n+\nI\n", ReaderOptions::default());
+        let r2 = FastqReader::from_bytes(b"@a/2\nT\n+\nI\n", ReaderOptions::default());
+        PairedFastqReader::new(r1, r2, ErrorPolicy::Skip)
+    };
+    let mut rdr = open();
+    assert!(rdr.next().unwrap().is_ok());
+    let err = rdr.next().unwrap().unwrap_err();
+    assert!(matches!(err, FastqError::Orphan { ref source, .. } if source.id == "b/1"));
+
+    let mut rdr = open().with_orphan_policy(OrphanPolicy::Drop);
+    assert_eq!(rdr.by_ref().count(), 1);
+    assert_eq!(rdr.orphans().r1, 1);
+}
+
+#[test]
+fn paired_reader_orphans_the_partner_of_a_failed_record() {
+    use kira_cdh_compat_fastq_reader::paired::{Mate, Orphan};
+    use kira_cdh_compat_fastq_reader::{
+        ErrorPolicy, FastqError, FastqReader, OrphanPolicy, PairedFastqReader, ReaderOptions,
+    };
+    use std::sync::{Arc, Mutex};
+
+    let strict = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let open = || {
+        // b/1 has a quality line shorter than its sequence
+        let r1 = FastqReader::from_bytes(
+            b"@a/1\nA\n+\nI\n@b/1\nCC\n+\nI\n@c/1\nG\n+\nI\n",
+            strict.clone(),
+        );
+        let r2 = FastqReader::from_bytes(
+            b"@a/2\nT\n+\nI\n@b/2\nG\n+\nI\n@c/2\nC\n+\nI\n",
+            strict.clone(),
+        );
+        PairedFastqReader::new(r1, r2, ErrorPolicy::Return)
+    };
+
+    let diverted = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&diverted);
+    let mut rdr = open().with_orphan_policy(OrphanPolicy::divert(move |o: Orphan| {
+        sink.lock()
+            .unwrap()
+            .push((o.mate, o.record.id().to_owned()))
+    }));
+    assert_eq!(rdr.next().unwrap().unwrap().0.id(), "a/1");
+    assert!(matches!(
+        rdr.next().unwrap(),
+        Err(FastqError::Format { .. })
+    ));
+    assert_eq!(*diverted.lock().unwrap(), [(Mate::R2, "b/2".to_owned())]);
+    assert_eq!(rdr.orphans().r2, 1);
+    assert_eq!(rdr.next().unwrap().unwrap().0.id(), "c/1");
+
+    // under the default policy the orphan is an error of its own
+    let mut rdr = open();
+    rdr.next().unwrap().unwrap();
+    assert!(matches!(
+        rdr.next().unwrap(),
+        Err(FastqError::Format { .. })
+    ));
+    let err = rdr.next().unwrap().unwrap_err();
+    assert!(matches!(err, FastqError::Orphan { ref source, .. } if source.id == "b/2"));
+}
+
+#[test]
+fn paired_reader_resyncs_after_a_skipped_record() {
+    use kira_cdh_compat_fastq_reader::{
+        ErrorPolicy, FastqReader, OrphanPolicy, PairedFastqReader, ReaderOptions,
+    };
+
+    // b/1 is malformed and dropped by R1's reader under the default Skip
+    let r1 = b"@a/1\nA\n+\nI\n@b/1\nCC\n+\nI\n@c/1\nG\n+\nI\n@d/1\nT\n+\nI\n";
+    let r2 = b"@a/2\nT\n+\nI\n@b/2\nG\n+\nI\n@c/2\nC\n+\nI\n@d/2\nA\n+\nI\n";
+    let open = |r1: &[u8], r2: &[u8]| {
+        let r1 = FastqReader::from_bytes(r1, ReaderOptions::default());
+        let r2 = FastqReader::from_bytes(r2, ReaderOptions::default());
+        PairedFastqReader::new(r1, r2, ErrorPolicy::Skip).with_orphan_policy(OrphanPolicy::Drop)
+    };
+
+    let mut rdr = open(r1, r2);
+    let ids: Vec<_> = rdr.by_ref().map(|p| p.unwrap().1.id().to_owned()).collect();
+    assert_eq!(ids, ["a/2", "c/2", "d/2"]);
+    assert_eq!(rdr.orphans().r2, 1);
+    assert_eq!(rdr.skipped_pairs(), 0);
+
+    // the same from the other side
+    let mut rdr = open(r2, r1);
+    assert_eq!(rdr.by_ref().filter_map(Result::ok).count(), 3);
+    assert_eq!(rdr.orphans().r1, 1);
+}
+
+#[test]
+fn interleaved_pairs_validate_mates_and_report_orphans() {
+    use kira_cdh_compat_fastq_reader::{
+        ErrorPolicy, FastqError, FastqReader, FormatError, OrphanPolicy, ReaderOptions,
+    };
+
+    let data = b"@a/1\nA\n+\nI\n@a/2\nT\n+\nI\n@b/1\nC\n+\nI\n@c/1\nG\n+\nI\n@c/2\nC\n+\nI\n@d/1\nA\n+\nI\n";
+    let strict = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let mut pairs = FastqReader::from_bytes(data, strict).pairs();
+    let (r1, r2) = pairs.next().unwrap().unwrap();
+    assert_eq!((r1.id(), r2.id()), ("a/1", "a/2"));
+    let err = pairs.next().unwrap().unwrap_err();
+    let FastqError::Format {
+        source: FormatError::PairDesync(d),
+        ..
+    } = err
+    else {
+        panic!("expected PairDesync, got {err:?}");
+    };
+    assert_eq!((d.r1_id.as_str(), d.r1_line), ("b/1", 9));
+    assert_eq!((d.r2_id.as_str(), d.r2_line), ("c/1", 13));
+
+    // Skip orphans b/1 alone and realigns on c/1 + c/2; d/1 is a trailing orphan
+    let mut pairs = FastqReader::from_bytes(data, ReaderOptions::default()).pairs();
+    assert_eq!(pairs.next().unwrap().unwrap().0.id(), "a/1");
+    let err = pairs.next().unwrap().unwrap_err();
+    assert!(matches!(err, FastqError::Orphan { ref source, .. } if source.id == "b/1"));
+    assert_eq!(pairs.next().unwrap().unwrap().0.id(), "c/1");
+    let err = pairs.next().unwrap().unwrap_err();
+    assert!(matches!(err, FastqError::Orphan { ref source, .. } if source.id == "d/1"));
+    assert!(pairs.next().is_none());
+    assert_eq!((pairs.pairs(), pairs.orphans().r1), (2, 2));
+
+    let pairs = FastqReader::from_bytes(data, ReaderOptions::default())
+        .pairs()
+        .with_orphan_policy(OrphanPolicy::Drop);
+    assert_eq!(pairs.map(Result::unwrap).count(), 2);
+}
+
+#[test]
+fn interleaved_pairs_divert_skipped_and_failed_partners() {
+    use kira_cdh_compat_fastq_reader::paired::Orphan;
+    use kira_cdh_compat_fastq_reader::{
+        ErrorPolicy, FastqError, FastqReader, OrphanPolicy, ReaderOptions,
+    };
+    use std::sync::{Arc, Mutex};
+
+    let diverted = Arc::new(Mutex::new(Vec::new()));
+    let divert = || {
+        let sink = Arc::clone(&diverted);
+        OrphanPolicy::divert(move |o: Orphan| sink.lock().unwrap().push(o.record.id().to_owned()))
+    };
+
+    // under Skip the first record of a mismatched pair is an orphan
+    let data = b"@a/1\nA\n+\nI\n@a/2\nT\n+\nI\n@b/1\nC\n+\nI\n@c/1\nG\n+\nI\n@c/2\nC\n+\nI\n";
+    let mut pairs = FastqReader::from_bytes(data, ReaderOptions::default())
+        .pairs()
+        .with_orphan_policy(divert());
+    assert_eq!(pairs.by_ref().map(Result::unwrap).count(), 2);
+    assert_eq!(*diverted.lock().unwrap(), ["b/1"]);
+    assert_eq!(pairs.orphans().r1, 1);
+
+    // b/2 is malformed: the reader's error comes first, then b/1's orphan
+    diverted.lock().unwrap().clear();
+    let data = b"@a/1\nA\n+\nI\n@a/2\nT\n+\nI\n@b/1\nC\n+\nI\n@b/2\nGG\n+\nI\n@c/1\nG\n+\nI\n@c/2\nC\n+\nI\n";
+    let strict = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let mut pairs = FastqReader::from_bytes(data, strict.clone())
+        .pairs()
+        .with_orphan_policy(divert());
+    assert_eq!(pairs.next().unwrap().unwrap().0.id(), "a/1");
+    assert!(matches!(
+        pairs.next().unwrap(),
+        Err(FastqError::Format { .. })
+    ));
+    assert_eq!(*diverted.lock().unwrap(), ["b/1"]);
+    assert_eq!(pairs.orphans().r1, 1);
+    assert_eq!(pairs.next().unwrap().unwrap().0.id(), "c/1");
+
+    let mut pairs = FastqReader::from_bytes(data, strict).pairs();
+    pairs.next().unwrap().unwrap();
+    assert!(matches!(
+        pairs.next().unwrap(),
+        Err(FastqError::Format { .. })
+    ));
+    let err = pairs.next().unwrap().unwrap_err();
+    assert!(matches!(err, FastqError::Orphan { ref source, .. } if source.id == "b/1"));
+    assert_eq!(pairs.next().unwrap().unwrap().0.id(), "c/1");
+}
+
+#[test]
+fn mate_policy_overrides_error_policy() {
+    use kira_cdh_compat_fastq_reader::{
+        ErrorPolicy, FastqReader, MatePolicy, PairedFastqReader, ReaderOptions,
+    };
+
+    let r1 = b"@a/1\nA\n+\nI\n@c/1\nA\n+\nI\n@d/1\nA\n+\nI\n";
+    let r2 = b"@a/2\nT\n+\nI\n@x/2\nT\n+\nI\n@d/2\nT\n+\nI\n";
+    let open = |error_policy, mate_policy| {
+        let r1 = FastqReader::from_bytes(r1, ReaderOptions::default());
+        let r2 = FastqReader::from_bytes(r2, ReaderOptions::default());
+        PairedFastqReader::new(r1, r2, error_policy).with_mate_policy(mate_policy)
+    };
+
+    let mut rdr = open(ErrorPolicy::Return, MatePolicy::SkipPair);
+    let ids: Vec<_> = rdr.by_ref().map(|p| p.unwrap().0.id().to_owned()).collect();
+    assert_eq!(ids, ["a/1", "d/1"]);
+    assert_eq!(rdr.skipped_pairs(), 1);
+
+    let rdr = open(ErrorPolicy::Skip, MatePolicy::Error);
+    let errors = rdr.filter(Result::is_err).count();
+    assert_eq!(errors, 1);
+
+    let rdr = open(ErrorPolicy::Return, MatePolicy::TrustOrder);
+    assert_eq!(rdr.map(Result::unwrap).count(), 3);
+
+    let data = b"@a/1\nA\n+\nI\n@a/2\nT\n+\nI\n@b/1\nC\n+\nI\n@c/2\nG\n+\nI\n";
+    let strict = ReaderOptions {
+        error_policy: ErrorPolicy::Return,
+        ..Default::default()
+    };
+    let mut pairs = FastqReader::from_bytes(data, strict.clone())
+        .pairs()
+        .with_mate_policy(MatePolicy::SkipPair);
+    assert_eq!(pairs.by_ref().map(Result::unwrap).count(), 1);
+    assert_eq!(pairs.skipped_records(), 2);
+
+    let pairs = FastqReader::from_bytes(data, strict)
+        .pairs()
+        .with_mate_policy(MatePolicy::TrustOrder);
+    assert_eq!(pairs.map(Result::unwrap).count(), 2);
+}
+
+#[test]
+fn mate_files_found_by_naming_convention() {
+    use kira_cdh_compat_fastq_reader::paired::Mate;
+    use kira_cdh_compat_fastq_reader::{MateFileError, discover_pairs, find_mates, mate_path};
+    use std::path::{Path, PathBuf};
+
+    let swap = |p: &str| mate_path(Path::new(p)).map(|(q, m)| (q.display().to_string(), m));
+    assert_eq!(
+        swap("d/S1_L001_R1_001.fastq.gz"),
+        Some(("d/S1_L001_R2_001.fastq.gz".into(), Mate::R1))
+    );
+    assert_eq!(swap("run_2.fq.gz"), Some(("run_1.fq.gz".into(), Mate::R2)));
+    assert_eq!(swap("run.1.fq"), Some(("run.2.fq".into(), Mate::R1)));
+    assert_eq!(swap("reads.fastq"), None);
+
+    let dir = tempfile::tempdir().unwrap();
+    for name in [
+        "a_R1_001.fq",
+        "a_R2_001.fq",
+        "b_1.fastq.gz",
+        "b_2.fastq.gz",
+        "c.fq",
+        "notes_1.txt",
+        "d.1.fq",
+        // an index sidecar is not a FASTQ file, so it needs no mate
+        "a_R1_001.fq.fqi",
+        "e_R1.fq.zst",
+        "e_R2.fq.zst",
+    ] {
+        std::fs::write(dir.path().join(name), "").unwrap();
+    }
+    let r2 = dir.path().join("b_2.fastq.gz");
+    assert_eq!(
+        find_mates(&r2).unwrap(),
+        (dir.path().join("b_1.fastq.gz"), r2)
+    );
+    assert!(matches!(
+        find_mates(dir.path().join("d.1.fq")),
+        Err(MateFileError::MissingMate { .. })
+    ));
+    assert!(matches!(
+        find_mates(dir.path().join("c.fq")),
+        Err(MateFileError::NoMarker(_))
+    ));
+    assert!(discover_pairs(dir.path()).is_err());
+
+    std::fs::remove_file(dir.path().join("d.1.fq")).unwrap();
+    let names = |p: &PathBuf| p.file_name().unwrap().to_str().unwrap().to_owned();
+    let pairs: Vec<_> = discover_pairs(dir.path())
+        .unwrap()
+        .iter()
+        .map(|(a, b)| (names(a), names(b)))
+        .collect();
+    assert_eq!(
+        pairs,
+        [
+            ("a_R1_001.fq".to_owned(), "a_R2_001.fq".to_owned()),
+            ("b_1.fastq.gz".to_owned(), "b_2.fastq.gz".to_owned()),
+            ("e_R1.fq.zst".to_owned(), "e_R2.fq.zst".to_owned()),
+        ]
+    );
+}
@@ -0,0 +1,62 @@
+#[cfg(feature = "rayon")]
+mod t {
+    use kira_cdh_compat_fastq_reader::{ErrorPolicy, FastqReader, ReaderOptions};
+
+    fn fastq(records: usize) -> String {
+        (0..records)
+            .map(|i| {
+                format!(
+                    "@r{i}\n{}\n+\n{}\n",
+                    "A".repeat(i % 7 + 1),
+                    "I".repeat(i % 7 + 1)
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ordered_results_follow_input() {
+        let text = fastq(10_000);
+        let expected: Vec<_> = FastqReader::from_bytes(text.as_bytes(), ReaderOptions::default())
+            .map(|r| r.unwrap().len())
+            .collect();
+        let got: Vec<_> = FastqReader::from_bytes(text.as_bytes(), ReaderOptions::default())
+            .par_process(|rec| rec.len())
+            .batch_size(100)
+            .results()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn unordered_results_cover_every_record() {
+        let text = fastq(5_000);
+        let mut ids: Vec<u32> = FastqReader::from_bytes(text.as_bytes(), ReaderOptions::default())
+            .par_process(|rec| rec.id()[1..].parse().unwrap())
+            .batch_size(64)
+            .ordered(false)
+            .results()
+            .map(Result::unwrap)
+            .collect();
+        ids.sort_unstable();
+        assert_eq!(ids, (0..5_000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn error_ends_the_stream_after_earlier_records() {
+        let text = format!("{}@bad\nAC\n+\nI\n{}", fastq(250), fastq(10));
+        let opts = ReaderOptions {
+            error_policy: ErrorPolicy::Return,
+            ..Default::default()
+        };
+        let out: Vec<_> = FastqReader::from_bytes(text.as_bytes(), opts)
+            .par_process(|rec| rec.id().to_string())
+            .batch_size(100)
+            .results()
+            .collect();
+        assert_eq!(out.len(), 251);
+        assert!(out[..250].iter().all(Result::is_ok));
+        assert!(out[250].is_err());
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(feature = "python")]
+mod t {
+    use kira_cdh_compat_fastq_reader::python::kira_cdh_compat_fastq_reader as module;
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+    use std::ffi::CString;
+    use tempfile::tempdir;
+
+    /// Run `lines` (joined with newlines) with the module bound to `kira`
+    /// and the directory to `d`.
+    fn run(dir: &std::path::Path, lines: &[&str]) -> PyResult<()> {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let locals = PyDict::new(py);
+            locals.set_item("kira", pyo3::wrap_pymodule!(module)(py))?;
+            locals.set_item("d", dir.to_str().unwrap())?;
+            let code = CString::new(lines.join("\n")).unwrap();
+            py.run(&code, None, Some(&locals))
+        })
+    }
+
+    #[test]
+    fn python_iterator_yields_tuples_and_honours_strict() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("in.fq"),
+            "@r1 lane=1\nACGT\n+\nIIII\n@bad\nAC\n+\nI\n@r2\nGG\n+\n!!\n",
+        )
+        .unwrap();
+        run(
+            dir.path(),
+            &[
+                "recs = list(kira.FastqReader(d + '/in.fq'))",
+                "assert recs == [('r1', 'lane=1', 'ACGT', 'IIII'), ('r2', None, 'GG', '!!')], recs",
+                "try:",
+                "    list(kira.FastqReader(d + '/in.fq', strict=True))",
+                "    raise AssertionError('strict reader skipped a malformed record')",
+                "except ValueError as e:",
+                "    assert 'format error' in str(e), e",
+                "try:",
+                "    kira.FastqReader(d + '/missing.fq')",
+                "    raise AssertionError('opened a missing file')",
+                "except OSError:",
+                "    pass",
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn python_records_expose_seq_and_qual_as_memoryviews() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("in.fq"),
+            "@r1 lane=1\nACGT\n+\nIIII\n@bad\nAC\n+\nI\n@r2\nGG\n+\n!!\n",
+        )
+        .unwrap();
+        run(
+            dir.path(),
+            &[
+                "recs = list(kira.FastqReader(d + '/in.fq').records())",
+                "assert [(r.id, r.desc, len(r)) for r in recs] == [('r1', 'lane=1', 4), ('r2', None, 2)]",
+                "seq = recs[0].seq",
+                "assert isinstance(seq, memoryview) and seq.readonly and seq.format == 'B'",
+                "assert bytes(seq) == b'ACGT' and recs[0].qual.tobytes() == b'IIII'",
+                "del recs",
+                "assert bytes(seq) == b'ACGT'",
+                "try:",
+                "    seq[0] = 0",
+                "    raise AssertionError('wrote through a read-only view')",
+                "except TypeError:",
+                "    pass",
+            ],
+        )
+        .unwrap();
+    }
+}
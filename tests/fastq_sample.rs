@@ -0,0 +1,54 @@
+use kira_cdh_compat_fastq_reader::{
+    ErrorPolicy, FastqReader, PairedFastqReader, ReaderOptions, Subsample,
+};
+
+fn reads(n: usize, mate: u8) -> FastqReader {
+    let text: String = (0..n)
+        .map(|i| format!("@r{i}/{mate}\nACGT\n+\nIIII\n"))
+        .collect();
+    FastqReader::from_vec(text.into_bytes(), ReaderOptions::default())
+}
+
+fn ids(recs: impl IntoIterator<Item = kira_cdh_compat_fastq_reader::FastqRecord>) -> Vec<String> {
+    recs.into_iter().map(|r| r.id().to_owned()).collect()
+}
+
+#[test]
+fn sample_fraction_is_deterministic_per_seed() {
+    let a = ids(reads(10_000, 1).sample_fraction(0.1, 7).map(Result::unwrap));
+    let b = ids(reads(10_000, 1).sample_fraction(0.1, 7).map(Result::unwrap));
+    let c = ids(reads(10_000, 1).sample_fraction(0.1, 8).map(Result::unwrap));
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert!((900..1100).contains(&a.len()), "{}", a.len());
+    assert_eq!(reads(50, 1).sample_fraction(1.0, 0).count(), 50);
+    assert_eq!(reads(50, 1).sample_fraction(0.0, 0).count(), 0);
+}
+
+#[test]
+fn sample_n_keeps_input_order() {
+    let picked = ids(reads(1000, 1).sample_n(20, 3).unwrap());
+    assert_eq!(picked.len(), 20);
+    let index = |id: &str| id[1..id.len() - 2].parse::<usize>().unwrap();
+    assert!(picked.windows(2).all(|w| index(&w[0]) < index(&w[1])));
+    assert_eq!(picked, ids(reads(1000, 1).sample_n(20, 3).unwrap()));
+    assert_eq!(reads(5, 1).sample_n(20, 3).unwrap().len(), 5);
+}
+
+#[test]
+fn paired_sampling_selects_both_mates() {
+    let pairs = || PairedFastqReader::new(reads(500, 1), reads(500, 2), ErrorPolicy::Skip);
+    for (r1, r2) in pairs().sample_fraction(0.2, 42).map(Result::unwrap) {
+        assert_eq!(
+            r1.id().trim_end_matches("/1"),
+            r2.id().trim_end_matches("/2")
+        );
+    }
+    let (r1, r2): (Vec<_>, Vec<_>) = pairs().sample_n(10, 1).unwrap().into_iter().unzip();
+    let strip = |ids: Vec<String>| -> Vec<String> {
+        ids.into_iter()
+            .map(|id| id[..id.len() - 2].to_owned())
+            .collect()
+    };
+    assert_eq!(strip(ids(r1)), strip(ids(r2)));
+}
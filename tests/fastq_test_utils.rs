@@ -0,0 +1,61 @@
+#[cfg(feature = "test-utils")]
+mod t {
+    use kira_cdh_compat_fastq_reader::test_utils::{
+        Corruption, Corruptor, GeneratorConfig, QualityProfile, RecordGenerator, to_fastq_bytes,
+    };
+    use kira_cdh_compat_fastq_reader::{ErrorPolicy, FastqReader, LineMode, ReaderOptions};
+    use std::io::Cursor;
+
+    fn config() -> GeneratorConfig {
+        GeneratorConfig {
+            seed: 7,
+            quality: QualityProfile::Constant(30),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn generator_is_deterministic_and_round_trips() {
+        let a = RecordGenerator::new(config()).take_records(10);
+        let b = RecordGenerator::new(config()).take_records(10);
+        assert_eq!(a, b);
+
+        let bytes = to_fastq_bytes(&a, Some(60));
+        let opts = ReaderOptions {
+            line_mode: LineMode::Multi,
+            error_policy: ErrorPolicy::Return,
+            ..Default::default()
+        };
+        let parsed: Vec<_> = FastqReader::from_bufread(Cursor::new(bytes), opts)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(parsed, a);
+    }
+
+    #[test]
+    fn corruptions_are_skipped_or_reported() {
+        let recs = RecordGenerator::new(config()).take_records(10);
+        let bytes = Corruptor::new()
+            .at(3, Corruption::LengthMismatch)
+            .at(6, Corruption::BadPlusLine)
+            .at(9, Corruption::Truncate)
+            .apply(&recs);
+
+        let skipped: Vec<_> =
+            FastqReader::from_bufread(Cursor::new(bytes.clone()), ReaderOptions::default())
+                .map(|r| r.unwrap().id().to_string())
+                .collect();
+        assert_eq!(skipped.len(), 7);
+        assert!(!skipped.contains(&"read3".to_string()));
+
+        let strict = ReaderOptions {
+            error_policy: ErrorPolicy::Return,
+            ..Default::default()
+        };
+        let mut fq = FastqReader::from_bufread(Cursor::new(bytes), strict);
+        for _ in 0..3 {
+            fq.next().unwrap().unwrap();
+        }
+        assert!(fq.next().unwrap().is_err());
+    }
+}
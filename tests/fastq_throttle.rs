@@ -0,0 +1,27 @@
+use kira_cdh_compat_fastq_reader::{FastqReader, ReaderOptions, Throttle};
+use std::time::{Duration, Instant};
+
+const FIVE: &str = "@a\nAC\n+\nII\n@b\nAC\n+\nII\n@c\nAC\n+\nII\n@d\nAC\n+\nII\n@e\nAC\n+\nII\n";
+
+#[test]
+fn records_per_sec_paces_iteration() {
+    let opts = ReaderOptions {
+        throttle: Some(Throttle::RecordsPerSec(20)),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    assert_eq!(FastqReader::from_bytes(FIVE.as_bytes(), opts).count(), 5);
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
+
+#[test]
+fn bytes_per_sec_paces_raw_input() {
+    let opts = ReaderOptions {
+        throttle: Some(Throttle::BytesPerSec(200)),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    assert_eq!(FastqReader::from_bytes(FIVE.as_bytes(), opts).count(), 5);
+    // 60 bytes at 200 B/s
+    assert!(start.elapsed() >= Duration::from_millis(250));
+}
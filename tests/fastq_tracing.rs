@@ -0,0 +1,98 @@
+#[cfg(feature = "tracing")]
+mod t {
+    use kira_cdh_compat_fastq_reader::{FastqReader, ReaderOptions};
+    use std::fmt::Debug;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use tempfile::tempdir;
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Events as `level span_name: field=value ...`, spans by ID.
+    #[derive(Default)]
+    struct Capture {
+        next_id: AtomicU64,
+        spans: Mutex<Vec<String>>,
+        events: Mutex<Vec<String>>,
+    }
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.push_str(&format!(" {}={value:?}", field.name()));
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = Fields(span.metadata().name().to_owned());
+            span.record(&mut fields);
+            self.spans.lock().unwrap().push(fields.0);
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let parent = event.parent().map_or(String::new(), |id| {
+                self.spans.lock().unwrap()[id.into_u64() as usize - 1].clone()
+            });
+            let mut fields = Fields(format!("{} [{parent}]", event.metadata().level()));
+            event.record(&mut fields);
+            self.events.lock().unwrap().push(fields.0);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn skips_are_structured_events_inside_a_per_file_span() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("in.fq");
+        std::fs::write(
+            &path,
+            "@r1\nACGT\n+\nIIII\n@bad\nAC\n+\nI\n@r2\nGG\n+\n!!\n",
+        )
+        .unwrap();
+        let shown = path.display().to_string();
+
+        let capture = std::sync::Arc::new(Capture::default());
+        tracing::subscriber::with_default(std::sync::Arc::clone(&capture), || {
+            let n = FastqReader::from_path(&path, ReaderOptions::default())
+                .unwrap()
+                .count();
+            assert_eq!(n, 2);
+        });
+
+        let span = format!("fastq_input path={shown:?}");
+        assert_eq!(*capture.spans.lock().unwrap(), std::slice::from_ref(&span));
+        let events = capture.events.lock().unwrap();
+        assert_eq!(events.len(), 3, "{events:?}");
+        assert_eq!(events[0], format!("DEBUG [{span}] message=opened"));
+        assert!(
+            events[1].starts_with(&format!(
+                "WARN [{span}] message=skipping malformed record: format error"
+            )),
+            "{}",
+            events[1]
+        );
+        assert!(
+            events[1].ends_with(&format!(
+                " line=8 byte_pos=28 kind=\"length_mismatch\" path={shown:?}"
+            )),
+            "{}",
+            events[1]
+        );
+        assert_eq!(events[2], format!("DEBUG [{span}] message=closed"));
+    }
+}
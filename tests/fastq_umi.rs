@@ -0,0 +1,78 @@
+use kira_cdh_compat_fastq_reader::{
+    FastqReader, FastqRecord, HeaderPart, ReaderOptions, UmiExtractor, UmiPattern, UmiSource,
+};
+
+#[test]
+fn extracts_umi_and_barcode_from_read_start() {
+    let pattern = UmiPattern::new("CCNNNX").unwrap();
+    let mut rec = FastqRecord::new("r1", Some("lane=1"), b"ACGGTTAAAA", b"ABCDEFGHIJ");
+    let umi = UmiExtractor::new(UmiSource::ReadStart(pattern))
+        .extract(&mut rec)
+        .unwrap();
+    assert_eq!(umi.barcode, b"AC");
+    assert_eq!(umi.barcode_qual, b"AB");
+    assert_eq!(umi.umi, b"GGT");
+    assert_eq!(umi.umi_qual, b"CDE");
+    assert_eq!(rec.id(), "r1_AC_GGT");
+    assert_eq!(rec.desc(), Some("lane=1"));
+    assert_eq!(rec.seq(), b"TAAAA");
+    assert_eq!(rec.qual(), b"FGHIJ");
+}
+
+#[test]
+fn extracts_from_read_end_and_header_fields() {
+    let pattern = UmiPattern::new("NNNN").unwrap();
+    let mut rec = FastqRecord::new("r1", None, b"AAAACGTT", b"IIIIJJJJ");
+    let umi = UmiExtractor::new(UmiSource::ReadEnd(pattern))
+        .id_separator(None)
+        .extract(&mut rec)
+        .unwrap();
+    assert_eq!(umi.umi, b"CGTT");
+    assert_eq!(rec, FastqRecord::new("r1", None, b"AAAA", b"IIII"));
+
+    let field = UmiSource::HeaderField {
+        part: HeaderPart::Id,
+        sep: ':',
+        index: 7,
+    };
+    let mut rec = FastqRecord::new("M1:5:FC:1:1101:10:20:ACGTAC", None, b"GG", b"II");
+    let before = rec.clone();
+    let umi = UmiExtractor::new(field).extract(&mut rec).unwrap();
+    assert_eq!(umi.umi, b"ACGTAC");
+    assert!(umi.umi_qual.is_empty());
+    assert_eq!(rec, before);
+
+    assert!(UmiPattern::new("NNZ").is_err());
+    assert!(UmiPattern::new("XX").is_err());
+}
+
+#[test]
+fn stage_drops_reads_shorter_than_the_pattern() {
+    let data = b"@r1\nACGTAA\n+\nIIIIII\n@r2\nAC\n+\nII\n@r3\nTTTTGG\n+\nIIIIII\n";
+    let fq = FastqReader::from_bytes(data, ReaderOptions::default());
+    let mut stage =
+        UmiExtractor::new(UmiSource::ReadStart(UmiPattern::new("NNNN").unwrap())).stage(fq);
+    let out: Vec<_> = stage.by_ref().map(Result::unwrap).collect();
+    let ids: Vec<_> = out.iter().map(|(rec, _)| rec.id()).collect();
+    assert_eq!(ids, ["r1_ACGT", "r3_TTTT"]);
+    assert_eq!(out[1].0.seq(), b"GG");
+    assert_eq!(stage.dropped(), 1);
+}
+
+#[test]
+fn records_without_quality_or_with_a_short_one() {
+    let extractor = UmiExtractor::new(UmiSource::ReadEnd(UmiPattern::new("XNN").unwrap()));
+    // keep_quality: false leaves the quality empty
+    let mut rec = FastqRecord::new("r1", None, b"AAAACGT", b"");
+    let umi = extractor.extract(&mut rec).unwrap();
+    assert_eq!(
+        (umi.umi.as_slice(), umi.umi_qual.as_slice()),
+        (&b"GT"[..], &b""[..])
+    );
+    assert_eq!(rec, FastqRecord::new("r1_GT", None, b"AAAAC", b""));
+
+    let mut rec = FastqRecord::new("r2", None, b"AAAACGT", b"II");
+    let before = rec.clone();
+    assert!(extractor.extract(&mut rec).is_none());
+    assert_eq!(rec, before);
+}
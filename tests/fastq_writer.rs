@@ -0,0 +1,171 @@
+#[cfg(feature = "gzip")]
+mod t {
+    use kira_cdh_compat_fastq_reader::{
+        Compression, FastqReader, FastqRecord, FastqWriter, LineEnding, ReaderOptions,
+        WriterOptions,
+    };
+    use tempfile::tempdir;
+
+    fn records() -> Vec<FastqRecord> {
+        vec![
+            FastqRecord::new("r1", Some("lane=1"), b"ACGT", b"IIII"),
+            FastqRecord::new("r2", None, b"GG", b"!!").with_plus_line(Some("r2")),
+        ]
+    }
+
+    #[test]
+    fn gzip_by_extension_round_trips() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("out.fq.gz");
+        let mut w = FastqWriter::from_path(&path, WriterOptions::default()).unwrap();
+        for rec in &records() {
+            w.write_record(rec).unwrap();
+        }
+        assert_eq!(w.records(), 2);
+        w.finish().unwrap();
+
+        assert!(std::fs::read(&path).unwrap().starts_with(&[0x1F, 0x8B]));
+        let opts = ReaderOptions {
+            keep_plus_line: true,
+            ..Default::default()
+        };
+        let back: Vec<_> = FastqReader::from_path(&path, opts)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(back, records());
+    }
+
+    #[test]
+    fn records_without_a_full_quality_are_rejected() {
+        let opts = ReaderOptions {
+            keep_quality: false,
+            ..Default::default()
+        };
+        let rec = FastqReader::from_bytes(b"@r1\nACGT\n+\nIIII\n", opts)
+            .next()
+            .unwrap()
+            .unwrap();
+        let mut w = FastqWriter::from_writer(Vec::new(), WriterOptions::default()).unwrap();
+        let err = w.write_record(&rec).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let short = FastqRecord::new("r2", None, b"ACGT", b"II");
+        assert!(w.write_record(&short).is_err());
+        assert_eq!(w.records(), 0);
+        w.write_record(&records()[0]).unwrap();
+        assert_eq!(w.finish().unwrap(), b"@r1 lane=1\nACGT\n+\nIIII\n");
+    }
+
+    #[test]
+    fn plain_output_with_crlf() {
+        let opts = WriterOptions {
+            line_ending: LineEnding::CrLf,
+            ..Default::default()
+        };
+        let mut w = FastqWriter::from_writer(Vec::new(), opts).unwrap();
+        w.write_record(&records()[0]).unwrap();
+        let out = w.finish().unwrap();
+        assert_eq!(out, b"@r1 lane=1\r\nACGT\r\n+\r\nIIII\r\n");
+
+        let opts = WriterOptions {
+            compression: Some(Compression::Gzip),
+            level: 1,
+            ..Default::default()
+        };
+        let mut w = FastqWriter::from_writer(Vec::new(), opts).unwrap();
+        w.write_record(&records()[0]).unwrap();
+        let gz = w.finish().unwrap();
+        let rec = FastqReader::from_vec(gz, ReaderOptions::default())
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(rec, records()[0]);
+    }
+
+    #[test]
+    fn zstd_output_round_trips_or_is_rejected() {
+        let opts = WriterOptions {
+            compression: Some(Compression::Zstd),
+            ..Default::default()
+        };
+        let w = FastqWriter::from_writer(Vec::new(), opts);
+        if cfg!(feature = "zstd") {
+            let mut w = w.unwrap();
+            for rec in &records() {
+                w.write_record(rec).unwrap();
+            }
+            let zst = w.finish().unwrap();
+            assert!(zst.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]));
+            let opts = ReaderOptions {
+                keep_plus_line: true,
+                ..Default::default()
+            };
+            let back: Vec<_> = FastqReader::from_vec(zst, opts)
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(back, records());
+        } else {
+            let err = w.err().unwrap();
+            assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+        }
+    }
+
+    #[test]
+    fn jsonl_and_tsv_selected_fields() {
+        use kira_cdh_compat_fastq_reader::{FastqRecord, JsonlWriter, RecordField, TsvWriter};
+
+        let recs = [
+            FastqRecord::new("r1", Some("x=\"1\"\tlane"), b"ACGT", b"II#I"),
+            FastqRecord::new("r2", None, b"", b""),
+        ];
+        let fields = [
+            RecordField::Id,
+            RecordField::Desc,
+            RecordField::Length,
+            RecordField::MeanQuality,
+        ];
+
+        let mut w = JsonlWriter::from_writer(Vec::new(), &fields);
+        for rec in &recs {
+            w.write_record(rec).unwrap();
+        }
+        assert_eq!(w.records(), 2);
+        let out = String::from_utf8(w.finish().unwrap()).unwrap();
+        let lines: Vec<serde_json::Value> = out
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(
+            lines[0],
+            serde_json::json!({"id": "r1", "desc": "x=\"1\"\tlane", "length": 4, "mean_quality": 30.5})
+        );
+        assert_eq!(
+            lines[1],
+            serde_json::json!({"id": "r2", "desc": null, "length": 0, "mean_quality": null})
+        );
+
+        let mut w = JsonlWriter::from_writer(Vec::new(), &[RecordField::Seq, RecordField::Qual]);
+        w.write_record(&recs[0]).unwrap();
+        assert_eq!(
+            w.finish().unwrap(),
+            b"{\"seq\":\"ACGT\",\"qual\":\"II#I\"}\n"
+        );
+
+        let mut w = TsvWriter::from_writer(Vec::new(), &RecordField::ALL);
+        for rec in &recs {
+            w.write_record(rec).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(w.finish().unwrap()).unwrap(),
+            "id\tdesc\tlength\tmean_quality\tseq\tqual\n\
+             r1\tx=\"1\" lane\t4\t30.5\tACGT\tII#I\n\
+             r2\t\t0\t\t\t\n"
+        );
+
+        let w = TsvWriter::from_writer(Vec::new(), &[RecordField::Id]);
+        assert_eq!(w.finish().unwrap(), b"id\n");
+        let mut w = TsvWriter::from_writer(Vec::new(), &[RecordField::Id]).without_header();
+        w.write_record(&recs[1]).unwrap();
+        assert_eq!(w.finish().unwrap(), b"r2\n");
+    }
+}
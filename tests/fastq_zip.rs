@@ -0,0 +1,163 @@
+#[cfg(feature = "zip")]
+mod t {
+    use kira_cdh_compat_fastq_reader::{ErrorPolicy, ReaderOptions, ZipFastqReader};
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    /// Minimal zip writer: (name, data, deflate?) entries, no data descriptors.
+    fn build_zip(entries: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        build_zip_with(entries, false)
+    }
+
+    /// With `descriptors`, the local headers leave CRC and sizes at zero and
+    /// a data descriptor follows each entry, as streaming writers do.
+    fn build_zip_with(entries: &[(&str, &[u8], bool)], descriptors: bool) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for &(name, data, deflate) in entries {
+            let body = if deflate {
+                let mut enc =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                enc.write_all(data).unwrap();
+                enc.finish().unwrap()
+            } else {
+                data.to_vec()
+            };
+            let mut crc = flate2::Crc::new();
+            crc.update(data);
+            let method: u16 = if deflate { 8 } else { 0 };
+            let flags: u16 = if descriptors { 0x08 } else { 0 };
+            let offset = out.len() as u32;
+            let mut sums = Vec::new();
+            sums.extend_from_slice(&crc.sum().to_le_bytes());
+            sums.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            sums.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            let header = |sums: &[u8]| {
+                let mut h = Vec::new();
+                h.extend_from_slice(&flags.to_le_bytes());
+                h.extend_from_slice(&method.to_le_bytes());
+                h.extend_from_slice(&[0; 4]); // mtime, mdate
+                h.extend_from_slice(sums);
+                h.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                h.extend_from_slice(&0u16.to_le_bytes()); // extra len
+                h
+            };
+
+            out.extend_from_slice(&0x0403_4B50u32.to_le_bytes());
+            out.extend_from_slice(&[20, 0]); // version
+            out.extend_from_slice(&header(if descriptors { &[0; 12] } else { &sums }));
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&body);
+            if descriptors {
+                out.extend_from_slice(&0x0807_4B50u32.to_le_bytes());
+                out.extend_from_slice(&sums);
+            }
+
+            central.extend_from_slice(&0x0201_4B50u32.to_le_bytes());
+            central.extend_from_slice(&[20, 0, 20, 0]); // made by, needed
+            central.extend_from_slice(&header(&sums));
+            central.extend_from_slice(&[0; 10]); // comment len, disk, int/ext attrs
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let cd_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(&0x0605_4B50u32.to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn reads_matching_entries_in_order() {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gz.write_all(b"@c\nTT\n+\nII\n").unwrap();
+        let gz = gz.finish().unwrap();
+        let zip = build_zip(&[
+            ("run/S1_R1.fastq", b"@a\nACGT\n+\n!!!!\n", true),
+            ("run/S1_R2.fastq", b"@x\nGG\n+\n!!\n", false),
+            ("run/S2_R1.fastq.gz", &gz, false),
+            ("run/README.txt", b"not fastq", false),
+        ]);
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("run.zip");
+        std::fs::write(&path, zip).unwrap();
+
+        let opts = ReaderOptions {
+            error_policy: ErrorPolicy::Return,
+            ..Default::default()
+        };
+        let mut rdr = ZipFastqReader::from_path(&path, "*_R1*.fastq*", opts).unwrap();
+        let names: Vec<_> = rdr.entries().iter().map(|e| e.name.clone()).collect();
+        assert_eq!(names, ["run/S1_R1.fastq", "run/S2_R1.fastq.gz"]);
+
+        let first = rdr.next().unwrap().unwrap();
+        assert_eq!(first.seq(), b"ACGT");
+        assert_eq!(rdr.current_entry(), Some("run/S1_R1.fastq"));
+        let ids: Vec<_> = rdr.map(|r| r.unwrap().id().to_string()).collect();
+        assert_eq!(ids, ["c"]);
+    }
+
+    #[test]
+    fn data_descriptors_and_zip64_entries() {
+        use zip::write::{SimpleFileOptions, ZipWriter};
+
+        let zip = build_zip_with(
+            &[
+                ("a_R1.fastq", b"@a\nACGT\n+\n!!!!\n", true),
+                ("b_R1.fastq", b"@b\nGG\n+\n!!\n", false),
+            ],
+            true,
+        );
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("streamed.zip");
+        std::fs::write(&path, zip).unwrap();
+        let rdr = ZipFastqReader::from_path(&path, "*", ReaderOptions::default()).unwrap();
+        let ids: Vec<_> = rdr.map(|r| r.unwrap().id().to_owned()).collect();
+        assert_eq!(ids, ["a", "b"]);
+
+        // sizes and offsets in ZIP64 extra fields
+        let mut w = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        w.start_file("c_R1.fastq", SimpleFileOptions::default().large_file(true))
+            .unwrap();
+        w.write_all(b"@c\nTT\n+\nII\n").unwrap();
+        let path = dir.path().join("zip64.zip");
+        std::fs::write(&path, w.finish().unwrap().into_inner()).unwrap();
+        let mut rdr = ZipFastqReader::from_path(&path, "*", ReaderOptions::default()).unwrap();
+        assert_eq!(rdr.entries()[0].uncompressed_size, 11);
+        assert_eq!(rdr.next().unwrap().unwrap().seq(), b"TT");
+        assert!(rdr.next().is_none());
+    }
+
+    #[test]
+    fn unsupported_compression_method_is_an_error() {
+        let mut zip = build_zip(&[("a.fastq", b"@a\nAC\n+\n!!\n", false)]);
+        // method 12 (bzip2) in the local and the central header
+        let central = zip.len() - 22 - 46 - "a.fastq".len();
+        for at in [8, central + 10] {
+            zip[at..at + 2].copy_from_slice(&12u16.to_le_bytes());
+        }
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bzip2.zip");
+        std::fs::write(&path, zip).unwrap();
+        let mut rdr = ZipFastqReader::from_path(&path, "*", ReaderOptions::default()).unwrap();
+        match rdr.next().unwrap().unwrap_err() {
+            kira_cdh_compat_fastq_reader::FastqError::Io { source, .. } => {
+                assert_eq!(source.kind(), std::io::ErrorKind::Unsupported)
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn rejects_non_zip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("plain.zip");
+        std::fs::write(&path, b"@a\nACGT\n+\n!!!!\n").unwrap();
+        assert!(ZipFastqReader::from_path(&path, "*", ReaderOptions::default()).is_err());
+    }
+}